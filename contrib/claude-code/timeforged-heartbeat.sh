@@ -2,7 +2,8 @@
 # TimeForged heartbeat hook for Claude Code
 # Sends events to the TimeForged daemon on every interaction.
 # Fires on: UserPromptSubmit, PostToolUse, Stop
-# Runs curl in the background so it never blocks Claude Code.
+# Runs in the background so it never blocks Claude Code. Goes through
+# `tf send` when tf is installed, which queues the event if the daemon is down.
 
 # Config path: Windows (%APPDATA%) or Linux/macOS (~/.config)
 if [[ -n "$APPDATA" ]]; then
@@ -96,9 +97,15 @@ PAYLOAD=$(jq -n \
     metadata: { source: "claude-code", hook: $source }
   }')
 
-# Send in background, ignore failures
-curl -s -o /dev/null --max-time 5 \
-  -X POST -H "Content-Type: application/json" -H "X-Api-Key: $TF_API_KEY" \
-  -d "$PAYLOAD" "${TF_SERVER_URL}/api/v1/events" &
+# Send in background; tf keeps the event for later if the daemon is down,
+# plain curl just drops it
+if command -v tf >/dev/null 2>&1; then
+  printf '%s' "$PAYLOAD" | tf send --stdin --server "$TF_SERVER_URL" --key "$TF_API_KEY" \
+    >/dev/null 2>&1 &
+else
+  curl -s -o /dev/null --max-time 5 \
+    -X POST -H "Content-Type: application/json" -H "X-Api-Key: $TF_API_KEY" \
+    -d "$PAYLOAD" "${TF_SERVER_URL}/api/v1/events" &
+fi
 
 exit 0
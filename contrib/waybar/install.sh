@@ -67,7 +67,7 @@ else
     fi
 
     # Add module definitions before closing }
-    DEFINITION=",\n\n    \"image#timeforged\": {\n        \"path\": \"${HOME}/.config/waybar/timeforged-icon.png\",\n        \"size\": 26,\n        \"tooltip\": false,\n        \"on-click\": \"xdg-open http://127.0.0.1:6175\"\n    },\n\n    \"custom/timeforged\": {\n        \"format\": \"{}\",\n        \"return-type\": \"json\",\n        \"interval\": 60,\n        \"exec\": \"~/.config/waybar/modules/timeforged.sh\",\n        \"on-click\": \"xdg-open http://127.0.0.1:6175\"\n    }"
+    DEFINITION=",\n\n    \"image#timeforged\": {\n        \"path\": \"${HOME}/.config/waybar/timeforged-icon.png\",\n        \"size\": 26,\n        \"tooltip\": false,\n        \"on-click\": \"xdg-open http://127.0.0.1:6175\"\n    },\n\n    \"custom/timeforged\": {\n        \"format\": \"{}\",\n        \"return-type\": \"json\",\n        \"exec\": \"~/.config/waybar/modules/timeforged.sh --follow\",\n        \"on-click\": \"xdg-open http://127.0.0.1:6175\"\n    }"
 
     sed -i '$ s/}/'"$(echo -e "$DEFINITION")"'\n}/' "$WAYBAR_CONFIG"
     ok "Module definitions added to config"
@@ -1,5 +1,8 @@
 #!/usr/bin/env bash
 # TimeForged waybar module — shows today's coding time
+#
+# Usage: timeforged.sh            print once (use with "interval")
+#        timeforged.sh --follow   print on every change from the live stream
 
 CONFIG="$HOME/.config/timeforged/cli.toml"
 API_KEY=""
@@ -11,6 +14,61 @@ if [[ -f "$CONFIG" ]]; then
     [[ -n "$srv" ]] && SERVER="$srv"
 fi
 
+# Render a summary/totals JSON document (stdin) as one waybar line
+render() {
+    local RESPONSE
+    RESPONSE=$(cat)
+    TOTAL=$(echo "$RESPONSE" | jq -r '.total_seconds // 0' 2>/dev/null)
+    if [[ -z "$TOTAL" || "$TOTAL" == "null" ]]; then
+        offline "Failed to parse"
+        return
+    fi
+
+    HOURS=$(echo "$TOTAL" | awk '{printf "%d", $1 / 3600}')
+    MINS=$(echo "$TOTAL" | awk '{printf "%d", ($1 % 3600) / 60}')
+
+    # Tooltip: per-project breakdown (skip 0m, trim paths to basename)
+    TOOLTIP=$(echo "$RESPONSE" | jq -r '
+        .projects // [] | sort_by(-.total_seconds) |
+        map(select(.total_seconds >= 60)) |
+        map(
+            (.total_seconds / 3600 | floor | tostring) + ":" +
+            ((.total_seconds % 3600 / 60) | floor | tostring | if length == 1 then "0" + . else . end) + "  " +
+            (.name | split("/") | last)
+        ) | join("\n")
+    ' 2>/dev/null)
+
+    [[ -z "$TOOLTIP" ]] && TOOLTIP="no projects today"
+
+    CLASS="active"
+    [[ "$HOURS" -eq 0 && "$MINS" -eq 0 ]] && CLASS="idle"
+
+    TOOLTIP_JSON=$(printf '%s' "$TOOLTIP" | jq -Rsc '.')
+    printf '{"text":"󱑂  %d:%02d","tooltip":%s,"class":"%s"}\n' \
+        "$HOURS" "$MINS" "$TOOLTIP_JSON" "$CLASS"
+}
+
+offline() {
+    printf '{"text":"󱑂  offline","tooltip":"%s","class":"offline"}\n' "$1"
+}
+
+if [[ "${1:-}" == "--follow" ]]; then
+    # Each "totals" message on the stream has the same shape as the summary
+    while true; do
+        curl -sfN --connect-timeout 3 \
+            -H "X-Api-Key: ${API_KEY}" \
+            "${SERVER}/api/v1/stream" 2>/dev/null |
+        while IFS= read -r line; do
+            [[ "$line" == data:* ]] || continue
+            data="${line#data:}"
+            [[ "$(jq -r '.type' <<<"$data" 2>/dev/null)" == "totals" ]] || continue
+            render <<<"$data"
+        done
+        offline "Daemon not responding"
+        sleep 5
+    done
+fi
+
 TODAY=$(date -u +%Y-%m-%dT00:00:00Z)
 TOMORROW=$(date -u -d "+1 day" +%Y-%m-%dT00:00:00Z)
 
@@ -19,35 +77,8 @@ RESPONSE=$(curl -sf --max-time 3 \
     "${SERVER}/api/v1/reports/summary?from=${TODAY}&to=${TOMORROW}" 2>/dev/null)
 
 if [[ -z "$RESPONSE" ]]; then
-    printf '{"text":"󱑂  offline","tooltip":"Daemon not responding","class":"offline"}\n'
-    exit 0
-fi
-
-TOTAL=$(echo "$RESPONSE" | jq -r '.total_seconds // 0' 2>/dev/null)
-if [[ -z "$TOTAL" || "$TOTAL" == "null" ]]; then
-    printf '{"text":"󱑂  offline","tooltip":"Failed to parse","class":"offline"}\n'
+    offline "Daemon not responding"
     exit 0
 fi
 
-HOURS=$(echo "$TOTAL" | awk '{printf "%d", $1 / 3600}')
-MINS=$(echo "$TOTAL" | awk '{printf "%d", ($1 % 3600) / 60}')
-
-# Tooltip: per-project breakdown (skip 0m, trim paths to basename)
-TOOLTIP=$(echo "$RESPONSE" | jq -r '
-    .projects // [] | sort_by(-.total_seconds) |
-    map(select(.total_seconds >= 60)) |
-    map(
-        (.total_seconds / 3600 | floor | tostring) + ":" +
-        ((.total_seconds % 3600 / 60) | floor | tostring | if length == 1 then "0" + . else . end) + "  " +
-        (.name | split("/") | last)
-    ) | join("\n")
-' 2>/dev/null)
-
-[[ -z "$TOOLTIP" ]] && TOOLTIP="no projects today"
-
-CLASS="active"
-[[ "$HOURS" -eq 0 && "$MINS" -eq 0 ]] && CLASS="idle"
-
-TOOLTIP_JSON=$(printf '%s' "$TOOLTIP" | jq -Rsc '.')
-printf '{"text":"󱑂  %d:%02d","tooltip":%s,"class":"%s"}\n' \
-    "$HOURS" "$MINS" "$TOOLTIP_JSON" "$CLASS"
+render <<<"$RESPONSE"
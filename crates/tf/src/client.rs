@@ -1,34 +1,127 @@
-use reqwest::Client;
+use colored::Colorize;
+use reqwest::{Client, RequestBuilder, Response};
 use serde::de::DeserializeOwned;
 
 use timeforged_core::api::ErrorResponse;
 use timeforged_core::config::CliConfig;
+use timeforged_sync::send_with_backoff;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The server could not be reached at all.
+    Request(String),
+    /// The server answered 2xx but the body didn't match what we expected.
+    Parse(String),
+    /// The server returned an `ErrorResponse`.
+    Api {
+        status: u16,
+        code: String,
+        message: String,
+        request_id: Option<String>,
+    },
+}
+
+impl ClientError {
+    /// What the user can do about it, keyed on the server's error code.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            ClientError::Request(_) => {
+                Some("is the daemon running? Start it with: timeforged".into())
+            }
+            ClientError::Parse(_) => Some("client and server versions may not match".into()),
+            ClientError::Api {
+                status,
+                code,
+                request_id,
+                ..
+            } => match code.as_str() {
+                "unauthorized" => {
+                    Some("check your API key (`api_key` in cli.toml or TF_API_KEY)".into())
+                }
+                "rate_limited" => Some("too many requests, wait a bit and retry".into()),
+                "conflict" => Some("the resource already exists".into()),
+                "internal_error" => Some(match request_id {
+                    Some(id) => format!("server error, see daemon logs for request {id}"),
+                    None => "server error, see daemon logs".into(),
+                }),
+                // Servers before error codes existed answer unknown routes with a bare 404
+                "" if *status == 404 => {
+                    Some("endpoint not found, is the server up to date?".into())
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Print the hint (if any) under an error line.
+    pub fn print_hint(&self) {
+        if let Some(hint) = self.hint() {
+            eprintln!("  {} {hint}", "hint:".yellow());
+        }
+    }
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Request(e) => write!(f, "request failed: {e}"),
+            ClientError::Parse(e) => write!(f, "parse error: {e}"),
+            ClientError::Api { message, .. } => write!(f, "{message}"),
+        }
+    }
+}
 
 pub struct TfClient {
     http: Client,
     base_url: String,
     api_key: Option<String>,
+    backoff: bool,
 }
 
 impl TfClient {
+    /// A client that hands a 429 straight back to the caller.
     pub fn new(config: &CliConfig) -> Self {
         Self {
             http: Client::new(),
             base_url: config.server_url.trim_end_matches('/').to_string(),
             api_key: config.api_key.clone(),
+            backoff: false,
         }
     }
 
+    /// Wait out rate limits instead of failing, for bulk work like imports
+    /// where a pause beats giving up halfway.
+    pub fn with_backoff(mut self) -> Self {
+        self.backoff = true;
+        self
+    }
+
     fn url(&self, path: &str) -> String {
         format!("{}{}", self.base_url, path)
     }
 
-    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
-        let mut req = self.http.get(self.url(path));
-        if let Some(ref key) = self.api_key {
-            req = req.header("X-Api-Key", key);
-        }
-        let resp = req.send().await.map_err(|e| format!("request failed: {e}"))?;
+    /// Send a request built by `build`, attaching the API key. With backoff
+    /// on, rate limits are waited out the way the sync engine does (see
+    /// [`send_with_backoff`]), so the next call (e.g. the next import chunk)
+    /// doesn't get rejected.
+    async fn send(&self, build: impl Fn() -> RequestBuilder) -> Result<Response, ClientError> {
+        let keyed = || match self.api_key {
+            Some(ref key) => build().header("X-Api-Key", key),
+            None => build(),
+        };
+        let resp = if self.backoff {
+            send_with_backoff(keyed, |secs| {
+                eprintln!("rate limited by server, retrying in {secs}s");
+            })
+            .await
+        } else {
+            keyed().send().await
+        };
+        resp.map_err(|e| ClientError::Request(e.to_string()))
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        let resp = self.send(|| self.http.get(self.url(path))).await?;
         handle_response(resp).await
     }
 
@@ -36,12 +129,8 @@ impl TfClient {
         &self,
         path: &str,
         query: &[(&str, &str)],
-    ) -> Result<T, String> {
-        let mut req = self.http.get(self.url(path)).query(query);
-        if let Some(ref key) = self.api_key {
-            req = req.header("X-Api-Key", key);
-        }
-        let resp = req.send().await.map_err(|e| format!("request failed: {e}"))?;
+    ) -> Result<T, ClientError> {
+        let resp = self.send(|| self.http.get(self.url(path)).query(query)).await?;
         handle_response(resp).await
     }
 
@@ -49,25 +138,47 @@ impl TfClient {
         &self,
         path: &str,
         body: &B,
-    ) -> Result<T, String> {
-        let mut req = self.http.post(self.url(path)).json(body);
-        if let Some(ref key) = self.api_key {
-            req = req.header("X-Api-Key", key);
-        }
-        let resp = req.send().await.map_err(|e| format!("request failed: {e}"))?;
+    ) -> Result<T, ClientError> {
+        let resp = self.send(|| self.http.post(self.url(path)).json(body)).await?;
         handle_response(resp).await
     }
 
+    /// POST a raw body, e.g. JSON Lines.
+    pub async fn post_body<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        content_type: &str,
+        body: &str,
+    ) -> Result<T, ClientError> {
+        let resp = self
+            .send(|| {
+                let req = self.http.post(self.url(path)).header("Content-Type", content_type);
+                req.body(body.to_owned())
+            })
+            .await?;
+        handle_response(resp).await
+    }
+
+    /// GET a response to read as it arrives, e.g. a streamed export.
+    pub async fn get_stream(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Response, ClientError> {
+        let resp = self.send(|| self.http.get(self.url(path)).query(query)).await?;
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            Err(api_error(resp).await)
+        }
+    }
+
     pub async fn put<T: DeserializeOwned, B: serde::Serialize>(
         &self,
         path: &str,
         body: &B,
-    ) -> Result<T, String> {
-        let mut req = self.http.put(self.url(path)).json(body);
-        if let Some(ref key) = self.api_key {
-            req = req.header("X-Api-Key", key);
-        }
-        let resp = req.send().await.map_err(|e| format!("request failed: {e}"))?;
+    ) -> Result<T, ClientError> {
+        let resp = self.send(|| self.http.put(self.url(path)).json(body)).await?;
         handle_response(resp).await
     }
 
@@ -75,30 +186,48 @@ impl TfClient {
         &self,
         path: &str,
         body: &B,
-    ) -> Result<T, String> {
-        let mut req = self.http.delete(self.url(path)).json(body);
-        if let Some(ref key) = self.api_key {
-            req = req.header("X-Api-Key", key);
-        }
-        let resp = req.send().await.map_err(|e| format!("request failed: {e}"))?;
+    ) -> Result<T, ClientError> {
+        let resp = self.send(|| self.http.delete(self.url(path)).json(body)).await?;
         handle_response(resp).await
     }
 
-    pub async fn health(&self) -> Result<timeforged_core::api::HealthResponse, String> {
+    /// DELETE that answers `204 No Content`.
+    pub async fn delete(&self, path: &str) -> Result<(), ClientError> {
+        let resp = self.send(|| self.http.delete(self.url(path))).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(api_error(resp).await)
+        }
+    }
+
+    pub async fn health(&self) -> Result<timeforged_core::api::HealthResponse, ClientError> {
         self.get("/health").await
     }
 }
 
-async fn handle_response<T: DeserializeOwned>(resp: reqwest::Response) -> Result<T, String> {
+async fn handle_response<T: DeserializeOwned>(resp: reqwest::Response) -> Result<T, ClientError> {
     let status = resp.status();
     if status.is_success() {
-        resp.json::<T>().await.map_err(|e| format!("parse error: {e}"))
-    } else {
-        let body = resp
-            .json::<ErrorResponse>()
+        resp.json::<T>()
             .await
-            .map(|e| e.error)
-            .unwrap_or_else(|_| format!("HTTP {status}"));
-        Err(body)
+            .map_err(|e| ClientError::Parse(e.to_string()))
+    } else {
+        Err(api_error(resp).await)
+    }
+}
+
+async fn api_error(resp: reqwest::Response) -> ClientError {
+    let status = resp.status();
+    let body = resp.json::<ErrorResponse>().await.unwrap_or_else(|_| ErrorResponse {
+        error: format!("HTTP {status}"),
+        code: String::new(),
+        request_id: None,
+    });
+    ClientError::Api {
+        status: status.as_u16(),
+        code: body.code,
+        message: body.error,
+        request_id: body.request_id,
     }
 }
@@ -0,0 +1,250 @@
+use colored::Colorize;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use timeforged_core::api::{ActivityBilling, ClientRequest};
+use timeforged_core::models::{
+    ActivityType, Client, ClientProject, RoundingDirection, RoundingScope,
+};
+
+use crate::client::TfClient;
+use crate::commands::{fail, usage};
+
+/// What `tf clients add` and `edit` set on a client. `--projects` replaces
+/// the whole list rather than adding to it.
+#[derive(Default)]
+pub struct Changes {
+    pub name: Option<String>,
+    pub rate: Option<f64>,
+    pub currency: Option<String>,
+    pub round: Option<String>,
+    pub per: Option<String>,
+    pub direction: Option<String>,
+    pub projects: Option<Vec<String>>,
+}
+
+impl Changes {
+    fn apply(self, req: &mut ClientRequest) -> Result<(), String> {
+        if let Some(name) = self.name {
+            req.name = name;
+        }
+        req.hourly_rate = self.rate.unwrap_or(req.hourly_rate);
+        if let Some(currency) = self.currency {
+            req.currency = currency;
+        }
+        if let Some(round) = self.round {
+            req.rounding.minutes = parse_minutes(&round)?;
+        }
+        if let Some(per) = self.per {
+            req.rounding.per = match per.trim() {
+                "session" => RoundingScope::Session,
+                "day" => RoundingScope::Day,
+                _ => return Err(format!("invalid --per '{per}', expected session or day")),
+            };
+        }
+        if let Some(direction) = self.direction {
+            req.rounding.direction = match direction.trim() {
+                "up" => RoundingDirection::Up,
+                "nearest" => RoundingDirection::Nearest,
+                "down" => RoundingDirection::Down,
+                _ => {
+                    return Err(format!(
+                        "invalid --direction '{direction}', expected up, nearest or down"
+                    ));
+                }
+            };
+        }
+        if let Some(projects) = self.projects {
+            req.projects = parse_projects(&projects)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a rounding increment like `15m`, `1h`, `6` (minutes) or `0` (none).
+pub fn parse_minutes(spec: &str) -> Result<u32, String> {
+    let spec = spec.trim();
+    let invalid = || format!("invalid rounding '{spec}', expected e.g. 6m, 15m or 1h");
+    let (number, factor) = match spec.strip_suffix('h') {
+        Some(hours) => (hours, 60),
+        None => (spec.strip_suffix('m').unwrap_or(spec), 1),
+    };
+    let value: u32 = number.trim().parse().map_err(|_| invalid())?;
+    value.checked_mul(factor).ok_or_else(invalid)
+}
+
+/// Parse `--projects site,audit=120`: projects, some at their own rate.
+pub fn parse_projects(specs: &[String]) -> Result<Vec<ClientProject>, String> {
+    specs
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|spec| match spec.split_once('=') {
+            Some((name, rate)) => {
+                let rate = rate.trim().parse().map_err(|_| {
+                    format!("invalid rate in '{spec}', expected e.g. {}=120", name.trim())
+                })?;
+                Ok(ClientProject { name: name.trim().to_string(), hourly_rate: Some(rate) })
+            }
+            None => Ok(ClientProject { name: spec.to_string(), hourly_rate: None }),
+        })
+        .collect()
+}
+
+/// `/api/v1/clients/<key>`, with the name or id percent-encoded.
+fn client_path(key: &str) -> String {
+    let mut path = String::from("/api/v1/clients/");
+    for byte in key.trim().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                path.push(byte as char)
+            }
+            _ => path += &format!("%{byte:02X}"),
+        }
+    }
+    path
+}
+
+fn parse_activity(name: &str) -> Result<ActivityType, String> {
+    ActivityType::ALL.into_iter().find(|a| a.as_str() == name.trim()).ok_or_else(|| {
+        let known: Vec<&str> = ActivityType::ALL.iter().map(|a| a.as_str()).collect();
+        format!("unknown activity '{name}', expected one of {}", known.join(", "))
+    })
+}
+
+fn projects(client: &Client) -> String {
+    let names: Vec<String> = client
+        .projects
+        .iter()
+        .map(|p| match p.hourly_rate {
+            Some(rate) => format!("{} ({rate})", p.name),
+            None => p.name.clone(),
+        })
+        .collect();
+    names.join(", ")
+}
+
+fn print_client(client: &Client) {
+    println!("  Rate:     {} {}/h", client.hourly_rate, client.currency);
+    println!("  Rounding: {}", client.rounding.label());
+    if client.projects.is_empty() {
+        println!("  Projects: {}", "none yet; add them with --projects".dimmed());
+    } else {
+        println!("  Projects: {}", projects(client));
+    }
+}
+
+pub async fn add(client: &TfClient, name: &str, changes: Changes) {
+    let mut req = ClientRequest {
+        name: name.to_string(),
+        hourly_rate: 0.0,
+        currency: "EUR".into(),
+        rounding: Default::default(),
+        projects: Vec::new(),
+    };
+    changes.apply(&mut req).unwrap_or_else(|e| usage(e));
+
+    match client.post::<Client, _>("/api/v1/clients", &req).await {
+        Ok(added) => {
+            println!("{} client {} added", "✓".green().bold(), added.name.bold());
+            print_client(&added);
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// Change the settings given and keep the others.
+pub async fn edit(client: &TfClient, key: &str, changes: Changes) {
+    let path = client_path(key);
+    let existing = client.get::<Client>(&path).await.unwrap_or_else(|e| fail(e));
+    let mut req = ClientRequest {
+        name: existing.name,
+        hourly_rate: existing.hourly_rate,
+        currency: existing.currency,
+        rounding: existing.rounding,
+        projects: existing.projects,
+    };
+    changes.apply(&mut req).unwrap_or_else(|e| usage(e));
+
+    match client.put::<Client, _>(&path, &req).await {
+        Ok(updated) => {
+            println!("{} client {} updated", "✓".green().bold(), updated.name.bold());
+            print_client(&updated);
+        }
+        Err(e) => fail(e),
+    }
+}
+
+pub async fn list(client: &TfClient) {
+    let clients = client.get::<Vec<Client>>("/api/v1/clients").await.unwrap_or_else(|e| fail(e));
+    if clients.is_empty() {
+        println!("No clients yet.");
+        println!("Add one with {}", "tf clients add Acme --rate 80 --projects site".bold());
+        return;
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(vec!["Client", "Rate", "Rounding", "Projects"]);
+    for c in &clients {
+        table.add_row(vec![
+            c.name.clone(),
+            format!("{} {}/h", c.hourly_rate, c.currency),
+            c.rounding.label(),
+            projects(c),
+        ]);
+    }
+    println!("{table}");
+}
+
+pub async fn remove(client: &TfClient, key: &str) {
+    let path = client_path(key);
+    match client.delete(&path).await {
+        Ok(()) => println!("{} client {key} removed", "✓".green().bold()),
+        Err(e) => fail(e),
+    }
+}
+
+/// Mark activity types billable or not, then show them all.
+pub async fn activities(client: &TfClient, billable: &[String], not_billable: &[String]) {
+    let mut changes = Vec::new();
+    for (names, billable) in [(billable, true), (not_billable, false)] {
+        for name in names.iter().filter(|n| !n.trim().is_empty()) {
+            let activity = parse_activity(name).unwrap_or_else(|e| usage(e));
+            changes.push(ActivityBilling { activity, billable });
+        }
+    }
+
+    let path = "/api/v1/billing/activities";
+    let activities = if changes.is_empty() {
+        client.get::<Vec<ActivityBilling>>(path).await
+    } else {
+        client.put::<Vec<ActivityBilling>, _>(path, &changes).await
+    };
+    for a in activities.unwrap_or_else(|e| fail(e)) {
+        let mark = if a.billable { "✓".green().bold() } else { "✗".red() };
+        println!("{mark} {}", a.activity.as_str());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounding_and_project_rates_parse() {
+        assert_eq!(parse_minutes("15m"), Ok(15));
+        assert_eq!(parse_minutes("6"), Ok(6));
+        assert_eq!(parse_minutes("1h"), Ok(60));
+        assert!(parse_minutes("quarter").is_err());
+
+        let projects = parse_projects(&["site".into(), " audit = 120 ".into(), "".into()]);
+        let projects = projects.unwrap();
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0], ClientProject { name: "site".into(), hourly_rate: None });
+        assert_eq!(projects[1], ClientProject { name: "audit".into(), hourly_rate: Some(120.0) });
+        assert!(parse_projects(&["audit=lots".into()]).is_err());
+        assert!(parse_activity("meetings").is_err());
+        assert_eq!(client_path("Acme Corp"), "/api/v1/clients/Acme%20Corp");
+        assert_eq!(client_path("Café"), "/api/v1/clients/Caf%C3%A9");
+    }
+}
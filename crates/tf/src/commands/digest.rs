@@ -0,0 +1,181 @@
+use chrono::Weekday;
+use colored::Colorize;
+
+use timeforged_core::api::{DigestPeriod, DigestSent, DigestSettings};
+
+use crate::client::{ClientError, TfClient};
+use crate::commands::{fail, usage};
+
+/// Flags of `tf digest`. With none given it only shows the settings;
+/// otherwise they are fetched, patched and put back.
+#[derive(Default)]
+pub struct Changes {
+    pub enabled: Option<bool>,
+    pub period: Option<String>,
+    pub hour: Option<u8>,
+    pub weekday: Option<String>,
+    pub email: Option<String>,
+    pub directory: Option<bool>,
+}
+
+impl Changes {
+    fn is_empty(&self) -> bool {
+        self.enabled.is_none()
+            && self.period.is_none()
+            && self.hour.is_none()
+            && self.weekday.is_none()
+            && self.email.is_none()
+            && self.directory.is_none()
+    }
+
+    fn apply(self, settings: &mut DigestSettings) -> Result<(), String> {
+        if let Some(period) = self.period {
+            settings.period = parse_period(&period)?;
+        }
+        if let Some(weekday) = self.weekday {
+            settings.weekday = weekday
+                .trim()
+                .parse::<Weekday>()
+                .map_err(|_| format!("unknown weekday '{weekday}'"))?;
+        }
+        settings.enabled = self.enabled.unwrap_or(settings.enabled);
+        settings.hour = self.hour.unwrap_or(settings.hour);
+        settings.directory = self.directory.unwrap_or(settings.directory);
+        if let Some(email) = self.email {
+            settings.email = Some(email).filter(|e| !e.trim().is_empty());
+        }
+        Ok(())
+    }
+}
+
+fn parse_period(period: &str) -> Result<DigestPeriod, String> {
+    DigestPeriod::parse(period.trim())
+        .ok_or_else(|| format!("invalid period '{period}', expected daily or weekly"))
+}
+
+fn print_settings(settings: &DigestSettings) {
+    let (mark, state) = if settings.enabled {
+        ("✓".green().bold(), "on")
+    } else {
+        ("•".dimmed(), "off")
+    };
+    println!("{mark} Scheduled digests {}", state.bold());
+    let when = match settings.period {
+        DigestPeriod::Daily => format!("Daily at {:02}:00 UTC", settings.hour),
+        DigestPeriod::Weekly => {
+            format!("Weekly, {}s at {:02}:00 UTC", full_name(settings.weekday), settings.hour)
+        }
+    };
+    println!("  {when}");
+    let files = if settings.directory { "the daemon's digest directory" } else { "off" };
+    println!("  Files:    {files}");
+    println!("  Email:    {}", settings.email.as_deref().unwrap_or("off"));
+    println!("  Webhooks: {}", "tf webhooks add <url> --events digest".dimmed());
+}
+
+fn full_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// Show the digest settings, changing them first if asked to.
+pub async fn settings(client: &TfClient, changes: Changes) {
+    let mut settings = client
+        .get::<DigestSettings>("/api/v1/me/digest")
+        .await
+        .unwrap_or_else(|e| fail(e));
+    if !changes.is_empty() {
+        changes.apply(&mut settings).unwrap_or_else(|e| usage(e));
+        settings = client
+            .put::<DigestSettings, _>("/api/v1/me/digest", &settings)
+            .await
+            .unwrap_or_else(|e| fail(e));
+    }
+    print_settings(&settings);
+}
+
+/// Print the digest through yesterday, rendered as it is delivered.
+pub async fn preview(client: &TfClient, period: Option<&str>, html: bool) {
+    let format = if html { "html" } else { "markdown" };
+    let mut query = vec![("format", format)];
+    if let Some(period) = period {
+        let period = parse_period(period).unwrap_or_else(|e| usage(e));
+        query.push(("period", period.as_str()));
+    }
+    let resp = client.get_stream("/api/v1/digest", &query).await.unwrap_or_else(|e| fail(e));
+    match resp.text().await {
+        Ok(text) => print!("{text}"),
+        Err(e) => fail(ClientError::Request(e.to_string())),
+    }
+}
+
+/// Deliver the digest through yesterday now.
+pub async fn send(client: &TfClient) {
+    let sent = client
+        .post::<DigestSent, _>("/api/v1/digest/send", &serde_json::json!({}))
+        .await
+        .unwrap_or_else(|e| fail(e));
+    for file in &sent.files {
+        println!("{} wrote {file}", "✓".green().bold());
+    }
+    if let Some(to) = &sent.emailed {
+        println!("{} emailed {to}", "✓".green().bold());
+    }
+    if sent.webhooks > 0 {
+        println!("{} queued {} webhook deliveries", "✓".green().bold(), sent.webhooks);
+    }
+    if sent.files.is_empty() && sent.emailed.is_none() && sent.webhooks == 0 {
+        println!("Nowhere to send it: turn on --directory, set --email or add a digest webhook.");
+    }
+    for error in &sent.errors {
+        eprintln!("{} {error}", "error:".red().bold());
+    }
+    if !sent.errors.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_keep_what_is_not_given() {
+        let mut settings = DigestSettings {
+            email: Some("dev@example.com".into()),
+            ..Default::default()
+        };
+        let changes = Changes {
+            enabled: Some(true),
+            period: Some("daily".into()),
+            hour: Some(18),
+            ..Default::default()
+        };
+        changes.apply(&mut settings).unwrap();
+        assert!(settings.enabled);
+        assert_eq!((settings.period, settings.hour), (DigestPeriod::Daily, 18));
+        assert_eq!(settings.email.as_deref(), Some("dev@example.com"));
+        assert!(settings.directory);
+
+        let stop_email = Changes {
+            email: Some("".into()),
+            weekday: Some("fri".into()),
+            ..Default::default()
+        };
+        stop_email.apply(&mut settings).unwrap();
+        assert_eq!((settings.email, settings.weekday), (None, Weekday::Fri));
+
+        let bad = Changes {
+            period: Some("monthly".into()),
+            ..Default::default()
+        };
+        assert!(bad.apply(&mut DigestSettings::default()).is_err());
+    }
+}
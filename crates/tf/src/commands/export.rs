@@ -0,0 +1,98 @@
+use std::io::Write;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use colored::Colorize;
+
+use timeforged_core::interchange::ExportFormat;
+
+use crate::client::TfClient;
+use crate::commands::usage;
+
+/// `--from`/`--to`: an RFC 3339 time, or a date. As an end, a date
+/// includes that whole day.
+pub fn parse_bound(s: &str, end: bool) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+        return Some(at.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    let day = if end { day.succ_opt()? } else { day };
+    Some(day.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+/// Stream every event in range to `output` (stdout by default).
+pub async fn run(
+    client: &TfClient,
+    format: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+    output: Option<&str>,
+) {
+    let format = ExportFormat::parse(format)
+        .unwrap_or_else(|| usage(format!("invalid format '{format}', expected jsonl or csv")));
+    let bound = |s: &str, end| {
+        let invalid = || usage(format!("invalid date '{s}', expected YYYY-MM-DD or RFC 3339"));
+        parse_bound(s, end).unwrap_or_else(invalid).to_rfc3339()
+    };
+    let mut query = vec![("format", format.as_str().to_string())];
+    if let Some(from) = from {
+        query.push(("from", bound(from, false)));
+    }
+    if let Some(to) = to {
+        query.push(("to", bound(to, true)));
+    }
+    let query: Vec<(&str, &str)> = query.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+    let mut resp = match client.get_stream("/api/v1/events/export", &query).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            eprintln!("{}: {e}", "error".red());
+            e.print_hint();
+            std::process::exit(1);
+        }
+    };
+
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => match std::fs::File::create(path) {
+            Ok(file) => Box::new(std::io::BufWriter::new(file)),
+            Err(e) => usage(format!("could not create {path}: {e}")),
+        },
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut bytes = 0;
+    loop {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => {
+                bytes += chunk.len();
+                if let Err(e) = out.write_all(&chunk) {
+                    usage(format!("could not write the export: {e}"));
+                }
+            }
+            Ok(None) => break,
+            // The server stops mid-stream if reading the database fails
+            Err(e) => usage(format!("export interrupted: {e}")),
+        }
+    }
+    if let Err(e) = out.flush() {
+        usage(format!("could not write the export: {e}"));
+    }
+
+    if let Some(path) = output {
+        eprintln!("{} exported to {} ({} KiB)", "✓".green(), path.bold(), bytes.div_ceil(1024));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_accept_dates_and_times() {
+        let from = parse_bound("2026-03-01", false).unwrap();
+        assert_eq!(from.to_rfc3339(), "2026-03-01T00:00:00+00:00");
+        let to = parse_bound("2026-03-01", true).unwrap();
+        assert_eq!(to.to_rfc3339(), "2026-03-02T00:00:00+00:00");
+        let at = parse_bound("2026-03-01T10:30:00+02:00", true).unwrap();
+        assert_eq!(at.to_rfc3339(), "2026-03-01T08:30:00+00:00");
+        assert!(parse_bound("last week", false).is_none());
+    }
+}
@@ -0,0 +1,64 @@
+use colored::Colorize;
+
+use timeforged_core::config::CliConfig;
+use timeforged_sync::spool::FlushReport;
+use timeforged_sync::{HttpEndpoint, Spool};
+
+fn daemon(config: &CliConfig) -> HttpEndpoint {
+    HttpEndpoint::new(&config.server_url, config.api_key.clone(), "local")
+}
+
+/// Deliver the events queued while the daemon was unreachable.
+pub async fn run(config: &CliConfig) {
+    let spool = Spool::open();
+    let pending = spool.pending();
+    if pending == 0 {
+        println!("Nothing queued.");
+        return;
+    }
+
+    println!("{}", format!("Flushing {pending} queued events...").dimmed());
+    match spool.flush(&daemon(config)).await {
+        Ok(report) => {
+            println!(
+                "{} {} events ({} already there, {} rejected)",
+                "Delivered".green(),
+                report.accepted.to_string().cyan(),
+                report.duplicates,
+                report.rejected,
+            );
+            print_pending(&report);
+        }
+        Err(e) => {
+            eprintln!("{}: {e}", "error".red());
+            eprintln!("  {} {} events still queued", "hint:".yellow(), spool.pending());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// An event just got through, so the daemon is back: send what waited.
+/// Failures leave the queue for next time.
+pub async fn after_send(config: &CliConfig) {
+    let spool = Spool::open();
+    if spool.pending() == 0 {
+        return;
+    }
+    match spool.flush(&daemon(config)).await {
+        Ok(report) => {
+            println!(
+                "{} {} queued events",
+                "Delivered".green(),
+                report.accepted + report.duplicates
+            );
+            print_pending(&report);
+        }
+        Err(e) => eprintln!("{}: could not flush queued events: {e}", "warning".yellow()),
+    }
+}
+
+fn print_pending(report: &FlushReport) {
+    if report.pending > 0 {
+        println!("{} events queued meanwhile, run `tf flush` again", report.pending);
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::Weekday;
+use colored::Colorize;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use timeforged_core::api::{CreateGoalRequest, GoalProgress};
+use timeforged_core::models::{Goal, GoalPeriod};
+
+use crate::client::TfClient;
+use crate::commands::{fail, usage};
+
+/// "1h 05m", "45m"
+fn hm(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).floor() as u64;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m:02}m"),
+    }
+}
+
+/// Parse a target like `2h/day`, `90m/day` or `1h30m/week`.
+pub fn parse_target(spec: &str) -> Result<(GoalPeriod, u64), String> {
+    let invalid = || format!("invalid target '{spec}', expected e.g. 2h/day or 10h/week");
+    let (amount, period) = spec.trim().split_once('/').ok_or_else(invalid)?;
+    let period = GoalPeriod::parse(period.trim()).ok_or_else(invalid)?;
+
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for c in amount.trim().chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' if !number.is_empty() => {
+                let value: f64 = number.parse().map_err(|_| invalid())?;
+                seconds += value * if c == 'h' { 3600.0 } else { 60.0 };
+                number.clear();
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || seconds < 1.0 {
+        return Err(invalid());
+    }
+    Ok((period, seconds.round() as u64))
+}
+
+/// Parse `--rest sat,sun`.
+pub fn parse_rest_days(days: &[String]) -> Result<Vec<Weekday>, String> {
+    days.iter()
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse().map_err(|_| format!("unknown weekday '{d}'")))
+        .collect()
+}
+
+fn progress_bar(percent: f64) -> String {
+    const WIDTH: usize = 20;
+    let filled = ((percent / 100.0).clamp(0.0, 1.0) * WIDTH as f64).round() as usize;
+    format!("{}{}", "█".repeat(filled), "░".repeat(WIDTH - filled))
+}
+
+fn streak(progress: &GoalProgress) -> String {
+    let unit = progress.goal.period.as_str();
+    let n = progress.streak.current;
+    let unit = if n == 1 { unit.to_string() } else { format!("{unit}s") };
+    format!("streak {n} {unit} (best {})", progress.streak.longest)
+}
+
+pub async fn add(
+    client: &TfClient,
+    target: &str,
+    project: Option<String>,
+    language: Option<String>,
+    rest: &[String],
+) {
+    let (period, target_seconds) = parse_target(target).unwrap_or_else(|e| usage(e));
+    let rest_days = parse_rest_days(rest).unwrap_or_else(|e| usage(e));
+    let req = CreateGoalRequest {
+        period,
+        target_seconds,
+        project,
+        language,
+        rest_days,
+    };
+
+    match client.post::<Goal, _>("/api/v1/goals", &req).await {
+        Ok(goal) => {
+            println!("{} goal {} added: {}", "✓".green().bold(), goal.id, goal.label().bold());
+            if !goal.rest_days.is_empty() {
+                let days: Vec<String> = goal.rest_days.iter().map(|d| d.to_string()).collect();
+                println!("Rest days: {}", days.join(", "));
+            }
+        }
+        Err(e) => fail(e),
+    }
+}
+
+pub async fn list(client: &TfClient) {
+    let goals = client
+        .get_with_query::<Vec<GoalProgress>>("/api/v1/goals", &[("history", "0")])
+        .await
+        .unwrap_or_else(|e| fail(e));
+
+    if goals.is_empty() {
+        println!("No goals yet.");
+        println!("Add one with {}", "tf goals add 2h/day".bold());
+        return;
+    }
+
+    for progress in &goals {
+        let mark = if progress.current.met { "✓".green().bold() } else { "•".dimmed() };
+        println!("{mark} {}  {}", progress.label.bold(), progress.goal.id.to_string().dimmed());
+        let done = hm(progress.current.seconds);
+        let target = hm(progress.goal.target_seconds as f64);
+        let bar = progress_bar(progress.percent);
+        let bar = if progress.current.met { bar.green() } else { bar.yellow() };
+        println!("  {bar} {:>3.0}%  {done} / {target}", progress.percent.floor());
+        println!("  {}", streak(progress).dimmed());
+    }
+}
+
+pub async fn history(client: &TfClient, id: &str, periods: usize) {
+    let path = format!("/api/v1/goals/{id}");
+    let periods = periods.to_string();
+    let progress = client
+        .get_with_query::<GoalProgress>(&path, &[("history", &periods)])
+        .await
+        .unwrap_or_else(|e| fail(e));
+
+    println!("{}  {}", progress.label.bold(), streak(&progress).dimmed());
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    let first_column = match progress.goal.period {
+        GoalPeriod::Day => "Day",
+        GoalPeriod::Week => "Week of",
+    };
+    table.set_header(vec![first_column, "Time", "Met"]);
+    let rows = progress.history.iter().chain([&progress.current]).rev();
+    for (i, period) in rows.enumerate() {
+        let met = match (period.met, period.rest) {
+            (true, _) => "yes".green().to_string(),
+            (false, _) if i == 0 => "in progress".yellow().to_string(),
+            (false, true) => "rest day".dimmed().to_string(),
+            (false, false) => "no".red().to_string(),
+        };
+        table.add_row(vec![
+            period.start.format("%a %Y-%m-%d").to_string(),
+            hm(period.seconds),
+            met,
+        ]);
+    }
+    println!("{table}");
+}
+
+pub async fn remove(client: &TfClient, id: &str) {
+    match client.delete(&format!("/api/v1/goals/{id}")).await {
+        Ok(()) => println!("{} goal {id} removed", "✓".green().bold()),
+        Err(e) => fail(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_read_like_the_goal() {
+        assert_eq!(parse_target("2h/day").unwrap(), (GoalPeriod::Day, 7200));
+        assert_eq!(parse_target("1h30m/week").unwrap(), (GoalPeriod::Week, 5400));
+        assert_eq!(parse_target("1.5h/day").unwrap(), (GoalPeriod::Day, 5400));
+        assert_eq!(parse_target(" 45m/day ").unwrap(), (GoalPeriod::Day, 2700));
+        for bad in ["2h", "2/day", "2h/month", "h/day", "0m/day", "2x/day"] {
+            assert!(parse_target(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn rest_days_are_weekday_names() {
+        let days = parse_rest_days(&["sat".into(), " Sunday".into(), "".into()]).unwrap();
+        assert_eq!(days, [Weekday::Sat, Weekday::Sun]);
+        assert!(parse_rest_days(&["someday".into()]).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::io::{BufReader, Read};
+
+use colored::Colorize;
+
+use timeforged_core::api::{ImportError, ImportResponse, MAX_IMPORT_LINES};
+use timeforged_core::interchange::{self, ExportFormat, Parsed};
+
+use crate::client::TfClient;
+use crate::commands::usage;
+
+/// Errors kept to list after the summary; the earliest lines are shown.
+const MAX_KEPT_ERRORS: usize = 100;
+const MAX_SHOWN_ERRORS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Export(ExportFormat),
+    WakaTime,
+}
+
+impl Format {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "wakatime" => Some(Self::WakaTime),
+            _ => ExportFormat::parse(s).map(Self::Export),
+        }
+    }
+
+    /// `.json` is taken to be a WakaTime dump: our own exports are `.jsonl`.
+    fn from_path(path: &str) -> Option<Self> {
+        match path.rsplit_once('.')?.1.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::WakaTime),
+            ext => ExportFormat::parse(ext).map(Self::Export),
+        }
+    }
+}
+
+/// Events read from the file, with each one's line (or heartbeat number).
+fn read(format: Format, input: Box<dyn Read>) -> Result<Box<dyn Iterator<Item = Parsed>>, String> {
+    Ok(match format {
+        Format::Export(ExportFormat::Jsonl) => {
+            Box::new(interchange::read_jsonl(BufReader::new(input)))
+        }
+        Format::Export(ExportFormat::Csv) => Box::new(interchange::read_csv(input)?),
+        Format::WakaTime => Box::new(interchange::read_wakatime(input)?.into_iter()),
+    })
+}
+
+/// Events ready to send, and where in the file each came from.
+#[derive(Default)]
+struct Chunk {
+    body: String,
+    lines: Vec<usize>,
+}
+
+#[derive(Default)]
+struct Summary {
+    read: usize,
+    resp: ImportResponse,
+}
+
+impl Summary {
+    fn unreadable(&mut self, line: usize, error: String) {
+        self.resp.rejected += 1;
+        self.error(ImportError { line, error });
+    }
+
+    fn error(&mut self, error: ImportError) {
+        if self.resp.errors.len() < MAX_KEPT_ERRORS {
+            self.resp.errors.push(error);
+        }
+    }
+
+    /// The server numbers lines within the request; map them back to the file.
+    fn add(&mut self, chunk: &Chunk, resp: ImportResponse) {
+        self.resp.accepted += resp.accepted;
+        self.resp.duplicates += resp.duplicates;
+        self.resp.rejected += resp.rejected;
+        for e in resp.errors {
+            let line = chunk.lines.get(e.line.wrapping_sub(1)).copied().unwrap_or_default();
+            self.error(ImportError { line, ..e });
+        }
+    }
+}
+
+/// Import a file of events; `-` reads stdin. Events already stored are
+/// skipped by the daemon, so a file can be imported again after an
+/// interruption.
+pub async fn run(client: &TfClient, path: &str, format: Option<&str>, dry_run: bool) {
+    let format = match format {
+        Some(f) => Format::parse(f).unwrap_or_else(|| {
+            usage(format!("invalid format '{f}', expected jsonl, csv or wakatime"))
+        }),
+        None => Format::from_path(path)
+            .unwrap_or_else(|| usage("can't tell the format from the file name; pass --format")),
+    };
+    let input: Box<dyn Read> = if path == "-" {
+        Box::new(std::io::stdin())
+    } else {
+        match std::fs::File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => usage(format!("could not open {path}: {e}")),
+        }
+    };
+    let events = read(format, input).unwrap_or_else(|e| usage(e));
+
+    let mut summary = Summary::default();
+    let mut chunk = Chunk::default();
+    for (line, parsed) in events {
+        summary.read += 1;
+        match parsed.and_then(|e| serde_json::to_string(&e).map_err(|e| e.to_string())) {
+            Ok(json) => {
+                chunk.body.push_str(&json);
+                chunk.body.push('\n');
+                chunk.lines.push(line);
+            }
+            Err(e) => summary.unreadable(line, e),
+        }
+        if chunk.lines.len() == MAX_IMPORT_LINES {
+            send(client, &mut chunk, &mut summary, dry_run).await;
+            eprintln!("{}", format!("{} events read...", summary.read).dimmed());
+        }
+    }
+    if !chunk.lines.is_empty() {
+        send(client, &mut chunk, &mut summary, dry_run).await;
+    }
+
+    print_summary(&summary, dry_run);
+}
+
+async fn send(client: &TfClient, chunk: &mut Chunk, summary: &mut Summary, dry_run: bool) {
+    if !dry_run {
+        let path = "/api/v1/events/import";
+        match client.post_body::<ImportResponse>(path, "application/x-ndjson", &chunk.body).await {
+            Ok(resp) => summary.add(chunk, resp),
+            Err(e) => {
+                eprintln!("{}: {e}", "error".red());
+                e.print_hint();
+                let done = summary.resp.accepted + summary.resp.duplicates;
+                eprintln!(
+                    "  {} {done} events were stored; rerun the import to finish, \
+                     they won't be imported twice",
+                    "hint:".yellow()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    *chunk = Chunk::default();
+}
+
+fn print_summary(summary: &Summary, dry_run: bool) {
+    let resp = &summary.resp;
+    if dry_run {
+        println!(
+            "{} {} events read, {} unreadable; nothing imported",
+            "Dry run:".bold(),
+            summary.read.to_string().cyan(),
+            resp.rejected,
+        );
+    } else {
+        println!(
+            "{} {} events ({} already there, {} rejected) out of {} read",
+            "Imported".green(),
+            resp.accepted.to_string().cyan(),
+            resp.duplicates,
+            resp.rejected,
+            summary.read,
+        );
+    }
+
+    let mut errors = resp.errors.clone();
+    errors.sort_by_key(|e| e.line);
+    errors.truncate(MAX_SHOWN_ERRORS);
+    for e in &errors {
+        println!("  {} line {}: {}", "!".red(), e.line, e.error);
+    }
+    if resp.rejected > errors.len() {
+        println!("  {}", format!("... and {} more", resp.rejected - errors.len()).dimmed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_from_flag_or_extension() {
+        assert_eq!(Format::parse("wakatime"), Some(Format::WakaTime));
+        assert_eq!(Format::parse("csv"), Some(Format::Export(ExportFormat::Csv)));
+        assert_eq!(Format::parse("xml"), None);
+
+        assert_eq!(Format::from_path("wakatime-dev.json"), Some(Format::WakaTime));
+        assert_eq!(Format::from_path("events.JSONL"), Some(Format::Export(ExportFormat::Jsonl)));
+        assert_eq!(Format::from_path("events.ndjson"), Some(Format::Export(ExportFormat::Jsonl)));
+        assert_eq!(Format::from_path("events"), None);
+    }
+
+    #[test]
+    fn server_errors_point_at_file_lines() {
+        let chunk = Chunk {
+            body: String::new(),
+            lines: vec![2, 5, 9],
+        };
+        let mut summary = Summary::default();
+        summary.unreadable(3, "bad".into());
+        summary.add(
+            &chunk,
+            ImportResponse {
+                accepted: 2,
+                duplicates: 0,
+                rejected: 1,
+                errors: vec![ImportError {
+                    line: 2,
+                    error: "entity cannot be empty".into(),
+                }],
+            },
+        );
+        let lines: Vec<_> = summary.resp.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [3, 5]);
+        assert_eq!((summary.resp.accepted, summary.resp.rejected), (2, 2));
+    }
+}
@@ -4,7 +4,7 @@ use colored::Colorize;
 
 use timeforged_core::api::{WatchActionResponse, WatchRequest};
 
-use crate::client::TfClient;
+use crate::client::{ClientError, TfClient};
 
 pub async fn run(client: &TfClient, path: &str) {
     let canonical = match std::fs::canonicalize(path) {
@@ -64,7 +64,7 @@ pub async fn run(client: &TfClient, path: &str) {
             );
         }
         Err(e) => {
-            if e.contains("connection refused") || e.contains("request failed") {
+            if matches!(e, ClientError::Request(_)) {
                 eprintln!(
                     "{} daemon is not running. Start it with: {}",
                     "warning:".yellow().bold(),
@@ -72,6 +72,7 @@ pub async fn run(client: &TfClient, path: &str) {
                 );
             } else {
                 eprintln!("{} {e}", "error:".red().bold());
+                e.print_hint();
             }
             std::process::exit(1);
         }
@@ -83,12 +84,11 @@ fn discover_projects(dir: &Path) -> Vec<String> {
     if let Ok(entries) = std::fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_dir() {
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if !name.starts_with('.') {
-                        projects.push(name.to_string());
-                    }
-                }
+            if path.is_dir()
+                && let Some(name) = path.file_name().and_then(|n| n.to_str())
+                && !name.starts_with('.')
+            {
+                projects.push(name.to_string());
             }
         }
     }
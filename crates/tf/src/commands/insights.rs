@@ -0,0 +1,160 @@
+use chrono::{Duration, Utc};
+use colored::Colorize;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use timeforged_core::api::{HourInsight, Insights, UnusualKind};
+
+use crate::client::TfClient;
+
+/// "1h 05m", "45m"
+fn hm(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as u64;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m:02}m"),
+    }
+}
+
+fn percent(ratio: f64) -> String {
+    format!("{:.0}%", ratio * 100.0)
+}
+
+/// One block per hour, scaled to the busiest.
+fn sparkline(hours: &[HourInsight]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = hours.iter().map(|h| h.seconds).fold(0.0, f64::max);
+    hours
+        .iter()
+        .map(|h| match h.seconds {
+            s if s <= 0.0 || max <= 0.0 => ' ',
+            s => BLOCKS[((s / max) * 7.0).round() as usize],
+        })
+        .collect()
+}
+
+/// "  Label:            value", lined up.
+fn row(label: &str, value: impl std::fmt::Display) {
+    println!("  {:<18}{value}", format!("{label}:"));
+}
+
+pub async fn run(client: &TfClient, days: u32, project: Option<&str>) {
+    let from = (Utc::now() - Duration::days(days as i64)).to_rfc3339();
+    let mut query: Vec<(&str, &str)> = vec![("from", &from)];
+    if let Some(p) = project {
+        query.push(("project", p));
+    }
+
+    match client.get_with_query::<Insights>("/api/v1/insights", &query).await {
+        Ok(insights) => print_insights(&insights, days),
+        Err(e) => {
+            eprintln!("{}: {e}", "error".red());
+            e.print_hint();
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_insights(insights: &Insights, days: u32) {
+    println!("{}", format!("Insights — last {days} days").bold());
+    if insights.active_seconds <= 0.0 {
+        println!("  No activity in this range.");
+        return;
+    }
+    row("Active", hm(insights.active_seconds).green());
+
+    let top: Vec<String> = insights.top_hours.iter().map(|h| format!("{h:02}:00")).collect();
+    row("Best hours", format!("{} (UTC)", top.join(", ")));
+    if let Some(best) = insights.best_weekday {
+        let average = insights
+            .weekdays
+            .iter()
+            .find(|w| w.weekday == best)
+            .map_or(0.0, |w| w.average_seconds);
+        row("Best weekday", format!("{best} (avg {})", hm(average)));
+    }
+
+    let sessions = &insights.sessions;
+    row(
+        "Sessions",
+        format!(
+            "{}, {} on average, longest {}",
+            sessions.count,
+            hm(sessions.average_seconds),
+            hm(sessions.longest_seconds)
+        ),
+    );
+    let switches = &insights.context_switches;
+    row("Context switches", format!("{} ({:.1} per hour)", switches.total, switches.per_hour));
+    let focus = &insights.focus;
+    row(
+        "Focus time",
+        format!(
+            "{} ({}) in stretches of {}+ on one project",
+            hm(focus.seconds),
+            percent(focus.ratio),
+            hm(focus.min_block_seconds as f64)
+        ),
+    );
+    let late = &insights.late_night;
+    let nights = format!(
+        "{} over {} night{} ({})",
+        hm(late.seconds),
+        late.days,
+        if late.days == 1 { "" } else { "s" },
+        percent(late.ratio)
+    );
+    let nights = if late.frequent { nights.yellow() } else { nights.normal() };
+    row("Late nights", nights);
+
+    println!("\n{}", "Hours (UTC)".bold());
+    println!("  {}", sparkline(&insights.hours).cyan());
+    println!("  {}", "0     6     12    18   23".dimmed());
+
+    if sessions.weekly.len() > 1 {
+        println!("\n{}", "Sessions by week".bold());
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL_CONDENSED);
+        table.set_header(vec!["Week of", "Sessions", "Average"]);
+        for week in &sessions.weekly {
+            table.add_row(vec![
+                week.week.format("%Y-%m-%d").to_string(),
+                week.sessions.to_string(),
+                hm(week.average_seconds),
+            ]);
+        }
+        println!("{table}");
+    }
+
+    if !insights.unusual_days.is_empty() {
+        println!("\n{}", "Unusual days".bold());
+        for day in &insights.unusual_days {
+            let (arrow, note) = match day.kind {
+                UnusualKind::High => ("▲".green(), "more than usual"),
+                UnusualKind::Low => ("▼".red(), "less than usual"),
+            };
+            println!(
+                "  {arrow} {}  {} vs {} usually, {note}",
+                day.date.format("%a %Y-%m-%d"),
+                hm(day.seconds),
+                hm(day.baseline_seconds)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_scale_to_the_busiest() {
+        let hours: Vec<HourInsight> = [0.0, 100.0, 50.0, 0.0]
+            .iter()
+            .enumerate()
+            .map(|(hour, seconds)| HourInsight { hour: hour as u8, seconds: *seconds })
+            .collect();
+        assert_eq!(sparkline(&hours), " █▅ ");
+        assert_eq!(hm(3900.0), "1h 05m");
+        assert_eq!(hm(2700.0), "45m");
+    }
+}
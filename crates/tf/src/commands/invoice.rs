@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use colored::Colorize;
+use comfy_table::{Cell, CellAlignment, Table, presets::UTF8_FULL_CONDENSED};
+
+use timeforged_core::api::{Timesheet, TimesheetFormat};
+
+use crate::client::{ClientError, TfClient};
+use crate::commands::{fail, usage};
+
+/// Decimal hours, as on the timesheet: "1.25"
+fn hours(seconds: f64) -> String {
+    format!("{:.2}", seconds / 3600.0)
+}
+
+/// Parse `--format csv,pdf`; JSON is what the table is printed from.
+pub fn parse_formats(formats: &[String]) -> Result<Vec<TimesheetFormat>, String> {
+    let mut parsed = Vec::new();
+    for format in formats.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+        match TimesheetFormat::parse(format) {
+            Some(TimesheetFormat::Json) | None => {
+                return Err(format!("invalid format '{format}', expected csv, html or pdf"));
+            }
+            Some(format) if !parsed.contains(&format) => parsed.push(format),
+            Some(_) => {}
+        }
+    }
+    Ok(parsed)
+}
+
+/// The file name in `attachment; filename="acme-2026-09.pdf"`.
+fn attachment_name(disposition: &str) -> Option<&str> {
+    let (_, name) = disposition.split_once("filename=")?;
+    let name = name.split(';').next()?.trim().trim_matches('"');
+    // Only ever a plain name in the current directory or --out
+    let plain = !name.is_empty() && !name.contains(['/', '\\']) && !name.starts_with('.');
+    plain.then_some(name)
+}
+
+/// Print the timesheet of `client` and write it in each of `formats`.
+pub async fn run(
+    client: &TfClient,
+    key: &str,
+    range: &[(&str, &str)],
+    formats: &[String],
+    out: Option<&str>,
+) {
+    let formats = parse_formats(formats).unwrap_or_else(|e| usage(e));
+    let mut query = vec![("client", key)];
+    query.extend_from_slice(range);
+
+    let path = "/api/v1/billing/timesheet";
+    let sheet = client.get_with_query::<Timesheet>(path, &query).await.unwrap_or_else(|e| fail(e));
+    print_timesheet(&sheet);
+
+    let dir = Path::new(out.unwrap_or("."));
+    if !formats.is_empty()
+        && let Err(e) = std::fs::create_dir_all(dir)
+    {
+        usage(format!("could not create {}: {e}", dir.display()));
+    }
+    for format in formats {
+        let mut query = query.clone();
+        query.push(("format", format.as_str()));
+        let resp = client.get_stream(path, &query).await.unwrap_or_else(|e| fail(e));
+        let fallback = format!("timesheet.{}", format.as_str());
+        let name = resp
+            .headers()
+            .get("content-disposition")
+            .and_then(|v| v.to_str().ok())
+            .and_then(attachment_name)
+            .unwrap_or(&fallback)
+            .to_string();
+        let body = resp.bytes().await.unwrap_or_else(|e| fail(ClientError::Request(e.to_string())));
+        let file = dir.join(name);
+        match std::fs::write(&file, &body) {
+            Ok(()) => println!("{} wrote {}", "✓".green().bold(), file.display()),
+            Err(e) => usage(format!("could not write {}: {e}", file.display())),
+        }
+    }
+}
+
+fn print_timesheet(sheet: &Timesheet) {
+    println!("{}", format!("Timesheet — {}", sheet.client).bold());
+    println!("  {} – {}, rounding {}", sheet.from, sheet.to, sheet.rounding.label());
+    if sheet.lines.is_empty() {
+        println!("  No billable time in this range.");
+        return;
+    }
+
+    let right = |text: String| Cell::new(text).set_alignment(CellAlignment::Right);
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(vec!["Date", "Project", "Tracked", "Billed", "Rate", "Amount"]);
+    for line in &sheet.lines {
+        table.add_row(vec![
+            Cell::new(line.date.format("%a %Y-%m-%d")),
+            Cell::new(&line.project),
+            right(hours(line.tracked_seconds)),
+            right(hours(line.billed_seconds)),
+            right(format!("{:.2}", line.hourly_rate)),
+            right(format!("{:.2}", line.amount)),
+        ]);
+    }
+    table.add_row(vec![
+        Cell::new("Total"),
+        Cell::new(""),
+        right(hours(sheet.tracked_seconds)),
+        right(hours(sheet.billed_seconds)),
+        Cell::new(""),
+        right(format!("{:.2} {}", sheet.amount, sheet.currency)),
+    ]);
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_file_names_parse() {
+        let formats = parse_formats(&["csv".into(), "pdf".into(), "csv".into()]).unwrap();
+        assert_eq!(formats, [TimesheetFormat::Csv, TimesheetFormat::Pdf]);
+        assert!(parse_formats(&["json".into()]).is_err());
+        assert!(parse_formats(&["docx".into()]).is_err());
+
+        let header = "attachment; filename=\"acme-2026-09.pdf\"";
+        assert_eq!(attachment_name(header), Some("acme-2026-09.pdf"));
+        assert_eq!(attachment_name("attachment; filename=\"../x.pdf\""), None);
+        assert_eq!(attachment_name("inline"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use colored::Colorize;
 
-use timeforged_core::api::HealthResponse;
+use timeforged_core::api::{HealthResponse, SyncMode};
 use timeforged_core::config::{CliConfig, config_dir};
 
 use crate::client::TfClient;
@@ -12,6 +12,7 @@ pub async fn run(remote_key: &str, remote_url: &str) {
         api_key: Some(remote_key.to_string()),
         remote_url: None,
         remote_key: None,
+        remotes: Vec::new(),
     };
     let remote = TfClient::new(&remote_config);
 
@@ -23,6 +24,7 @@ pub async fn run(remote_key: &str, remote_url: &str) {
         Err(e) => {
             println!("{}", "failed".red());
             eprintln!("{}: could not reach remote: {e}", "error".red());
+            e.print_hint();
             std::process::exit(1);
         }
     }
@@ -33,20 +35,7 @@ pub async fn run(remote_key: &str, remote_url: &str) {
     config.remote_url = Some(remote_url.to_string());
     config.remote_key = Some(remote_key.to_string());
 
-    let content = format!(
-        "server_url = \"{}\"\n{}\nremote_url = \"{}\"\nremote_key = \"{}\"\n",
-        config.server_url,
-        config
-            .api_key
-            .as_ref()
-            .map(|k| format!("api_key = \"{}\"", k))
-            .unwrap_or_default(),
-        remote_url,
-        remote_key,
-    );
-
-    std::fs::create_dir_all(config_dir()).ok();
-    if let Err(e) = std::fs::write(&config_path, &content) {
+    if let Err(e) = config.save() {
         eprintln!("{}: failed to write config: {e}", "error".red());
         std::process::exit(1);
     }
@@ -55,19 +44,11 @@ pub async fn run(remote_key: &str, remote_url: &str) {
     // 3. Run initial sync
     println!();
     println!("Running initial sync...");
-    let local = TfClient::new(&config);
-    let remote_for_sync = CliConfig {
-        server_url: remote_url.to_string(),
-        api_key: Some(remote_key.to_string()),
-        remote_url: None,
-        remote_key: None,
-    };
-    let remote_client = TfClient::new(&remote_for_sync);
-    super::sync::run(&local, &remote_client).await;
+    super::sync::run(&config, SyncMode::Both, None).await;
 
     println!();
     println!(
-        "{} This machine is now linked! Auto-sync will push events every 5 minutes.",
+        "{} This machine is now linked! Auto-sync will push and pull events every 5 minutes.",
         "✓".green()
     );
 }
@@ -32,6 +32,7 @@ pub async fn run(client: &TfClient) {
         }
         Err(e) => {
             eprintln!("{} {e}", "error:".red().bold());
+            e.print_hint();
             std::process::exit(1);
         }
     }
@@ -1,11 +1,39 @@
+pub mod clients;
+pub mod digest;
+pub mod export;
+pub mod flush;
+pub mod goals;
+pub mod import;
 pub mod init;
+pub mod insights;
+pub mod invoice;
 pub mod link;
 pub mod list;
 pub mod profile;
 pub mod register;
+pub mod remote;
 pub mod report;
 pub mod send;
 pub mod status;
 pub mod sync;
 pub mod today;
 pub mod unwatch;
+pub mod webhooks;
+
+use colored::Colorize;
+
+use crate::client::ClientError;
+
+/// Report a failed API call, with a hint when there is one, and exit.
+pub fn fail(e: ClientError) -> ! {
+    eprintln!("{} {e}", "error:".red().bold());
+    e.print_hint();
+    std::process::exit(1);
+}
+
+/// Report a mistake in the arguments, or anything else the server wasn't
+/// asked about, and exit.
+pub fn usage(msg: impl std::fmt::Display) -> ! {
+    eprintln!("{} {msg}", "error:".red().bold());
+    std::process::exit(1);
+}
@@ -1,12 +1,26 @@
 use colored::Colorize;
 
+use timeforged_core::api::PublicProfileSettings;
+
 use crate::client::TfClient;
 
-pub async fn run(client: &TfClient, public: bool) {
-    let body = serde_json::json!({ "public": public });
+/// Change visibility and hidden projects, keeping the rest of the
+/// profile settings as they are.
+pub async fn run(client: &TfClient, public: bool, hide: &[String], unhide: &[String]) {
+    // Older servers only know `public`; start from the defaults there
+    let mut settings = client
+        .get::<serde_json::Value>("/api/v1/me/public-profile")
+        .await
+        .ok()
+        .and_then(|resp| serde_json::from_value(resp["settings"].clone()).ok())
+        .unwrap_or_else(PublicProfileSettings::default);
+    settings.public = public;
+    let unhidden = |p: &String| unhide.iter().any(|u| u.to_lowercase() == p.to_lowercase());
+    settings.hidden_projects.retain(|p| !unhidden(p));
+    settings.hidden_projects.extend(hide.iter().cloned());
 
     match client
-        .put::<serde_json::Value, _>("/api/v1/me/public-profile", &body)
+        .put::<serde_json::Value, _>("/api/v1/me/public-profile", &settings)
         .await
     {
         Ok(resp) => {
@@ -15,13 +29,22 @@ pub async fn run(client: &TfClient, public: bool) {
 
             if is_public {
                 println!("{} public profile enabled", "✓".green());
+                if let Some(profile_url) = resp["profile_url"].as_str() {
+                    println!("Profile:  {}", profile_url.cyan());
+                }
                 println!("Card URL: {}", card_url.cyan());
             } else {
                 println!("{} public profile disabled", "✓".green());
             }
+            let hidden = resp["settings"]["hidden_projects"].as_array();
+            if let Some(hidden) = hidden.filter(|h| !h.is_empty()) {
+                let names: Vec<_> = hidden.iter().filter_map(|p| p.as_str()).collect();
+                println!("Hidden projects: {}", names.join(", "));
+            }
         }
         Err(e) => {
             eprintln!("{}: {e}", "error".red());
+            e.print_hint();
             std::process::exit(1);
         }
     }
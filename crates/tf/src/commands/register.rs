@@ -29,6 +29,7 @@ pub async fn run(remote: &TfClient, username: &str, display_name: Option<&str>)
         }
         Err(e) => {
             eprintln!("{}: {e}", "error".red());
+            e.print_hint();
             std::process::exit(1);
         }
     }
@@ -0,0 +1,193 @@
+use colored::Colorize;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use timeforged_core::api::{HealthResponse, SyncMode};
+use timeforged_core::config::{CliConfig, RemoteConfig};
+
+use crate::client::TfClient;
+use crate::commands::usage;
+
+fn save(config: &CliConfig) {
+    if let Err(e) = config.save() {
+        usage(format!("failed to write cli.toml: {e}"));
+    }
+}
+
+/// Cursors belong to the server that issued them: a remote that is new
+/// (or reuses a removed one's name) must start from scratch.
+fn forget_state(name: &str) {
+    if let Err(e) = timeforged_sync::state::forget(name) {
+        eprintln!("{} could not reset sync state of '{name}': {e}", "warning:".yellow());
+    }
+}
+
+fn projects(list: &[String], empty: &str) -> String {
+    if list.is_empty() {
+        empty.into()
+    } else {
+        list.join(", ")
+    }
+}
+
+pub async fn add(mut config: CliConfig, remote: RemoteConfig) {
+    if !RemoteConfig::valid_name(&remote.name) {
+        usage("remote names may only contain letters, digits, '-' and '_' (up to 32)");
+    }
+    if config.all_remotes().iter().any(|r| r.name == remote.name) {
+        usage(format!("a remote named '{}' already exists", remote.name));
+    }
+
+    let client = TfClient::new(&CliConfig {
+        server_url: remote.url.clone(),
+        api_key: Some(remote.key.clone()),
+        remote_url: None,
+        remote_key: None,
+        remotes: Vec::new(),
+    });
+    print!("Checking remote {}... ", remote.url.cyan());
+    match client.get::<HealthResponse>("/health").await {
+        Ok(resp) => println!("{} (v{})", "ok".green(), resp.version),
+        Err(e) => {
+            println!("{}", "failed".red());
+            eprintln!("{}: could not reach remote: {e}", "error".red());
+            e.print_hint();
+            std::process::exit(1);
+        }
+    }
+
+    forget_state(&remote.name);
+    let name = remote.name.clone();
+    let encrypted = remote.encrypted.then(|| remote.clone());
+    config.remotes.push(remote);
+    save(&config);
+    if let Some(remote) = encrypted {
+        unlock_remote(&remote).await;
+    }
+    println!(
+        "{} remote {} added; the daemon syncs it from its next round",
+        "✓".green(),
+        name.bold()
+    );
+    println!("Run {} to sync it now.", format!("tf sync --remote {name}").bold());
+}
+
+pub async fn unlock(config: &CliConfig, name: &str) {
+    let Some(remote) = config.all_remotes().into_iter().find(|r| r.name == name) else {
+        usage(format!("no remote named '{name}'"));
+    };
+    if !remote.encrypted {
+        usage(format!("remote '{name}' is not encrypted"));
+    }
+    unlock_remote(&remote).await;
+}
+
+/// `TF_SYNC_PASSPHRASE`, else asked on the terminal.
+fn passphrase() -> String {
+    if let Ok(passphrase) = std::env::var("TF_SYNC_PASSPHRASE") {
+        return passphrase;
+    }
+    match rpassword::read_password_from_tty(Some("Encryption passphrase: ")) {
+        Ok(passphrase) if !passphrase.is_empty() => passphrase,
+        Ok(_) => usage("the passphrase can't be empty"),
+        Err(e) => usage(format!("could not read the passphrase: {e}")),
+    }
+}
+
+async fn unlock_remote(remote: &RemoteConfig) {
+    let passphrase = passphrase();
+    print!("Deriving the key for {}... ", remote.name.bold());
+    match timeforged_sync::remote::unlock(remote, &passphrase).await {
+        Ok(()) => {
+            println!("{}", "ok".green());
+            println!(
+                "{}",
+                "Events are encrypted before they leave this machine. Keep the passphrase: \
+                 without it, nothing on the remote can be read back."
+                    .dimmed()
+            );
+        }
+        Err(e) => {
+            println!("{}", "failed".red());
+            usage(e);
+        }
+    }
+}
+
+pub fn list(config: &CliConfig) {
+    let remotes = config.all_remotes();
+    if remotes.is_empty() {
+        println!("No remotes configured.");
+        println!("Use {} to add one.", "tf remote add <name> <url> --remote-key <key>".bold());
+        return;
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(vec![
+        "Name", "URL", "Direction", "Projects", "Excluded", "Last sync", "Pushed", "Pulled",
+    ]);
+    for remote in &remotes {
+        let state = timeforged_sync::state::read(&remote.name);
+        let last = match (state.last_run_at, &state.last_error) {
+            (None, _) => "never".to_string(),
+            (Some(at), None) => at.format("%Y-%m-%d %H:%M").to_string(),
+            (Some(at), Some(_)) => format!("{} (failed)", at.format("%Y-%m-%d %H:%M")),
+        };
+        let mut direction = remote.effective_direction().as_str().to_string();
+        if remote.encrypted {
+            direction.push_str(" (encrypted)");
+        } else if !remote.privacy.is_off() {
+            direction.push_str(" (redacted)");
+        }
+        table.add_row(vec![
+            remote.name.clone(),
+            remote.url.clone(),
+            direction,
+            projects(&remote.include_projects, "all"),
+            projects(&remote.exclude_projects, "-"),
+            last,
+            state.events_synced.to_string(),
+            state.events_pulled.to_string(),
+        ]);
+    }
+    println!("{table}");
+
+    for remote in &remotes {
+        let state = timeforged_sync::state::read(&remote.name);
+        if let Some(e) = state.last_error {
+            println!("{} {}: {e}", "!".red(), remote.name);
+        }
+        if let Some(at) = state.retry_at {
+            let at = at.format("%Y-%m-%d %H:%M:%S");
+            let retry = format!("retrying at {at} ({} failures in a row)", state.failures);
+            println!("  {}", retry.dimmed());
+        }
+    }
+}
+
+pub fn remove(mut config: CliConfig, name: &str) {
+    let before = config.remotes.len();
+    config.remotes.retain(|r| r.name != name);
+    let removed = if config.remotes.len() < before {
+        true
+    } else if name == RemoteConfig::LEGACY_NAME && config.remote_url.is_some() {
+        config.remote_url = None;
+        config.remote_key = None;
+        true
+    } else {
+        false
+    };
+    if !removed {
+        usage(format!("no remote named '{name}'"));
+    }
+
+    save(&config);
+    forget_state(name);
+    println!("{} remote {} removed", "✓".green(), name.bold());
+}
+
+/// `--direction` value.
+pub fn parse_direction(s: &str) -> SyncMode {
+    SyncMode::parse(s)
+        .unwrap_or_else(|| usage(format!("invalid direction '{s}', expected push, pull or both")))
+}
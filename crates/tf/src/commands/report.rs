@@ -2,6 +2,7 @@ use chrono::Utc;
 use colored::Colorize;
 use comfy_table::{Cell, Table};
 
+use timeforged_core::api::{CompareEntry, ComparePeriod, CompareReport};
 use timeforged_core::models::Summary;
 
 use crate::client::TfClient;
@@ -30,6 +31,36 @@ pub async fn run(client: &TfClient, range: Range, project: Option<&str>) {
         Ok(summary) => print_summary(&summary, &range),
         Err(e) => {
             eprintln!("{}: {e}", "error".red());
+            e.print_hint();
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn compare(
+    client: &TfClient,
+    period: ComparePeriod,
+    offset: u32,
+    full: bool,
+    project: Option<&str>,
+) {
+    let offset = offset.to_string();
+    let mut query: Vec<(&str, &str)> = vec![("period", period.as_str()), ("offset", &offset)];
+    if full {
+        query.push(("full", "true"));
+    }
+    if let Some(p) = project {
+        query.push(("project", p));
+    }
+
+    match client
+        .get_with_query::<CompareReport>("/api/v1/reports/compare", &query)
+        .await
+    {
+        Ok(report) => print_compare(&report),
+        Err(e) => {
+            eprintln!("{}: {e}", "error".red());
+            e.print_hint();
             std::process::exit(1);
         }
     }
@@ -129,3 +160,100 @@ fn print_summary(summary: &Summary, range: &Range) {
         println!("{table}");
     }
 }
+
+/// "This week vs last week", "This month vs 12 months ago"
+fn compare_label(period: ComparePeriod, offset: u32) -> String {
+    let (current, previous) = match period {
+        ComparePeriod::Day => ("Today", "yesterday"),
+        ComparePeriod::Week => ("This week", "last week"),
+        ComparePeriod::Month => ("This month", "last month"),
+        ComparePeriod::Year => ("This year", "last year"),
+    };
+    if offset == 1 {
+        format!("{current} vs {previous}")
+    } else {
+        format!("{current} vs {offset} {}s ago", period.as_str())
+    }
+}
+
+/// "▲ 1h 05m (+25%)", "▼ 20m (-40%)", "new"
+fn change_text(entry: &CompareEntry) -> String {
+    let minutes = (entry.delta_seconds.abs() / 60.0).round() as u64;
+    let amount = match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m:02}m"),
+    };
+    match entry.percent_change {
+        None if entry.current_seconds > 0.0 => "new".into(),
+        None => "–".into(),
+        Some(_) if minutes == 0 => "= 0m".into(),
+        Some(p) if entry.delta_seconds > 0.0 => format!("▲ {amount} (+{p:.0}%)"),
+        Some(p) => format!("▼ {amount} ({p:.0}%)"),
+    }
+}
+
+fn change(entry: &CompareEntry) -> String {
+    let text = change_text(entry);
+    match text.chars().next() {
+        Some('▲') => text.green().to_string(),
+        Some('▼') => text.red().to_string(),
+        Some('n') => text.cyan().to_string(),
+        _ => text.dimmed().to_string(),
+    }
+}
+
+fn hm(seconds: f64) -> String {
+    format!("{:.0}h {:.0}m", (seconds / 3600.0).floor(), (seconds % 3600.0) / 60.0)
+}
+
+fn print_compare(report: &CompareReport) {
+    let label = compare_label(report.period, report.offset);
+    println!("{}", format!("Report — {label}").bold());
+    let span = |s: &Summary| {
+        format!("{} – {}", s.from.format("%Y-%m-%d %H:%M"), s.to.format("%Y-%m-%d %H:%M"))
+    };
+    println!("  {}", format!("now:    {}", span(&report.current)).dimmed());
+    println!("  {}", format!("before: {}", span(&report.previous)).dimmed());
+    println!(
+        "  Total: {} vs {}  {}",
+        hm(report.total.current_seconds).green(),
+        hm(report.total.previous_seconds),
+        change(&report.total)
+    );
+
+    for (title, entries) in [("Projects", &report.projects), ("Languages", &report.languages)] {
+        if entries.is_empty() {
+            continue;
+        }
+        println!("\n{}", title.bold());
+        let mut table = Table::new();
+        table.set_header(vec![&title[..title.len() - 1], "Now", "Before", "Change"]);
+        for e in entries {
+            table.add_row(vec![
+                Cell::new(&e.name),
+                Cell::new(hm(e.current_seconds)),
+                Cell::new(hm(e.previous_seconds)),
+                Cell::new(change(e)),
+            ]);
+        }
+        println!("{table}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparisons_read_naturally() {
+        assert_eq!(compare_label(ComparePeriod::Week, 1), "This week vs last week");
+        assert_eq!(compare_label(ComparePeriod::Month, 12), "This month vs 12 months ago");
+        assert_eq!(compare_label(ComparePeriod::Day, 7), "Today vs 7 days ago");
+
+        assert_eq!(change_text(&CompareEntry::new("a", 4500.0, 3600.0)), "▲ 15m (+25%)");
+        assert_eq!(change_text(&CompareEntry::new("a", 1800.0, 7200.0)), "▼ 1h 30m (-75%)");
+        assert_eq!(change_text(&CompareEntry::new("a", 600.0, 0.0)), "new");
+        assert_eq!(change_text(&CompareEntry::new("a", 600.0, 610.0)), "= 0m");
+        assert_eq!(change_text(&CompareEntry::new("a", 0.0, 0.0)), "–");
+    }
+}
@@ -1,19 +1,24 @@
+use std::io::Read;
+
 use chrono::Utc;
 use colored::Colorize;
 
 use timeforged_core::api::{CreateEventRequest, EventResponse};
+use timeforged_core::config::CliConfig;
 use timeforged_core::models::EventType;
+use timeforged_sync::Spool;
 
-use crate::client::TfClient;
+use crate::client::{ClientError, TfClient};
+use crate::commands::flush;
 
-pub async fn run(
-    client: &TfClient,
+/// An event for `entity` happening now.
+pub fn event(
     entity: &str,
     project: Option<&str>,
     language: Option<&str>,
     event_type: Option<&str>,
-) {
-    let req = CreateEventRequest {
+) -> CreateEventRequest {
+    CreateEventRequest {
         timestamp: Utc::now(),
         event_type: event_type
             .map(EventType::from_str_lossy)
@@ -23,9 +28,32 @@ pub async fn run(
         language: language.map(String::from),
         branch: None,
         activity: None,
-        machine: hostname(),
+        machine: None,
         metadata: None,
-    };
+    }
+}
+
+/// An event as JSON on stdin, the way hooks hand them over.
+pub fn event_from_stdin() -> CreateEventRequest {
+    let mut input = String::new();
+    let parsed = std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::from_str(&input).map_err(|e| e.to_string()));
+    parsed.unwrap_or_else(|e| {
+        eprintln!("{}: invalid event on stdin: {e}", "error".red());
+        std::process::exit(1);
+    })
+}
+
+/// Send an event to the daemon. If the daemon can't be reached or is rate
+/// limiting us the event is spooled instead, and everything spooled goes out
+/// after the next event that gets through. Hooks run this on every save, so
+/// it never waits out a rate limit.
+pub async fn run(client: &TfClient, config: &CliConfig, mut req: CreateEventRequest) {
+    if req.machine.is_none() {
+        req.machine = hostname();
+    }
 
     match client.post::<EventResponse, _>("/api/v1/events", &req).await {
         Ok(resp) => {
@@ -35,11 +63,51 @@ pub async fn run(
                 resp.id,
                 resp.entity
             );
+            flush::after_send(config).await;
         }
+        Err(e) if rate_limited(&e) => spool(req, &e, "rate limited"),
+        Err(e) if unreachable(&e) => spool(req, &e, "daemon unreachable"),
         Err(e) => {
             eprintln!("{}: {e}", "error".red());
+            e.print_hint();
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The daemon is down, restarting, or behind a proxy that says so.
+fn unreachable(e: &ClientError) -> bool {
+    match e {
+        ClientError::Request(_) => true,
+        ClientError::Api { status, .. } => matches!(status, 502..=504),
+        _ => false,
+    }
+}
+
+fn rate_limited(e: &ClientError) -> bool {
+    matches!(e, ClientError::Api { status: 429, .. })
+}
+
+fn spool(req: CreateEventRequest, cause: &ClientError, why: &str) {
+    let queued = match Spool::open().push(req) {
+        Ok(queued) => queued,
+        Err(spool_err) => {
+            eprintln!("{}: {cause}", "error".red());
+            eprintln!("{}: could not queue the event: {spool_err}", "error".red());
             std::process::exit(1);
         }
+    };
+    println!(
+        "{} {why}, event kept for later ({} waiting, `tf flush` to retry)",
+        "Queued".yellow(),
+        queued.pending
+    );
+    if queued.dropped > 0 {
+        eprintln!(
+            "{}: queue full, dropped the {} oldest events",
+            "warning".yellow(),
+            queued.dropped
+        );
     }
 }
 
@@ -57,4 +125,21 @@ mod tests {
         assert!(h.is_some());
         assert!(!h.unwrap().is_empty());
     }
+
+    #[test]
+    fn only_outages_are_spooled() {
+        let api = |status| ClientError::Api {
+            status,
+            code: String::new(),
+            message: String::new(),
+            request_id: None,
+        };
+        assert!(unreachable(&ClientError::Request("connection refused".into())));
+        assert!(unreachable(&api(503)));
+        assert!(!unreachable(&api(401)));
+        assert!(!unreachable(&api(422)));
+        assert!(!unreachable(&ClientError::Parse("eof".into())));
+        assert!(rate_limited(&api(429)));
+        assert!(!rate_limited(&api(503)));
+    }
 }
@@ -12,6 +12,15 @@ pub async fn run(client: &TfClient) {
             println!("  Status:   {}", s.status.green());
             println!("  Users:    {}", s.user_count);
             println!("  Events:   {}", s.event_count);
+            let cards = &s.card_cache;
+            if cards.hits + cards.misses > 0 {
+                println!(
+                    "  Cards:    {:.0}% from cache ({} of {} requests)",
+                    cards.hit_rate * 100.0,
+                    cards.hits,
+                    cards.hits + cards.misses
+                );
+            }
         }
         Err(e) => {
             // Try health endpoint as fallback
@@ -1,207 +1,196 @@
+use chrono::{DateTime, Utc};
 use colored::Colorize;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use timeforged_core::api::{
+    SyncDirectionReport, SyncMode, SyncReport, SyncRequest, SyncStatusResponse,
+};
+use timeforged_core::config::CliConfig;
+use timeforged_sync::{Direction, HttpEndpoint, Progress, SyncError, remote};
+
+use crate::client::{ClientError, TfClient};
+
+/// Ask the daemon to sync, so `tf sync` never races its auto-sync. Daemons
+/// that predate `POST /api/v1/sync` are synced from here instead.
+pub async fn run(config: &CliConfig, mode: SyncMode, only: Option<&str>) {
+    let local = TfClient::new(config);
+    let req = SyncRequest {
+        mode,
+        remote: only.map(str::to_string),
+    };
+
+    println!("{}", format!("Syncing ({})...", mode.as_str()).dimmed());
+    let reports = match local.post::<Vec<SyncReport>, _>("/api/v1/sync", &req).await {
+        Ok(reports) => reports,
+        Err(ClientError::Api { status: 404, ref code, .. }) if code.is_empty() => {
+            println!("{}", "daemon is too old to sync itself, syncing from here".dimmed());
+            match run_here(config, mode, only).await {
+                Ok(reports) => reports,
+                Err(e) => {
+                    eprintln!("{}: {e}", "error".red());
+                    return;
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("{}: sync failed: {e}", "error".red());
+            e.print_hint();
+            return;
+        }
+    };
 
-use timeforged_core::api::{BatchEventRequest, BatchEventResponse, CreateEventRequest, ExportEventsResponse, SyncStateFile};
-use timeforged_core::config::config_dir;
-
-use crate::client::TfClient;
-
-fn sync_state_path() -> std::path::PathBuf {
-    config_dir().join("sync-state.toml")
+    if reports.is_empty() {
+        println!("no remote syncs in this direction");
+    }
+    for report in &reports {
+        print_report(report);
+    }
 }
 
-fn load_sync_state() -> SyncStateFile {
-    let path = sync_state_path();
-    if let Ok(content) = std::fs::read_to_string(&path) {
-        toml::from_str(&content).unwrap_or(SyncStateFile {
-            last_synced: None,
-            events_synced: 0,
-            last_pulled: None,
-            events_pulled: 0,
-        })
-    } else {
-        SyncStateFile {
-            last_synced: None,
-            events_synced: 0,
-            last_pulled: None,
-            events_pulled: 0,
+/// Drive the sync engine against the daemon's API and each remote.
+async fn run_here(
+    config: &CliConfig,
+    mode: SyncMode,
+    only: Option<&str>,
+) -> Result<Vec<SyncReport>, SyncError> {
+    let mut progress = |p: Progress| {
+        if let Progress::Page { direction, events } = p {
+            let arrow = match direction {
+                Direction::Push => "↑",
+                Direction::Pull => "↓",
+            };
+            println!("  {} {} events to sync...", arrow, events.to_string().cyan());
         }
-    }
-}
+    };
 
-fn save_sync_state(state: &SyncStateFile) {
-    let path = sync_state_path();
-    if let Ok(content) = toml::to_string_pretty(state) {
-        let _ = std::fs::create_dir_all(path.parent().unwrap());
-        let _ = std::fs::write(&path, content);
+    let mut reports = Vec::new();
+    for target in remote::select(config, only)? {
+        let local = HttpEndpoint::new(&config.server_url, config.api_key.clone(), "local");
+        if let Some(report) = remote::sync_remote(local, &target, mode, &mut progress).await {
+            reports.push(report);
+        }
     }
+    Ok(reports)
 }
 
-pub async fn run(local: &TfClient, remote: &TfClient) {
-    let mut sync_state = load_sync_state();
-
-    // Phase 1: Push (local → remote)
-    println!("{}", "Pushing local → remote...".dimmed());
-    let (pushed, push_rejected) = sync_events(local, remote, &mut sync_state, Direction::Push).await;
-
-    // Phase 2: Pull (remote → local)
-    println!("{}", "Pulling remote → local...".dimmed());
-    let (pulled, pull_rejected) = sync_events(remote, local, &mut sync_state, Direction::Pull).await;
+fn print_report(report: &SyncReport) {
+    println!("{}", report.remote.bold());
+    for e in report.errors() {
+        eprintln!("  {}: {e}", "error".red());
+    }
 
-    // Summary
-    if pushed == 0 && pulled == 0 && push_rejected == 0 && pull_rejected == 0 {
-        println!("{} everything up to date", "✓".green());
-    } else {
-        if pushed > 0 || push_rejected > 0 {
-            println!(
-                "{} pushed {} events ({} rejected)",
-                "↑".cyan(),
-                pushed.to_string().cyan(),
-                push_rejected,
-            );
-        }
-        if pulled > 0 || pull_rejected > 0 {
-            println!(
-                "{} pulled {} events ({} rejected)",
-                "↓".cyan(),
-                pulled.to_string().cyan(),
-                pull_rejected,
-            );
+    let (pushed, pulled) = (moved(&report.push), moved(&report.pull));
+    if pushed.is_none() && pulled.is_none() {
+        if report.errors().next().is_none() {
+            println!("  {} everything up to date", "✓".green());
         }
+        return;
+    }
+
+    if let Some(push) = pushed {
         println!(
-            "  total synced: {} pushed, {} pulled",
-            sync_state.events_synced,
-            sync_state.events_pulled,
+            "  {} pushed {} events ({} rejected{})",
+            "↑".cyan(),
+            push.accepted.to_string().cyan(),
+            push.rejected,
+            skipped(push),
         );
     }
+    if let Some(pull) = pulled {
+        println!(
+            "  {} pulled {} events ({} rejected{})",
+            "↓".cyan(),
+            pull.accepted.to_string().cyan(),
+            pull.rejected,
+            skipped(pull),
+        );
+    }
+    let state = timeforged_sync::state::read(&report.remote);
+    println!(
+        "  total synced: {} pushed, {} pulled",
+        state.events_synced, state.events_pulled,
+    );
 }
 
-enum Direction {
-    Push,
-    Pull,
+fn moved(d: &Option<SyncDirectionReport>) -> Option<&SyncDirectionReport> {
+    d.as_ref().filter(|d| d.accepted > 0 || d.rejected > 0)
 }
 
-async fn sync_events(
-    source: &TfClient,
-    target: &TfClient,
-    sync_state: &mut SyncStateFile,
-    direction: Direction,
-) -> (usize, usize) {
-    let batch_size = 100;
-    let page_size = 5_000;
-    let mut grand_accepted = 0usize;
-    let mut grand_rejected = 0usize;
-
-    let last_timestamp = match direction {
-        Direction::Push => &sync_state.last_synced,
-        Direction::Pull => &sync_state.last_pulled,
+fn skipped(d: &SyncDirectionReport) -> String {
+    if d.skipped > 0 {
+        format!(", {} left out by project filters", d.skipped)
+    } else {
+        String::new()
     }
-    .clone();
-
-    let mut current_timestamp = last_timestamp;
+}
 
-    loop {
-        let mut query = vec![("limit", page_size.to_string())];
-        if let Some(since) = &current_timestamp {
-            query.push(("since", since.to_rfc3339()));
+/// Show how syncing has been going: per remote, then the most recent runs.
+pub async fn status(client: &TfClient, only: Option<&str>) {
+    let mut query = vec![("limit", "15")];
+    if let Some(remote) = only {
+        query.push(("remote", remote));
+    }
+    let resp = client.get_with_query::<SyncStatusResponse>("/api/v1/sync/status", &query);
+    let status = match resp.await {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("{}: {e}", "error".red());
+            e.print_hint();
+            std::process::exit(1);
         }
-
-        let query_refs: Vec<(&str, &str)> = query.iter().map(|(k, v)| (*k, v.as_str())).collect();
-
-        let export: ExportEventsResponse = match source
-            .get_with_query("/api/v1/events", &query_refs)
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                let label = match direction {
-                    Direction::Push => "local",
-                    Direction::Pull => "remote",
-                };
-                eprintln!("{}: failed to fetch {} events: {e}", "error".red(), label);
-                break;
+    };
+
+    let when = |t: Option<DateTime<Utc>>| {
+        t.map_or("never".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string())
+    };
+    for remote in status.remotes.iter().filter(|r| only.is_none_or(|name| r.name == name)) {
+        let health = match remote.failing_since {
+            Some(since) if remote.alert => {
+                format!("failing since {}", since.format("%Y-%m-%d %H:%M")).red().bold()
             }
-        };
-
-        if export.events.is_empty() {
-            break;
-        }
-
-        let page_count = export.count;
-        let arrow = match direction {
-            Direction::Push => "↑",
-            Direction::Pull => "↓",
+            Some(since) => format!("failing since {}", since.format("%H:%M")).yellow(),
+            None => "ok".green(),
         };
         println!(
-            "  {} {} events to sync...",
-            arrow,
-            page_count.to_string().cyan()
+            "{} ({}): {health}, last success {}",
+            remote.name.bold(),
+            remote.direction.as_str(),
+            when(remote.last_success_at),
         );
-
-        let mut page_accepted = 0usize;
-        let mut page_rejected = 0usize;
-        let mut latest_timestamp = current_timestamp;
-        let mut had_error = false;
-
-        for chunk in export.events.chunks(batch_size) {
-            let events: Vec<CreateEventRequest> = chunk
-                .iter()
-                .map(|e| {
-                    if latest_timestamp.is_none() || Some(e.timestamp) > latest_timestamp {
-                        latest_timestamp = Some(e.timestamp);
-                    }
-                    CreateEventRequest {
-                        timestamp: e.timestamp,
-                        event_type: e.event_type.clone(),
-                        entity: e.entity.clone(),
-                        project: e.project.clone(),
-                        language: e.language.clone(),
-                        branch: e.branch.clone(),
-                        activity: e.activity.clone(),
-                        machine: e.machine.clone(),
-                        metadata: e.metadata.clone(),
-                    }
-                })
-                .collect();
-
-            let batch = BatchEventRequest { events };
-
-            match target
-                .post::<BatchEventResponse, _>("/api/v1/events/batch", &batch)
-                .await
-            {
-                Ok(resp) => {
-                    page_accepted += resp.accepted;
-                    page_rejected += resp.rejected;
-                }
-                Err(e) => {
-                    eprintln!("{}: failed to push batch: {e}", "error".red());
-                    had_error = true;
-                    break;
-                }
-            }
+        if let Some(e) = &remote.last_error {
+            println!("  {} {e}", "last error:".dimmed());
         }
-
-        current_timestamp = latest_timestamp;
-
-        match direction {
-            Direction::Push => {
-                sync_state.last_synced = current_timestamp;
-                sync_state.events_synced += page_accepted as u64;
-            }
-            Direction::Pull => {
-                sync_state.last_pulled = current_timestamp;
-                sync_state.events_pulled += page_accepted as u64;
-            }
-        }
-        save_sync_state(sync_state);
-
-        grand_accepted += page_accepted;
-        grand_rejected += page_rejected;
-
-        if had_error || page_count < page_size {
-            break;
+        if let Some(at) = remote.retry_at {
+            println!("  {} {}", "next retry:".dimmed(), at.format("%Y-%m-%d %H:%M:%S"));
         }
     }
+    if status.alert {
+        let hours = status.alert_after_secs as f64 / 3600.0;
+        println!("{} sync has been failing for over {hours:.1}h", "⚠".red().bold());
+    }
 
-    (grand_accepted, grand_rejected)
+    if status.runs.is_empty() {
+        println!("No sync runs recorded yet.");
+        return;
+    }
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(vec![
+        "Time", "Remote", "Dir", "By", "New", "Dup", "Rejected", "Skipped", "Took", "Error",
+    ]);
+    for run in &status.runs {
+        table.add_row(vec![
+            run.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            run.remote.clone(),
+            run.direction.as_str().to_string(),
+            run.trigger.as_str().to_string(),
+            run.accepted.to_string(),
+            run.duplicates.to_string(),
+            run.rejected.to_string(),
+            run.skipped.to_string(),
+            format!("{:.1}s", run.duration_ms as f64 / 1000.0),
+            run.error.clone().unwrap_or_default(),
+        ]);
+    }
+    println!("{table}");
 }
@@ -50,6 +50,7 @@ pub async fn run(client: &TfClient) {
         }
         Err(e) => {
             eprintln!("{}: {e}", "error".red());
+            e.print_hint();
             std::process::exit(1);
         }
     }
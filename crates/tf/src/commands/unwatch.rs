@@ -20,6 +20,7 @@ pub async fn run(client: &TfClient, path: &str) {
         }
         Err(e) => {
             eprintln!("{} {e}", "error:".red().bold());
+            e.print_hint();
             std::process::exit(1);
         }
     }
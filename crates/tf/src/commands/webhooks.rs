@@ -0,0 +1,153 @@
+use colored::Colorize;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use timeforged_core::api::{CreateWebhookRequest, CreateWebhookResponse};
+use timeforged_core::models::{DeliveryStatus, Webhook, WebhookDelivery, WebhookTrigger};
+
+use crate::client::TfClient;
+use crate::commands::fail;
+
+fn triggers_list(triggers: &[WebhookTrigger]) -> String {
+    triggers.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+/// Parse `--events a,b`; empty means every trigger.
+pub fn parse_triggers(spec: Option<&str>) -> Result<Vec<WebhookTrigger>, String> {
+    let Some(spec) = spec else {
+        return Ok(Vec::new());
+    };
+    spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            WebhookTrigger::parse(s).ok_or_else(|| {
+                let valid = triggers_list(&WebhookTrigger::SUBSCRIBABLE);
+                format!("unknown event '{s}', expected one of: {valid}")
+            })
+        })
+        .collect()
+}
+
+pub async fn add(client: &TfClient, url: &str, events: Option<&str>) {
+    let triggers = parse_triggers(events).unwrap_or_else(|e| {
+        eprintln!("{} {e}", "error:".red().bold());
+        std::process::exit(1);
+    });
+    let req = CreateWebhookRequest {
+        url: url.to_string(),
+        triggers,
+    };
+
+    match client.post::<CreateWebhookResponse, _>("/api/v1/webhooks", &req).await {
+        Ok(resp) => {
+            println!("{} webhook {} created", "✓".green().bold(), resp.id);
+            println!("Events: {}", triggers_list(&resp.triggers));
+            println!("Secret: {}", resp.secret.cyan());
+            println!(
+                "Verify {} with this secret; it won't be shown again.",
+                "X-TimeForged-Signature".bold()
+            );
+        }
+        Err(e) => fail(e),
+    }
+}
+
+pub async fn list(client: &TfClient) {
+    let hooks = client
+        .get::<Vec<Webhook>>("/api/v1/webhooks")
+        .await
+        .unwrap_or_else(|e| fail(e));
+
+    if hooks.is_empty() {
+        println!("No webhooks configured.");
+        println!("Use {} to add one.", "tf webhooks add <url>".bold());
+        return;
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(vec!["ID", "URL", "Events", "Created"]);
+    for hook in &hooks {
+        table.add_row(vec![
+            hook.id.to_string(),
+            hook.url.clone(),
+            triggers_list(&hook.triggers),
+            hook.created_at.format("%Y-%m-%d %H:%M").to_string(),
+        ]);
+    }
+    println!("{table}");
+}
+
+pub async fn remove(client: &TfClient, id: &str) {
+    match client.delete(&format!("/api/v1/webhooks/{id}")).await {
+        Ok(()) => println!("{} webhook {id} removed", "✓".green().bold()),
+        Err(e) => fail(e),
+    }
+}
+
+pub async fn test(client: &TfClient, id: &str) {
+    let path = format!("/api/v1/webhooks/{id}/test");
+    match client.post::<WebhookDelivery, _>(&path, &serde_json::json!({})).await {
+        Ok(delivery) => {
+            println!("{} ping {} queued", "✓".green().bold(), delivery.id);
+            println!("Check the result with {}", format!("tf webhooks log {id}").bold());
+        }
+        Err(e) => fail(e),
+    }
+}
+
+pub async fn log(client: &TfClient, id: &str) {
+    let deliveries = client
+        .get::<Vec<WebhookDelivery>>(&format!("/api/v1/webhooks/{id}/deliveries"))
+        .await
+        .unwrap_or_else(|e| fail(e));
+
+    if deliveries.is_empty() {
+        println!("No deliveries yet.");
+        return;
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table.set_header(vec!["Time", "Event", "Status", "Attempts", "Last result"]);
+    for d in &deliveries {
+        let status = match d.status {
+            DeliveryStatus::Delivered => "delivered".green().to_string(),
+            DeliveryStatus::Pending => match d.next_attempt_at {
+                Some(next) if d.attempts > 0 => {
+                    format!("retry at {}", next.format("%H:%M:%S")).yellow().to_string()
+                }
+                _ => "pending".yellow().to_string(),
+            },
+            DeliveryStatus::Failed => "failed".red().to_string(),
+        };
+        let result = match (&d.response_status, &d.error) {
+            (_, Some(error)) => error.clone(),
+            (Some(code), None) => format!("HTTP {code}"),
+            (None, None) => String::new(),
+        };
+        table.add_row(vec![
+            d.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            d.trigger.as_str().to_string(),
+            status,
+            d.attempts.to_string(),
+            result,
+        ]);
+    }
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_triggers_accepts_lists() {
+        assert!(parse_triggers(None).unwrap().is_empty());
+        assert_eq!(
+            parse_triggers(Some("session_ended, daily_summary")).unwrap(),
+            vec![WebhookTrigger::SessionEnded, WebhookTrigger::DailySummary]
+        );
+        assert!(parse_triggers(Some("session_ended,bogus")).is_err());
+    }
+}
@@ -1,9 +1,10 @@
 mod client;
 mod commands;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 
-use timeforged_core::config::CliConfig;
+use timeforged_core::api::{ComparePeriod, SyncMode};
+use timeforged_core::config::{CliConfig, RemoteConfig};
 
 use crate::client::TfClient;
 
@@ -30,7 +31,7 @@ enum Commands {
     Today,
     /// Generate a report
     Report {
-        /// Time range: today, yesterday, week, month
+        /// Time range: today, yesterday, week, month (year with --compare)
         #[arg(long, default_value = "week")]
         range: String,
         /// Filter by project
@@ -42,11 +43,33 @@ enum Commands {
         /// Custom end date (ISO 8601)
         #[arg(long)]
         to: Option<String>,
+        /// Compare the current day, week, month or year with an earlier one
+        /// (--range today, week, month or year)
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        compare: bool,
+        /// With --compare: how many periods back to compare with
+        #[arg(long, default_value_t = 1, requires = "compare")]
+        offset: u32,
+        /// With --compare: take all of the earlier period, not just as far
+        /// into it as the current one has got
+        #[arg(long, requires = "compare")]
+        full: bool,
     },
-    /// Send a heartbeat event
+    /// Productive hours and weekdays, sessions, context switches, focus and
+    /// late-night time, and days that stand out
+    Insights {
+        /// Days back from now to look at
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+        /// Filter by project
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Send a heartbeat event (queued if the daemon is unreachable)
     Send {
         /// Entity (file path, URL, etc.)
-        entity: String,
+        #[arg(required_unless_present = "stdin")]
+        entity: Option<String>,
         /// Project name
         #[arg(long)]
         project: Option<String>,
@@ -56,6 +79,37 @@ enum Commands {
         /// Event type: file, terminal, browser, meeting, custom
         #[arg(long, name = "type")]
         event_type: Option<String>,
+        /// Read the whole event as JSON from stdin instead (for hooks)
+        #[arg(long, conflicts_with = "entity")]
+        stdin: bool,
+    },
+    /// Deliver events queued while the daemon was unreachable
+    Flush,
+    /// Export every event, streamed as JSON Lines or CSV
+    Export {
+        /// jsonl or csv
+        #[arg(long, default_value = "jsonl")]
+        format: String,
+        /// Start date (YYYY-MM-DD) or time (RFC 3339), inclusive
+        #[arg(long)]
+        from: Option<String>,
+        /// End date or time; a date includes that whole day
+        #[arg(long)]
+        to: Option<String>,
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Import events from an export, a CSV file or a WakaTime data dump
+    Import {
+        /// File to import, or - for stdin
+        file: String,
+        /// jsonl, csv or wakatime (default: from the file extension)
+        #[arg(long)]
+        format: Option<String>,
+        /// Read the file and report problems without importing anything
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Start watching a directory for file changes
     Init {
@@ -69,14 +123,35 @@ enum Commands {
         /// Directory to stop watching
         path: String,
     },
-    /// Set public profile visibility (enables card by username)
+    /// Set public profile visibility (enables profile page and card by username)
     Profile {
         /// Enable or disable public profile
         #[arg(long)]
         public: bool,
+        /// Comma-separated projects to leave off the profile and cards
+        #[arg(long, value_delimiter = ',')]
+        hide: Vec<String>,
+        /// Comma-separated hidden projects to list again
+        #[arg(long, value_delimiter = ',')]
+        unhide: Vec<String>,
+    },
+    /// Sync events with the remote servers (run by the daemon)
+    Sync {
+        /// push, pull or both
+        #[arg(long, default_value = "both")]
+        mode: String,
+        /// Only sync this remote (default: all)
+        #[arg(long)]
+        remote: Option<String>,
+        /// Show recent sync runs and failures instead of syncing
+        #[arg(long)]
+        status: bool,
+    },
+    /// Manage the remote servers this machine syncs with
+    Remote {
+        #[command(subcommand)]
+        action: RemoteAction,
     },
-    /// Sync local events to remote server
-    Sync,
     /// Link this machine to an existing remote account
     Link {
         /// Remote API key (from another machine's cli.toml)
@@ -96,6 +171,253 @@ enum Commands {
         #[arg(long)]
         remote: Option<String>,
     },
+    /// Manage webhooks (signed HTTP callbacks)
+    Webhooks {
+        #[command(subcommand)]
+        action: WebhooksAction,
+    },
+    /// Daily and weekly coding goals (lists them without a subcommand)
+    Goals {
+        #[command(subcommand)]
+        action: Option<GoalsAction>,
+    },
+    /// Clients, their rates and rounding, and which activities are billed
+    /// (lists clients without a subcommand)
+    Clients {
+        #[command(subcommand)]
+        action: Option<ClientsAction>,
+    },
+    /// Timesheet of a client's billable time per day and project, written as
+    /// CSV, PDF or HTML
+    Invoice {
+        /// Client name or ID
+        #[arg(long)]
+        client: String,
+        /// Month to bill, e.g. 2026-09 (default: the current month)
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        month: Option<String>,
+        /// First day to bill (YYYY-MM-DD)
+        #[arg(long, requires = "to")]
+        from: Option<String>,
+        /// Last day to bill (YYYY-MM-DD)
+        #[arg(long, requires = "from")]
+        to: Option<String>,
+        /// Comma-separated files to write: csv, pdf, html ("" only prints)
+        #[arg(long, value_delimiter = ',', default_value = "csv,pdf")]
+        format: Vec<String>,
+        /// Directory to write the files to
+        #[arg(long)]
+        out: Option<String>,
+    },
+    /// Daily or weekly digests: show or change their schedule, preview or send one
+    Digest {
+        /// Print the digest through yesterday (Markdown unless --html)
+        #[arg(long)]
+        preview: bool,
+        /// With --preview, print the HTML version
+        #[arg(long, requires = "preview")]
+        html: bool,
+        /// Deliver the digest through yesterday now
+        #[arg(long, conflicts_with = "preview")]
+        send: bool,
+        /// Turn scheduled digests on
+        #[arg(long)]
+        enable: bool,
+        /// Turn scheduled digests off
+        #[arg(long, conflicts_with = "enable")]
+        disable: bool,
+        /// daily or weekly
+        #[arg(long)]
+        period: Option<String>,
+        /// Hour of the day (UTC) digests go out at
+        #[arg(long)]
+        hour: Option<u8>,
+        /// Day weekly digests go out on, e.g. mon
+        #[arg(long)]
+        weekday: Option<String>,
+        /// Email digests to this address ("" stops emails)
+        #[arg(long)]
+        email: Option<String>,
+        /// Write digests to the daemon's digest directory: true or false
+        #[arg(long)]
+        directory: Option<bool>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RemoteAction {
+    /// Add a remote
+    Add {
+        /// Remote name (letters, digits, '-' and '_')
+        name: String,
+        /// Remote server URL
+        url: String,
+        /// API key on the remote server
+        #[arg(long)]
+        remote_key: String,
+        /// push, pull or both
+        #[arg(long, default_value = "both")]
+        direction: String,
+        /// Comma-separated projects to sync (default: all)
+        #[arg(long, value_delimiter = ',')]
+        include: Vec<String>,
+        /// Comma-separated projects to keep local
+        #[arg(long, value_delimiter = ',')]
+        exclude: Vec<String>,
+        /// Encrypt events end to end; the remote only sees timestamps
+        #[arg(long)]
+        encrypt: bool,
+    },
+    /// Enter an encrypted remote's passphrase on this machine
+    Unlock {
+        /// Remote name
+        name: String,
+    },
+    /// List remotes and their sync state
+    List,
+    /// Remove a remote and its sync state
+    Remove {
+        /// Remote name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WebhooksAction {
+    /// Add a webhook
+    Add {
+        /// Receiver URL (http or https)
+        url: String,
+        /// Comma-separated events: events_ingested, session_ended, daily_summary,
+        /// goal_reached, digest (default: all)
+        #[arg(long)]
+        events: Option<String>,
+    },
+    /// List webhooks
+    List,
+    /// Remove a webhook and its delivery log
+    Remove {
+        /// Webhook ID
+        id: String,
+    },
+    /// Send a test ping
+    Test {
+        /// Webhook ID
+        id: String,
+    },
+    /// Show recent deliveries
+    Log {
+        /// Webhook ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum GoalsAction {
+    /// Add a goal
+    Add {
+        /// Time per day or week, e.g. 2h/day, 90m/day or 10h/week
+        target: String,
+        /// Only count time on this project
+        #[arg(long)]
+        project: Option<String>,
+        /// Only count time in this language
+        #[arg(long)]
+        language: Option<String>,
+        /// Comma-separated weekdays a daily goal can be missed on without
+        /// breaking its streak, e.g. sat,sun
+        #[arg(long, value_delimiter = ',')]
+        rest: Vec<String>,
+    },
+    /// Show goals with today's or this week's progress and streaks
+    List,
+    /// Show which days or weeks a goal was met
+    History {
+        /// Goal ID
+        id: String,
+        /// Days or weeks to show
+        #[arg(long, default_value_t = 14)]
+        periods: usize,
+    },
+    /// Remove a goal
+    Remove {
+        /// Goal ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ClientsAction {
+    /// Add a client
+    Add {
+        /// Client name
+        name: String,
+        #[command(flatten)]
+        settings: ClientArgs,
+    },
+    /// Change a client's settings; the ones not given stay as they are
+    Edit {
+        /// Client name or ID
+        client: String,
+        /// New name
+        #[arg(long)]
+        name: Option<String>,
+        #[command(flatten)]
+        settings: ClientArgs,
+    },
+    /// List clients
+    List,
+    /// Remove a client; its projects are no longer billed
+    Remove {
+        /// Client name or ID
+        client: String,
+    },
+    /// Show which activity types are billed, or change them
+    Billable {
+        /// Comma-separated activities to bill, e.g. coding,debugging
+        #[arg(long, value_delimiter = ',')]
+        on: Vec<String>,
+        /// Comma-separated activities not to bill, e.g. browsing
+        #[arg(long, value_delimiter = ',')]
+        off: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+struct ClientArgs {
+    /// Hourly rate
+    #[arg(long)]
+    rate: Option<f64>,
+    /// Currency code, e.g. EUR or USD
+    #[arg(long)]
+    currency: Option<String>,
+    /// Round billed time to this increment, e.g. 6m or 15m (0 for none)
+    #[arg(long)]
+    round: Option<String>,
+    /// Round per session or per day
+    #[arg(long)]
+    per: Option<String>,
+    /// Round up, nearest or down
+    #[arg(long)]
+    direction: Option<String>,
+    /// Comma-separated projects billed to the client, replacing the current
+    /// ones; give one its own rate with name=rate, e.g. site,audit=120
+    #[arg(long, value_delimiter = ',')]
+    projects: Option<Vec<String>>,
+}
+
+impl ClientArgs {
+    fn changes(self, name: Option<String>) -> commands::clients::Changes {
+        commands::clients::Changes {
+            name,
+            rate: self.rate,
+            currency: self.currency,
+            round: self.round,
+            per: self.per,
+            direction: self.direction,
+            projects: self.projects,
+        }
+    }
 }
 
 #[tokio::main]
@@ -120,7 +442,19 @@ async fn main() {
             project,
             from,
             to,
+            compare,
+            offset,
+            full,
         } => {
+            if compare {
+                let Some(period) = ComparePeriod::parse(&range) else {
+                    eprintln!("Cannot compare range {range}. Use today, week, month, or year.");
+                    std::process::exit(1);
+                };
+                commands::report::compare(&client, period, offset, full, project.as_deref())
+                    .await;
+                return;
+            }
             let r = if from.is_some() || to.is_some() {
                 commands::report::Range::Custom {
                     from: from.unwrap_or_default(),
@@ -140,20 +474,45 @@ async fn main() {
             };
             commands::report::run(&client, r, project.as_deref()).await;
         }
+        Commands::Insights { days, project } => {
+            commands::insights::run(&client, days, project.as_deref()).await
+        }
         Commands::Send {
             entity,
             project,
             language,
             event_type,
+            stdin,
+        } => {
+            let event = if stdin {
+                commands::send::event_from_stdin()
+            } else {
+                commands::send::event(
+                    &entity.unwrap_or_default(),
+                    project.as_deref(),
+                    language.as_deref(),
+                    event_type.as_deref(),
+                )
+            };
+            commands::send::run(&client, &config, event).await;
+        }
+        Commands::Flush => commands::flush::run(&config).await,
+        Commands::Export {
+            format,
+            from,
+            to,
+            output,
+        } => {
+            let (from, to, output) = (from.as_deref(), to.as_deref(), output.as_deref());
+            commands::export::run(&client, &format, from, to, output).await;
+        }
+        Commands::Import {
+            file,
+            format,
+            dry_run,
         } => {
-            commands::send::run(
-                &client,
-                &entity,
-                project.as_deref(),
-                language.as_deref(),
-                event_type.as_deref(),
-            )
-            .await;
+            let client = TfClient::new(&config).with_backoff();
+            commands::import::run(&client, &file, format.as_deref(), dry_run).await;
         }
         Commands::Init { path } => {
             let dir = path.unwrap_or_else(|| ".".to_string());
@@ -163,23 +522,28 @@ async fn main() {
         Commands::Unwatch { path } => {
             commands::unwatch::run(&client, &path).await;
         }
-        Commands::Profile { public } => {
-            commands::profile::run(&client, public).await;
+        Commands::Profile {
+            public,
+            hide,
+            unhide,
+        } => {
+            commands::profile::run(&client, public, &hide, &unhide).await;
         }
-        Commands::Sync => {
-            let remote_url = config.remote_url.clone().unwrap_or_else(|| {
-                let cfg = timeforged_core::config::config_dir().join("cli.toml");
-                eprintln!("No remote URL configured. Set remote_url in {} or TF_REMOTE_URL env var.", cfg.display());
+        Commands::Sync {
+            status: true,
+            remote,
+            ..
+        } => commands::sync::status(&client, remote.as_deref()).await,
+        Commands::Sync { mode, remote, .. } => {
+            if config.all_remotes().is_empty() {
+                eprintln!("No remote configured. Add one with `tf remote add` or `tf link`.");
+                std::process::exit(1);
+            }
+            let mode = SyncMode::parse(&mode).unwrap_or_else(|| {
+                eprintln!("invalid mode '{mode}', expected push, pull or both");
                 std::process::exit(1);
             });
-            let remote_config = CliConfig {
-                server_url: remote_url,
-                api_key: config.remote_key.clone(),
-                remote_url: None,
-                remote_key: None,
-            };
-            let remote = TfClient::new(&remote_config);
-            commands::sync::run(&client, &remote).await;
+            commands::sync::run(&config, mode, remote.as_deref()).await;
         }
         Commands::Link { remote_key, remote } => {
             commands::link::run(&remote_key, &remote).await;
@@ -201,9 +565,117 @@ async fn main() {
                 api_key: None,
                 remote_url: None,
                 remote_key: None,
+                remotes: Vec::new(),
             };
             let remote_client = TfClient::new(&remote_config);
             commands::register::run(&remote_client, &username, display_name.as_deref()).await;
         }
+        Commands::Remote { action } => match action {
+            RemoteAction::Add {
+                name,
+                url,
+                remote_key,
+                direction,
+                include,
+                exclude,
+                encrypt,
+            } => {
+                let mut remote = RemoteConfig::new(&name, &url, &remote_key);
+                remote.direction = commands::remote::parse_direction(&direction);
+                remote.include_projects = include;
+                remote.exclude_projects = exclude;
+                remote.encrypted = encrypt;
+                // Saved as loaded, without --server/--key overrides
+                commands::remote::add(CliConfig::load(), remote).await;
+            }
+            RemoteAction::Unlock { name } => commands::remote::unlock(&config, &name).await,
+            RemoteAction::List => commands::remote::list(&config),
+            RemoteAction::Remove { name } => commands::remote::remove(CliConfig::load(), &name),
+        },
+        Commands::Webhooks { action } => match action {
+            WebhooksAction::Add { url, events } => {
+                commands::webhooks::add(&client, &url, events.as_deref()).await;
+            }
+            WebhooksAction::List => commands::webhooks::list(&client).await,
+            WebhooksAction::Remove { id } => commands::webhooks::remove(&client, &id).await,
+            WebhooksAction::Test { id } => commands::webhooks::test(&client, &id).await,
+            WebhooksAction::Log { id } => commands::webhooks::log(&client, &id).await,
+        },
+        Commands::Goals { action } => match action.unwrap_or(GoalsAction::List) {
+            GoalsAction::Add {
+                target,
+                project,
+                language,
+                rest,
+            } => commands::goals::add(&client, &target, project, language, &rest).await,
+            GoalsAction::List => commands::goals::list(&client).await,
+            GoalsAction::History { id, periods } => {
+                commands::goals::history(&client, &id, periods).await;
+            }
+            GoalsAction::Remove { id } => commands::goals::remove(&client, &id).await,
+        },
+        Commands::Clients { action } => match action.unwrap_or(ClientsAction::List) {
+            ClientsAction::Add { name, settings } => {
+                if settings.rate.is_none() {
+                    eprintln!("--rate is required for a new client");
+                    std::process::exit(1);
+                }
+                commands::clients::add(&client, &name, settings.changes(None)).await;
+            }
+            ClientsAction::Edit {
+                client: key,
+                name,
+                settings,
+            } => commands::clients::edit(&client, &key, settings.changes(name)).await,
+            ClientsAction::List => commands::clients::list(&client).await,
+            ClientsAction::Remove { client: key } => commands::clients::remove(&client, &key).await,
+            ClientsAction::Billable { on, off } => {
+                commands::clients::activities(&client, &on, &off).await;
+            }
+        },
+        Commands::Invoice {
+            client: key,
+            month,
+            from,
+            to,
+            format,
+            out,
+        } => {
+            let range: Vec<(&str, &str)> = match (&month, &from, &to) {
+                (Some(month), _, _) => vec![("month", month)],
+                (None, Some(from), Some(to)) => vec![("from", from), ("to", to)],
+                _ => Vec::new(),
+            };
+            commands::invoice::run(&client, &key, &range, &format, out.as_deref()).await;
+        }
+        Commands::Digest {
+            preview: true,
+            html,
+            period,
+            ..
+        } => {
+            commands::digest::preview(&client, period.as_deref(), html).await;
+        }
+        Commands::Digest { send: true, .. } => commands::digest::send(&client).await,
+        Commands::Digest {
+            enable,
+            disable,
+            period,
+            hour,
+            weekday,
+            email,
+            directory,
+            ..
+        } => {
+            let changes = commands::digest::Changes {
+                enabled: (enable || disable).then_some(enable),
+                period,
+                hour,
+                weekday,
+                email,
+                directory,
+            };
+            commands::digest::settings(&client, changes).await;
+        }
     }
 }
@@ -1,11 +1,18 @@
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 
-use crate::models::{ActivityType, EventType};
+use crate::interchange::ExportFormat;
+use crate::models::{
+    ActivityType, CategorySummary, ClientProject, Event, EventType, Goal, GoalPeriod, Rounding,
+    Session, Summary, WebhookTrigger,
+};
 
 // --- Event requests ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CreateEventRequest {
     pub timestamp: DateTime<Utc>,
     pub event_type: EventType,
@@ -24,12 +31,37 @@ pub struct CreateEventRequest {
     pub metadata: Option<serde_json::Value>,
 }
 
+impl CreateEventRequest {
+    /// Re-submit a stored event elsewhere (sync, import).
+    pub fn from_event(e: &Event) -> Self {
+        Self {
+            timestamp: e.timestamp,
+            event_type: e.event_type.clone(),
+            entity: e.entity.clone(),
+            project: e.project.clone(),
+            language: e.language.clone(),
+            branch: e.branch.clone(),
+            activity: e.activity.clone(),
+            machine: e.machine.clone(),
+            metadata: e.metadata.clone(),
+        }
+    }
+}
+
+/// Events a batch request may carry; clients split larger sends.
+pub const MAX_BATCH_EVENTS: usize = 100;
+
+/// Lines an import request may carry; clients split larger imports.
+pub const MAX_IMPORT_LINES: usize = 5000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BatchEventRequest {
     pub events: Vec<CreateEventRequest>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct EventResponse {
     pub id: i64,
     pub timestamp: DateTime<Utc>,
@@ -38,19 +70,26 @@ pub struct EventResponse {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BatchEventResponse {
     pub accepted: usize,
     pub rejected: usize,
+    /// Already stored (same content); counted as neither accepted nor
+    /// rejected. Always 0 from servers without deduplication.
+    #[serde(default)]
+    pub duplicates: usize,
 }
 
 // --- API key requests ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CreateApiKeyRequest {
     pub label: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CreateApiKeyResponse {
     pub id: uuid::Uuid,
     pub label: String,
@@ -60,27 +99,32 @@ pub struct CreateApiKeyResponse {
 // --- Watcher requests ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WatchRequest {
     pub path: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct UnwatchRequest {
     pub path: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WatchedDirResponse {
     pub path: String,
     pub added_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WatchedResponse {
     pub dirs: Vec<WatchedDirResponse>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WatchActionResponse {
     pub message: String,
 }
@@ -88,6 +132,7 @@ pub struct WatchActionResponse {
 // --- Registration ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct RegisterRequest {
     pub username: String,
     #[serde(default)]
@@ -95,6 +140,7 @@ pub struct RegisterRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct RegisterResponse {
     pub username: String,
     pub api_key: String,
@@ -103,7 +149,14 @@ pub struct RegisterResponse {
 // --- Events export ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
 pub struct ExportEventsQuery {
+    /// Resume after this cursor, in insertion order. Pass an empty value to
+    /// start from the beginning, then the `next_cursor` of each response.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Legacy timestamp watermark, used only without `cursor`. Loses events
+    /// that share the boundary timestamp or arrive late; prefer `cursor`.
     #[serde(default)]
     pub since: Option<DateTime<Utc>>,
     #[serde(default = "default_export_limit")]
@@ -115,42 +168,1270 @@ fn default_export_limit() -> i64 {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ExportEventsResponse {
     pub events: Vec<crate::models::Event>,
     pub count: usize,
+    /// Where to resume; unchanged if there was nothing new. Only set in
+    /// cursor mode, so its absence means the server predates cursors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// More events are ready right away
+    #[serde(default)]
+    pub has_more: bool,
 }
 
-// --- Sync ---
+/// `GET /api/v1/events/export`: every event with a timestamp in range,
+/// streamed in insertion order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct ExportQuery {
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub format: ExportFormat,
+    /// Inclusive
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// What `POST /api/v1/events/import` did. Importing the same file again
+/// only adds duplicates, so an interrupted import can simply be rerun.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportResponse {
+    pub accepted: usize,
+    pub duplicates: usize,
+    /// Lines that weren't events or failed validation
+    pub rejected: usize,
+    /// Why, for the first few rejected lines
+    #[serde(default)]
+    pub errors: Vec<ImportError>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportError {
+    /// 1-based line of the request body
+    pub line: usize,
+    pub error: String,
+}
+
+/// Position in a server's event log: the insertion sequence (rowid) of the
+/// last event seen. Clients treat the encoded form as opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct EventCursor(pub i64);
+
+impl EventCursor {
+    const PREFIX: &'static str = "v1.";
+
+    pub fn encode(&self) -> String {
+        format!("{}{}", Self::PREFIX, self.0)
+    }
+
+    /// Empty means the beginning of the log.
+    pub fn decode(s: &str) -> Option<Self> {
+        if s.is_empty() {
+            return Some(Self(0));
+        }
+        let seq: i64 = s.strip_prefix(Self::PREFIX)?.parse().ok()?;
+        (seq >= 0).then_some(Self(seq))
+    }
+}
+
+// --- Sync ---
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SyncStateFile {
+    /// Legacy push watermark, superseded by `push_cursor`
     #[serde(default)]
     pub last_synced: Option<DateTime<Utc>>,
     #[serde(default)]
     pub events_synced: u64,
+    /// Legacy pull watermark, superseded by `pull_cursor`
     #[serde(default)]
     pub last_pulled: Option<DateTime<Utc>>,
     #[serde(default)]
     pub events_pulled: u64,
+    /// Position in the local event log up to which events were pushed
+    #[serde(default)]
+    pub push_cursor: Option<String>,
+    /// Position in the remote event log up to which events were pulled
+    #[serde(default)]
+    pub pull_cursor: Option<String>,
+    #[serde(default)]
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// First error of the last run, if it had one
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Runs that failed in a row, for the retry backoff
+    #[serde(default)]
+    pub failures: u32,
+    /// Automatic syncs wait until then after a failure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Key for the hashes of a privacy policy, so they stay stable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy_key: Option<String>,
+    /// Names generated for projects by `redact_projects`, by real name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub project_aliases: BTreeMap<String, String>,
+    /// Key of an encrypted remote, derived from its passphrase (base64)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e2e_key: Option<String>,
+}
+
+/// Which way a sync copies events between this machine and the remote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMode {
+    /// Local → remote
+    Push,
+    /// Remote → local
+    Pull,
+    #[default]
+    Both,
+}
+
+impl SyncMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Push => "push",
+            Self::Pull => "pull",
+            Self::Both => "both",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "push" => Some(Self::Push),
+            "pull" => Some(Self::Pull),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    pub fn pushes(&self) -> bool {
+        matches!(self, Self::Push | Self::Both)
+    }
+
+    pub fn pulls(&self) -> bool {
+        matches!(self, Self::Pull | Self::Both)
+    }
+
+    /// What both modes allow, if anything.
+    pub fn intersect(self, other: SyncMode) -> Option<SyncMode> {
+        match (self.pushes() && other.pushes(), self.pulls() && other.pulls()) {
+            (true, true) => Some(Self::Both),
+            (true, false) => Some(Self::Push),
+            (false, true) => Some(Self::Pull),
+            (false, false) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncRequest {
+    /// Narrows each remote's configured direction
+    #[serde(default)]
+    pub mode: SyncMode,
+    /// Only this remote (default: all of them)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+}
+
+/// Outcome of one direction of a sync run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncDirectionReport {
+    pub accepted: usize,
+    /// Already on the other side
+    pub duplicates: usize,
+    pub rejected: usize,
+    /// Left out by the remote's project filters
+    #[serde(default)]
+    pub skipped: usize,
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Why this direction stopped early; the cursor keeps what was done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncReport {
+    #[serde(default)]
+    pub remote: String,
+    /// Why the remote wasn't synced at all (e.g. another sync was running)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Absent when the mode doesn't push
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push: Option<SyncDirectionReport>,
+    /// Absent when the mode doesn't pull
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull: Option<SyncDirectionReport>,
+}
+
+impl SyncReport {
+    pub fn errors(&self) -> impl Iterator<Item = &str> {
+        let directions = [&self.push, &self.pull]
+            .into_iter()
+            .filter_map(|d| d.as_ref()?.error.as_deref());
+        self.error.as_deref().into_iter().chain(directions)
+    }
+}
+
+/// What started a sync run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum SyncTrigger {
+    /// The daemon's periodic sync
+    Auto,
+    /// `tf sync`, `POST /api/v1/sync` or `POST /api/v1/sync/run`
+    Manual,
+}
+
+impl SyncTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Manual => "manual",
+        }
+    }
+
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "manual" => Self::Manual,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// One direction of one attempt to sync with a remote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncRun {
+    pub id: i64,
+    pub remote: String,
+    /// `push` or `pull`; the whole mode when the remote couldn't be synced
+    pub direction: SyncMode,
+    pub trigger: SyncTrigger,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub accepted: usize,
+    /// Already on the other side
+    pub duplicates: usize,
+    pub rejected: usize,
+    pub skipped: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct SyncStatusQuery {
+    /// Only runs with this remote
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Number of recent runs (max 200)
+    #[serde(default = "default_sync_runs_limit")]
+    pub limit: i64,
+}
+
+fn default_sync_runs_limit() -> i64 {
+    20
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncStatusResponse {
+    /// Some remote has been failing for longer than `alert_after_secs`
+    pub alert: bool,
+    pub alert_after_secs: u64,
+    pub remotes: Vec<RemoteStatus>,
+    /// Most recent first
+    pub runs: Vec<SyncRun>,
+}
+
+// --- Sealed (end-to-end encrypted) events ---
+
+/// An event encrypted by the client before it left. The server only learns
+/// when it happened and whose it is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SealedEvent {
+    /// Insertion sequence, set by the server on export
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+    /// Keyed hash of the content, so repeats are dropped without revealing it
+    pub key: String,
+    /// Base64 of the nonce followed by the ciphertext
+    pub blob: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SealedBatchRequest {
+    pub events: Vec<SealedEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct SealedExportQuery {
+    /// Resume after this cursor; empty or absent starts from the beginning
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default = "default_export_limit")]
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SealedExportResponse {
+    pub events: Vec<SealedEvent>,
+    pub next_cursor: String,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// What a machine needs to derive an account's key from the passphrase and
+/// tell whether it got it right. Set once, by the first machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SealedKeyInfo {
+    /// Argon2id salt, base64
+    pub salt: String,
+    /// A known value sealed with the key
+    pub check: String,
+}
+
+// --- Live stream ---
+
+/// A short-lived token for opening `GET /api/v1/stream?token=`, so clients
+/// that can't set headers (browser `EventSource`) never put their API key
+/// in a URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StreamTokenResponse {
+    pub token: String,
+    /// Streams must be opened before this; open ones stay open
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Messages pushed over `GET /api/v1/stream` (SSE). The SSE event name is
+/// the same as `type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// A new event was recorded (API, batch or file watcher)
+    Event(Event),
+    /// Today's totals (UTC day) after new activity
+    Totals(TodayTotals),
+    SessionStarted {
+        start: DateTime<Utc>,
+        #[serde(default)]
+        project: Option<String>,
+    },
+    /// Sent once the session has been idle for `idle_timeout`
+    SessionEnded(Session),
+    /// A batch request finished
+    BatchIngested { accepted: usize, rejected: usize },
+}
+
+impl StreamEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StreamEvent::Event(_) => "event",
+            StreamEvent::Totals(_) => "totals",
+            StreamEvent::SessionStarted { .. } => "session_started",
+            StreamEvent::SessionEnded(_) => "session_ended",
+            StreamEvent::BatchIngested { .. } => "batch_ingested",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TodayTotals {
+    pub date: NaiveDate,
+    pub total_seconds: f64,
+    pub projects: Vec<CategorySummary>,
+    pub languages: Vec<CategorySummary>,
+}
+
+// --- Webhooks ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Defaults to every trigger
+    #[serde(default)]
+    pub triggers: Vec<WebhookTrigger>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateWebhookResponse {
+    pub id: uuid::Uuid,
+    pub url: String,
+    pub triggers: Vec<WebhookTrigger>,
+    /// Signing secret; only shown once
+    pub secret: String,
+}
+
+/// Body POSTed to webhook receivers. The raw body is signed with
+/// HMAC-SHA256 as `X-TimeForged-Signature: sha256=<hex>` over
+/// `<X-TimeForged-Timestamp>.<body>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookPayload {
+    /// Delivery id, stable across retries
+    pub id: uuid::Uuid,
+    pub event: WebhookTrigger,
+    pub created_at: DateTime<Utc>,
+    /// Trigger-specific: a `Session`, a `Summary`, batch counts, ...
+    pub data: serde_json::Value,
+}
+
+// --- Backups ---
+
+/// A backup the daemon wrote. `removed` lists older scheduled backups
+/// deleted to keep `backup.keep`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BackupInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+// --- Public profiles ---
+
+/// What a public profile shows. Unset fields take their defaults, so
+/// `{"public": true}` shows everything but goals.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct PublicProfileSettings {
+    /// Profile, cards and badges can be seen without a key
+    pub public: bool,
+    /// Total time, active days and daily average
+    pub show_totals: bool,
+    pub show_languages: bool,
+    pub show_projects: bool,
+    /// Current and longest streaks of active days
+    pub show_streak: bool,
+    /// Goal progress on public `layout=goals` cards; off unless asked for
+    pub show_goals: bool,
+    /// Projects left out wherever projects are listed; their time still
+    /// counts in totals and languages
+    pub hidden_projects: Vec<String>,
+}
+
+impl Default for PublicProfileSettings {
+    fn default() -> Self {
+        Self {
+            public: false,
+            show_totals: true,
+            show_languages: true,
+            show_projects: true,
+            show_streak: true,
+            show_goals: false,
+            hidden_projects: Vec::new(),
+        }
+    }
+}
+
+impl PublicProfileSettings {
+    /// Whether `project` may be listed. Names compare case-insensitively.
+    pub fn shows_project(&self, project: &str) -> bool {
+        self.show_projects
+            && !self.hidden_projects.iter().any(|p| p.to_lowercase() == project.to_lowercase())
+    }
+}
+
+/// A user's public profile over the last year. Fields the user hides are
+/// left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PublicProfile {
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totals: Option<ProfileTotals>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<CategorySummary>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projects: Option<Vec<CategorySummary>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streak: Option<ProfileStreak>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProfileTotals {
+    pub total_seconds: f64,
+    pub active_days: u32,
+    /// Per active day
+    pub daily_average_seconds: f64,
+}
+
+/// Streaks of days with a minute or more of activity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProfileStreak {
+    /// Days, up to today (or yesterday, while today has no activity yet)
+    pub current: u32,
+    pub longest: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longest_from: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longest_to: Option<NaiveDate>,
+}
+
+// --- Comparison reports ---
+
+/// A calendar period, in UTC; weeks start on Monday.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ComparePeriod {
+    Day,
+    #[default]
+    Week,
+    Month,
+    Year,
+}
+
+impl ComparePeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Year => "year",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "day" | "today" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            "month" => Some(Self::Month),
+            "year" => Some(Self::Year),
+            _ => None,
+        }
+    }
+}
+
+/// `GET /api/v1/reports/compare`: the period so far against the same
+/// stretch of an earlier one, e.g. `period=week&offset=1` for this week
+/// against last week, or `period=month&offset=12` for the same month last
+/// year.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct CompareQuery {
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub period: ComparePeriod,
+    /// How many periods back the earlier one is
+    #[serde(default = "default_compare_offset")]
+    pub offset: u32,
+    /// Compare with all of the earlier period, not just as far into it as
+    /// the current one has got
+    #[serde(default)]
+    pub full: bool,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+fn default_compare_offset() -> u32 {
+    1
+}
+
+/// Time on one thing, or in total, in both periods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CompareEntry {
+    pub name: String,
+    pub current_seconds: f64,
+    pub previous_seconds: f64,
+    pub delta_seconds: f64,
+    /// Change on the earlier period; absent when there was no time then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent_change: Option<f64>,
+}
+
+impl CompareEntry {
+    pub fn new(name: impl Into<String>, current_seconds: f64, previous_seconds: f64) -> Self {
+        Self {
+            name: name.into(),
+            current_seconds,
+            previous_seconds,
+            delta_seconds: current_seconds - previous_seconds,
+            percent_change: (previous_seconds > 0.0)
+                .then(|| (current_seconds / previous_seconds - 1.0) * 100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CompareReport {
+    pub period: ComparePeriod,
+    pub offset: u32,
+    pub current: Summary,
+    pub previous: Summary,
+    pub total: CompareEntry,
+    /// Everything with time in either period, most time now first
+    pub projects: Vec<CompareEntry>,
+    pub languages: Vec<CompareEntry>,
+}
+
+// --- Insights ---
+
+/// `GET /api/v1/insights`: patterns in how time was spent, by UTC hour and
+/// weekday like the other reports.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct InsightsQuery {
+    /// Defaults to 30 days before `to`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    /// Defaults to now
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HourInsight {
+    pub hour: u8,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WeekdayInsight {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub weekday: Weekday,
+    pub seconds: f64,
+    /// Per occurrence of the weekday in the range, idle ones included
+    pub average_seconds: f64,
+}
+
+/// Sessions started in the week beginning `week` (a Monday).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionTrend {
+    pub week: NaiveDate,
+    pub sessions: u32,
+    pub average_seconds: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionInsights {
+    pub count: u32,
+    pub average_seconds: f64,
+    pub longest_seconds: f64,
+    /// Oldest week first
+    pub weekly: Vec<SessionTrend>,
+}
+
+/// Moves from one project to another without a break in between.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ContextSwitches {
+    pub total: u32,
+    /// Per hour of active time
+    pub per_hour: f64,
+}
+
+/// Time spent in unbroken stretches on one project of at least
+/// `min_block_seconds`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FocusInsight {
+    pub min_block_seconds: u64,
+    pub seconds: f64,
+    /// Share of active time, 0 to 1
+    pub ratio: f64,
+}
+
+/// Time between 22:00 and 05:00.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LateNightInsight {
+    pub seconds: f64,
+    /// Share of active time, 0 to 1
+    pub ratio: f64,
+    /// Days with any late-night time, counted by the day the night began
+    pub days: u32,
+    /// Whether late nights are a habit: a large share of time or nights
+    pub frequent: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum UnusualKind {
+    High,
+    Low,
+}
+
+/// A day far off the average of the days before it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UnusualDay {
+    pub date: NaiveDate,
+    pub seconds: f64,
+    /// Average over the preceding baseline days
+    pub baseline_seconds: f64,
+    /// Standard deviations from the baseline
+    pub z_score: f64,
+    pub kind: UnusualKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Insights {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub active_seconds: f64,
+    /// All 24 hours
+    pub hours: Vec<HourInsight>,
+    /// The three hours with most time, best first
+    pub top_hours: Vec<u8>,
+    /// Monday first
+    pub weekdays: Vec<WeekdayInsight>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub best_weekday: Option<Weekday>,
+    pub sessions: SessionInsights,
+    pub context_switches: ContextSwitches,
+    pub focus: FocusInsight,
+    pub late_night: LateNightInsight,
+    pub unusual_days: Vec<UnusualDay>,
+}
+
+// --- Goals ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateGoalRequest {
+    pub period: GoalPeriod,
+    pub target_seconds: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Daily goals only: weekdays that can be missed without breaking the
+    /// streak, e.g. `["Sat", "Sun"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub rest_days: Vec<Weekday>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct GoalQuery {
+    /// Finished days or weeks to list per goal, newest last
+    #[serde(default = "default_goal_history")]
+    pub history: usize,
+}
+
+fn default_goal_history() -> usize {
+    14
+}
+
+/// Time towards a goal over one day or week.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GoalPeriodResult {
+    /// First day of the period
+    pub start: NaiveDate,
+    pub seconds: f64,
+    pub met: bool,
+    /// A rest day: missing the goal doesn't break the streak
+    #[serde(default)]
+    pub rest: bool,
+}
+
+/// Periods in a row the goal was met. Missed rest days are skipped over;
+/// the period in progress only counts once it is met.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GoalStreak {
+    pub current: u32,
+    /// Longest over the last year
+    pub longest: u32,
+}
+
+/// A goal and how it is going.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GoalProgress {
+    pub goal: Goal,
+    /// e.g. "2h/day on timeforged"
+    pub label: String,
+    /// The day or week in progress
+    pub current: GoalPeriodResult,
+    /// Share of the target done in the current period; over 100 once exceeded
+    pub percent: f64,
+    pub streak: GoalStreak,
+    /// Finished periods, oldest first
+    #[serde(default)]
+    pub history: Vec<GoalPeriodResult>,
+}
+
+/// Sent as the data of `goal_reached` webhooks, once per goal and period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GoalReached {
+    pub goal: Goal,
+    pub label: String,
+    pub period_start: NaiveDate,
+    pub seconds: f64,
+    pub streak: u32,
+}
+
+// --- Digests ---
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DigestPeriod {
+    /// The day before
+    Daily,
+    /// The seven days before
+    #[default]
+    Weekly,
+}
+
+impl DigestPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "daily" | "day" => Some(Self::Daily),
+            "weekly" | "week" => Some(Self::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn days(&self) -> i64 {
+        match self {
+            Self::Daily => 1,
+            Self::Weekly => 7,
+        }
+    }
+}
+
+/// When a user's digest is sent and where it goes. Unset fields take their
+/// defaults. Webhooks subscribed to `digest` get it too.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct DigestSettings {
+    /// Send digests on schedule
+    pub enabled: bool,
+    pub period: DigestPeriod,
+    /// Hour of the day (UTC) a digest goes out, covering the days before
+    pub hour: u8,
+    /// Day weekly digests go out on
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub weekday: Weekday,
+    /// Write Markdown and HTML files to the daemon's digest directory
+    pub directory: bool,
+    /// Email the digest here; needs an SMTP server in the daemon config
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period: DigestPeriod::Weekly,
+            hour: 8,
+            weekday: Weekday::Mon,
+            directory: true,
+            email: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFormat {
+    #[default]
+    Markdown,
+    Html,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct DigestQuery {
+    /// Default: the period in the digest settings
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub period: Option<DigestPeriod>,
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub format: DigestFormat,
+}
+
+/// Time on a project or language, and in the period before.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DigestEntry {
+    pub name: String,
+    pub seconds: f64,
+    pub previous_seconds: f64,
+}
+
+/// How a goal went over the digest's days.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DigestGoal {
+    pub label: String,
+    /// Days or weeks the target was met
+    pub met: usize,
+    /// Days or weeks that ended in the digest's range, missed rest days
+    /// left out
+    pub periods: usize,
+    pub streak: GoalStreak,
+}
+
+/// A day or week of coding, compared with the one before.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Digest {
+    pub period: DigestPeriod,
+    pub from: NaiveDate,
+    /// Last day included
+    pub to: NaiveDate,
+    pub total_seconds: f64,
+    pub previous_seconds: f64,
+    pub active_days: usize,
+    /// Top five
+    pub projects: Vec<DigestEntry>,
+    /// Top five
+    pub languages: Vec<DigestEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longest_session: Option<Session>,
+    #[serde(default)]
+    pub goals: Vec<DigestGoal>,
+}
+
+/// Where `POST /api/v1/digest/send` delivered the digest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DigestSent {
+    /// Files written
+    pub files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emailed: Option<String>,
+    /// Webhook deliveries queued
+    pub webhooks: usize,
+    /// Deliveries that failed
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+// --- Billing ---
+
+/// A new client, or all of one replacing it on `PUT`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ClientRequest {
+    pub name: String,
+    pub hourly_rate: f64,
+    /// ISO 4217 code
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub rounding: Rounding,
+    /// Projects billed to the client; a project can only have one client
+    #[serde(default)]
+    pub projects: Vec<ClientProject>,
+}
+
+fn default_currency() -> String {
+    "EUR".into()
+}
+
+/// Whether time spent on an activity type is billed. Events without an
+/// activity count as coding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ActivityBilling {
+    pub activity: ActivityType,
+    pub billable: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum TimesheetFormat {
+    #[default]
+    Json,
+    Csv,
+    Html,
+    Pdf,
+}
+
+impl TimesheetFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "html" => Some(Self::Html),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// `GET /api/v1/billing/timesheet`: a client's billable time over a month
+/// or from one day through another, UTC like the reports.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct TimesheetQuery {
+    /// Client name or id
+    pub client: String,
+    /// `YYYY-MM`; default the current month unless `from` and `to` are given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub month: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+    /// Last day included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", param(inline))]
+    pub format: TimesheetFormat,
+}
+
+/// Time on one project on one day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TimesheetLine {
+    pub date: NaiveDate,
+    pub project: String,
+    /// Billable time as tracked
+    pub tracked_seconds: f64,
+    /// After rounding
+    pub billed_seconds: f64,
+    pub hourly_rate: f64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TimesheetProject {
+    pub name: String,
+    pub tracked_seconds: f64,
+    pub billed_seconds: f64,
+    pub hourly_rate: f64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Timesheet {
+    pub client: String,
+    pub currency: String,
+    pub from: NaiveDate,
+    /// Last day included
+    pub to: NaiveDate,
+    pub rounding: Rounding,
+    /// By day, then project
+    pub lines: Vec<TimesheetLine>,
+    /// Most billed first
+    pub projects: Vec<TimesheetProject>,
+    pub tracked_seconds: f64,
+    pub billed_seconds: f64,
+    /// Rounded to cents, as are the amounts it sums
+    pub amount: f64,
 }
 
 // --- Generic responses ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ErrorResponse {
     pub error: String,
+    /// Machine-readable code, see `AppError::code`. Empty from older servers.
+    #[serde(default)]
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct StatusResponse {
     pub status: String,
     pub version: String,
     pub user_count: i64,
     pub event_count: i64,
+    /// Sync targets configured on this machine
+    #[serde(default)]
+    pub remotes: Vec<RemoteStatus>,
+    /// Card and badge renders since the daemon started
+    #[serde(default)]
+    pub card_cache: CardCacheStats,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CardCacheStats {
+    /// Renders held right now
+    pub entries: usize,
+    /// Requests served from the cache
+    pub hits: u64,
+    /// Requests that had to render
+    pub misses: u64,
+    /// Share of requests served from the cache, 0-1
+    pub hit_rate: f64,
+    /// Times a user's renders were dropped because they recorded activity
+    pub invalidations: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RemoteStatus {
+    pub name: String,
+    pub direction: SyncMode,
+    /// Pushed events are redacted by a privacy policy
+    #[serde(default)]
+    pub redacted: bool,
+    /// Events are encrypted end to end
+    #[serde(default)]
+    pub encrypted: bool,
+    pub events_pushed: u64,
+    pub events_pulled: u64,
+    #[serde(default)]
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Last run in which every direction succeeded
+    #[serde(default)]
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// Start of the current streak of failed runs
+    #[serde(default)]
+    pub failing_since: Option<DateTime<Utc>>,
+    /// Automatic syncs leave the remote alone until then, backing off
+    #[serde(default)]
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Failing for longer than the daemon's `sync_alert_after`
+    #[serde(default)]
+    pub alert: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_cursor_roundtrip() {
+        assert_eq!(EventCursor::decode(""), Some(EventCursor(0)));
+        assert_eq!(EventCursor::decode(&EventCursor(42).encode()), Some(EventCursor(42)));
+        for bad in ["42", "v1.", "v1.-3", "v2.5", "v1.x"] {
+            assert_eq!(EventCursor::decode(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn sync_state_reads_legacy_file() {
+        let state: SyncStateFile =
+            toml::from_str("last_synced = \"2026-03-01T10:00:00Z\"\nevents_synced = 7\n").unwrap();
+        assert_eq!(state.events_synced, 7);
+        assert!(state.push_cursor.is_none());
+    }
+
+    #[test]
+    fn stream_event_tagged_with_kind() {
+        let ev = StreamEvent::Totals(TodayTotals {
+            date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            total_seconds: 60.0,
+            projects: vec![],
+            languages: vec![],
+        });
+        let json = serde_json::to_value(&ev).unwrap();
+        assert_eq!(json["type"], ev.kind());
+        assert_eq!(json["total_seconds"], 60.0);
+
+        let started: StreamEvent = serde_json::from_str(
+            r#"{"type":"session_started","start":"2026-03-01T10:00:00Z","project":"app"}"#,
+        )
+        .unwrap();
+        assert_eq!(started.kind(), "session_started");
+    }
+
+    #[test]
+    fn profile_settings_default_to_showing_everything() {
+        let settings: PublicProfileSettings = serde_json::from_str(r#"{"public":true}"#).unwrap();
+        assert!(settings.public && settings.show_totals && settings.show_streak);
+        assert!(settings.shows_project("api"));
+        // Goals are opt-in
+        assert!(!settings.show_goals);
+
+        let settings: PublicProfileSettings =
+            serde_json::from_str(r#"{"hidden_projects":["Acme-Billing"]}"#).unwrap();
+        assert!(!settings.public);
+        assert!(!settings.shows_project("acme-billing") && settings.shows_project("api"));
+
+        let only_languages = PublicProfileSettings {
+            show_projects: false,
+            ..Default::default()
+        };
+        assert!(!only_languages.shows_project("api"));
+    }
 }
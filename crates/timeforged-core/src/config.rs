@@ -1,5 +1,10 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
+use crate::api::SyncMode;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     #[serde(default = "default_host")]
@@ -14,6 +19,21 @@ pub struct AppConfig {
     pub log_level: String,
     #[serde(default = "default_sync_interval")]
     pub sync_interval: u64,
+    /// Seconds a remote may keep failing before the tray and dashboard warn
+    #[serde(default = "default_sync_alert_after")]
+    pub sync_alert_after: u64,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub card: CardConfig,
+    #[serde(default)]
+    pub digest: DigestConfig,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
 }
 
 fn default_host() -> String {
@@ -52,6 +72,13 @@ fn default_sync_interval() -> u64 {
         .unwrap_or(300)
 }
 
+fn default_sync_alert_after() -> u64 {
+    std::env::var("TF_SYNC_ALERT_AFTER")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600)
+}
+
 fn dirs_or_default() -> String {
     dirs_data().unwrap_or_else(|| {
         let tmp = std::env::temp_dir().join("timeforged");
@@ -73,6 +100,13 @@ impl Default for AppConfig {
             idle_timeout: default_idle_timeout(),
             log_level: default_log_level(),
             sync_interval: default_sync_interval(),
+            sync_alert_after: default_sync_alert_after(),
+            rate_limit: RateLimitConfig::default(),
+            backup: BackupConfig::default(),
+            retention: RetentionConfig::default(),
+            card: CardConfig::default(),
+            digest: DigestConfig::default(),
+            webhooks: WebhookConfig::default(),
         }
     }
 }
@@ -90,6 +124,240 @@ impl AppConfig {
     pub fn bind_addr(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    /// The database file, unless `database_url` is in-memory.
+    pub fn database_path(&self) -> Option<PathBuf> {
+        let path = self.database_url.strip_prefix("sqlite:")?;
+        let path = path.split('?').next().unwrap_or(path).trim_start_matches("//");
+        (!path.is_empty() && path != ":memory:").then(|| PathBuf::from(path))
+    }
+
+    /// Where backups go: `backup.dir`, else `backups/` next to the database.
+    pub fn backup_dir(&self) -> Option<PathBuf> {
+        if let Some(dir) = &self.backup.dir {
+            return Some(PathBuf::from(dir));
+        }
+        Some(self.database_path()?.parent()?.join("backups"))
+    }
+
+    /// Where compaction archives go: `retention.archive_dir`, else
+    /// `archive/` next to the database.
+    pub fn archive_dir(&self) -> Option<PathBuf> {
+        if let Some(dir) = &self.retention.archive_dir {
+            return Some(PathBuf::from(dir));
+        }
+        Some(self.database_path()?.parent()?.join("archive"))
+    }
+
+    /// Where digests are written: `digest.dir`, else `digests/` next to the
+    /// database.
+    pub fn digest_dir(&self) -> Option<PathBuf> {
+        if let Some(dir) = &self.digest.dir {
+            return Some(PathBuf::from(dir));
+        }
+        Some(self.database_path()?.parent()?.join("digests"))
+    }
+}
+
+// --- Backups ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Seconds between scheduled backups; 0 turns them off
+    #[serde(default = "default_backup_interval")]
+    pub interval: u64,
+    /// Backups kept in the backup directory, newest first; 0 keeps them all
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+    /// Default: `backups/` next to the database
+    #[serde(default)]
+    pub dir: Option<String>,
+}
+
+fn default_backup_interval() -> u64 {
+    std::env::var("TF_BACKUP_INTERVAL")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(86400)
+}
+
+fn default_backup_keep() -> usize {
+    7
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            interval: default_backup_interval(),
+            keep: default_backup_keep(),
+            dir: None,
+        }
+    }
+}
+
+// --- Retention ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Days raw heartbeats are kept before they are compacted; 0 keeps them
+    #[serde(default)]
+    pub compact_after_days: u32,
+    /// Write the heartbeats compaction removes to gzipped JSON Lines first
+    #[serde(default = "default_archive")]
+    pub archive: bool,
+    /// Default: `archive/` next to the database
+    #[serde(default)]
+    pub archive_dir: Option<String>,
+    /// Seconds between maintenance runs (compaction, `ANALYZE`, `VACUUM`); 0 turns them off
+    #[serde(default = "default_maintenance_interval")]
+    pub maintenance_interval: u64,
+}
+
+fn default_archive() -> bool {
+    true
+}
+
+fn default_maintenance_interval() -> u64 {
+    86400
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            compact_after_days: 0,
+            archive: default_archive(),
+            archive_dir: None,
+            maintenance_interval: default_maintenance_interval(),
+        }
+    }
+}
+
+// --- Cards ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardConfig {
+    /// Seconds a rendered card or badge is served from memory; 0 turns the cache off
+    #[serde(default = "default_card_cache_secs")]
+    pub cache_secs: u64,
+    /// Rendered cards kept in memory at most
+    #[serde(default = "default_card_cache_entries")]
+    pub cache_entries: usize,
+}
+
+fn default_card_cache_secs() -> u64 {
+    300
+}
+
+fn default_card_cache_entries() -> usize {
+    1000
+}
+
+impl Default for CardConfig {
+    fn default() -> Self {
+        Self {
+            cache_secs: default_card_cache_secs(),
+            cache_entries: default_card_cache_entries(),
+        }
+    }
+}
+
+// --- Digests ---
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestConfig {
+    /// Default: `digests/` next to the database; one subdirectory per user
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Mail server for emailed digests; without it they can't be turned on
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+}
+
+/// A plain SMTP server, usually a local relay; the connection isn't
+/// encrypted, so a login is only sent to a server on a loopback address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    /// Sender address
+    pub from: String,
+    /// Log in with AUTH PLAIN; refused unless `host` is loopback
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    25
+}
+
+// --- Webhooks ---
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Let webhooks post to loopback, private and link-local addresses,
+    /// e.g. a receiver on this machine or the LAN. Off by default, since
+    /// any user can add a webhook and the daemon posts from inside the
+    /// network it runs in.
+    #[serde(default)]
+    pub allow_private: bool,
+}
+
+// --- Rate limiting ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Registrations allowed per client IP per `register_window_secs`
+    #[serde(default = "default_register_limit")]
+    pub register_limit: usize,
+    #[serde(default = "default_register_window_secs")]
+    pub register_window_secs: u64,
+    /// Event writes allowed per API key per `event_window_secs`
+    #[serde(default = "default_event_limit")]
+    pub event_limit: usize,
+    #[serde(default = "default_event_window_secs")]
+    pub event_window_secs: u64,
+    /// Proxies whose `X-Forwarded-For` header is trusted (IPs or CIDRs)
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    /// How often idle buckets are dropped from memory
+    #[serde(default = "default_eviction_interval_secs")]
+    pub eviction_interval_secs: u64,
+}
+
+fn default_register_limit() -> usize {
+    10
+}
+
+fn default_register_window_secs() -> u64 {
+    3600
+}
+
+fn default_event_limit() -> usize {
+    120
+}
+
+fn default_event_window_secs() -> u64 {
+    60
+}
+
+fn default_eviction_interval_secs() -> u64 {
+    300
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            register_limit: default_register_limit(),
+            register_window_secs: default_register_window_secs(),
+            event_limit: default_event_limit(),
+            event_window_secs: default_event_window_secs(),
+            trusted_proxies: Vec::new(),
+            eviction_interval_secs: default_eviction_interval_secs(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +370,115 @@ pub struct CliConfig {
     pub remote_url: Option<String>,
     #[serde(default)]
     pub remote_key: Option<String>,
+    /// Additional sync targets, see [`CliConfig::all_remotes`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remotes: Vec<RemoteConfig>,
+}
+
+/// A sync target, as a `[[remotes]]` entry in cli.toml.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    pub name: String,
+    pub url: String,
+    pub key: String,
+    #[serde(default)]
+    pub direction: SyncMode,
+    /// Only events of these projects travel (empty: all)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_projects: Vec<String>,
+    /// Events of these projects never travel
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_projects: Vec<String>,
+    /// What the remote may learn about pushed events
+    #[serde(default, skip_serializing_if = "PrivacyConfig::is_off")]
+    pub privacy: PrivacyConfig,
+    /// Events are encrypted end to end; the remote only stores blobs
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+}
+
+/// Redaction applied to events before they are pushed to a remote, as a
+/// `[remotes.privacy]` table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// File paths, URLs and app names
+    #[serde(default)]
+    pub entities: Redaction,
+    /// Machine hostnames
+    #[serde(default)]
+    pub machines: Redaction,
+    #[serde(default)]
+    pub drop_branches: bool,
+    /// Send projects without an alias as `project-<hash>`
+    #[serde(default)]
+    pub redact_projects: bool,
+    /// Names projects go by on the remote
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub project_aliases: BTreeMap<String, String>,
+    /// Metadata keys that are never sent; `"*"` drops all metadata
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drop_metadata: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Redaction {
+    #[default]
+    Keep,
+    /// Replace with a keyed hash: stable, so time still groups per value
+    Hash,
+    Strip,
+}
+
+impl PrivacyConfig {
+    /// Whether pushed events go out unchanged.
+    pub fn is_off(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl RemoteConfig {
+    /// Name of the remote given by the legacy `remote_url`/`remote_key`.
+    pub const LEGACY_NAME: &'static str = "default";
+
+    pub fn new(name: &str, url: &str, key: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            key: key.to_string(),
+            direction: SyncMode::Both,
+            include_projects: Vec::new(),
+            exclude_projects: Vec::new(),
+            privacy: PrivacyConfig::default(),
+            encrypted: false,
+        }
+    }
+
+    /// The directions this remote is synced in. Redacted events can't be
+    /// matched with the originals, so pulling them back would duplicate
+    /// them: a remote with a privacy policy is only pushed to.
+    pub fn effective_direction(&self) -> SyncMode {
+        match self.direction {
+            SyncMode::Both if !self.privacy.is_off() => SyncMode::Push,
+            direction => direction,
+        }
+    }
+
+    /// Names end up in file names: letters, digits, `-` and `_` only.
+    pub fn valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= 32
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    /// Whether events of `project` are synced with this remote. With an
+    /// include list, events without a project stay local.
+    pub fn carries(&self, project: Option<&str>) -> bool {
+        let included = self.include_projects.is_empty()
+            || project.is_some_and(|p| self.include_projects.iter().any(|i| i == p));
+        let excluded = project.is_some_and(|p| self.exclude_projects.iter().any(|e| e == p));
+        included && !excluded
+    }
 }
 
 fn default_server_url() -> String {
@@ -115,6 +492,7 @@ impl Default for CliConfig {
             api_key: std::env::var("TF_API_KEY").ok(),
             remote_url: std::env::var("TF_REMOTE_URL").ok(),
             remote_key: std::env::var("TF_REMOTE_KEY").ok(),
+            remotes: Vec::new(),
         }
     }
 }
@@ -128,11 +506,34 @@ impl CliConfig {
             Self::default()
         }
     }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::create_dir_all(config_dir())?;
+        std::fs::write(config_dir().join("cli.toml"), content)
+    }
+
+    /// Every sync target: the legacy `remote_url`/`remote_key` pair (as
+    /// [`RemoteConfig::LEGACY_NAME`]) followed by `[[remotes]]`.
+    pub fn all_remotes(&self) -> Vec<RemoteConfig> {
+        let legacy = match (&self.remote_url, &self.remote_key) {
+            (Some(url), Some(key)) if !self.has_remote(RemoteConfig::LEGACY_NAME) => {
+                Some(RemoteConfig::new(RemoteConfig::LEGACY_NAME, url, key))
+            }
+            _ => None,
+        };
+        legacy.into_iter().chain(self.remotes.iter().cloned()).collect()
+    }
+
+    fn has_remote(&self, name: &str) -> bool {
+        self.remotes.iter().any(|r| r.name == name)
+    }
 }
 
 pub fn config_dir() -> std::path::PathBuf {
     dirs::config_dir()
-        .unwrap_or_else(|| std::env::temp_dir())
+        .unwrap_or_else(std::env::temp_dir)
         .join("timeforged")
 }
 
@@ -198,7 +599,7 @@ impl WatchedRegistry {
         let dir = config_dir();
         std::fs::create_dir_all(&dir)?;
         let content = toml::to_string_pretty(self).map_err(|e| {
-            std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+            std::io::Error::other(e.to_string())
         })?;
         std::fs::write(dir.join("watched.toml"), content)
     }
@@ -249,8 +650,28 @@ mod tests {
         assert_eq!(config.idle_timeout, 300);
         assert_eq!(config.log_level, "info");
         assert_eq!(config.sync_interval, 300);
+        assert_eq!(config.sync_alert_after, 3600);
         assert!(config.database_url.starts_with("sqlite:"));
         assert!(config.database_url.contains("timeforged.db"));
+        assert_eq!((config.card.cache_secs, config.card.cache_entries), (300, 1000));
+    }
+
+    #[test]
+    fn database_and_backup_paths() {
+        let mut config = AppConfig {
+            database_url: "sqlite:/var/lib/tf/timeforged.db?mode=rwc".into(),
+            ..Default::default()
+        };
+        assert_eq!(config.database_path(), Some(PathBuf::from("/var/lib/tf/timeforged.db")));
+        assert_eq!(config.backup_dir(), Some(PathBuf::from("/var/lib/tf/backups")));
+        config.backup.dir = Some("/mnt/backups".into());
+        assert_eq!(config.backup_dir(), Some(PathBuf::from("/mnt/backups")));
+        assert_eq!(config.archive_dir(), Some(PathBuf::from("/var/lib/tf/archive")));
+        assert_eq!(config.retention.compact_after_days, 0);
+
+        config.database_url = "sqlite::memory:".into();
+        assert_eq!(config.database_path(), None);
+        assert_eq!(config.backup.keep, 7);
     }
 
     #[test]
@@ -280,6 +701,32 @@ mod tests {
         assert_eq!(config.log_level, "info");
     }
 
+    #[test]
+    fn rate_limit_config_defaults() {
+        let config = AppConfig::default();
+        assert_eq!(config.rate_limit.register_limit, 10);
+        assert_eq!(config.rate_limit.register_window_secs, 3600);
+        assert_eq!(config.rate_limit.event_limit, 120);
+        assert_eq!(config.rate_limit.event_window_secs, 60);
+        assert!(config.rate_limit.trusted_proxies.is_empty());
+    }
+
+    #[test]
+    fn rate_limit_config_from_toml() {
+        let toml_str = r#"
+            port = 8080
+
+            [rate_limit]
+            event_limit = 600
+            trusted_proxies = ["127.0.0.1", "10.0.0.0/8"]
+        "#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.rate_limit.event_limit, 600);
+        assert_eq!(config.rate_limit.event_window_secs, 60);
+        assert_eq!(config.rate_limit.register_limit, 10);
+        assert_eq!(config.rate_limit.trusted_proxies, vec!["127.0.0.1", "10.0.0.0/8"]);
+    }
+
     #[test]
     fn cli_config_from_toml() {
         let toml_str = r#"
@@ -295,6 +742,47 @@ mod tests {
         assert_eq!(config.remote_key.unwrap(), "tf_remotekey456");
     }
 
+    #[test]
+    fn cli_config_lists_legacy_and_named_remotes() {
+        let toml_str = r#"
+            remote_url = "https://remote.example.com"
+            remote_key = "tf_remotekey456"
+
+            [[remotes]]
+            name = "work"
+            url = "https://tf.work.example"
+            key = "tf_work"
+            direction = "push"
+            include_projects = ["client-a"]
+        "#;
+        let config: CliConfig = toml::from_str(toml_str).unwrap();
+        let remotes = config.all_remotes();
+        assert_eq!(remotes.len(), 2);
+        assert_eq!(remotes[0].name, RemoteConfig::LEGACY_NAME);
+        assert_eq!(remotes[0].direction, SyncMode::Both);
+        assert_eq!(remotes[1].direction, SyncMode::Push);
+
+        let saved = toml::to_string_pretty(&config).unwrap();
+        let reparsed: CliConfig = toml::from_str(&saved).unwrap();
+        assert_eq!(reparsed.all_remotes(), remotes);
+    }
+
+    #[test]
+    fn remote_project_filters() {
+        let mut remote = RemoteConfig::new("work", "https://tf.work.example", "tf_work");
+        assert!(remote.carries(Some("app")) && remote.carries(None));
+
+        remote.exclude_projects = vec!["secret".into()];
+        assert!(remote.carries(Some("app")) && !remote.carries(Some("secret")));
+
+        remote.include_projects = vec!["client-a".into()];
+        assert!(remote.carries(Some("client-a")));
+        assert!(!remote.carries(Some("app")) && !remote.carries(None));
+
+        assert!(RemoteConfig::valid_name("home-2"));
+        assert!(!RemoteConfig::valid_name("../x") && !RemoteConfig::valid_name(""));
+    }
+
     #[test]
     fn watcher_config_defaults() {
         let config = WatcherConfig::default();
@@ -335,4 +823,34 @@ mod tests {
         assert!(url.starts_with("sqlite:"), "database_url should start with sqlite:");
         assert!(url.contains("timeforged"), "database_url should contain 'timeforged'");
     }
+
+    #[test]
+    fn remote_privacy_from_toml() {
+        let toml_str = r#"
+            [[remotes]]
+            name = "public"
+            url = "https://remote.example.com"
+            key = "tf_remotekey456"
+
+            [remotes.privacy]
+            entities = "hash"
+            machines = "strip"
+            drop_branches = true
+            project_aliases = { acme-billing = "client-a" }
+            drop_metadata = ["*"]
+        "#;
+        let config: CliConfig = toml::from_str(toml_str).unwrap();
+        let remote = &config.remotes[0];
+        assert_eq!(remote.privacy.entities, Redaction::Hash);
+        assert_eq!(remote.privacy.machines, Redaction::Strip);
+        assert!(remote.privacy.drop_branches && !remote.privacy.redact_projects);
+        assert_eq!(remote.privacy.project_aliases["acme-billing"], "client-a");
+        assert_eq!(remote.effective_direction(), SyncMode::Push);
+
+        let plain = RemoteConfig::new("work", "https://tf.example.com", "tf_x");
+        assert!(plain.privacy.is_off());
+        assert_eq!(plain.effective_direction(), SyncMode::Both);
+        let saved = toml::to_string(&plain).unwrap();
+        assert!(!saved.contains("privacy") && !saved.contains("encrypted"));
+    }
 }
@@ -8,15 +8,80 @@ pub enum AppError {
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+
     #[error("bad request: {0}")]
     BadRequest(String),
 
     #[error("validation error: {0}")]
     Validation(String),
 
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    #[error("rate limit exceeded")]
+    RateLimited,
+
     #[error("database error: {0}")]
     Database(String),
 
     #[error("internal error: {0}")]
     Internal(String),
 }
+
+impl AppError {
+    /// Stable machine-readable code, sent to clients as `ErrorResponse::code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation(_) => "validation_error",
+            AppError::Conflict(_) => "conflict",
+            AppError::RateLimited => "rate_limited",
+            AppError::Database(_) | AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Internal errors carry details (SQL, paths) that must not reach clients.
+    pub fn is_internal(&self) -> bool {
+        matches!(self, AppError::Database(_) | AppError::Internal(_))
+    }
+
+    /// Message safe to return to clients.
+    pub fn public_message(&self) -> String {
+        match self {
+            AppError::NotFound(m)
+            | AppError::BadRequest(m)
+            | AppError::Forbidden(m)
+            | AppError::Validation(m)
+            | AppError::Conflict(m) => m.clone(),
+            AppError::Unauthorized => "unauthorized".into(),
+            AppError::RateLimited => "rate limit exceeded".into(),
+            AppError::Database(_) | AppError::Internal(_) => "internal server error".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_details_not_public() {
+        let e = AppError::Database("no such table: events".into());
+        assert!(e.is_internal());
+        assert_eq!(e.code(), "internal_error");
+        assert_eq!(e.public_message(), "internal server error");
+    }
+
+    #[test]
+    fn client_errors_keep_message() {
+        let e = AppError::Validation("entity must not be empty".into());
+        assert!(!e.is_internal());
+        assert_eq!(e.code(), "validation_error");
+        assert_eq!(e.public_message(), "entity must not be empty");
+    }
+}
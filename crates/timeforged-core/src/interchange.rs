@@ -0,0 +1,375 @@
+//! Event formats for export and import: JSON Lines and CSV, both written by
+//! the daemon and read back by `tf import`, and WakaTime's data dump, which
+//! can only be read.
+
+use std::io::{BufRead, Read};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::api::CreateEventRequest;
+use crate::models::{ActivityType, EventType};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One event per line, as `CreateEventRequest` JSON
+    #[default]
+    Jsonl,
+    /// A header row, then one event per row; metadata as a JSON string
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    /// The header row, if the format has one.
+    pub fn header(&self) -> Option<String> {
+        match self {
+            Self::Jsonl => None,
+            Self::Csv => Some(csv_line(&CSV_COLUMNS)),
+        }
+    }
+
+    /// One event, newline included.
+    pub fn line(&self, event: &CreateEventRequest) -> String {
+        match self {
+            Self::Jsonl => serde_json::to_string(event).unwrap_or_default() + "\n",
+            Self::Csv => csv_line(&csv_fields(event)),
+        }
+    }
+}
+
+/// A parsed event, or why it couldn't be, with the line it started on.
+pub type Parsed = (usize, Result<CreateEventRequest, String>);
+
+// --- JSON Lines ---
+
+/// Events of a JSON Lines stream; blank lines are skipped.
+pub fn read_jsonl(reader: impl BufRead) -> impl Iterator<Item = Parsed> {
+    reader.lines().enumerate().filter_map(|(i, line)| {
+        let parsed = match line {
+            Ok(line) if line.trim().is_empty() => return None,
+            Ok(line) => serde_json::from_str(&line).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        Some((i + 1, parsed))
+    })
+}
+
+// --- CSV ---
+
+pub const CSV_COLUMNS: [&str; 9] = [
+    "timestamp", "event_type", "entity", "project", "language", "branch", "activity", "machine",
+    "metadata",
+];
+
+fn csv_fields(event: &CreateEventRequest) -> [String; 9] {
+    let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+    [
+        event.timestamp.to_rfc3339(),
+        event.event_type.as_str().into(),
+        event.entity.clone(),
+        opt(&event.project),
+        opt(&event.language),
+        opt(&event.branch),
+        event.activity.as_ref().map(|a| a.as_str().to_string()).unwrap_or_default(),
+        opt(&event.machine),
+        event.metadata.as_ref().map(Value::to_string).unwrap_or_default(),
+    ]
+}
+
+fn csv_line<S: AsRef<[u8]>>(fields: &[S]) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing to memory can't fail
+    let _ = writer.write_record(fields);
+    let bytes = writer.into_inner().unwrap_or_default();
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+/// Events of a CSV stream with a header row. Columns are matched by name,
+/// in any order; only `timestamp` and `entity` are required, others
+/// default when missing or empty, and unknown ones are ignored.
+pub fn read_csv(reader: impl Read) -> Result<impl Iterator<Item = Parsed>, String> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    for required in ["timestamp", "entity"] {
+        if !headers.iter().any(|h| h == required) {
+            return Err(format!("missing column '{required}'"));
+        }
+    }
+
+    Ok(reader.into_records().map(move |record| match record {
+        Ok(record) => {
+            let line = record.position().map_or(0, |p| p.line() as usize);
+            (line, from_csv(&headers, &record))
+        }
+        Err(e) => {
+            let line = e.position().map_or(0, |p| p.line() as usize);
+            (line, Err(e.to_string()))
+        }
+    }))
+}
+
+fn from_csv(
+    headers: &csv::StringRecord,
+    record: &csv::StringRecord,
+) -> Result<CreateEventRequest, String> {
+    let field = |name: &str| {
+        let i = headers.iter().position(|h| h == name)?;
+        record.get(i).filter(|v| !v.is_empty()).map(String::from)
+    };
+
+    let timestamp = field("timestamp").ok_or("empty timestamp")?;
+    let timestamp = DateTime::parse_from_rfc3339(&timestamp)
+        .map_err(|e| format!("invalid timestamp '{timestamp}': {e}"))?
+        .with_timezone(&Utc);
+    let metadata = match field("metadata") {
+        Some(json) => {
+            Some(serde_json::from_str(&json).map_err(|e| format!("invalid metadata: {e}"))?)
+        }
+        None => None,
+    };
+    Ok(CreateEventRequest {
+        timestamp,
+        event_type: field("event_type")
+            .map_or(EventType::File, |t| EventType::from_str_lossy(&t)),
+        entity: field("entity").ok_or("empty entity")?,
+        project: field("project"),
+        language: field("language"),
+        branch: field("branch"),
+        activity: field("activity").map(|a| ActivityType::from_str_lossy(&a)),
+        machine: field("machine"),
+        metadata,
+    })
+}
+
+// --- WakaTime ---
+
+/// A WakaTime data dump (Settings → Export), or a bare list of heartbeats
+/// as the heartbeats API returns them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WakaTimeDump {
+    Days { days: Vec<WakaTimeDay> },
+    Data { data: Vec<WakaTimeHeartbeat> },
+    Heartbeats(Vec<WakaTimeHeartbeat>),
+}
+
+#[derive(Deserialize)]
+struct WakaTimeDay {
+    #[serde(default)]
+    heartbeats: Vec<WakaTimeHeartbeat>,
+}
+
+#[derive(Deserialize)]
+struct WakaTimeHeartbeat {
+    entity: String,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    /// Seconds since the epoch, with a fraction
+    time: f64,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    branch: Option<String>,
+    #[serde(default)]
+    machine_name: Option<String>,
+    #[serde(default)]
+    is_write: Option<bool>,
+    #[serde(default)]
+    lines: Option<i64>,
+}
+
+/// Events of a WakaTime dump. The whole document is read first, but only
+/// the heartbeat fields that map to events are kept. The number is the
+/// heartbeat's position in the dump.
+pub fn read_wakatime(reader: impl Read) -> Result<Vec<Parsed>, String> {
+    let dump: WakaTimeDump = serde_json::from_reader(reader)
+        .map_err(|e| format!("not a WakaTime data dump: {e}"))?;
+    let heartbeats = match dump {
+        WakaTimeDump::Days { days } => days.into_iter().flat_map(|d| d.heartbeats).collect(),
+        WakaTimeDump::Data { data } => data,
+        WakaTimeDump::Heartbeats(heartbeats) => heartbeats,
+    };
+    Ok(heartbeats
+        .into_iter()
+        .enumerate()
+        .map(|(i, heartbeat)| (i + 1, from_wakatime(heartbeat)))
+        .collect())
+}
+
+fn from_wakatime(h: WakaTimeHeartbeat) -> Result<CreateEventRequest, String> {
+    let millis = (h.time * 1000.0).round() as i64;
+    let timestamp = Utc
+        .timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| format!("invalid time {}", h.time))?;
+    let event_type = match h.kind.as_deref() {
+        Some("file") | None => EventType::File,
+        Some("domain" | "url") => EventType::Browser,
+        Some(_) => EventType::Custom,
+    };
+    let activity = h.category.as_deref().map(|c| match c {
+        "coding" | "writing tests" | "writing docs" | "code reviewing" | "ai coding" => {
+            ActivityType::Coding
+        }
+        "debugging" | "running tests" | "manual testing" => ActivityType::Debugging,
+        "building" | "indexing" => ActivityType::Building,
+        "browsing" | "researching" | "learning" => ActivityType::Browsing,
+        "communicating" | "meeting" => ActivityType::Communicating,
+        "designing" => ActivityType::Designing,
+        _ => ActivityType::Other,
+    });
+
+    let mut metadata = Map::new();
+    metadata.insert("source".into(), "wakatime".into());
+    if let Some(is_write) = h.is_write {
+        metadata.insert("is_write".into(), is_write.into());
+    }
+    if let Some(lines) = h.lines {
+        metadata.insert("lines".into(), lines.into());
+    }
+    Ok(CreateEventRequest {
+        timestamp,
+        event_type,
+        entity: h.entity,
+        project: h.project,
+        language: h.language,
+        branch: h.branch,
+        activity,
+        machine: h.machine_name,
+        metadata: Some(Value::Object(metadata)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event() -> CreateEventRequest {
+        CreateEventRequest {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap(),
+            event_type: EventType::File,
+            entity: "/home/dev/app/src/a, \"quoted\".rs".into(),
+            project: Some("app".into()),
+            language: Some("Rust".into()),
+            branch: None,
+            activity: Some(ActivityType::Debugging),
+            machine: Some("laptop".into()),
+            metadata: Some(json!({ "note": "two\nlines" })),
+        }
+    }
+
+    fn roundtrip(format: ExportFormat) -> CreateEventRequest {
+        let text = format.header().unwrap_or_default() + &format.line(&event());
+        let mut parsed: Vec<_> = match format {
+            ExportFormat::Jsonl => read_jsonl(text.as_bytes()).collect(),
+            ExportFormat::Csv => read_csv(text.as_bytes()).unwrap().collect(),
+        };
+        assert_eq!(parsed.len(), 1);
+        parsed.remove(0).1.unwrap()
+    }
+
+    #[test]
+    fn exports_read_back_unchanged() {
+        for format in [ExportFormat::Jsonl, ExportFormat::Csv] {
+            let back = roundtrip(format);
+            assert_eq!(
+                serde_json::to_value(back).unwrap(),
+                serde_json::to_value(event()).unwrap(),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn csv_columns_by_name_with_line_numbers() {
+        let text = "entity,timestamp,extra\n\
+                    a.rs,2026-03-01T10:00:00Z,x\n\
+                    b.rs,yesterday,x\n";
+        let parsed: Vec<_> = read_csv(text.as_bytes()).unwrap().collect();
+        let first = parsed[0].1.as_ref().unwrap();
+        assert_eq!((first.entity.as_str(), &first.event_type), ("a.rs", &EventType::File));
+        assert!(first.project.is_none() && first.metadata.is_none());
+        assert_eq!(parsed[1].0, 3);
+        assert!(parsed[1].1.as_ref().unwrap_err().contains("invalid timestamp"));
+
+        assert!(read_csv("entity,project\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn jsonl_reports_bad_lines() {
+        let line = ExportFormat::Jsonl.line(&event());
+        let text = format!("{line}\nnot json\n{line}");
+        let parsed: Vec<_> = read_jsonl(text.as_bytes()).collect();
+        let lines: Vec<_> = parsed.iter().map(|(n, r)| (*n, r.is_ok())).collect();
+        assert_eq!(lines, [(1, true), (3, false), (4, true)]);
+    }
+
+    #[test]
+    fn wakatime_heartbeats_become_events() {
+        let dump = json!({
+            "user": { "username": "dev" },
+            "days": [
+                { "date": "2026-03-01", "heartbeats": [
+                    {
+                        "entity": "/home/dev/app/main.rs", "type": "file",
+                        "category": "coding", "time": 1772359200.25, "project": "app",
+                        "language": "Rust", "branch": "main", "is_write": true,
+                        "lines": 40, "machine_name_id": "7d1e", "dependencies": []
+                    },
+                    { "entity": "docs.rs", "type": "domain", "category": "researching",
+                      "time": 1772359260.0 }
+                ] },
+                { "date": "2026-03-02", "heartbeats": [] }
+            ]
+        });
+        let parsed = read_wakatime(dump.to_string().as_bytes()).unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        let file = parsed[0].1.as_ref().unwrap();
+        assert_eq!(file.timestamp.timestamp_millis(), 1_772_359_200_250);
+        assert_eq!(file.activity, Some(ActivityType::Coding));
+        assert_eq!(file.branch.as_deref(), Some("main"));
+        assert_eq!(
+            file.metadata,
+            Some(json!({ "source": "wakatime", "is_write": true, "lines": 40 }))
+        );
+        let site = parsed[1].1.as_ref().unwrap();
+        assert_eq!(site.event_type, EventType::Browser);
+        assert_eq!(site.activity, Some(ActivityType::Browsing));
+
+        let bare = json!([{ "entity": "a.rs", "time": 1772359200 }]);
+        assert_eq!(read_wakatime(bare.to_string().as_bytes()).unwrap().len(), 1);
+        assert!(read_wakatime("{\"user\": {}}".as_bytes()).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 pub mod api;
 pub mod config;
 pub mod error;
+pub mod interchange;
 pub mod models;
 pub mod util;
 
@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What billed time is rounded over.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingScope {
+    /// Each stretch of work on a project without a break
+    #[default]
+    Session,
+    /// A project's total for the day
+    Day,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingDirection {
+    #[default]
+    Up,
+    Nearest,
+    Down,
+}
+
+/// How billed time is rounded, e.g. up to the next 15 minutes per session.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Rounding {
+    /// Increment in minutes, e.g. 6 or 15; 0 bills time as tracked
+    #[serde(default)]
+    pub minutes: u32,
+    #[serde(default)]
+    pub per: RoundingScope,
+    #[serde(default)]
+    pub direction: RoundingDirection,
+}
+
+impl Rounding {
+    /// `seconds` rounded to the increment.
+    pub fn apply(&self, seconds: f64) -> f64 {
+        if self.minutes == 0 || seconds <= 0.0 {
+            return seconds.max(0.0);
+        }
+        let step = self.minutes as f64 * 60.0;
+        // Tracked time is fractional; a hair over a step is still that step
+        let steps = (seconds / step * 1e6).round() / 1e6;
+        let steps = match self.direction {
+            RoundingDirection::Up => steps.ceil(),
+            RoundingDirection::Nearest => steps.round(),
+            RoundingDirection::Down => steps.floor(),
+        };
+        steps * step
+    }
+
+    /// A short description, e.g. "up to 15m per session".
+    pub fn label(&self) -> String {
+        if self.minutes == 0 {
+            return "none".into();
+        }
+        let direction = match self.direction {
+            RoundingDirection::Up => "up to",
+            RoundingDirection::Nearest => "to the nearest",
+            RoundingDirection::Down => "down to",
+        };
+        let per = match self.per {
+            RoundingScope::Session => "session",
+            RoundingScope::Day => "day",
+        };
+        format!("{direction} {}m per {per}", self.minutes)
+    }
+}
+
+/// A project billed to a client, at the client's rate unless it has its own.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ClientProject {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hourly_rate: Option<f64>,
+}
+
+/// Someone time on their projects is billed to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Client {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub hourly_rate: f64,
+    /// ISO 4217 code, e.g. "EUR"
+    pub currency: String,
+    pub rounding: Rounding,
+    pub projects: Vec<ClientProject>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Client {
+    /// The rate `project` is billed at.
+    pub fn rate_for(&self, project: &str) -> f64 {
+        self.projects
+            .iter()
+            .find(|p| p.name == project)
+            .and_then(|p| p.hourly_rate)
+            .unwrap_or(self.hourly_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounding(minutes: u32, direction: RoundingDirection) -> Rounding {
+        Rounding { minutes, per: RoundingScope::Session, direction }
+    }
+
+    #[test]
+    fn rounding_goes_to_whole_increments() {
+        let up = rounding(15, RoundingDirection::Up);
+        assert_eq!(up.apply(60.0), 900.0);
+        assert_eq!(up.apply(900.0), 900.0);
+        assert_eq!(up.apply(900.000001), 900.0);
+        assert_eq!(up.apply(901.0), 1800.0);
+        assert_eq!(up.apply(0.0), 0.0);
+
+        let nearest = rounding(6, RoundingDirection::Nearest);
+        assert_eq!(nearest.apply(170.0), 0.0);
+        assert_eq!(nearest.apply(190.0), 360.0);
+        assert_eq!(rounding(6, RoundingDirection::Down).apply(719.0), 360.0);
+        assert_eq!(rounding(0, RoundingDirection::Up).apply(61.5), 61.5);
+
+        assert_eq!(up.label(), "up to 15m per session");
+        assert_eq!(Rounding::default().label(), "none");
+    }
+
+    #[test]
+    fn projects_can_have_their_own_rate() {
+        let client = Client {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            name: "Acme".into(),
+            hourly_rate: 90.0,
+            currency: "EUR".into(),
+            rounding: Rounding::default(),
+            projects: vec![
+                ClientProject { name: "site".into(), hourly_rate: None },
+                ClientProject { name: "audit".into(), hourly_rate: Some(120.0) },
+            ],
+            created_at: Utc::now(),
+        };
+        assert_eq!(client.rate_for("site"), 90.0);
+        assert_eq!(client.rate_for("audit"), 120.0);
+    }
+}
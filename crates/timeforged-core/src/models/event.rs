@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum EventType {
     File,
@@ -35,6 +36,7 @@ impl EventType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ActivityType {
     Coding,
@@ -47,6 +49,16 @@ pub enum ActivityType {
 }
 
 impl ActivityType {
+    pub const ALL: [Self; 7] = [
+        Self::Coding,
+        Self::Browsing,
+        Self::Debugging,
+        Self::Building,
+        Self::Communicating,
+        Self::Designing,
+        Self::Other,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Coding => "coding",
@@ -73,6 +85,7 @@ impl ActivityType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Event {
     pub id: Option<i64>,
     pub user_id: Uuid,
@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How often a goal starts over. Days are UTC, like reports; weeks run
+/// Monday to Sunday.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GoalPeriod {
+    Day,
+    Week,
+}
+
+impl GoalPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            _ => None,
+        }
+    }
+}
+
+/// Time to spend per day or week, overall or on one project or language.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Goal {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub period: GoalPeriod,
+    pub target_seconds: u64,
+    /// Only time on this project counts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Only time in this language counts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Weekdays a daily goal can be missed on without breaking its streak
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub rest_days: Vec<Weekday>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Goal {
+    /// A short description, e.g. "2h/day on timeforged" or "10h/week in Rust".
+    pub fn label(&self) -> String {
+        let mut label = format!("{}/{}", format_target(self.target_seconds), self.period.as_str());
+        match (&self.project, &self.language) {
+            (Some(project), Some(language)) => label += &format!(" on {project} in {language}"),
+            (Some(project), None) => label += &format!(" on {project}"),
+            (None, Some(language)) => label += &format!(" in {language}"),
+            (None, None) => {}
+        }
+        label
+    }
+}
+
+/// "2h", "1h30m", "45m"
+fn format_target(seconds: u64) -> String {
+    let (h, m) = (seconds / 3600, seconds % 3600 / 60);
+    match (h, m) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h{m}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal(period: GoalPeriod, target_seconds: u64) -> Goal {
+        Goal {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            period,
+            target_seconds,
+            project: None,
+            language: None,
+            rest_days: vec![Weekday::Sat, Weekday::Sun],
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn labels_read_like_the_goal() {
+        let mut daily = goal(GoalPeriod::Day, 7200);
+        assert_eq!(daily.label(), "2h/day");
+        daily.project = Some("timeforged".into());
+        assert_eq!(daily.label(), "2h/day on timeforged");
+
+        let mut weekly = goal(GoalPeriod::Week, 5400);
+        weekly.language = Some("Rust".into());
+        assert_eq!(weekly.label(), "1h30m/week in Rust");
+        assert_eq!(goal(GoalPeriod::Day, 2700).label(), "45m/day");
+    }
+
+    #[test]
+    fn rest_days_use_weekday_names() {
+        let json = serde_json::to_value(goal(GoalPeriod::Day, 60)).unwrap();
+        assert_eq!(json["rest_days"], serde_json::json!(["Sat", "Sun"]));
+        assert_eq!(json["period"], "day");
+
+        let days: Vec<Weekday> = serde_json::from_str(r#"["mon","Friday"]"#).unwrap();
+        assert_eq!(days, [Weekday::Mon, Weekday::Fri]);
+    }
+}
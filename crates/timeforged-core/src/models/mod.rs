@@ -1,9 +1,15 @@
+pub mod client;
 pub mod event;
+pub mod goal;
 pub mod report;
 pub mod user;
+pub mod webhook;
 
+pub use client::{Client, ClientProject, Rounding, RoundingDirection, RoundingScope};
 pub use event::{ActivityType, Event, EventType};
+pub use goal::{Goal, GoalPeriod};
 pub use report::{
     CategorySummary, DaySummary, HourlyActivity, ReportRequest, Session, Summary,
 };
 pub use user::{ApiKey, User};
+pub use webhook::{DeliveryStatus, Webhook, WebhookDelivery, WebhookTrigger};
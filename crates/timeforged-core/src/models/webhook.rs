@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What a webhook can be subscribed to. Sent as the `event` field of the
+/// payload and the `X-TimeForged-Event` header.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookTrigger {
+    /// A batch of events was accepted via `POST /api/v1/events/batch`
+    EventsIngested,
+    /// A coding session went idle
+    SessionEnded,
+    /// Yesterday's summary, sent shortly after midnight UTC
+    DailySummary,
+    /// A goal was met
+    GoalReached,
+    /// A scheduled digest, see `DigestSettings`
+    Digest,
+    /// Test delivery; always sent regardless of subscriptions
+    Ping,
+}
+
+impl WebhookTrigger {
+    /// Triggers a webhook gets when none are given at creation.
+    pub const SUBSCRIBABLE: [WebhookTrigger; 5] = [
+        Self::EventsIngested,
+        Self::SessionEnded,
+        Self::DailySummary,
+        Self::GoalReached,
+        Self::Digest,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::EventsIngested => "events_ingested",
+            Self::SessionEnded => "session_ended",
+            Self::DailySummary => "daily_summary",
+            Self::GoalReached => "goal_reached",
+            Self::Digest => "digest",
+            Self::Ping => "ping",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "events_ingested" => Some(Self::EventsIngested),
+            "session_ended" => Some(Self::SessionEnded),
+            "daily_summary" => Some(Self::DailySummary),
+            "goal_reached" => Some(Self::GoalReached),
+            "digest" => Some(Self::Digest),
+            "ping" => Some(Self::Ping),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Webhook {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    pub triggers: Vec<WebhookTrigger>,
+    /// HMAC key for `X-TimeForged-Signature`; only returned at creation
+    #[serde(default, skip_serializing)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Not yet delivered; retried at `next_attempt_at`
+    Pending,
+    Delivered,
+    /// Gave up after the maximum number of attempts
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "delivered" => Self::Delivered,
+            "failed" => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// One entry of a webhook's delivery log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub trigger: WebhookTrigger,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    /// HTTP status of the last attempt, if the receiver answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<u16>,
+    /// Why the last attempt failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_str_roundtrip() {
+        for trigger in WebhookTrigger::SUBSCRIBABLE.iter().chain([&WebhookTrigger::Ping]) {
+            assert_eq!(WebhookTrigger::parse(trigger.as_str()), Some(*trigger));
+            let json = serde_json::to_string(trigger).unwrap();
+            assert_eq!(json, format!("\"{}\"", trigger.as_str()));
+        }
+        assert_eq!(WebhookTrigger::parse("nope"), None);
+    }
+}
@@ -91,18 +91,51 @@ pub fn is_ignored_path(path: &Path) -> bool {
     }
 
     // Ignore binary/lock files by extension
-    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        if matches!(
+    if let Some(ext) = path.extension().and_then(|e| e.to_str())
+        && matches!(
             ext,
             "lock" | "exe" | "dll" | "so" | "dylib" | "o" | "a" | "pyc" | "pyo" | "class" | "wasm"
-        ) {
-            return true;
-        }
+        )
+    {
+        return true;
     }
 
     false
 }
 
+/// Parse a delta-seconds header value (`Retry-After`, `RateLimit-Reset`),
+/// capped at `max` so a misbehaving server can't stall a client forever.
+/// The HTTP-date form of `Retry-After` is not supported.
+pub fn parse_delay_secs(value: &str, max: u64) -> Option<u64> {
+    value.trim().parse::<u64>().ok().map(|s| s.min(max))
+}
+
+/// `value` with the characters special to HTML and XML replaced and the ones
+/// XML can't hold at all (most control characters) dropped. Quotes only
+/// matter in attribute values, where line breaks and tabs are also kept as
+/// references so parsers don't fold them into spaces.
+pub fn escape_markup(value: &str, attr: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attr => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' if attr => out.push_str(&format!("&#{};", c as u32)),
+            c if !allowed_in_xml(c) => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// XML 1.0's `Char` production.
+pub fn allowed_in_xml(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}')
+        || c >= '\u{10000}'
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,4 +226,24 @@ mod tests {
         assert!(!is_ignored_path(&PathBuf::from("/project/app.ts")));
         assert!(!is_ignored_path(&PathBuf::from("/home/user/work/foo/bar.py")));
     }
+
+    // ── parse_delay_secs ──
+
+    #[test]
+    fn delay_secs_parsed_and_capped() {
+        assert_eq!(parse_delay_secs("30", 60), Some(30));
+        assert_eq!(parse_delay_secs(" 5 ", 60), Some(5));
+        assert_eq!(parse_delay_secs("3600", 60), Some(60));
+        assert_eq!(parse_delay_secs("Wed, 21 Oct 2015 07:28:00 GMT", 60), None);
+        assert_eq!(parse_delay_secs("-1", 60), None);
+    }
+
+    // ── escape_markup ──
+
+    #[test]
+    fn escape_markup_quotes_only_attributes() {
+        assert_eq!(escape_markup("a & <b> \"c\"", false), "a &amp; &lt;b&gt; \"c\"");
+        assert_eq!(escape_markup("say \"hi\"\n", true), "say &quot;hi&quot;&#10;");
+        assert_eq!(escape_markup("bell\u{7}", false), "bell");
+    }
 }
@@ -302,6 +302,124 @@ server.tool(
   }
 );
 
+// --- tf_insights ---
+server.tool(
+  "tf_insights",
+  "Get productivity patterns: best hours and weekdays, session lengths, context switches, focus and late-night time, and unusual days",
+  {
+    from: z
+      .string()
+      .optional()
+      .describe("Start datetime (ISO 8601). Defaults to 30 days before `to`."),
+    to: z
+      .string()
+      .optional()
+      .describe("End datetime (ISO 8601). Defaults to now."),
+    project: z.string().optional().describe("Filter by project name"),
+    language: z.string().optional().describe("Filter by language"),
+  },
+  async ({ from, to, project, language }) => {
+    try {
+      const params = new URLSearchParams();
+      if (from) params.set("from", from);
+      if (to) params.set("to", to);
+      if (project) params.set("project", project);
+      if (language) params.set("language", language);
+
+      const qs = params.toString();
+      const data = (await tfFetch(
+        `/api/v1/insights${qs ? `?${qs}` : ""}`
+      )) as {
+        from: string;
+        to: string;
+        active_seconds: number;
+        top_hours: number[];
+        weekdays: Array<{
+          weekday: string;
+          seconds: number;
+          average_seconds: number;
+        }>;
+        best_weekday?: string;
+        sessions: {
+          count: number;
+          average_seconds: number;
+          longest_seconds: number;
+          weekly: Array<{
+            week: string;
+            sessions: number;
+            average_seconds: number;
+          }>;
+        };
+        context_switches: { total: number; per_hour: number };
+        focus: { min_block_seconds: number; seconds: number; ratio: number };
+        late_night: {
+          seconds: number;
+          ratio: number;
+          days: number;
+          frequent: boolean;
+        };
+        unusual_days: Array<{
+          date: string;
+          seconds: number;
+          baseline_seconds: number;
+          z_score: number;
+          kind: "high" | "low";
+        }>;
+      };
+
+      const pct = (ratio: number) => `${(ratio * 100).toFixed(0)}%`;
+      const best = data.weekdays.find((w) => w.weekday === data.best_weekday);
+      const lines: string[] = [
+        `Period: ${data.from} → ${data.to}`,
+        `Active: ${formatSeconds(data.active_seconds)}`,
+        `Most productive hours (UTC): ${data.top_hours.map((h) => `${String(h).padStart(2, "0")}:00`).join(", ") || "none"}`,
+        ...(best
+          ? [
+              `Best weekday: ${best.weekday} (avg ${formatSeconds(best.average_seconds)})`,
+            ]
+          : []),
+        `Sessions: ${data.sessions.count}, average ${formatSeconds(data.sessions.average_seconds)}, longest ${formatSeconds(data.sessions.longest_seconds)}`,
+        `Context switches: ${data.context_switches.total} (${data.context_switches.per_hour.toFixed(1)} per active hour)`,
+        `Focus time: ${formatSeconds(data.focus.seconds)} (${pct(data.focus.ratio)}) in stretches of ${formatSeconds(data.focus.min_block_seconds)}+ on one project`,
+        `Late night (22:00-05:00): ${formatSeconds(data.late_night.seconds)} over ${data.late_night.days} nights (${pct(data.late_night.ratio)})${data.late_night.frequent ? ", a regular habit" : ""}`,
+      ];
+
+      if (data.sessions.weekly.length > 1) {
+        lines.push(
+          "",
+          "Average session by week:",
+          ...data.sessions.weekly.map(
+            (w) =>
+              `  ${w.week}: ${w.sessions} sessions, ${formatSeconds(w.average_seconds)} avg`
+          )
+        );
+      }
+
+      if (data.unusual_days.length > 0) {
+        lines.push(
+          "",
+          "Unusual days:",
+          ...data.unusual_days.map(
+            (d) =>
+              `  ${d.date}: ${formatSeconds(d.seconds)} vs ${formatSeconds(d.baseline_seconds)} usually (${d.kind}, z=${d.z_score.toFixed(1)})`
+          )
+        );
+      }
+
+      return {
+        content: [{ type: "text" as const, text: lines.join("\n") }],
+      };
+    } catch (e) {
+      return {
+        content: [
+          { type: "text" as const, text: `Error: ${(e as Error).message}` },
+        ],
+        isError: true,
+      };
+    }
+  }
+);
+
 // --- tf_send ---
 server.tool(
   "tf_send",
@@ -0,0 +1,283 @@
+use argon2::Argon2;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use timeforged_core::api::{
+    BatchEventResponse, CreateEventRequest, ExportEventsResponse, SealedBatchRequest,
+    SealedEvent, SealedExportResponse, SealedKeyInfo,
+};
+use timeforged_core::models::Event;
+
+use crate::engine::Endpoint;
+use crate::error::SyncError;
+use crate::http::HttpEndpoint;
+
+/// Sealed with the key into [`SealedKeyInfo::check`], to tell a wrong
+/// passphrase from a right one before anything is pushed.
+const CHECK: &[u8] = b"timeforged e2e v1";
+const NONCE_LEN: usize = 24;
+
+/// An account's end-to-end key. Events are encrypted with XChaCha20-Poly1305
+/// under a subkey, with the cleartext timestamp as associated data so the
+/// server can't move a blob in time; repeats are recognised by an HMAC of
+/// the content under a second subkey.
+pub struct SealKey {
+    cipher: XChaCha20Poly1305,
+    mac_key: [u8; 32],
+}
+
+impl SealKey {
+    /// A fresh random salt, for the first machine of an account.
+    pub fn new_salt() -> String {
+        BASE64.encode(Uuid::new_v4().as_bytes())
+    }
+
+    /// Stretch a passphrase into the key to keep in the sync state (base64).
+    /// Argon2id with its default cost, so guessing is slow.
+    pub fn derive(passphrase: &str, salt: &str) -> Result<String, SyncError> {
+        let salt = decode(salt)?;
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| SyncError::Crypto(e.to_string()))?;
+        Ok(BASE64.encode(key))
+    }
+
+    pub fn from_encoded(key: &str) -> Result<Self, SyncError> {
+        let master = decode(key)?;
+        let cipher = XChaCha20Poly1305::new_from_slice(&hmac(&master, b"encrypt"))
+            .map_err(|e| SyncError::Crypto(e.to_string()))?;
+        Ok(Self {
+            cipher,
+            mac_key: hmac(&master, b"dedup"),
+        })
+    }
+
+    /// The value other machines check their passphrase against.
+    pub fn check(&self) -> Result<String, SyncError> {
+        self.encrypt(CHECK, b"check")
+    }
+
+    pub fn verify(&self, check: &str) -> bool {
+        self.decrypt(check, b"check").is_ok_and(|plain| plain == CHECK)
+    }
+
+    pub fn seal(&self, event: &CreateEventRequest) -> Result<SealedEvent, SyncError> {
+        let json = serde_json::to_vec(event).map_err(|e| SyncError::Crypto(e.to_string()))?;
+        let timestamp = event.timestamp.to_rfc3339();
+        Ok(SealedEvent {
+            id: None,
+            timestamp: event.timestamp,
+            key: self.content_key(event),
+            blob: self.encrypt(&json, timestamp.as_bytes())?,
+        })
+    }
+
+    /// Decrypt a blob. Fails if it was sealed with another key, altered, or
+    /// stored under another timestamp than it was sealed with.
+    pub fn open(&self, sealed: &SealedEvent) -> Result<CreateEventRequest, SyncError> {
+        let timestamp = sealed.timestamp.to_rfc3339();
+        let json = self.decrypt(&sealed.blob, timestamp.as_bytes())?;
+        serde_json::from_slice(&json).map_err(|e| SyncError::Crypto(e.to_string()))
+    }
+
+    /// Everything but the metadata, like the daemon's own duplicate check.
+    fn content_key(&self, event: &CreateEventRequest) -> String {
+        let identity = CreateEventRequest {
+            metadata: None,
+            ..event.clone()
+        };
+        let mac = hmac(&self.mac_key, &serde_json::to_vec(&identity).unwrap_or_default());
+        mac.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn encrypt(&self, plain: &[u8], aad: &[u8]) -> Result<String, SyncError> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload { msg: plain, aad };
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|e| SyncError::Crypto(e.to_string()))?;
+        Ok(BASE64.encode([nonce.as_slice(), &sealed].concat()))
+    }
+
+    fn decrypt(&self, blob: &str, aad: &[u8]) -> Result<Vec<u8>, SyncError> {
+        let bytes = decode(blob)?;
+        if bytes.len() < NONCE_LEN {
+            return Err(SyncError::Crypto("blob too short".into()));
+        }
+        let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+        let payload = Payload { msg: sealed, aad };
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), payload)
+            .map_err(|_| SyncError::Crypto("blob doesn't open with this key".into()))
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, SyncError> {
+    BASE64.decode(value).map_err(|e| SyncError::Crypto(e.to_string()))
+}
+
+/// A remote that stores this account's events sealed. Whatever is pushed is
+/// encrypted here first; whatever is pulled is decrypted here.
+pub struct SealedEndpoint {
+    http: HttpEndpoint,
+    key: SealKey,
+}
+
+impl SealedEndpoint {
+    pub fn new(http: HttpEndpoint, key: SealKey) -> Self {
+        Self { http, key }
+    }
+}
+
+impl Endpoint for SealedEndpoint {
+    async fn export(&self, cursor: &str, limit: usize) -> Result<ExportEventsResponse, SyncError> {
+        let limit = limit.to_string();
+        let query = [("cursor", cursor), ("limit", limit.as_str())];
+        let page: SealedExportResponse = self.http.get("/api/v1/sealed", &query).await?;
+
+        let events = page
+            .events
+            .iter()
+            .map(|sealed| {
+                let e = self.key.open(sealed)?;
+                Ok(Event {
+                    id: sealed.id,
+                    user_id: Uuid::nil(),
+                    timestamp: e.timestamp,
+                    event_type: e.event_type,
+                    entity: e.entity,
+                    project: e.project,
+                    language: e.language,
+                    branch: e.branch,
+                    activity: e.activity,
+                    machine: e.machine,
+                    metadata: e.metadata,
+                    created_at: None,
+                })
+            })
+            .collect::<Result<Vec<_>, SyncError>>()?;
+        Ok(ExportEventsResponse {
+            count: events.len(),
+            events,
+            next_cursor: Some(page.next_cursor),
+            has_more: page.has_more,
+        })
+    }
+
+    async fn ingest(
+        &self,
+        events: Vec<CreateEventRequest>,
+    ) -> Result<BatchEventResponse, SyncError> {
+        let events = events.iter().map(|e| self.key.seal(e)).collect::<Result<_, _>>()?;
+        self.http.post("/api/v1/sealed/batch", &SealedBatchRequest { events }).await
+    }
+}
+
+/// Derive this machine's key for an encrypted account from the passphrase.
+/// The first machine picks the salt and stores it on the server with a
+/// check value; later ones must produce a key that opens that check.
+pub async fn unlock(http: &HttpEndpoint, passphrase: &str) -> Result<String, SyncError> {
+    let existing = match http.get::<SealedKeyInfo>("/api/v1/sealed/key", &[]).await {
+        Ok(info) => Some(info),
+        Err(SyncError::Status { status: 404, .. }) => None,
+        Err(e) => return Err(e),
+    };
+
+    let info = match existing {
+        Some(info) => info,
+        None => {
+            let salt = SealKey::new_salt();
+            let key = SealKey::derive(passphrase, &salt)?;
+            let check = SealKey::from_encoded(&key)?.check()?;
+            // Another machine may have won the race: use what it set
+            http.put("/api/v1/sealed/key", &SealedKeyInfo { salt, check }).await?
+        }
+    };
+
+    let key = SealKey::derive(passphrase, &info.salt)?;
+    if !SealKey::from_encoded(&key)?.verify(&info.check) {
+        return Err(SyncError::WrongPassphrase);
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use timeforged_core::models::EventType;
+
+    use super::*;
+
+    fn key() -> SealKey {
+        SealKey::from_encoded(&BASE64.encode([7u8; 32])).unwrap()
+    }
+
+    fn event() -> CreateEventRequest {
+        CreateEventRequest {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap(),
+            event_type: EventType::File,
+            entity: "/home/dev/acme/src/secret_plan.rs".into(),
+            project: Some("acme".into()),
+            language: Some("Rust".into()),
+            branch: None,
+            activity: None,
+            machine: Some("laptop".into()),
+            metadata: Some(json!({ "lines": 3 })),
+        }
+    }
+
+    #[test]
+    fn sealed_events_open_only_with_the_key() {
+        let sealed = key().seal(&event()).unwrap();
+        assert!(!sealed.blob.contains("secret_plan") && !sealed.key.contains("secret_plan"));
+        let opened = key().open(&sealed).unwrap();
+        assert_eq!(opened.entity, event().entity);
+        assert_eq!(opened.metadata, event().metadata);
+
+        let other = SealKey::from_encoded(&BASE64.encode([8u8; 32])).unwrap();
+        assert!(other.open(&sealed).is_err());
+
+        // Moved in time by the server
+        let mut moved = sealed.clone();
+        moved.timestamp += chrono::Duration::minutes(1);
+        assert!(key().open(&moved).is_err());
+    }
+
+    #[test]
+    fn repeats_share_a_key_but_not_a_blob() {
+        let (a, b) = (key().seal(&event()).unwrap(), key().seal(&event()).unwrap());
+        assert_eq!(a.key, b.key);
+        assert_ne!(a.blob, b.blob);
+
+        let mut reshaped = event();
+        reshaped.metadata = None;
+        assert_eq!(key().seal(&reshaped).unwrap().key, a.key);
+    }
+
+    #[test]
+    fn passphrase_check() {
+        let salt = SealKey::new_salt();
+        let key = SealKey::derive("correct horse", &salt).unwrap();
+        assert_eq!(key, SealKey::derive("correct horse", &salt).unwrap());
+        let check = SealKey::from_encoded(&key).unwrap().check().unwrap();
+
+        let wrong = SealKey::derive("battery staple", &salt).unwrap();
+        assert!(SealKey::from_encoded(&key).unwrap().verify(&check));
+        assert!(!SealKey::from_encoded(&wrong).unwrap().verify(&check));
+    }
+}
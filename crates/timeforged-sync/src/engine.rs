@@ -0,0 +1,558 @@
+use std::future::Future;
+use std::time::Instant;
+
+use chrono::Utc;
+
+use timeforged_core::api::{
+    BatchEventResponse, CreateEventRequest, ExportEventsResponse, SyncDirectionReport, SyncMode,
+    MAX_BATCH_EVENTS, SyncReport,
+};
+use timeforged_core::models::Event;
+
+use crate::error::SyncError;
+use crate::state::{self, StateLock};
+
+/// One side of a sync: the daemon's own database, or any server reachable
+/// over HTTP. Sources page their log by cursor; targets drop events they
+/// already have, which is what makes resuming after a failure safe.
+pub trait Endpoint: Sync {
+    /// Events after `cursor` (empty: from the start), in insertion order.
+    /// `next_cursor` is `None` only for servers that predate cursors.
+    fn export(
+        &self,
+        cursor: &str,
+        limit: usize,
+    ) -> impl Future<Output = Result<ExportEventsResponse, SyncError>> + Send;
+
+    /// Store a batch of at most [`MAX_BATCH_EVENTS`] events.
+    fn ingest(
+        &self,
+        events: Vec<CreateEventRequest>,
+    ) -> impl Future<Output = Result<BatchEventResponse, SyncError>> + Send;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Push,
+    Pull,
+}
+
+impl Direction {
+    pub fn source(&self) -> &'static str {
+        match self {
+            Direction::Push => "local",
+            Direction::Pull => "remote",
+        }
+    }
+
+    pub fn target(&self) -> &'static str {
+        match self {
+            Direction::Push => "remote",
+            Direction::Pull => "local",
+        }
+    }
+}
+
+/// Reported while a sync runs, for progress output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Progress {
+    /// A page of events was read from the source
+    Page { direction: Direction, events: usize },
+    /// The target answered for one batch of the page
+    Batch {
+        direction: Direction,
+        accepted: usize,
+        duplicates: usize,
+        rejected: usize,
+    },
+}
+
+const PAGE_SIZE: usize = 5000;
+
+type EventFilter = Box<dyn Fn(&Event) -> bool + Send + Sync>;
+type Outbound = Box<dyn Fn(&mut CreateEventRequest) + Send + Sync>;
+
+pub struct SyncEngine<L, R> {
+    local: L,
+    remote: R,
+    page_size: usize,
+    carries: EventFilter,
+    outbound: Outbound,
+}
+
+impl<L: Endpoint, R: Endpoint> SyncEngine<L, R> {
+    pub fn new(local: L, remote: R) -> Self {
+        Self {
+            local,
+            remote,
+            page_size: PAGE_SIZE,
+            carries: Box::new(|_| true),
+            outbound: Box::new(|_| {}),
+        }
+    }
+
+    /// Only sync events `carries` accepts, both ways. The others still move
+    /// the cursor and are counted as skipped.
+    pub fn carrying(mut self, carries: impl Fn(&Event) -> bool + Send + Sync + 'static) -> Self {
+        self.carries = Box::new(carries);
+        self
+    }
+
+    /// Rewrite every event before it is pushed to the remote, e.g. to
+    /// redact it. Pulled events are stored as they come.
+    pub fn outbound(
+        mut self,
+        outbound: impl Fn(&mut CreateEventRequest) + Send + Sync + 'static,
+    ) -> Self {
+        self.outbound = Box::new(outbound);
+        self
+    }
+
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub fn local(&self) -> &L {
+        &self.local
+    }
+
+    /// Push, then pull, as `mode` asks. Each direction resumes from its
+    /// cursor in `lock` and saves it after every completed page; a failing
+    /// direction is reported in its part of the report and doesn't stop the
+    /// other.
+    pub async fn run(
+        &self,
+        mode: SyncMode,
+        lock: &mut StateLock,
+        progress: &mut (dyn FnMut(Progress) + Send),
+    ) -> SyncReport {
+        let mut report = SyncReport::default();
+        if mode.pushes() {
+            let out = self.copy(&self.local, &self.remote, Direction::Push, lock, progress);
+            report.push = Some(out.await);
+        }
+        if mode.pulls() {
+            let out = self.copy(&self.remote, &self.local, Direction::Pull, lock, progress);
+            report.pull = Some(out.await);
+        }
+
+        let now = Utc::now();
+        lock.state.last_run_at = Some(now);
+        lock.state.last_error = report.errors().next().map(str::to_string);
+        if lock.state.last_error.is_some() {
+            lock.state.failures += 1;
+            lock.state.retry_at = Some(now + state::retry_delay(lock.state.failures));
+        } else {
+            lock.state.failures = 0;
+            lock.state.retry_at = None;
+        }
+        if let Err(e) = lock.save() {
+            report.error.get_or_insert(e.to_string());
+        }
+        report
+    }
+
+    async fn copy(
+        &self,
+        source: &impl Endpoint,
+        target: &impl Endpoint,
+        direction: Direction,
+        lock: &mut StateLock,
+        progress: &mut (dyn FnMut(Progress) + Send),
+    ) -> SyncDirectionReport {
+        let started = Instant::now();
+        let mut out = self.copy_pages(source, target, direction, lock, progress).await;
+        out.duration_ms = started.elapsed().as_millis() as u64;
+        out
+    }
+
+    async fn copy_pages(
+        &self,
+        source: &impl Endpoint,
+        target: &impl Endpoint,
+        direction: Direction,
+        lock: &mut StateLock,
+        progress: &mut (dyn FnMut(Progress) + Send),
+    ) -> SyncDirectionReport {
+        let mut out = SyncDirectionReport::default();
+
+        let (saved, legacy) = match direction {
+            Direction::Push => (&lock.state.push_cursor, lock.state.last_synced.is_some()),
+            Direction::Pull => (&lock.state.pull_cursor, lock.state.last_pulled.is_some()),
+        };
+
+        // Moving off the old timestamp watermark means starting over, which
+        // is only safe if the target drops what it already has
+        if saved.is_none() && legacy {
+            match supports_cursors(target).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    let e = SyncError::Unsupported(direction.target().into());
+                    out.error = Some(e.to_string());
+                    return out;
+                }
+                Err(e) => {
+                    out.error = Some(e.to_string());
+                    return out;
+                }
+            }
+        }
+
+        let mut cursor = saved.clone().unwrap_or_default();
+
+        loop {
+            let page = match source.export(&cursor, self.page_size).await {
+                Ok(page) => page,
+                Err(e) => {
+                    out.error = Some(e.to_string());
+                    break;
+                }
+            };
+            let Some(next_cursor) = page.next_cursor else {
+                let e = SyncError::Unsupported(direction.source().into());
+                out.error = Some(e.to_string());
+                break;
+            };
+            if page.events.is_empty() {
+                break;
+            }
+            progress(Progress::Page {
+                direction,
+                events: page.events.len(),
+            });
+
+            let (carried, skipped): (Vec<&Event>, Vec<&Event>) =
+                page.events.iter().partition(|e| (self.carries)(e));
+            out.skipped += skipped.len();
+
+            let mut page_accepted = 0;
+            for chunk in carried.chunks(MAX_BATCH_EVENTS) {
+                let mut batch: Vec<_> =
+                    chunk.iter().map(|e| CreateEventRequest::from_event(e)).collect();
+                if direction == Direction::Push {
+                    batch.iter_mut().for_each(&self.outbound);
+                }
+                match target.ingest(batch).await {
+                    Ok(resp) => {
+                        page_accepted += resp.accepted;
+                        out.duplicates += resp.duplicates;
+                        out.rejected += resp.rejected;
+                        progress(Progress::Batch {
+                            direction,
+                            accepted: resp.accepted,
+                            duplicates: resp.duplicates,
+                            rejected: resp.rejected,
+                        });
+                    }
+                    Err(e) => {
+                        out.error = Some(e.to_string());
+                        break;
+                    }
+                }
+            }
+            out.accepted += page_accepted;
+
+            // Only a fully delivered page moves the cursor; a partial one is
+            // resent next time and the target drops what it already got
+            match direction {
+                Direction::Push => lock.state.events_synced += page_accepted as u64,
+                Direction::Pull => lock.state.events_pulled += page_accepted as u64,
+            }
+            if out.error.is_none() {
+                cursor = next_cursor;
+                match direction {
+                    Direction::Push => lock.state.push_cursor = Some(cursor.clone()),
+                    Direction::Pull => lock.state.pull_cursor = Some(cursor.clone()),
+                }
+            }
+            if let Err(e) = lock.save() {
+                out.error.get_or_insert(e.to_string());
+            }
+
+            if out.error.is_some() || !page.has_more {
+                break;
+            }
+        }
+
+        out
+    }
+}
+
+/// Whether the endpoint pages by cursor, and so also drops duplicates.
+pub async fn supports_cursors(endpoint: &impl Endpoint) -> Result<bool, SyncError> {
+    Ok(endpoint.export("", 1).await?.next_cursor.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    use chrono::{DateTime, TimeZone, Utc};
+    use timeforged_core::api::EventCursor;
+    use timeforged_core::config::{PrivacyConfig, Redaction, RemoteConfig};
+    use timeforged_core::models::{Event, EventType};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::privacy::Redactor;
+
+    /// An event log with the same paging and dedup rules as the daemon.
+    #[derive(Default)]
+    struct Memory {
+        events: Mutex<Vec<Event>>,
+        /// Pages by timestamp only, like servers before cursors
+        legacy: bool,
+        /// Ingest calls that succeed before the endpoint goes down
+        fail_after: Option<usize>,
+        ingests: Mutex<usize>,
+        /// Every ingested batch, as sent over the wire
+        payloads: Mutex<Vec<String>>,
+    }
+
+    impl Memory {
+        fn with(entities: &[&str], machine: &str) -> Self {
+            let mem = Memory::default();
+            for (i, entity) in entities.iter().enumerate() {
+                mem.store(event(entity, machine, i as i64));
+            }
+            mem
+        }
+
+        fn store(&self, mut e: Event) -> bool {
+            let mut events = self.events.lock().unwrap();
+            let key = |e: &Event| (e.timestamp, e.entity.clone(), e.machine.clone());
+            if events.iter().any(|x| key(x) == key(&e)) {
+                return false;
+            }
+            e.id = Some(events.len() as i64 + 1);
+            events.push(e);
+            true
+        }
+
+        fn entities(&self) -> HashSet<String> {
+            self.events.lock().unwrap().iter().map(|e| e.entity.clone()).collect()
+        }
+    }
+
+    fn event(entity: &str, machine: &str, minute: i64) -> Event {
+        let at: DateTime<Utc> = Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap();
+        Event {
+            id: None,
+            user_id: Uuid::nil(),
+            timestamp: at + chrono::Duration::minutes(minute),
+            event_type: EventType::File,
+            entity: entity.into(),
+            project: None,
+            language: None,
+            branch: None,
+            activity: None,
+            machine: Some(machine.into()),
+            metadata: None,
+            created_at: None,
+        }
+    }
+
+    impl Endpoint for Memory {
+        async fn export(
+            &self,
+            cursor: &str,
+            limit: usize,
+        ) -> Result<ExportEventsResponse, SyncError> {
+            let after = EventCursor::decode(cursor).ok_or(SyncError::Parse(cursor.into()))?;
+            let all = self.events.lock().unwrap();
+            let rest: Vec<Event> = all.iter().filter(|e| e.id > Some(after.0)).cloned().collect();
+            let events: Vec<Event> = rest.iter().take(limit).cloned().collect();
+            let next = events.last().and_then(|e| e.id).map(EventCursor).unwrap_or(after);
+            Ok(ExportEventsResponse {
+                count: events.len(),
+                has_more: rest.len() > events.len(),
+                events,
+                next_cursor: (!self.legacy).then(|| next.encode()),
+            })
+        }
+
+        async fn ingest(
+            &self,
+            events: Vec<CreateEventRequest>,
+        ) -> Result<BatchEventResponse, SyncError> {
+            let mut calls = self.ingests.lock().unwrap();
+            if self.fail_after.is_some_and(|n| *calls >= n) {
+                return Err(SyncError::Request("connection refused".into()));
+            }
+            *calls += 1;
+            self.payloads.lock().unwrap().push(serde_json::to_string(&events).unwrap());
+
+            let mut resp = BatchEventResponse {
+                accepted: 0,
+                rejected: 0,
+                duplicates: 0,
+            };
+            for req in events {
+                let mut e = event(&req.entity, req.machine.as_deref().unwrap_or(""), 0);
+                e.timestamp = req.timestamp;
+                e.project = req.project;
+                if self.store(e) {
+                    resp.accepted += 1;
+                } else {
+                    resp.duplicates += 1;
+                }
+            }
+            Ok(resp)
+        }
+    }
+
+    fn scratch_lock(name: &str) -> StateLock {
+        let dir = std::env::temp_dir().join(format!("tf-engine-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        StateLock::acquire_in(&dir, "test").unwrap()
+    }
+
+    fn counts(d: &Option<SyncDirectionReport>) -> (usize, usize) {
+        let d = d.as_ref().unwrap();
+        assert!(d.error.is_none(), "{:?}", d.error);
+        (d.accepted, d.duplicates)
+    }
+
+    #[tokio::test]
+    async fn both_ways_converges_and_resumes() {
+        let local = Memory::with(&["a1", "a2", "a3"], "a");
+        let remote = Memory::with(&["b1", "b2"], "b");
+        let engine = SyncEngine::new(local, remote).page_size(2);
+        let mut lock = scratch_lock("both");
+        let mut pages = 0;
+
+        let first = engine
+            .run(SyncMode::Both, &mut lock, &mut |p| {
+                if let Progress::Page { .. } = p {
+                    pages += 1;
+                }
+            })
+            .await;
+        assert_eq!(counts(&first.push), (3, 0));
+        // The remote now also has a1..a3; those come back as duplicates
+        assert_eq!(counts(&first.pull), (2, 3));
+        assert_eq!(pages, 2 + 3);
+        assert_eq!(engine.local.entities(), engine.remote.entities());
+        assert_eq!(lock.state.events_synced, 3);
+        assert_eq!(lock.state.events_pulled, 2);
+
+        // Pulled events are new to the local log and get offered back once
+        let second = engine.run(SyncMode::Both, &mut lock, &mut |_| {}).await;
+        assert_eq!(counts(&second.push), (0, 2));
+        assert_eq!(counts(&second.pull), (0, 0));
+
+        let third = engine.run(SyncMode::Both, &mut lock, &mut |_| {}).await;
+        assert_eq!(counts(&third.push), (0, 0));
+        assert_eq!(counts(&third.pull), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn failed_page_is_resent_without_loss() {
+        let local = Memory::with(&["a1", "a2", "a3", "a4", "a5"], "a");
+        let remote = Memory {
+            fail_after: Some(1),
+            ..Default::default()
+        };
+        let engine = SyncEngine::new(local, remote).page_size(2);
+        let mut lock = scratch_lock("resume");
+
+        let report = engine.run(SyncMode::Push, &mut lock, &mut |_| {}).await;
+        assert!(report.pull.is_none());
+        assert!(report.push.as_ref().unwrap().error.is_some());
+        let resumed_from = lock.state.push_cursor.clone();
+        assert_eq!(resumed_from.as_deref(), Some("v1.2"));
+        assert_eq!(lock.state.failures, 1);
+        assert!(lock.state.retry_at.is_some());
+
+        let engine = SyncEngine::new(engine.local, Memory::default()).page_size(2);
+        let report = engine.run(SyncMode::Push, &mut lock, &mut |_| {}).await;
+        assert_eq!(counts(&report.push), (3, 0));
+        assert_eq!(lock.state.push_cursor.as_deref(), Some("v1.5"));
+        assert_eq!((lock.state.failures, lock.state.retry_at), (0, None));
+    }
+
+    #[tokio::test]
+    async fn legacy_state_needs_a_deduplicating_target() {
+        let local = Memory::with(&["a1"], "a");
+        let remote = Memory {
+            legacy: true,
+            ..Default::default()
+        };
+        let engine = SyncEngine::new(local, remote);
+        let mut lock = scratch_lock("legacy");
+        lock.state.last_synced = Some(Utc::now());
+
+        let report = engine.run(SyncMode::Both, &mut lock, &mut |_| {}).await;
+        let push = report.push.unwrap();
+        assert!(push.error.unwrap().contains("remote server is too old"));
+        assert!(engine.remote.entities().is_empty());
+        // Pulling has no legacy state, but the old remote can't be paged
+        assert!(report.pull.unwrap().error.unwrap().contains("remote server is too old"));
+        assert!(lock.state.push_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn filtered_projects_stay_local() {
+        let local = Memory::default();
+        for (i, project) in [Some("work"), Some("side"), None, Some("work")].iter().enumerate() {
+            let mut e = event(&format!("a{i}"), "a", i as i64);
+            e.project = project.map(str::to_string);
+            local.store(e);
+        }
+        let mut remote = RemoteConfig::new("work", "http://work", "key");
+        remote.include_projects = vec!["work".into()];
+        let engine = SyncEngine::new(local, Memory::default())
+            .page_size(3)
+            .carrying(move |e| remote.carries(e.project.as_deref()));
+        let mut lock = scratch_lock("filter");
+
+        let report = engine.run(SyncMode::Push, &mut lock, &mut |_| {}).await;
+        assert_eq!(counts(&report.push), (2, 0));
+        assert_eq!(report.push.unwrap().skipped, 2);
+        let sent = engine.remote.events.lock().unwrap();
+        assert!(sent.iter().all(|e| e.project.as_deref() == Some("work")));
+        // Left-out events are passed over for good, not retried every round
+        assert_eq!(lock.state.push_cursor.as_deref(), Some("v1.4"));
+    }
+
+    #[tokio::test]
+    async fn redacted_fields_never_leave() {
+        let local = Memory::default();
+        for i in 0..5 {
+            let mut e = event(&format!("/home/dev/acme/secret-{i}.rs"), "dev-laptop", i);
+            e.project = Some("acme-billing".into());
+            e.branch = Some("fix/acme-invoices".into());
+            e.language = Some("Rust".into());
+            e.metadata = Some(serde_json::json!({ "git_remote": "git@acme.internal:billing" }));
+            local.store(e);
+        }
+        let policy = PrivacyConfig {
+            entities: Redaction::Hash,
+            machines: Redaction::Hash,
+            drop_branches: true,
+            redact_projects: true,
+            drop_metadata: vec!["git_remote".into()],
+            ..Default::default()
+        };
+        let redactor = Redactor::new(policy, Redactor::new_key());
+        let engine = SyncEngine::new(local, Memory::default())
+            .page_size(2)
+            .outbound(move |e| redactor.apply(e));
+        let mut lock = scratch_lock("privacy");
+
+        let report = engine.run(SyncMode::Push, &mut lock, &mut |_| {}).await;
+        assert_eq!(counts(&report.push), (5, 0));
+        let payloads = engine.remote.payloads.lock().unwrap();
+        assert_eq!(payloads.len(), 3);
+        for payload in payloads.iter() {
+            for secret in ["acme", "secret", "dev-laptop", "fix/", "git_remote", "/home"] {
+                assert!(!payload.contains(secret), "{secret} leaked in {payload}");
+            }
+            assert!(payload.contains("Rust"));
+        }
+        // Only the copies are redacted
+        assert!(engine.local.entities().iter().all(|e| e.starts_with("/home/dev/acme/")));
+    }
+}
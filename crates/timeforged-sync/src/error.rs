@@ -0,0 +1,55 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SyncError {
+    /// The server could not be reached at all
+    #[error("request failed: {0}")]
+    Request(String),
+
+    /// The server answered with an error status
+    #[error("{side} server returned HTTP {status}: {message}")]
+    Status {
+        side: String,
+        status: u16,
+        message: String,
+    },
+
+    #[error("parse error: {0}")]
+    Parse(String),
+
+    /// Reading or writing the local store failed
+    #[error("store error: {0}")]
+    Store(String),
+
+    /// The server pages by timestamp only and doesn't drop duplicates
+    #[error("the {0} server is too old for cursor sync; upgrade it first")]
+    Unsupported(String),
+
+    #[error("no remote configured (add one with `tf remote add` or `tf link`)")]
+    NotConfigured,
+
+    #[error("no remote named '{0}'")]
+    UnknownRemote(String),
+
+    /// Another process holds the sync state lock
+    #[error("another sync is already running")]
+    Locked,
+
+    #[error("sync state: {0}")]
+    State(String),
+
+    /// Encrypting or decrypting sealed events failed
+    #[error("encryption: {0}")]
+    Crypto(String),
+
+    #[error("wrong passphrase for this account's encrypted events")]
+    WrongPassphrase,
+
+    /// An encrypted remote this machine hasn't derived the key for yet
+    #[error("remote '{0}' is encrypted and locked here; run `tf remote unlock {0}`")]
+    NoKey(String),
+
+    /// Reading or writing the outbound spool failed
+    #[error("spool: {0}")]
+    Spool(String),
+}
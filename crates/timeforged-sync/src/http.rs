@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use timeforged_core::api::{
+    BatchEventRequest, BatchEventResponse, CreateEventRequest, ErrorResponse,
+    ExportEventsResponse,
+};
+use timeforged_core::util::parse_delay_secs;
+
+use crate::engine::Endpoint;
+use crate::error::SyncError;
+
+/// How many times a rate-limited (429) request is retried before giving up.
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+/// Upper bound on a single rate-limit pause, whatever the server asks for.
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// A TimeForged server's events API.
+pub struct HttpEndpoint {
+    http: Client,
+    base_url: String,
+    api_key: Option<String>,
+    /// "local" or "remote", for error messages
+    side: &'static str,
+}
+
+impl HttpEndpoint {
+    pub fn new(base_url: &str, api_key: Option<String>, side: &'static str) -> Self {
+        Self {
+            http: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            side,
+        }
+    }
+
+    /// Send with the API key, waiting out rate limits.
+    async fn send(&self, build: impl Fn() -> RequestBuilder) -> Result<Response, SyncError> {
+        let keyed = || match self.api_key {
+            Some(ref key) => build().header("X-Api-Key", key),
+            None => build(),
+        };
+        let resp = send_with_backoff(keyed, |secs| {
+            tracing::info!("sync: rate limited by {} server, retrying in {secs}s", self.side);
+        })
+        .await
+        .map_err(|e| SyncError::Request(e.to_string()))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let message = resp
+                .json::<ErrorResponse>()
+                .await
+                .map(|e| e.error)
+                .unwrap_or_else(|_| status.to_string());
+            return Err(SyncError::Status {
+                side: self.side.into(),
+                status: status.as_u16(),
+                message,
+            });
+        }
+        Ok(resp)
+    }
+
+    async fn json<T: DeserializeOwned>(resp: Response) -> Result<T, SyncError> {
+        resp.json::<T>().await.map_err(|e| SyncError::Parse(e.to_string()))
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, SyncError> {
+        let url = format!("{}{path}", self.base_url);
+        let resp = self.send(|| self.http.get(&url).query(query)).await?;
+        Self::json(resp).await
+    }
+
+    pub(crate) async fn post<T: DeserializeOwned, B: Serialize>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, SyncError> {
+        let url = format!("{}{path}", self.base_url);
+        let resp = self.send(|| self.http.post(&url).json(body)).await?;
+        Self::json(resp).await
+    }
+
+    pub(crate) async fn put<T: DeserializeOwned, B: Serialize>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, SyncError> {
+        let url = format!("{}{path}", self.base_url);
+        let resp = self.send(|| self.http.put(&url).json(body)).await?;
+        Self::json(resp).await
+    }
+}
+
+impl Endpoint for HttpEndpoint {
+    async fn export(&self, cursor: &str, limit: usize) -> Result<ExportEventsResponse, SyncError> {
+        let limit = limit.to_string();
+        self.get("/api/v1/events", &[("cursor", cursor), ("limit", &limit)]).await
+    }
+
+    async fn ingest(
+        &self,
+        events: Vec<CreateEventRequest>,
+    ) -> Result<BatchEventResponse, SyncError> {
+        self.post("/api/v1/events/batch", &BatchEventRequest { events }).await
+    }
+}
+
+/// Send the request `build` makes, waiting out rate limits: a 429 is
+/// rebuilt and retried after `Retry-After` (`on_retry` hears how long), and
+/// when a success reports the window exhausted we pause until it resets so
+/// the next request isn't rejected. Any other status is the caller's.
+pub async fn send_with_backoff(
+    build: impl Fn() -> RequestBuilder,
+    on_retry: impl Fn(u64),
+) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        let resp = build().send().await?;
+
+        let status = resp.status();
+        if status == StatusCode::TOO_MANY_REQUESTS && attempt < MAX_RATE_LIMIT_RETRIES {
+            attempt += 1;
+            let secs = header_secs(&resp, "retry-after").unwrap_or(1 << attempt);
+            on_retry(secs);
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            continue;
+        }
+
+        if status.is_success()
+            && header_str(&resp, "ratelimit-remaining") == Some("0")
+            && let Some(secs) = header_secs(&resp, "ratelimit-reset")
+        {
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+        }
+        return Ok(resp);
+    }
+}
+
+fn header_str<'a>(resp: &'a Response, name: &str) -> Option<&'a str> {
+    resp.headers().get(name)?.to_str().ok()
+}
+
+fn header_secs(resp: &Response, name: &str) -> Option<u64> {
+    parse_delay_secs(header_str(resp, name)?, MAX_BACKOFF_SECS)
+}
@@ -0,0 +1,30 @@
+//! Event sync between a local daemon and a remote server, shared by the
+//! daemon's auto-sync and `tf sync`.
+//!
+//! Both sides are [`Endpoint`]s that page their event log by cursor and
+//! deduplicate what they ingest, so a sync can stop anywhere and resume.
+//! Each remote from cli.toml keeps its progress in its own state file,
+//! guarded by a file lock so only one process syncs it at a time. A remote's
+//! privacy policy is applied by a [`Redactor`] to everything pushed to it,
+//! and an encrypted remote is reached through a [`SealedEndpoint`], which
+//! encrypts what is pushed and decrypts what is pulled.
+//!
+//! Events `tf send` couldn't deliver to the daemon wait in a [`Spool`];
+//! remotes that fail are retried with exponential backoff.
+
+pub mod e2e;
+mod engine;
+mod error;
+mod http;
+mod privacy;
+pub mod remote;
+pub mod spool;
+pub mod state;
+
+pub use engine::{Direction, Endpoint, Progress, SyncEngine, supports_cursors};
+pub use e2e::{SealKey, SealedEndpoint};
+pub use error::SyncError;
+pub use http::{HttpEndpoint, send_with_backoff};
+pub use privacy::Redactor;
+pub use spool::Spool;
+pub use state::StateLock;
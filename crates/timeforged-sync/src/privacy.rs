@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use timeforged_core::api::CreateEventRequest;
+use timeforged_core::config::{PrivacyConfig, Redaction};
+
+/// Stands in for stripped entities, which the server requires.
+const STRIPPED_ENTITY: &str = "redacted";
+
+/// Applies a remote's [`PrivacyConfig`] to the events pushed to it. Hashes
+/// are keyed with a secret that stays on this machine, so the remote can't
+/// recover paths or names by hashing guesses; the same key makes them
+/// stable, so replayed events still deduplicate.
+pub struct Redactor {
+    policy: PrivacyConfig,
+    key: String,
+    generated: Mutex<BTreeMap<String, String>>,
+}
+
+impl Redactor {
+    pub fn new(policy: PrivacyConfig, key: String) -> Self {
+        Self {
+            policy,
+            key,
+            generated: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// A fresh key, kept in the remote's sync state.
+    pub fn new_key() -> String {
+        Uuid::new_v4().simple().to_string()
+    }
+
+    pub fn apply(&self, event: &mut CreateEventRequest) {
+        match self.policy.entities {
+            Redaction::Keep => {}
+            Redaction::Hash => event.entity = self.hash(&event.entity),
+            Redaction::Strip => event.entity = STRIPPED_ENTITY.into(),
+        }
+        event.machine = match self.policy.machines {
+            Redaction::Keep => event.machine.take(),
+            Redaction::Hash => event.machine.as_deref().map(|m| self.hash(m)),
+            Redaction::Strip => None,
+        };
+        if self.policy.drop_branches {
+            event.branch = None;
+        }
+        event.project = event.project.as_deref().map(|p| self.project(p));
+        self.strip_metadata(&mut event.metadata);
+    }
+
+    /// Aliases made up by `redact_projects` so far, by real project name.
+    pub fn generated_aliases(&self) -> BTreeMap<String, String> {
+        self.generated.lock().unwrap().clone()
+    }
+
+    fn project(&self, name: &str) -> String {
+        if let Some(alias) = self.policy.project_aliases.get(name) {
+            return alias.clone();
+        }
+        if !self.policy.redact_projects {
+            return name.to_string();
+        }
+        let alias = format!("project-{}", &self.hash(name)[..8]);
+        self.generated.lock().unwrap().insert(name.to_string(), alias.clone());
+        alias
+    }
+
+    /// Metadata that isn't an object can't have keys removed, so it is
+    /// dropped whole once any key is listed.
+    fn strip_metadata(&self, metadata: &mut Option<Value>) {
+        let drop = &self.policy.drop_metadata;
+        if drop.is_empty() {
+            return;
+        }
+        match metadata {
+            Some(Value::Object(map)) if !drop.iter().any(|k| k == "*") => {
+                map.retain(|key, _| !drop.contains(key));
+            }
+            _ => *metadata = None,
+        }
+    }
+
+    fn hash(&self, value: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(value.as_bytes());
+        let hex = format!("{:x}", mac.finalize().into_bytes());
+        hex[..16].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use timeforged_core::models::EventType;
+
+    use super::*;
+
+    fn request() -> CreateEventRequest {
+        CreateEventRequest {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap(),
+            event_type: EventType::File,
+            entity: "/home/dev/acme/billing/src/invoice.rs".into(),
+            project: Some("acme-billing".into()),
+            language: Some("Rust".into()),
+            branch: Some("fix/acme-1234".into()),
+            activity: None,
+            machine: Some("dev-laptop".into()),
+            metadata: Some(json!({ "lines": 120, "git_remote": "git@acme.internal:billing" })),
+        }
+    }
+
+    fn redact(policy: PrivacyConfig) -> CreateEventRequest {
+        let mut req = request();
+        Redactor::new(policy, "k1".into()).apply(&mut req);
+        req
+    }
+
+    #[test]
+    fn default_policy_changes_nothing() {
+        let req = redact(PrivacyConfig::default());
+        assert_eq!(serde_json::to_value(req).unwrap(), serde_json::to_value(request()).unwrap());
+    }
+
+    #[test]
+    fn hashes_are_keyed_and_stable() {
+        let policy = PrivacyConfig {
+            entities: Redaction::Hash,
+            machines: Redaction::Hash,
+            ..Default::default()
+        };
+        let a = redact(policy.clone());
+        assert_eq!(a.entity.len(), 16);
+        assert_eq!(a.entity, redact(policy.clone()).entity);
+        assert_ne!(a.entity, a.machine.clone().unwrap());
+
+        let mut other = request();
+        Redactor::new(policy, "k2".into()).apply(&mut other);
+        assert_ne!(a.entity, other.entity);
+    }
+
+    #[test]
+    fn strip_and_drop() {
+        let req = redact(PrivacyConfig {
+            entities: Redaction::Strip,
+            machines: Redaction::Strip,
+            drop_branches: true,
+            drop_metadata: vec!["git_remote".into()],
+            ..Default::default()
+        });
+        assert_eq!(req.entity, STRIPPED_ENTITY);
+        assert!(req.machine.is_none() && req.branch.is_none());
+        assert_eq!(req.metadata, Some(json!({ "lines": 120 })));
+        assert_eq!(req.language.as_deref(), Some("Rust"));
+
+        let req = redact(PrivacyConfig {
+            drop_metadata: vec!["*".into()],
+            ..Default::default()
+        });
+        assert!(req.metadata.is_none());
+    }
+
+    #[test]
+    fn projects_use_aliases_then_hashes() {
+        let mut policy = PrivacyConfig {
+            redact_projects: true,
+            ..Default::default()
+        };
+        let redactor = Redactor::new(policy.clone(), "k1".into());
+        let mut req = request();
+        redactor.apply(&mut req);
+        let alias = req.project.unwrap();
+        assert!(alias.starts_with("project-") && alias.len() == 16);
+        assert_eq!(redactor.generated_aliases()["acme-billing"], alias);
+
+        policy.project_aliases.insert("acme-billing".into(), "client-a".into());
+        assert_eq!(redact(policy).project.as_deref(), Some("client-a"));
+    }
+}
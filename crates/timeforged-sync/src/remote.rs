@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use timeforged_core::api::{SyncMode, SyncReport};
+use timeforged_core::config::{CliConfig, RemoteConfig};
+
+use crate::e2e::{self, SealKey, SealedEndpoint};
+use crate::engine::{Endpoint, Progress, SyncEngine};
+use crate::error::SyncError;
+use crate::http::HttpEndpoint;
+use crate::privacy::Redactor;
+use crate::state::StateLock;
+
+/// The remotes a sync should cover: all configured ones, or just `only`.
+pub fn select(config: &CliConfig, only: Option<&str>) -> Result<Vec<RemoteConfig>, SyncError> {
+    let remotes = config.all_remotes();
+    if remotes.is_empty() {
+        return Err(SyncError::NotConfigured);
+    }
+    match only {
+        None => Ok(remotes),
+        Some(name) => match remotes.into_iter().find(|r| r.name == name) {
+            Some(remote) => Ok(vec![remote]),
+            None => Err(SyncError::UnknownRemote(name.to_string())),
+        },
+    }
+}
+
+/// Sync `local` with one configured remote, in the directions both `mode`
+/// and the remote allow, carrying only the projects it accepts, redacted by
+/// its privacy policy and, for an encrypted remote, sealed. `None` if there
+/// is no such direction. A remote that can't be synced right now (e.g.
+/// locked by another sync) gets a report with `error` set.
+pub async fn sync_remote<L: Endpoint>(
+    local: L,
+    remote: &RemoteConfig,
+    mode: SyncMode,
+    progress: &mut (dyn FnMut(Progress) + Send),
+) -> Option<SyncReport> {
+    let mode = remote.effective_direction().intersect(mode)?;
+
+    let mut report = match StateLock::acquire(&remote.name) {
+        Ok(mut lock) => {
+            let http = HttpEndpoint::new(&remote.url, Some(remote.key.clone()), "remote");
+            if !remote.encrypted {
+                run(local, http, remote, mode, &mut lock, progress).await
+            } else {
+                match sealed(http, remote, &lock) {
+                    Ok(endpoint) => run(local, endpoint, remote, mode, &mut lock, progress).await,
+                    Err(e) => failed(e),
+                }
+            }
+        }
+        Err(e) => failed(e),
+    };
+    report.remote = remote.name.clone();
+    Some(report)
+}
+
+fn failed(e: SyncError) -> SyncReport {
+    SyncReport {
+        error: Some(e.to_string()),
+        ..Default::default()
+    }
+}
+
+fn sealed(
+    http: HttpEndpoint,
+    remote: &RemoteConfig,
+    lock: &StateLock,
+) -> Result<SealedEndpoint, SyncError> {
+    let key = lock.state.e2e_key.as_deref().ok_or_else(|| SyncError::NoKey(remote.name.clone()))?;
+    Ok(SealedEndpoint::new(http, SealKey::from_encoded(key)?))
+}
+
+async fn run<L: Endpoint, R: Endpoint>(
+    local: L,
+    endpoint: R,
+    remote: &RemoteConfig,
+    mode: SyncMode,
+    lock: &mut StateLock,
+    progress: &mut (dyn FnMut(Progress) + Send),
+) -> SyncReport {
+    let filter = remote.clone();
+    let mut engine =
+        SyncEngine::new(local, endpoint).carrying(move |e| filter.carries(e.project.as_deref()));
+
+    let redactor = (!remote.privacy.is_off()).then(|| {
+        let key = lock.state.privacy_key.get_or_insert_with(Redactor::new_key);
+        Arc::new(Redactor::new(remote.privacy.clone(), key.clone()))
+    });
+    if let Some(redactor) = &redactor {
+        let redactor = redactor.clone();
+        engine = engine.outbound(move |e| redactor.apply(e));
+    }
+
+    let mut report = engine.run(mode, lock, progress).await;
+    if let Some(redactor) = redactor {
+        let aliases = redactor.generated_aliases();
+        if aliases.iter().any(|(k, v)| lock.state.project_aliases.get(k) != Some(v)) {
+            lock.state.project_aliases.extend(aliases);
+            if let Err(e) = lock.save() {
+                report.error.get_or_insert(e.to_string());
+            }
+        }
+    }
+    report
+}
+
+/// Derive an encrypted remote's key from the passphrase and keep it in the
+/// remote's sync state, so this machine can sync it unattended.
+pub async fn unlock(remote: &RemoteConfig, passphrase: &str) -> Result<(), SyncError> {
+    let http = HttpEndpoint::new(&remote.url, Some(remote.key.clone()), "remote");
+    let key = e2e::unlock(&http, passphrase).await?;
+    let mut lock = StateLock::acquire(&remote.name)?;
+    lock.state.e2e_key = Some(key);
+    lock.save()
+}
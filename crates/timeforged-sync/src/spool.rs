@@ -0,0 +1,350 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use timeforged_core::api::{CreateEventRequest, MAX_BATCH_EVENTS};
+use timeforged_core::config::config_dir;
+
+use crate::engine::Endpoint;
+use crate::error::SyncError;
+
+/// Events kept at most; the oldest are dropped to make room.
+pub const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    queued_at: DateTime<Utc>,
+    event: CreateEventRequest,
+}
+
+/// What [`Spool::push`] did with an event.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Enqueued {
+    /// False if the same event was already waiting
+    pub queued: bool,
+    /// Oldest events dropped because the spool was full
+    pub dropped: usize,
+    pub pending: usize,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    pub accepted: usize,
+    pub duplicates: usize,
+    /// Refused by the server; these are dropped, a retry wouldn't help
+    pub rejected: usize,
+    /// Still waiting, e.g. queued by another process during the flush
+    pub pending: usize,
+}
+
+/// Events that couldn't be delivered to the daemon, kept as JSON lines in
+/// `spool.jsonl` until they can be. Each carries a content key, so an event
+/// queued twice (a hook retrying, two flushes racing) is stored and sent
+/// once; the daemon drops repeats of what it already has.
+pub struct Spool {
+    dir: PathBuf,
+    max_entries: usize,
+}
+
+/// Exclusive hold on the spool file, released on drop. Holders only do
+/// file work, so waiting for it is short.
+struct SpoolLock {
+    _file: File,
+}
+
+impl Spool {
+    /// The spool in the config directory.
+    pub fn open() -> Self {
+        Self::in_dir(&config_dir())
+    }
+
+    pub fn in_dir(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            max_entries: MAX_ENTRIES,
+        }
+    }
+
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max.max(1);
+        self
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join("spool.jsonl")
+    }
+
+    fn lock(&self) -> Result<SpoolLock, SyncError> {
+        std::fs::create_dir_all(&self.dir).map_err(io)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(self.dir.join("spool.lock"))
+            .map_err(io)?;
+        file.lock().map_err(io)?;
+        Ok(SpoolLock { _file: file })
+    }
+
+    /// Events waiting to be delivered.
+    pub fn pending(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Queue an event, unless the same one is already waiting.
+    pub fn push(&self, event: CreateEventRequest) -> Result<Enqueued, SyncError> {
+        let _lock = self.lock()?;
+        let (mut entries, torn) = self.read_checked();
+        let key = event_key(&event);
+        if entries.iter().any(|e| e.key == key) {
+            return Ok(Enqueued {
+                queued: false,
+                dropped: 0,
+                pending: entries.len(),
+            });
+        }
+
+        let entry = Entry {
+            key,
+            queued_at: Utc::now(),
+            event,
+        };
+        let dropped = (entries.len() + 1).saturating_sub(self.max_entries);
+        if dropped > 0 || torn {
+            entries.drain(..dropped);
+            entries.push(entry);
+            self.write(&entries)?;
+        } else {
+            let line = to_line(&entry)?;
+            let mut file =
+                OpenOptions::new().create(true).append(true).open(self.path()).map_err(io)?;
+            file.write_all(line.as_bytes()).map_err(io)?;
+            entries.push(entry);
+        }
+        Ok(Enqueued {
+            queued: true,
+            dropped,
+            pending: entries.len(),
+        })
+    }
+
+    /// Send everything waiting to `endpoint`, oldest first. Each batch is
+    /// removed once the endpoint took it, so a flush that fails halfway
+    /// leaves only the rest queued.
+    pub async fn flush<E: Endpoint>(&self, endpoint: &E) -> Result<FlushReport, SyncError> {
+        // Not locked while sending, so hooks never wait on the network
+        let snapshot = {
+            let _lock = self.lock()?;
+            self.read()
+        };
+
+        let mut report = FlushReport::default();
+        for batch in snapshot.chunks(MAX_BATCH_EVENTS) {
+            let events = batch.iter().map(|e| e.event.clone()).collect();
+            let resp = endpoint.ingest(events).await?;
+            report.accepted += resp.accepted;
+            report.duplicates += resp.duplicates;
+            report.rejected += resp.rejected;
+
+            let sent: HashSet<&str> = batch.iter().map(|e| e.key.as_str()).collect();
+            let _lock = self.lock()?;
+            let mut entries = self.read();
+            entries.retain(|e| !sent.contains(e.key.as_str()));
+            self.write(&entries)?;
+        }
+        report.pending = self.pending();
+        Ok(report)
+    }
+
+    fn read(&self) -> Vec<Entry> {
+        self.read_checked().0
+    }
+
+    /// Lines that don't parse (say, cut short by a crash) are skipped; the
+    /// flag says there were some, so the file is rewritten rather than
+    /// appended to.
+    fn read_checked(&self) -> (Vec<Entry>, bool) {
+        let content = std::fs::read_to_string(self.path()).unwrap_or_default();
+        let lines = content.lines().count();
+        let entries: Vec<Entry> =
+            content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect();
+        let torn = entries.len() < lines;
+        (entries, torn)
+    }
+
+    fn write(&self, entries: &[Entry]) -> Result<(), SyncError> {
+        let content = entries.iter().map(to_line).collect::<Result<String, _>>()?;
+        let tmp = self.path().with_extension("jsonl.tmp");
+        std::fs::write(&tmp, content)
+            .and_then(|()| std::fs::rename(&tmp, self.path()))
+            .map_err(io)
+    }
+}
+
+fn to_line(entry: &Entry) -> Result<String, SyncError> {
+    serde_json::to_string(entry)
+        .map(|json| json + "\n")
+        .map_err(|e| SyncError::Spool(e.to_string()))
+}
+
+fn io(e: std::io::Error) -> SyncError {
+    SyncError::Spool(e.to_string())
+}
+
+/// Everything but the metadata, like the daemon's own duplicate check.
+fn event_key(event: &CreateEventRequest) -> String {
+    let identity = (
+        event.timestamp,
+        &event.event_type,
+        &event.entity,
+        &event.project,
+        &event.language,
+        &event.branch,
+        &event.activity,
+        &event.machine,
+    );
+    let json = serde_json::to_vec(&identity).unwrap_or_default();
+    format!("{:x}", Sha256::digest(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::TimeZone;
+    use timeforged_core::api::{BatchEventResponse, ExportEventsResponse};
+    use timeforged_core::models::EventType;
+
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tf-spool-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn event(entity: &str, minute: u32) -> CreateEventRequest {
+        CreateEventRequest {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 1, 10, minute, 0).unwrap(),
+            event_type: EventType::File,
+            entity: entity.into(),
+            project: None,
+            language: None,
+            branch: None,
+            activity: None,
+            machine: Some("laptop".into()),
+            metadata: None,
+        }
+    }
+
+    /// A daemon that is either down or takes every batch it would accept.
+    #[derive(Default)]
+    struct Daemon {
+        down: bool,
+        received: Mutex<Vec<String>>,
+    }
+
+    impl Endpoint for Daemon {
+        async fn export(&self, _: &str, _: usize) -> Result<ExportEventsResponse, SyncError> {
+            unreachable!("a spool only sends")
+        }
+
+        async fn ingest(
+            &self,
+            events: Vec<CreateEventRequest>,
+        ) -> Result<BatchEventResponse, SyncError> {
+            if self.down {
+                return Err(SyncError::Request("connection refused".into()));
+            }
+            if events.len() > MAX_BATCH_EVENTS {
+                return Err(SyncError::Status {
+                    side: "local".into(),
+                    status: 400,
+                    message: format!("batch size exceeds {MAX_BATCH_EVENTS}"),
+                });
+            }
+            let mut received = self.received.lock().unwrap();
+            received.extend(events.iter().map(|e| e.entity.clone()));
+            Ok(BatchEventResponse {
+                accepted: events.len(),
+                rejected: 0,
+                duplicates: 0,
+            })
+        }
+    }
+
+    #[test]
+    fn repeats_are_queued_once_and_oldest_dropped_when_full() {
+        let dir = scratch_dir("bounded");
+        let spool = Spool::in_dir(&dir).max_entries(2);
+
+        assert!(spool.push(event("a.rs", 1)).unwrap().queued);
+        let mut repeat = event("a.rs", 1);
+        repeat.metadata = Some(serde_json::json!({ "hook": "Stop" }));
+        assert!(!spool.push(repeat).unwrap().queued);
+        spool.push(event("b.rs", 2)).unwrap();
+
+        let full = spool.push(event("c.rs", 3)).unwrap();
+        assert_eq!((full.dropped, full.pending), (1, 2));
+        let entities: Vec<_> = spool.read().into_iter().map(|e| e.event.entity).collect();
+        assert_eq!(entities, ["b.rs", "c.rs"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn flush_keeps_events_until_delivered() {
+        let dir = scratch_dir("flush");
+        let spool = Spool::in_dir(&dir);
+        spool.push(event("a.rs", 1)).unwrap();
+        spool.push(event("b.rs", 2)).unwrap();
+
+        let down = Daemon {
+            down: true,
+            ..Default::default()
+        };
+        assert!(spool.flush(&down).await.is_err());
+        assert_eq!(spool.pending(), 2);
+
+        let up = Daemon::default();
+        let report = spool.flush(&up).await.unwrap();
+        assert_eq!((report.accepted, report.pending), (2, 0));
+        assert_eq!(*up.received.lock().unwrap(), ["a.rs", "b.rs"]);
+        assert_eq!(spool.flush(&up).await.unwrap(), FlushReport::default());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn an_outage_worth_of_events_drains() {
+        let dir = scratch_dir("outage");
+        let spool = Spool::in_dir(&dir);
+        for i in 0..250 {
+            spool.push(event(&format!("{i}.rs"), i % 60)).unwrap();
+        }
+
+        let up = Daemon::default();
+        let report = spool.flush(&up).await.unwrap();
+        assert_eq!((report.accepted, report.pending), (250, 0));
+        assert_eq!(up.received.lock().unwrap().len(), 250);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn torn_lines_are_skipped() {
+        let dir = scratch_dir("torn");
+        let spool = Spool::in_dir(&dir);
+        spool.push(event("a.rs", 1)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(spool.path()).unwrap();
+        write!(file, "{{\"key\":\"abc\",\"queued").unwrap();
+        assert_eq!(spool.pending(), 1);
+        // The next push drops the torn line rather than appending to it
+        spool.push(event("b.rs", 2)).unwrap();
+        assert_eq!(spool.pending(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
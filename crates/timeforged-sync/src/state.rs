@@ -0,0 +1,174 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use timeforged_core::api::SyncStateFile;
+use timeforged_core::config::{RemoteConfig, config_dir};
+
+use crate::error::SyncError;
+
+/// First wait after a failed run; it doubles with every further failure.
+const RETRY_BASE_SECS: i64 = 30;
+/// Longest wait between retries, however long a remote stays down.
+const RETRY_MAX_SECS: i64 = 3600;
+
+/// `sync-state.toml` for the legacy remote, so its cursors carry over, and
+/// `sync-state.<name>.toml` for the others. The lock file is next to it.
+fn file_name(remote: &str, ext: &str) -> String {
+    if remote == RemoteConfig::LEGACY_NAME {
+        format!("sync-state.{ext}")
+    } else {
+        format!("sync-state.{remote}.{ext}")
+    }
+}
+
+/// Exclusive hold on one remote's sync state, with the state loaded. The
+/// lock is taken on a sibling `.lock` file so the state file itself can be
+/// replaced atomically; the OS releases it on drop or if the process dies.
+pub struct StateLock {
+    _lock: File,
+    path: PathBuf,
+    pub state: SyncStateFile,
+}
+
+/// A remote's saved state without taking the lock, for display.
+pub fn read(remote: &str) -> SyncStateFile {
+    read_from(&config_dir().join(file_name(remote, "toml")))
+}
+
+/// Drop a remote's state, so it syncs from scratch next time. Used when a
+/// remote is removed or pointed elsewhere; cursors are only meaningful for
+/// the server that issued them.
+pub fn forget(remote: &str) -> Result<(), SyncError> {
+    let lock = StateLock::acquire(remote)?;
+    match std::fs::remove_file(&lock.path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(SyncError::State(e.to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// How long to wait before retrying after `failures` failed runs in a row:
+/// 30s, 1m, 2m, ... up to an hour.
+pub fn retry_delay(failures: u32) -> Duration {
+    let doublings = failures.saturating_sub(1).min(16);
+    Duration::seconds((RETRY_BASE_SECS << doublings).min(RETRY_MAX_SECS))
+}
+
+/// When a remote is next due for an automatic sync: `interval` after its
+/// last run, or its retry time while it is failing. Never-synced remotes
+/// are due right away.
+pub fn next_due(state: &SyncStateFile, interval: Duration) -> DateTime<Utc> {
+    match (state.retry_at, state.last_run_at) {
+        (Some(retry_at), _) => retry_at,
+        (None, Some(last_run_at)) => last_run_at + interval,
+        (None, None) => DateTime::<Utc>::MIN_UTC,
+    }
+}
+
+fn read_from(path: &Path) -> SyncStateFile {
+    // A missing or unreadable file starts over; the remote drops repeats
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+impl StateLock {
+    /// Lock a remote's state in the config directory. Fails with
+    /// [`SyncError::Locked`] instead of waiting if another sync holds it.
+    pub fn acquire(remote: &str) -> Result<Self, SyncError> {
+        Self::acquire_in(&config_dir(), remote)
+    }
+
+    pub fn acquire_in(dir: &Path, remote: &str) -> Result<Self, SyncError> {
+        let io = |e: std::io::Error| SyncError::State(e.to_string());
+
+        std::fs::create_dir_all(dir).map_err(io)?;
+        let lock = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(dir.join(file_name(remote, "lock")))
+            .map_err(io)?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(SyncError::Locked),
+            Err(TryLockError::Error(e)) => return Err(io(e)),
+        }
+
+        let path = dir.join(file_name(remote, "toml"));
+        let state = read_from(&path);
+
+        Ok(Self {
+            _lock: lock,
+            path,
+            state,
+        })
+    }
+
+    /// Write the state through a temp file so a crash never leaves half of it.
+    pub fn save(&self) -> Result<(), SyncError> {
+        let content =
+            toml::to_string_pretty(&self.state).map_err(|e| SyncError::State(e.to_string()))?;
+        let tmp = self.path.with_extension("toml.tmp");
+        std::fs::write(&tmp, content)
+            .and_then(|()| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| SyncError::State(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tf-sync-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn second_holder_is_refused_until_release() {
+        let dir = scratch_dir("lock");
+        let held = StateLock::acquire_in(&dir, "default").unwrap();
+        assert!(matches!(StateLock::acquire_in(&dir, "default"), Err(SyncError::Locked)));
+        // Other remotes sync independently
+        assert!(StateLock::acquire_in(&dir, "work").is_ok());
+        drop(held);
+        assert!(StateLock::acquire_in(&dir, "default").is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failing_remotes_back_off() {
+        let secs: Vec<_> = [1, 2, 3, 7, 8, 40].map(|n| retry_delay(n).num_seconds()).into();
+        assert_eq!(secs, [30, 60, 120, 1920, 3600, 3600]);
+
+        let at = Utc::now();
+        let interval = Duration::minutes(5);
+        let mut state = SyncStateFile::default();
+        assert!(next_due(&state, interval) < at);
+        state.last_run_at = Some(at);
+        assert_eq!(next_due(&state, interval), at + interval);
+        state.retry_at = Some(at + Duration::seconds(30));
+        assert_eq!(next_due(&state, interval), at + Duration::seconds(30));
+    }
+
+    #[test]
+    fn state_survives_reacquire() {
+        let dir = scratch_dir("state");
+        let mut lock = StateLock::acquire_in(&dir, "work").unwrap();
+        assert!(lock.state.push_cursor.is_none());
+        lock.state.push_cursor = Some("v1.9".into());
+        lock.state.events_pulled = 3;
+        lock.save().unwrap();
+        drop(lock);
+
+        assert!(dir.join("sync-state.work.toml").exists());
+        let lock = StateLock::acquire_in(&dir, "work").unwrap();
+        assert_eq!(lock.state.push_cursor.as_deref(), Some("v1.9"));
+        assert_eq!(lock.state.events_pulled, 3);
+        assert!(StateLock::acquire_in(&dir, "default").unwrap().state.push_cursor.is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
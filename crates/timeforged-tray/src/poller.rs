@@ -5,7 +5,60 @@ use chrono::Utc;
 use reqwest::Client;
 use serde::Deserialize;
 
+use timeforged_core::api::{GoalProgress, StreamEvent, SyncStatusResponse};
+
+/// Only used while the live stream is unavailable (older daemon, offline).
 const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Sync health isn't on the stream, so it is polled.
+const SYNC_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Goal progress isn't on the stream either.
+const GOALS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct Parts {
+    summary: String,
+    goals: Option<String>,
+    sync_warning: Option<String>,
+}
+
+/// Today's totals, goal progress and a warning while sync is failing,
+/// written to the string the tray loop shows.
+struct Tooltip {
+    state: Arc<Mutex<String>>,
+    parts: Mutex<Parts>,
+}
+
+impl Tooltip {
+    fn update(&self, change: impl FnOnce(&mut Parts)) {
+        if let Ok(mut parts) = self.parts.lock() {
+            change(&mut parts);
+            self.publish(&parts);
+        }
+    }
+
+    fn set_summary(&self, summary: String) {
+        self.update(|parts| parts.summary = summary);
+    }
+
+    fn set_goals(&self, goals: Option<String>) {
+        self.update(|parts| parts.goals = goals);
+    }
+
+    fn set_sync_warning(&self, warning: Option<String>) {
+        self.update(|parts| parts.sync_warning = warning);
+    }
+
+    fn publish(&self, parts: &Parts) {
+        if let Ok(mut s) = self.state.lock() {
+            *s = [Some(&parts.summary), parts.goals.as_ref(), parts.sync_warning.as_ref()]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct SummaryResponse {
@@ -27,16 +80,177 @@ pub async fn run(server_url: String, api_key: String, state: Arc<Mutex<String>>)
         .timeout(Duration::from_secs(5))
         .build()
         .unwrap();
+    let tooltip = Arc::new(Tooltip {
+        parts: Mutex::new(Parts {
+            summary: state.lock().map(|s| s.clone()).unwrap_or_default(),
+            ..Default::default()
+        }),
+        state,
+    });
+    tokio::spawn(watch_sync(
+        client.clone(),
+        server_url.clone(),
+        api_key.clone(),
+        tooltip.clone(),
+    ));
+    tokio::spawn(watch_goals(
+        client.clone(),
+        server_url.clone(),
+        api_key.clone(),
+        tooltip.clone(),
+    ));
+    // No overall timeout: the stream stays open indefinitely
+    let stream_client = Client::builder()
+        .connect_timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
 
     loop {
-        let tooltip = fetch_tooltip(&client, &server_url, &api_key).await;
-        if let Ok(mut s) = state.lock() {
-            *s = tooltip;
+        // Live updates until the stream drops
+        if let Err(e) = follow_stream(&stream_client, &server_url, &api_key, &tooltip).await {
+            tracing::debug!("tray: live stream unavailable: {e}");
         }
+
+        // Fall back to one poll, then try the stream again
+        tooltip.set_summary(fetch_tooltip(&client, &server_url, &api_key).await);
         tokio::time::sleep(POLL_INTERVAL).await;
     }
 }
 
+async fn watch_sync(client: Client, server_url: String, api_key: String, tooltip: Arc<Tooltip>) {
+    let url = format!("{}/api/v1/sync/status?limit=1", server_url.trim_end_matches('/'));
+    loop {
+        let status = match client.get(&url).header("X-Api-Key", &api_key).send().await {
+            Ok(r) if r.status().is_success() => r.json::<SyncStatusResponse>().await.ok(),
+            // Offline is already shown; older daemons have no sync status
+            _ => None,
+        };
+        tooltip.set_sync_warning(status.as_ref().and_then(sync_warning));
+        tokio::time::sleep(SYNC_CHECK_INTERVAL).await;
+    }
+}
+
+async fn watch_goals(client: Client, server_url: String, api_key: String, tooltip: Arc<Tooltip>) {
+    let url = format!("{}/api/v1/goals?history=0", server_url.trim_end_matches('/'));
+    loop {
+        let goals = match client.get(&url).header("X-Api-Key", &api_key).send().await {
+            Ok(r) if r.status().is_success() => r.json::<Vec<GoalProgress>>().await.ok(),
+            // Older daemons have no goals
+            _ => None,
+        };
+        tooltip.set_goals(goals.as_deref().and_then(goal_lines));
+        tokio::time::sleep(GOALS_CHECK_INTERVAL).await;
+    }
+}
+
+/// One line per goal: progress in the current day or week and the streak.
+pub(crate) fn goal_lines(goals: &[GoalProgress]) -> Option<String> {
+    let hm = |secs: f64| {
+        let minutes = (secs / 60.0) as u64;
+        format!("{}:{:02}", minutes / 60, minutes % 60)
+    };
+    let lines: Vec<String> = goals
+        .iter()
+        .map(|g| {
+            let mark = if g.current.met { "✓" } else { "○" };
+            let done = hm(g.current.seconds);
+            let target = hm(g.goal.target_seconds as f64);
+            let streak = g.streak.current;
+            format!("{mark} {}  {done} / {target} · streak {streak}", g.label)
+        })
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// A line for remotes that have been failing past the daemon's threshold.
+pub(crate) fn sync_warning(status: &SyncStatusResponse) -> Option<String> {
+    let failing: Vec<String> = status
+        .remotes
+        .iter()
+        .filter(|r| r.alert)
+        .map(|r| match r.failing_since {
+            Some(since) => format!("{} since {}", r.name, since.format("%b %d %H:%M")),
+            None => r.name.clone(),
+        })
+        .collect();
+    (!failing.is_empty()).then(|| format!("⚠ sync failing: {}", failing.join(", ")))
+}
+
+async fn follow_stream(
+    client: &Client,
+    server_url: &str,
+    api_key: &str,
+    tooltip: &Tooltip,
+) -> Result<(), String> {
+    let url = format!("{}/api/v1/stream", server_url.trim_end_matches('/'));
+    let mut resp = client
+        .get(&url)
+        .header("X-Api-Key", api_key)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+
+    let mut parser = SseParser::default();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+        for data in parser.feed(&chunk) {
+            if let Some(summary) = totals_from_stream(&data) {
+                tooltip.set_summary(format_tooltip(&summary));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Minimal Server-Sent Events parser: yields the `data` of each complete
+/// message. Event names are ignored since the payload carries its `type`.
+/// Bytes are buffered until a message is complete, so a character split
+/// across chunks is decoded whole.
+#[derive(Default)]
+pub(crate) struct SseParser {
+    buf: Vec<u8>,
+}
+
+impl SseParser {
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buf.extend(chunk.iter().filter(|&&b| b != b'\r'));
+
+        let mut messages = Vec::new();
+        while let Some(end) = self.buf.windows(2).position(|w| w == b"\n\n") {
+            let bytes: Vec<u8> = self.buf.drain(..end + 2).collect();
+            let raw = String::from_utf8_lossy(&bytes);
+            let data: Vec<&str> = raw
+                .lines()
+                .filter_map(|l| l.strip_prefix("data:"))
+                .map(|l| l.strip_prefix(' ').unwrap_or(l))
+                .collect();
+            if !data.is_empty() {
+                messages.push(data.join("\n"));
+            }
+        }
+        messages
+    }
+}
+
+pub(crate) fn totals_from_stream(data: &str) -> Option<SummaryResponse> {
+    match serde_json::from_str::<StreamEvent>(data).ok()? {
+        StreamEvent::Totals(totals) => Some(SummaryResponse {
+            total_seconds: totals.total_seconds,
+            projects: totals
+                .projects
+                .into_iter()
+                .map(|p| ProjectSummary {
+                    name: p.name,
+                    total_seconds: p.total_seconds,
+                })
+                .collect(),
+        }),
+        _ => None,
+    }
+}
+
 async fn fetch_tooltip(client: &Client, server_url: &str, api_key: &str) -> String {
     let now = Utc::now();
     let today = now.format("%Y-%m-%dT00:00:00Z");
@@ -220,4 +434,63 @@ mod tests {
         assert_eq!(summary.total_seconds, 0.0);
         assert!(summary.projects.is_empty());
     }
+
+    #[test]
+    fn sse_parser_splits_messages_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(b"event: totals\ndata: {\"a\"").is_empty());
+        let msgs = parser.feed(b":1}\n\n: keep-alive\n\nevent: event\ndata: x\n\n");
+        assert_eq!(msgs, vec![r#"{"a":1}"#.to_string(), "x".to_string()]);
+    }
+
+    #[test]
+    fn sse_parser_keeps_characters_split_across_chunks() {
+        let mut parser = SseParser::default();
+        let msg = "data: ✓\r\n\r\n".as_bytes();
+        assert!(parser.feed(&msg[..7]).is_empty());
+        assert_eq!(parser.feed(&msg[7..]), vec!["✓".to_string()]);
+    }
+
+    #[test]
+    fn totals_from_stream_reads_totals_only() {
+        let totals = r#"{"type":"totals","date":"2026-03-01","total_seconds":120.0,
+            "projects":[{"name":"app","total_seconds":120.0,"percent":100.0}],"languages":[]}"#;
+        let summary = totals_from_stream(totals).unwrap();
+        assert_eq!(summary.total_seconds, 120.0);
+        assert_eq!(summary.projects[0].name, "app");
+
+        let started = r#"{"type":"session_started","start":"2026-03-01T10:00:00Z"}"#;
+        assert!(totals_from_stream(started).is_none());
+        assert!(totals_from_stream("not json").is_none());
+    }
+
+    #[test]
+    fn goal_lines_show_progress_and_streak() {
+        let json = r#"[{"goal":{"id":"00000000-0000-0000-0000-000000000000",
+            "user_id":"00000000-0000-0000-0000-000000000000","period":"day",
+            "target_seconds":7200,"project":"timeforged","created_at":"2026-03-01T00:00:00Z"},
+            "label":"2h/day on timeforged","percent":60.0,"streak":{"current":4,"longest":9},
+            "current":{"start":"2026-03-02","seconds":4320.0,"met":false}}]"#;
+        let goals: Vec<GoalProgress> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            goal_lines(&goals).unwrap(),
+            "○ 2h/day on timeforged  1:12 / 2:00 · streak 4"
+        );
+        assert!(goal_lines(&[]).is_none());
+    }
+
+    #[test]
+    fn sync_warning_names_alerting_remotes() {
+        let json = r#"{"alert":true,"alert_after_secs":3600,"runs":[],"remotes":[
+            {"name":"work","direction":"push","events_pushed":1,"events_pulled":0,
+             "failing_since":"2026-03-01T08:00:00Z","alert":true},
+            {"name":"home","direction":"both","events_pushed":1,"events_pulled":0,
+             "failing_since":"2026-03-01T10:30:00Z","alert":false}]}"#;
+        let status: SyncStatusResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(sync_warning(&status).unwrap(), "⚠ sync failing: work since Mar 01 08:00");
+
+        let healthy = r#"{"alert":false,"alert_after_secs":3600,"remotes":[],"runs":[]}"#;
+        let healthy: SyncStatusResponse = serde_json::from_str(healthy).unwrap();
+        assert!(sync_warning(&healthy).is_none());
+    }
 }
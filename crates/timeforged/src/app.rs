@@ -1,16 +1,26 @@
-use axum::{Router, middleware, routing::{delete, get, post, put}};
+use axum::{Router, body::Bytes, middleware, routing::get};
 use axum::http::{HeaderValue, Method, header};
 use sqlx::SqlitePool;
 use tokio::sync::mpsc;
 use tower_http::cors::CorsLayer;
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
 
 use timeforged_core::config::AppConfig;
 
 use crate::auth;
-use crate::handlers::{card, events, health, register, reports, users, watcher};
-use crate::rate_limit;
+use crate::bus::EventBus;
+use crate::handlers::{
+    admin, billing, card, digest, events, goals, health, insights, profile, register, reports, sealed,
+    stream, sync, users, watcher, webhooks,
+};
+use crate::openapi::{self, ApiDoc};
+use crate::rate_limit::{self, RateLimits};
+use crate::request_id;
+use crate::service::card_service::RenderCache;
+use crate::service::stream_service::StreamTokens;
 use crate::watcher::WatcherCommand;
 use crate::web;
 
@@ -19,6 +29,29 @@ pub struct AppState {
     pub db: SqlitePool,
     pub config: AppConfig,
     pub watcher_tx: mpsc::Sender<WatcherCommand>,
+    pub rate_limits: RateLimits,
+    pub bus: EventBus,
+    /// Rendered cards and badges
+    pub cards: RenderCache,
+    pub stream_tokens: StreamTokens,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// Default config over `db`, with nothing listening on the watcher.
+    pub fn for_tests(db: SqlitePool) -> Self {
+        let config = AppConfig::default();
+        let (watcher_tx, _) = mpsc::channel(1);
+        Self {
+            db,
+            rate_limits: RateLimits::from_config(&config.rate_limit),
+            cards: RenderCache::from_config(&config.card),
+            config,
+            watcher_tx,
+            bus: EventBus::new(),
+            stream_tokens: StreamTokens::default(),
+        }
+    }
 }
 
 pub fn build_router(state: AppState) -> Router {
@@ -33,52 +66,26 @@ pub fn build_router(state: AppState) -> Router {
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             axum::http::header::HeaderName::from_static("x-api-key"),
-        ]);
+            request_id::REQUEST_ID_HEADER.clone(),
+        ])
+        .expose_headers([request_id::REQUEST_ID_HEADER.clone()]);
 
-    let authed = Router::new()
-        // Events (rate limited: 120 writes/min/IP)
-        .route(
-            "/api/v1/events",
-            post(events::create_event)
-                .layer(middleware::from_fn(rate_limit::event_rate_limit))
-                .get(events::list_events),
-        )
-        .route(
-            "/api/v1/events/batch",
-            post(events::create_batch)
-                .layer(middleware::from_fn(rate_limit::event_rate_limit)),
-        )
-        // Reports
-        .route("/api/v1/reports/summary", get(reports::summary))
-        .route("/api/v1/reports/sessions", get(reports::sessions))
-        .route("/api/v1/reports/activity", get(reports::activity))
-        // Users
-        .route("/api/v1/me", get(users::me))
-        .route("/api/v1/me/public-profile", put(users::set_public_profile))
-        .route("/api/v1/api-keys", post(users::create_api_key).get(users::list_api_keys))
-        .route("/api/v1/api-keys/{id}", delete(users::delete_api_key))
-        // Watcher
-        .route("/api/v1/watch", post(watcher::watch).delete(watcher::unwatch))
-        .route("/api/v1/watched", get(watcher::list))
-        .layer(middleware::from_fn_with_state(
-            state.clone(),
-            auth::auth_middleware,
-        ));
+    let (api, doc) = api_router(&state).split_for_parts();
+    let spec = Bytes::from(doc.to_pretty_json().expect("openapi document serializes"));
 
-    let public = Router::new()
-        .route("/health", get(health::health))
-        .route("/api/v1/status", get(health::status))
-        .route("/api/v1/card.svg", get(card::card_svg))
-        .route("/api/v1/card/{username}", get(card::public_card_svg))
+    let docs = Router::new()
         .route(
-            "/api/v1/register",
-            post(register::register)
-                .layer(middleware::from_fn(rate_limit::register_rate_limit)),
-        );
+            "/api/v1/openapi.json",
+            get(move || {
+                let spec = spec.clone();
+                async move { openapi::spec_response(spec) }
+            }),
+        )
+        .route("/api/v1/docs", get(openapi::docs));
 
     Router::new()
-        .merge(authed)
-        .merge(public)
+        .merge(api)
+        .merge(docs)
         .fallback(web::static_handler)
         .layer(cors)
         .layer(SetResponseHeaderLayer::overriding(
@@ -98,5 +105,97 @@ pub fn build_router(state: AppState) -> Router {
             HeaderValue::from_static("strict-origin-when-cross-origin"),
         ))
         .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(request_id::request_id))
         .with_state(state)
 }
+
+/// All documented API routes. Paths and methods come from each handler's
+/// `#[utoipa::path]`, so the router and the OpenAPI document can't drift.
+pub fn api_router(state: &AppState) -> OpenApiRouter<AppState> {
+    // Event writes (rate limited per API key, see [rate_limit])
+    let event_writes = OpenApiRouter::new()
+        .routes(routes!(events::create_event))
+        .routes(routes!(events::create_batch))
+        .routes(routes!(events::import))
+        .routes(routes!(sealed::store_batch))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::event_rate_limit,
+        ));
+
+    let authed = OpenApiRouter::new()
+        .merge(event_writes)
+        .routes(routes!(events::list_events))
+        .routes(routes!(events::export))
+        // Reports
+        .routes(routes!(reports::summary))
+        .routes(routes!(reports::sessions))
+        .routes(routes!(reports::activity))
+        .routes(routes!(reports::compare))
+        // Insights
+        .routes(routes!(insights::insights))
+        // Users
+        .routes(routes!(users::me))
+        .routes(routes!(users::get_public_profile, users::set_public_profile))
+        .routes(routes!(users::create_api_key, users::list_api_keys))
+        .routes(routes!(users::delete_api_key))
+        // Watcher
+        .routes(routes!(watcher::watch, watcher::unwatch))
+        .routes(routes!(watcher::list))
+        // Sync
+        .routes(routes!(sync::sync))
+        .routes(routes!(sync::run))
+        .routes(routes!(sync::status))
+        // End-to-end encrypted events
+        .routes(routes!(sealed::get_key, sealed::set_key))
+        .routes(routes!(sealed::list))
+        // Goals
+        .routes(routes!(goals::create_goal, goals::list_goals))
+        .routes(routes!(goals::get_goal, goals::delete_goal))
+        // Digests
+        .routes(routes!(digest::get_digest_settings, digest::set_digest_settings))
+        .routes(routes!(digest::preview_digest))
+        .routes(routes!(digest::send_digest))
+        // Billing
+        .routes(routes!(billing::create_client, billing::list_clients))
+        .routes(routes!(billing::get_client, billing::update_client, billing::delete_client))
+        .routes(routes!(billing::get_activities, billing::set_activities))
+        .routes(routes!(billing::timesheet))
+        // Webhooks
+        .routes(routes!(webhooks::create_webhook, webhooks::list_webhooks))
+        .routes(routes!(webhooks::delete_webhook))
+        .routes(routes!(webhooks::list_deliveries))
+        .routes(routes!(webhooks::test_webhook))
+        // Live stream
+        .routes(routes!(stream::token))
+        // Daemon maintenance
+        .routes(routes!(admin::backup))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::auth_middleware,
+        ));
+
+    let register = OpenApiRouter::new()
+        .routes(routes!(register::register))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::register_rate_limit,
+        ));
+
+    let public = OpenApiRouter::new()
+        .routes(routes!(health::health))
+        .routes(routes!(health::status))
+        .routes(routes!(card::card_svg))
+        .routes(routes!(card::card_png))
+        .routes(routes!(card::public_card_svg))
+        .routes(routes!(card::badge))
+        .routes(routes!(profile::public_profile))
+        // Authenticates itself: EventSource can't send headers, so browsers
+        // bring a stream token instead
+        .routes(routes!(stream::stream))
+        .merge(register);
+
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .merge(authed)
+        .merge(public)
+}
@@ -1,13 +1,14 @@
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
     middleware::Next,
     response::Response,
 };
 
 use crate::app::AppState;
+use crate::error::ApiError;
 use crate::service::user_service;
 
+use timeforged_core::error::AppError;
 use timeforged_core::models::User;
 
 #[derive(Clone)]
@@ -17,18 +18,18 @@ pub async fn auth_middleware(
     State(state): State<AppState>,
     mut req: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, ApiError> {
     let api_key = req
         .headers()
         .get("X-Api-Key")
         .and_then(|v| v.to_str().ok())
         .map(String::from);
 
-    let key = api_key.ok_or(StatusCode::UNAUTHORIZED)?;
+    let key = api_key.ok_or(AppError::Unauthorized)?;
 
     let user = user_service::authenticate(&state.db, &key)
         .await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(|_| AppError::Unauthorized)?;
 
     req.extensions_mut().insert(AuthUser(user));
     Ok(next.run(req).await)
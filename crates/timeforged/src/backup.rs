@@ -0,0 +1,413 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::SqlitePool;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+use timeforged_core::api::BackupInfo;
+use timeforged_core::config::AppConfig;
+use timeforged_core::error::AppError;
+
+use crate::storage::sqlite;
+
+/// Backups in the backup directory are named `timeforged-<UTC time>.db`,
+/// to the millisecond; only files named like that are ever pruned.
+const PREFIX: &str = "timeforged-";
+const STAMP: &str = "%Y%m%d-%H%M%S%.3f";
+/// Wait before retrying a scheduled backup that failed.
+const RETRY_SECS: u64 = 300;
+
+fn io(e: std::io::Error) -> AppError {
+    AppError::Internal(e.to_string())
+}
+
+/// `<path>.partial`, where a file is written before it is moved into place.
+fn partial(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+pub async fn open(path: &Path, read_only: bool) -> Result<SqlitePool, AppError> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(read_only)
+        .create_if_missing(!read_only);
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("can't open {}: {e}", path.display())))
+}
+
+/// Copy the live database to `dest` with `VACUUM INTO`, which reads a
+/// consistent snapshot in one transaction, so the daemon keeps running.
+/// The copy only takes the name `dest` once it passed [`verify`].
+pub async fn backup_to(pool: &SqlitePool, dest: &Path) -> Result<BackupInfo, AppError> {
+    if dest.exists() {
+        return Err(AppError::Conflict(format!("{} already exists", dest.display())));
+    }
+    if let Some(dir) = dest.parent() {
+        std::fs::create_dir_all(dir).map_err(io)?;
+    }
+
+    let tmp = partial(dest);
+    let _ = std::fs::remove_file(&tmp);
+    sqlx::query("VACUUM INTO ?")
+        .bind(tmp.to_string_lossy().as_ref())
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    if let Err(e) = verify(&tmp).await {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    std::fs::rename(&tmp, dest).map_err(io)?;
+
+    Ok(BackupInfo {
+        path: dest.display().to_string(),
+        size_bytes: std::fs::metadata(dest).map_err(io)?.len(),
+        created_at: Utc::now(),
+        removed: Vec::new(),
+    })
+}
+
+/// Check that `path` is an intact TimeForged database.
+pub async fn verify(path: &Path) -> Result<(), AppError> {
+    if !path.is_file() {
+        return Err(AppError::NotFound(format!("no file at {}", path.display())));
+    }
+    let pool = open(path, true).await?;
+    let result = check(&pool).await;
+    pool.close().await;
+    result
+}
+
+async fn check(pool: &SqlitePool) -> Result<(), AppError> {
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("not a database: {e}")))?;
+    if problems != ["ok"] {
+        let problems = problems.join("; ");
+        return Err(AppError::BadRequest(format!("integrity check failed: {problems}")));
+    }
+
+    let tables: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('users', 'events')",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    if tables < 2 {
+        return Err(AppError::BadRequest("not a TimeForged database".into()));
+    }
+    Ok(())
+}
+
+/// Back up into the backup directory, then delete the oldest backups
+/// there beyond `backup.keep`.
+pub async fn backup_now(pool: &SqlitePool, config: &AppConfig) -> Result<BackupInfo, AppError> {
+    let in_memory = || AppError::BadRequest("the database is in memory, nothing to back up".into());
+    let dir = config.backup_dir().ok_or_else(in_memory)?;
+    let name = format!("{PREFIX}{}.db", Utc::now().format(STAMP));
+    let mut info = backup_to(pool, &dir.join(name)).await?;
+    info.removed = prune(&dir, config.backup.keep)
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    Ok(info)
+}
+
+/// Backups in `dir`, oldest first.
+fn backups(dir: &Path) -> Vec<(DateTime<Utc>, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let stamp = name.strip_prefix(PREFIX)?.strip_suffix(".db")?;
+            let at = NaiveDateTime::parse_from_str(stamp, STAMP).ok()?.and_utc();
+            Some((at, path))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// Delete all but the newest `keep` backups (0 keeps them all).
+fn prune(dir: &Path, keep: usize) -> Vec<PathBuf> {
+    let found = backups(dir);
+    if keep == 0 || found.len() <= keep {
+        return Vec::new();
+    }
+    let excess = found.len() - keep;
+    found
+        .into_iter()
+        .take(excess)
+        .filter_map(|(_, path)| match std::fs::remove_file(&path) {
+            Ok(()) => Some(path),
+            Err(e) => {
+                tracing::warn!("could not delete old backup {}: {e}", path.display());
+                None
+            }
+        })
+        .collect()
+}
+
+/// Scheduled backups every `backup.interval` seconds, counted from the
+/// newest backup in the directory so restarts don't reset the clock.
+pub async fn run(pool: SqlitePool, config: AppConfig) {
+    let interval = config.backup.interval;
+    let Some(dir) = config.backup_dir() else { return };
+    if interval == 0 {
+        return;
+    }
+    tracing::info!("backing up the database to {} every {interval}s", dir.display());
+
+    loop {
+        let due = backups(&dir).last().map_or_else(Utc::now, |(at, _)| {
+            *at + chrono::Duration::seconds(interval.min(i64::MAX as u64) as i64)
+        });
+        tokio::time::sleep((due - Utc::now()).to_std().unwrap_or_default()).await;
+
+        match backup_now(&pool, &config).await {
+            Ok(info) => {
+                let (path, size) = (&info.path, info.size_bytes);
+                tracing::info!("backed up the database to {path} ({size} bytes)");
+            }
+            Err(e) => {
+                tracing::warn!("scheduled backup failed: {e}");
+                tokio::time::sleep(Duration::from_secs(RETRY_SECS)).await;
+            }
+        }
+    }
+}
+
+/// Replace the database at `db` with `backup`, which must pass [`verify`].
+/// The daemon must not be running. The database being replaced is kept
+/// as `<db>.before-restore-<time>` (with its `-wal`, if any), and the
+/// restored one is brought up to this version's schema.
+pub async fn restore(backup: &Path, db: &Path) -> Result<Option<PathBuf>, AppError> {
+    verify(backup).await?;
+    if let Some(dir) = db.parent() {
+        std::fs::create_dir_all(dir).map_err(io)?;
+    }
+    let tmp = partial(db);
+    std::fs::copy(backup, &tmp).map_err(io)?;
+
+    let mut kept = None;
+    if db.exists() {
+        let mut name = db.as_os_str().to_owned();
+        name.push(format!(".before-restore-{}", Utc::now().format(STAMP)));
+        let aside = PathBuf::from(name);
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let (mut from, mut to) = (db.as_os_str().to_owned(), aside.as_os_str().to_owned());
+            from.push(suffix);
+            to.push(suffix);
+            if Path::new(&from).exists() {
+                std::fs::rename(&from, &to).map_err(io)?;
+            }
+        }
+        kept = Some(aside);
+    }
+    std::fs::rename(&tmp, db).map_err(io)?;
+
+    let pool = open(db, false).await?;
+    let migrated = match sqlite::init_db(&pool).await {
+        Ok(()) => check(&pool).await,
+        Err(e) => Err(e),
+    };
+    pool.close().await;
+    migrated.map(|()| kept)
+}
+
+/// Whether something answers on the daemon's address.
+pub async fn daemon_running(config: &AppConfig) -> bool {
+    let connect = tokio::net::TcpStream::connect(config.bind_addr());
+    matches!(tokio::time::timeout(Duration::from_secs(1), connect).await, Ok(Ok(_)))
+}
+
+/// Client errors read fine as they are; internal ones keep their details,
+/// since this is the operator's own terminal.
+pub fn describe(e: &AppError) -> String {
+    if e.is_internal() { e.to_string() } else { e.public_message() }
+}
+
+/// `timeforged backup [path]`: back up the database, to `path` or into
+/// the backup directory. Safe while the daemon runs.
+pub async fn backup_command(config: &AppConfig, path: Option<PathBuf>) -> anyhow::Result<()> {
+    let db = config
+        .database_path()
+        .ok_or_else(|| anyhow::anyhow!("the database is in memory, nothing to back up"))?;
+    if !db.is_file() {
+        anyhow::bail!("no database at {}", db.display());
+    }
+    let pool = open(&db, false).await.map_err(|e| anyhow::anyhow!(describe(&e)))?;
+    let result = match path {
+        Some(path) => backup_to(&pool, &path).await,
+        None => backup_now(&pool, config).await,
+    };
+    pool.close().await;
+
+    let info = result.map_err(|e| anyhow::anyhow!(describe(&e)))?;
+    println!("Backed up {} to {} ({} bytes)", db.display(), info.path, info.size_bytes);
+    for removed in &info.removed {
+        println!("  removed old backup {removed}");
+    }
+    Ok(())
+}
+
+/// `timeforged restore <path>`: replace the database with a backup.
+pub async fn restore_command(config: &AppConfig, path: &Path) -> anyhow::Result<()> {
+    let db = config
+        .database_path()
+        .ok_or_else(|| anyhow::anyhow!("the database is in memory, nothing to restore into"))?;
+    if daemon_running(config).await {
+        anyhow::bail!(
+            "the daemon is running on {}; stop it before restoring",
+            config.bind_addr()
+        );
+    }
+
+    let kept = restore(path, &db).await.map_err(|e| anyhow::anyhow!(describe(&e)))?;
+    println!("Restored {} from {}", db.display(), path.display());
+    if let Some(kept) = kept {
+        println!("  the previous database was kept as {}", kept.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use timeforged_core::api::{BatchEventRequest, CreateEventRequest};
+    use timeforged_core::models::{EventType, ReportRequest};
+    use uuid::Uuid;
+
+    use crate::bus::EventBus;
+    use crate::service::{event_service, report_service};
+
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tf-backup-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn daemon(db: &Path) -> (SqlitePool, Uuid) {
+        let pool = open(db, false).await.unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        (pool, user.id)
+    }
+
+    /// Everything the reports say about March 2026, as JSON.
+    async fn reports(pool: &SqlitePool, user_id: Uuid) -> serde_json::Value {
+        let req = ReportRequest {
+            from: Some(Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap()),
+            to: Some(Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap()),
+            project: None,
+            language: None,
+        };
+        let summary = report_service::get_summary(pool, user_id, &req, 300).await.unwrap();
+        let sessions = report_service::get_sessions(pool, user_id, &req, 300).await.unwrap();
+        let hourly = report_service::get_hourly_activity(pool, user_id, &req, 300).await.unwrap();
+        serde_json::json!({ "summary": summary, "sessions": sessions, "hourly": hourly })
+    }
+
+    #[tokio::test]
+    async fn restored_backup_reports_the_same() {
+        let dir = scratch_dir("restore");
+        let (pool, user_id) = daemon(&dir.join("live.db")).await;
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let events = (0..40)
+            .map(|i| CreateEventRequest {
+                timestamp: start + chrono::Duration::minutes(i * 2),
+                event_type: EventType::File,
+                entity: format!("/home/dev/app{}/src/main.rs", i % 3),
+                project: None,
+                language: None,
+                branch: None,
+                activity: None,
+                machine: None,
+                metadata: None,
+            })
+            .collect();
+        let batch = BatchEventRequest { events };
+        event_service::create_batch(&pool, &EventBus::new(), user_id, batch).await.unwrap();
+        let before = reports(&pool, user_id).await;
+        assert!(before["summary"]["total_seconds"].as_f64().unwrap() > 0.0);
+
+        let backup = dir.join("backups/snapshot.db");
+        let info = backup_to(&pool, &backup).await.unwrap();
+        assert!(info.size_bytes > 0 && !partial(&backup).exists());
+        assert!(matches!(backup_to(&pool, &backup).await, Err(AppError::Conflict(_))));
+
+        // A fresh daemon, then one that already has data of its own
+        let fresh = dir.join("fresh/timeforged.db");
+        assert_eq!(restore(&backup, &fresh).await.unwrap(), None);
+        let restored = open(&fresh, false).await.unwrap();
+        assert_eq!(reports(&restored, user_id).await, before);
+        restored.close().await;
+
+        let (other, _) = daemon(&dir.join("other.db")).await;
+        other.close().await;
+        let kept = restore(&backup, &dir.join("other.db")).await.unwrap().unwrap();
+        assert!(kept.exists());
+        let restored = open(&dir.join("other.db"), false).await.unwrap();
+        assert_eq!(reports(&restored, user_id).await, before);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn damaged_or_foreign_files_are_not_restored() {
+        let dir = scratch_dir("damaged");
+        let db = dir.join("timeforged.db");
+        let (pool, _) = daemon(&db).await;
+        pool.close().await;
+
+        let garbage = dir.join("garbage.db");
+        std::fs::write(&garbage, b"SQLite format 3\0 but not really").unwrap();
+        assert!(matches!(restore(&garbage, &db).await, Err(AppError::BadRequest(_))));
+
+        let foreign = dir.join("foreign.db");
+        let pool = open(&foreign, false).await.unwrap();
+        sqlx::query("CREATE TABLE notes (body TEXT)").execute(&pool).await.unwrap();
+        pool.close().await;
+        let err = restore(&foreign, &db).await.unwrap_err();
+        assert_eq!(err.public_message(), "not a TimeForged database");
+
+        // The database in place was left alone
+        verify(&db).await.unwrap();
+        assert!(!partial(&db).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_backups_only() {
+        let dir = scratch_dir("prune");
+        for name in [
+            "timeforged-20260301-020000.000.db",
+            "timeforged-20260302-020000.000.db",
+            "timeforged-20260303-020000.000.db",
+            "snapshot.db",
+            "timeforged-20260301-020000.000.db.partial",
+        ] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        assert!(prune(&dir, 0).is_empty());
+        let removed = prune(&dir, 2);
+        assert_eq!(removed, [dir.join("timeforged-20260301-020000.000.db")]);
+        let newest = backups(&dir).last().unwrap().0;
+        assert_eq!(newest, Utc.with_ymd_and_hms(2026, 3, 3, 2, 0, 0).unwrap());
+        assert!(dir.join("snapshot.db").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,44 @@
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use timeforged_core::api::StreamEvent;
+
+/// How many messages a slow subscriber may fall behind before it starts
+/// missing some (it gets `Lagged` and carries on).
+const CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct BusMessage {
+    pub user_id: Uuid,
+    pub event: StreamEvent,
+}
+
+/// In-process broadcast of live activity. Producers (event ingestion, the
+/// file watcher, the session tracker) publish; `/api/v1/stream` clients and
+/// background tasks subscribe.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<BusMessage>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Self { tx }
+    }
+
+    pub fn publish(&self, user_id: Uuid, event: StreamEvent) {
+        // No subscribers is not an error
+        let _ = self.tx.send(BusMessage { user_id, event });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BusMessage> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
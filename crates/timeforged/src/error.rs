@@ -0,0 +1,88 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use timeforged_core::api::ErrorResponse;
+use timeforged_core::error::AppError;
+
+use crate::request_id;
+
+/// HTTP wrapper around `AppError`. Handlers return `Result<_, ApiError>` and
+/// use `?` on service calls; the mapping to status, code and body lives here.
+#[derive(Debug)]
+pub struct ApiError(pub AppError);
+
+impl From<AppError> for ApiError {
+    fn from(e: AppError) -> Self {
+        Self(e)
+    }
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match &self.0 {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::BadRequest(_) | AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let request_id = request_id::current();
+        if self.0.is_internal() {
+            // Details stay in the log; the client only gets the request id
+            tracing::error!(request_id = request_id.as_deref(), "{}", self.0);
+        }
+
+        let body = ErrorResponse {
+            error: self.0.public_message(),
+            code: self.0.code().into(),
+            request_id,
+        };
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_json(resp: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn database_error_is_hidden() {
+        let resp = request_id::scope("req-1".into(), async {
+            ApiError(AppError::Database("UNIQUE constraint failed: users.username".into()))
+                .into_response()
+        })
+        .await;
+
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = body_json(resp).await;
+        assert_eq!(body["error"], "internal server error");
+        assert_eq!(body["code"], "internal_error");
+        assert_eq!(body["request_id"], "req-1");
+    }
+
+    #[tokio::test]
+    async fn client_error_keeps_message() {
+        let resp = ApiError(AppError::NotFound("user not found".into())).into_response();
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = body_json(resp).await;
+        assert_eq!(body["error"], "user not found");
+        assert_eq!(body["code"], "not_found");
+        assert!(body.get("request_id").is_none());
+    }
+}
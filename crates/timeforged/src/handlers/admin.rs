@@ -0,0 +1,30 @@
+use axum::{Extension, Json, extract::State, response::IntoResponse};
+
+use timeforged_core::api::{BackupInfo, ErrorResponse};
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::backup;
+use crate::error::ApiError;
+use crate::service::user_service;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backup",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Backup written to the backup directory; old ones beyond the retention were removed", body = BackupInfo),
+        (status = 400, description = "The database is in memory", body = ErrorResponse),
+        (status = 403, description = "Not the admin user", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn backup(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    user_service::require_admin(&state.db, &user).await?;
+    let info = backup::backup_now(&state.db, &state.config).await?;
+    tracing::info!("backed up the database to {} ({} bytes)", info.path, info.size_bytes);
+    Ok(Json(info))
+}
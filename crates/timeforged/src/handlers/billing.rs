@@ -0,0 +1,189 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+use timeforged_core::api::{
+    ActivityBilling, ClientRequest, ErrorResponse, Timesheet, TimesheetFormat, TimesheetQuery,
+};
+use timeforged_core::models::Client;
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::billing_service;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/clients",
+    tag = "billing",
+    request_body = ClientRequest,
+    responses(
+        (status = 201, description = "Client created", body = Client),
+        (status = 400, description = "Invalid name, rate, currency, rounding or project", body = ErrorResponse),
+        (status = 409, description = "Name taken, or a project already billed to another client", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn create_client(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(req): Json<ClientRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let client = billing_service::create_client(&state.db, user.id, req).await?;
+    Ok((StatusCode::CREATED, Json(client)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/clients",
+    tag = "billing",
+    responses((status = 200, description = "Clients of the user by name", body = Vec<Client>)),
+    security(("api_key" = []))
+)]
+pub async fn list_clients(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(billing_service::list_clients(&state.db, user.id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/clients/{client}",
+    tag = "billing",
+    params(("client" = String, Path, description = "Client id, or name ignoring case")),
+    responses(
+        (status = 200, description = "The client", body = Client),
+        (status = 404, description = "Client not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn get_client(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(client): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(billing_service::get_client(&state.db, user.id, &client).await?))
+}
+
+/// Replaces the client's name, rates, rounding and projects.
+#[utoipa::path(
+    put,
+    path = "/api/v1/clients/{client}",
+    tag = "billing",
+    params(("client" = String, Path, description = "Client id, or name ignoring case")),
+    request_body = ClientRequest,
+    responses(
+        (status = 200, description = "Client updated", body = Client),
+        (status = 400, description = "Invalid name, rate, currency, rounding or project", body = ErrorResponse),
+        (status = 404, description = "Client not found", body = ErrorResponse),
+        (status = 409, description = "Name taken, or a project already billed to another client", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn update_client(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(client): Path<String>,
+    Json(req): Json<ClientRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let client = billing_service::update_client(&state.db, user.id, &client, req).await?;
+    Ok(Json(client))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/clients/{client}",
+    tag = "billing",
+    params(("client" = String, Path, description = "Client id, or name ignoring case")),
+    responses(
+        (status = 204, description = "Client deleted; its projects are no longer billed"),
+        (status = 404, description = "Client not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn delete_client(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(client): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    billing_service::delete_client(&state.db, user.id, &client).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/billing/activities",
+    tag = "billing",
+    responses((status = 200, description = "Every activity type and whether its time is billed", body = Vec<ActivityBilling>)),
+    security(("api_key" = []))
+)]
+pub async fn get_activities(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(billing_service::activities(&state.db, user.id).await?))
+}
+
+/// Changes the activity types listed and leaves the others as they are.
+#[utoipa::path(
+    put,
+    path = "/api/v1/billing/activities",
+    tag = "billing",
+    request_body = Vec<ActivityBilling>,
+    responses((status = 200, description = "Every activity type and whether its time is billed", body = Vec<ActivityBilling>)),
+    security(("api_key" = []))
+)]
+pub async fn set_activities(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(changes): Json<Vec<ActivityBilling>>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(billing_service::set_activities(&state.db, user.id, &changes).await?))
+}
+
+/// Billable time on the client's projects per day and project, rounded and
+/// priced. CSV, HTML and PDF come as attachments named after the client.
+#[utoipa::path(
+    get,
+    path = "/api/v1/billing/timesheet",
+    tag = "billing",
+    params(TimesheetQuery),
+    responses(
+        (status = 200, description = "The timesheet as JSON (the default), CSV, HTML or PDF", content(
+            (Timesheet = "application/json"),
+            (String = "text/csv"),
+            (String = "text/html"),
+            (Vec<u8> = "application/pdf"),
+        )),
+        (status = 400, description = "Invalid month or range", body = ErrorResponse),
+        (status = 404, description = "Client not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn timesheet(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<TimesheetQuery>,
+) -> Result<Response, ApiError> {
+    let idle_timeout = state.config.idle_timeout;
+    let sheet = billing_service::timesheet(&state.db, user.id, &query, idle_timeout).await?;
+    let (content_type, body) = match query.format {
+        TimesheetFormat::Json => return Ok(Json(sheet).into_response()),
+        TimesheetFormat::Csv => ("text/csv; charset=utf-8", billing_service::csv(&sheet).into()),
+        TimesheetFormat::Html => {
+            ("text/html; charset=utf-8", billing_service::html(&sheet).into_bytes())
+        }
+        TimesheetFormat::Pdf => ("application/pdf", billing_service::pdf(&sheet)),
+    };
+    let file_name = billing_service::file_name(&sheet, query.format.as_str());
+    let disposition = format!("attachment; filename=\"{file_name}\"");
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::CONTENT_DISPOSITION, disposition),
+    ];
+    Ok((headers, body).into_response())
+}
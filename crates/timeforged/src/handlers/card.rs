@@ -1,24 +1,110 @@
 use axum::{
     extract::{Path, Query, State},
-    http::{StatusCode, header},
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
 };
 use chrono::Utc;
 use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
 
-use timeforged_core::models::ReportRequest;
+use timeforged_core::api::{ErrorResponse, PublicProfileSettings};
+use timeforged_core::config::config_dir;
+use timeforged_core::error::AppError;
+use timeforged_core::models::{ReportRequest, Summary};
 
 use crate::app::AppState;
-use crate::service::{card_service, report_service, user_service};
-use crate::storage::sqlite;
+use crate::error::ApiError;
+use crate::service::card_service::{
+    self, CardData, Layout, Lookup, Metric, Rendered, Theme, ThemeColors, raster,
+};
+use crate::service::{goal_service, profile_service, report_service, user_service};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct CardQuery {
+    /// API key; passed in the query so the card works in `<img>` tags
     pub key: Option<String>,
+}
+
+/// How a card looks. Colors are hex, with or without the `#`, and override
+/// the theme's.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CardStyle {
+    /// `dark`, `light`, or the name of a theme file in the config
+    /// directory's `themes/`
     #[serde(default = "default_theme")]
     pub theme: String,
+    /// Days to include, 1-365
     #[serde(default = "default_days")]
     pub days: u32,
+    /// Which card to draw
+    #[serde(default)]
+    #[param(inline)]
+    pub layout: Layout,
+    /// Background color
+    pub bg: Option<String>,
+    /// Border color
+    pub border: Option<String>,
+    /// Text color
+    pub text: Option<String>,
+    /// Color of labels and secondary text
+    pub muted: Option<String>,
+    /// Title and highlight color; shades the heatmap unless `heat` is given
+    pub accent: Option<String>,
+    /// Four comma-separated heatmap colors, least to most active
+    pub heat: Option<String>,
+}
+
+impl CardStyle {
+    async fn theme(&self) -> Result<Theme, AppError> {
+        let colors = ThemeColors {
+            bg: self.bg.clone(),
+            border: self.border.clone(),
+            text: self.text.clone(),
+            muted: self.muted.clone(),
+            accent: self.accent.clone(),
+            heat: self
+                .heat
+                .as_ref()
+                .map(|heat| heat.split(',').map(|c| c.trim().to_string()).collect()),
+            ..Default::default()
+        };
+        resolve_theme(&self.theme, &colors).await
+    }
+}
+
+/// How a badge looks.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BadgeQuery {
+    /// `dark`, `light`, or the name of a theme file
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// Days to include, 1-365
+    #[serde(default = "default_badge_days")]
+    pub days: u32,
+    /// Replaces the left-hand text
+    pub label: Option<String>,
+    /// Left-hand background, and the value's text color
+    pub bg: Option<String>,
+    /// Left-hand text color
+    pub text: Option<String>,
+    /// Right-hand background
+    pub accent: Option<String>,
+}
+
+impl BadgeQuery {
+    async fn theme(&self) -> Result<Theme, AppError> {
+        let colors = ThemeColors {
+            bg: self.bg.clone(),
+            text: self.text.clone(),
+            accent: self.accent.clone(),
+            ..Default::default()
+        };
+        resolve_theme(&self.theme, &colors).await
+    }
 }
 
 fn default_theme() -> String {
@@ -29,56 +115,194 @@ fn default_days() -> u32 {
     365
 }
 
+fn default_badge_days() -> u32 {
+    7
+}
+
+/// A built-in theme or a theme file from the config directory's `themes/`,
+/// with the query's colors on top. Only called on a render-cache miss, so
+/// cached cards and badges never touch the disk.
+async fn resolve_theme(name: &str, colors: &ThemeColors) -> Result<Theme, AppError> {
+    Theme::named(name, &config_dir().join("themes"))
+        .await
+        .and_then(|theme| theme.with(colors))
+        .map_err(AppError::BadRequest)
+}
+
+/// Cards and badges come as SVG, or as PNG for places that can't show SVG
+/// (chat unfurls, some markdown renderers, email).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Svg,
+    Png,
+}
+
+impl Format {
+    /// `name` without a `.svg` or `.png` extension, and the format it asks
+    /// for (SVG when there is none).
+    fn split(name: &str) -> (&str, Self) {
+        if let Some(name) = name.strip_suffix(".png") {
+            (name, Self::Png)
+        } else {
+            (name.strip_suffix(".svg").unwrap_or(name), Self::Svg)
+        }
+    }
+
+    async fn encode(self, svg: String) -> Result<Rendered, AppError> {
+        match self {
+            Self::Svg => Ok(Rendered::new(svg, "image/svg+xml")),
+            Self::Png => {
+                let png = tokio::task::spawn_blocking(move || raster::png(&svg))
+                    .await
+                    .map_err(|e| AppError::Internal(e.to_string()))?
+                    .map_err(AppError::Internal)?;
+                Ok(Rendered::new(png, "image/png"))
+            }
+        }
+    }
+}
+
 /// Private card: GET /api/v1/card.svg?key=...
 /// Always requires API key.
+#[utoipa::path(
+    get,
+    path = "/api/v1/card.svg",
+    tag = "cards",
+    params(CardQuery, CardStyle),
+    responses(
+        (status = 200, description = "SVG profile card", body = String, content_type = "image/svg+xml"),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag or `If-Modified-Since` date"),
+        (status = 400, description = "Unknown theme or invalid color", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid key", body = ErrorResponse),
+    )
+)]
 pub async fn card_svg(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Query(params): Query<CardQuery>,
-) -> impl IntoResponse {
-    let user = match params.key {
-        Some(ref key) => match user_service::authenticate(&state.db, key).await {
-            Ok(u) => u,
-            Err(_) => return (StatusCode::UNAUTHORIZED, "invalid api key").into_response(),
-        },
-        None => return (StatusCode::UNAUTHORIZED, "api key required").into_response(),
-    };
+    Query(style): Query<CardStyle>,
+) -> Result<Response, ApiError> {
+    private_card(&state, &headers, params, &style, Format::Svg).await
+}
 
-    render_card(&state, user.id, &params.theme, params.days).await
+/// Private card as PNG: GET /api/v1/card.png?key=...
+#[utoipa::path(
+    get,
+    path = "/api/v1/card.png",
+    tag = "cards",
+    params(CardQuery, CardStyle),
+    responses(
+        (status = 200, description = "PNG profile card, at twice the SVG's size", body = Vec<u8>, content_type = "image/png"),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag or `If-Modified-Since` date"),
+        (status = 400, description = "Unknown theme or invalid color", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid key", body = ErrorResponse),
+    )
+)]
+pub async fn card_png(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<CardQuery>,
+    Query(style): Query<CardStyle>,
+) -> Result<Response, ApiError> {
+    private_card(&state, &headers, params, &style, Format::Png).await
 }
 
-#[derive(Debug, Deserialize)]
-pub struct PublicCardQuery {
-    #[serde(default = "default_theme")]
-    pub theme: String,
-    #[serde(default = "default_days")]
-    pub days: u32,
+async fn private_card(
+    state: &AppState,
+    headers: &HeaderMap,
+    params: CardQuery,
+    style: &CardStyle,
+    format: Format,
+) -> Result<Response, ApiError> {
+    let key = params.key.ok_or(AppError::Unauthorized)?;
+    let user = user_service::authenticate(&state.db, &key)
+        .await
+        .map_err(|_| AppError::Unauthorized)?;
+    render_card(state, headers, user.id, None, style, format).await
 }
 
-/// Public card: GET /api/v1/card/:username.svg
+/// Public card: GET /api/v1/card/:username.svg (or `.png`)
 /// Only works if user has public_profile enabled.
 /// Returns 404 for both non-existent and private users (prevents enumeration).
+#[utoipa::path(
+    get,
+    path = "/api/v1/card/{username}",
+    tag = "cards",
+    params(
+        ("username" = String, Path, description = "Username, optionally with a `.svg` or `.png` suffix"),
+        CardStyle,
+    ),
+    responses(
+        (status = 200, description = "SVG profile card, or PNG for a `.png` suffix", body = String, content_type = "image/svg+xml"),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag or `If-Modified-Since` date"),
+        (status = 400, description = "Unknown theme or invalid color", body = ErrorResponse),
+        (status = 404, description = "No such user, profile is private, or the layout shows what it hides", body = ErrorResponse),
+    )
+)]
 pub async fn public_card_svg(
     State(state): State<AppState>,
-    Path(username_svg): Path<String>,
-    Query(params): Query<PublicCardQuery>,
-) -> impl IntoResponse {
-    let username = username_svg.strip_suffix(".svg").unwrap_or(&username_svg);
-
-    let user = match sqlite::get_user_by_username(&state.db, username).await {
-        Ok(Some(u)) if u.public_profile => u,
-        // Same 404 for non-existent, private, or DB error — prevents user enumeration
-        _ => return (StatusCode::NOT_FOUND, "not found").into_response(),
-    };
+    headers: HeaderMap,
+    Path(username): Path<String>,
+    Query(style): Query<CardStyle>,
+) -> Result<Response, ApiError> {
+    let (username, format) = Format::split(&username);
+    let (user, settings) = profile_service::public_user(&state.db, username).await?;
+    render_card(&state, &headers, user.id, Some(&settings), &style, format).await
+}
 
-    render_card(&state, user.id, &params.theme, params.days).await
+/// Badge: GET /api/v1/badge/:username/:metric.svg (or `.png`)
+/// Same visibility rules as the public card.
+#[utoipa::path(
+    get,
+    path = "/api/v1/badge/{username}/{metric}",
+    tag = "cards",
+    params(
+        ("username" = String, Path, description = "Username"),
+        ("metric" = String, Path, description = "`total`, `today`, `average`, `language`, `project` or `streak`, optionally with a `.svg` or `.png` suffix"),
+        BadgeQuery,
+    ),
+    responses(
+        (status = 200, description = "SVG badge, or PNG for a `.png` suffix", body = String, content_type = "image/svg+xml"),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag or `If-Modified-Since` date"),
+        (status = 400, description = "Unknown theme or invalid color", body = ErrorResponse),
+        (status = 404, description = "Unknown metric, no such user, profile is private, or it hides the metric", body = ErrorResponse),
+    )
+)]
+pub async fn badge(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((username, metric)): Path<(String, String)>,
+    Query(query): Query<BadgeQuery>,
+) -> Result<Response, ApiError> {
+    let (metric, format) = Format::split(&metric);
+    let metric = Metric::parse(metric).ok_or_else(|| {
+        let names = Metric::NAMES.join(", ");
+        AppError::NotFound(format!("unknown metric '{metric}', expected one of {names}"))
+    })?;
+    let (user, settings) = profile_service::public_user(&state.db, &username).await?;
+    if !profile_service::shows_metric(&settings, metric) {
+        return Err(AppError::NotFound("not found".into()).into());
+    }
+
+    let key = format!("badge:{metric:?}:{format:?}:{query:?}");
+    let slot = match state.cards.get(user.id, key) {
+        Lookup::Hit(hit) => return Ok(respond(&state, &headers, &hit, Scope::Public)),
+        Lookup::Miss(slot) => slot,
+    };
+    let theme = query.theme().await?;
+    let (mut summary, _) = summary(&state, user.id, query.days).await?;
+    profile_service::redact(&mut summary, &settings);
+    let svg = card_service::render_badge(&summary, metric, query.label.as_deref(), &theme);
+    let rendered = state.cards.insert(slot, format.encode(svg).await?);
+    Ok(respond(&state, &headers, &rendered, Scope::Public))
 }
 
-async fn render_card(
+/// The summary of the last `days` days (1-365), and the request behind it.
+async fn summary(
     state: &AppState,
-    user_id: uuid::Uuid,
-    theme: &str,
+    user_id: Uuid,
     days: u32,
-) -> axum::response::Response {
+) -> Result<(Summary, ReportRequest), AppError> {
     let days = days.clamp(1, 365);
     let now = Utc::now();
     let from = now - chrono::Duration::days(days as i64);
@@ -89,35 +313,205 @@ async fn render_card(
         project: None,
         language: None,
     };
+    let summary =
+        report_service::get_summary(&state.db, user_id, &req, state.config.idle_timeout).await?;
+    Ok((summary, req))
+}
 
-    let summary = match report_service::get_summary(
-        &state.db,
-        user_id,
-        &req,
-        state.config.idle_timeout,
-    )
-    .await
-    {
-        Ok(s) => s,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("failed to get summary: {e}"),
-            )
-                .into_response()
-        }
+/// A user's card; a public one leaves out what their profile settings hide.
+async fn render_card(
+    state: &AppState,
+    headers: &HeaderMap,
+    user_id: Uuid,
+    public: Option<&PublicProfileSettings>,
+    style: &CardStyle,
+    format: Format,
+) -> Result<Response, ApiError> {
+    if public.is_some_and(|settings| !profile_service::shows_layout(settings, style.layout)) {
+        return Err(AppError::NotFound("not found".into()).into());
+    }
+    let scope = if public.is_some() { Scope::Public } else { Scope::Private };
+    let key = format!("card:{scope:?}:{format:?}:{style:?}");
+    let slot = match state.cards.get(user_id, key) {
+        Lookup::Hit(hit) => return Ok(respond(state, headers, &hit, scope)),
+        Lookup::Miss(slot) => slot,
+    };
+
+    let theme = style.theme().await?;
+    let (mut summary, req) = summary(state, user_id, style.days).await?;
+    if let Some(settings) = public {
+        profile_service::redact(&mut summary, settings);
+    }
+    let idle_timeout = state.config.idle_timeout;
+    let hourly = if style.layout.needs_hourly() {
+        report_service::get_hourly_activity(&state.db, user_id, &req, idle_timeout).await?
+    } else {
+        Vec::new()
+    };
+    let mut goals = if style.layout.needs_goals() {
+        goal_service::list_goals(&state.db, user_id, 0, idle_timeout).await?
+    } else {
+        Vec::new()
     };
+    if let Some(settings) = public {
+        // Goals are only public when asked for, and not on hidden projects
+        goals.retain(|g| {
+            settings.show_goals && g.goal.project.as_ref().is_none_or(|p| settings.shows_project(p))
+        });
+    }
 
-    let theme = card_service::Theme::from_str(theme);
-    let svg = card_service::render_svg(&summary, theme);
+    let data = CardData {
+        summary: &summary,
+        hourly: &hourly,
+        goals: &goals,
+    };
+    let svg = card_service::render_svg(&data, style.layout, &theme);
+    let rendered = state.cards.insert(slot, format.encode(svg).await?);
+    Ok(respond(state, headers, &rendered, scope))
+}
+
+/// Who a render may be cached for: anyone, or only the client that sent
+/// the API key (shared caches must not hand a private card to others).
+#[derive(Debug, Clone, Copy)]
+enum Scope {
+    Public,
+    Private,
+}
 
+/// The render, or 304 when the client already has it. Clients may keep a
+/// render as long as the server does, then revalidate.
+fn respond(state: &AppState, headers: &HeaderMap, rendered: &Rendered, scope: Scope) -> Response {
+    let max_age = state.cards.ttl().as_secs();
+    let cache_control = match scope {
+        Scope::Public => format!("public, max-age={max_age}"),
+        Scope::Private => format!("private, max-age={max_age}"),
+    };
+    let cache_headers = [
+        (header::ETAG, rendered.etag.clone()),
+        (header::LAST_MODIFIED, rendered.last_modified()),
+        (header::CACHE_CONTROL, cache_control),
+    ];
+    if rendered.not_modified(headers) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
     (
         StatusCode::OK,
-        [
-            (header::CONTENT_TYPE, "image/svg+xml"),
-            (header::CACHE_CONTROL, "public, max-age=1800"),
-        ],
-        svg,
+        [(header::CONTENT_TYPE, rendered.content_type)],
+        cache_headers,
+        rendered.body.clone(),
     )
         .into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use timeforged_core::api::CreateApiKeyRequest;
+
+    use crate::storage::sqlite;
+
+    /// State with one public user, "dev", showing what `settings` allow.
+    async fn setup(settings: PublicProfileSettings) -> AppState {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        let settings = PublicProfileSettings {
+            public: true,
+            ..settings
+        };
+        profile_service::update_settings(&pool, user.id, settings).await.unwrap();
+        AppState::for_tests(pool)
+    }
+
+    async fn badge_status(state: &AppState, metric: &str) -> StatusCode {
+        let query: BadgeQuery = serde_json::from_str("{}").unwrap();
+        let path = Path(("dev".to_string(), format!("{metric}.svg")));
+        match badge(State(state.clone()), HeaderMap::new(), path, Query(query)).await {
+            Ok(response) => response.status(),
+            Err(e) => e.status(),
+        }
+    }
+
+    async fn card_status(state: &AppState, layout: &str) -> StatusCode {
+        let style = format!(r#"{{"layout":"{layout}"}}"#);
+        let style: CardStyle = serde_json::from_str(&style).unwrap();
+        let path = Path("dev.svg".to_string());
+        match public_card_svg(State(state.clone()), HeaderMap::new(), path, Query(style)).await {
+            Ok(response) => response.status(),
+            Err(e) => e.status(),
+        }
+    }
+
+    #[tokio::test]
+    async fn hidden_totals_have_no_badges_or_cards() {
+        let state = setup(PublicProfileSettings {
+            show_totals: false,
+            ..Default::default()
+        })
+        .await;
+        for metric in ["total", "today", "average"] {
+            assert_eq!(badge_status(&state, metric).await, StatusCode::NOT_FOUND, "{metric}");
+        }
+        for metric in ["language", "project", "streak"] {
+            assert_eq!(badge_status(&state, metric).await, StatusCode::OK, "{metric}");
+        }
+        for layout in ["badge", "weekly"] {
+            assert_eq!(card_status(&state, layout).await, StatusCode::NOT_FOUND, "{layout}");
+        }
+        assert_eq!(card_status(&state, "full").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn a_hidden_streak_has_no_badge_or_card() {
+        let state = setup(PublicProfileSettings {
+            show_streak: false,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(badge_status(&state, "streak").await, StatusCode::NOT_FOUND);
+        assert_eq!(badge_status(&state, "total").await, StatusCode::OK);
+        assert_eq!(card_status(&state, "streak").await, StatusCode::NOT_FOUND);
+        assert_eq!(card_status(&state, "full").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn hidden_languages_and_projects_have_no_badges() {
+        let state = setup(PublicProfileSettings {
+            show_languages: false,
+            show_projects: false,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(badge_status(&state, "language").await, StatusCode::NOT_FOUND);
+        assert_eq!(badge_status(&state, "project").await, StatusCode::NOT_FOUND);
+        assert_eq!(badge_status(&state, "total").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn only_public_cards_are_publicly_cacheable() {
+        let state = setup(PublicProfileSettings::default()).await;
+        let user = sqlite::get_user_by_username(&state.db, "dev").await.unwrap().unwrap();
+        let label = CreateApiKeyRequest { label: "card".into() };
+        let key = user_service::create_api_key(&state.db, user.id, label).await.unwrap().key;
+        let cache_control = |response: &Response| {
+            response.headers()[header::CACHE_CONTROL].to_str().unwrap().to_string()
+        };
+
+        let style: CardStyle = serde_json::from_str("{}").unwrap();
+        let params = CardQuery { key: Some(key) };
+        let private = card_svg(State(state.clone()), HeaderMap::new(), Query(params), Query(style));
+        let private = private.await.unwrap();
+        assert!(cache_control(&private).starts_with("private,"), "{}", cache_control(&private));
+
+        let style: CardStyle = serde_json::from_str("{}").unwrap();
+        let path = Path("dev.svg".to_string());
+        let public = public_card_svg(State(state.clone()), HeaderMap::new(), path, Query(style));
+        let public = public.await.unwrap();
+        assert!(cache_control(&public).starts_with("public,"), "{}", cache_control(&public));
+    }
+}
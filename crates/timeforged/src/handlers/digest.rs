@@ -0,0 +1,112 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use timeforged_core::api::{
+    Digest, DigestFormat, DigestQuery, DigestSent, DigestSettings, ErrorResponse,
+};
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::digest_service;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/digest",
+    tag = "digests",
+    responses((status = 200, description = "Digest schedule and delivery", body = DigestSettings)),
+    security(("api_key" = []))
+)]
+pub async fn get_digest_settings(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(digest_service::settings(&state.db, user.id).await?))
+}
+
+/// Replaces all settings; fields left out take their defaults.
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/digest",
+    tag = "digests",
+    request_body = DigestSettings,
+    responses(
+        (status = 200, description = "Digest settings updated; the next digest is the first due from now", body = DigestSettings),
+        (status = 400, description = "Invalid hour or email address, or email without an SMTP server configured", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn set_digest_settings(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(settings): Json<DigestSettings>,
+) -> Result<impl IntoResponse, ApiError> {
+    let settings =
+        digest_service::update_settings(&state.db, &state.config, user.id, settings).await?;
+    Ok(Json(settings))
+}
+
+/// The digest through yesterday, rendered as it is delivered.
+#[utoipa::path(
+    get,
+    path = "/api/v1/digest",
+    tag = "digests",
+    params(DigestQuery),
+    responses(
+        (status = 200, description = "The digest as Markdown (the default), HTML or JSON", content(
+            (String = "text/markdown"),
+            (String = "text/html"),
+            (Digest = "application/json"),
+        )),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn preview_digest(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<DigestQuery>,
+) -> Result<Response, ApiError> {
+    let period = match query.period {
+        Some(period) => period,
+        None => digest_service::settings(&state.db, user.id).await?.period,
+    };
+    let idle_timeout = state.config.idle_timeout;
+    let digest = digest_service::latest(&state.db, user.id, period, idle_timeout).await?;
+    Ok(match query.format {
+        DigestFormat::Markdown => (
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            digest_service::markdown(&digest),
+        )
+            .into_response(),
+        DigestFormat::Html => (
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            digest_service::html(&digest),
+        )
+            .into_response(),
+        DigestFormat::Json => Json(digest).into_response(),
+    })
+}
+
+/// Deliver the digest through yesterday now, to everywhere the settings
+/// name, whether or not scheduled digests are on.
+#[utoipa::path(
+    post,
+    path = "/api/v1/digest/send",
+    tag = "digests",
+    responses((status = 200, description = "Where the digest went, and what failed", body = DigestSent)),
+    security(("api_key" = []))
+)]
+pub async fn send_digest(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    let settings = digest_service::settings(&state.db, user.id).await?;
+    let idle_timeout = state.config.idle_timeout;
+    let digest = digest_service::latest(&state.db, user.id, settings.period, idle_timeout).await?;
+    let sent = digest_service::deliver(&state.db, &state.config, &user, &settings, &digest).await;
+    Ok(Json(sent))
+}
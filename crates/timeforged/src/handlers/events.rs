@@ -1,63 +1,134 @@
-use axum::{Extension, Json, extract::{Query, State}, http::StatusCode, response::IntoResponse};
-use chrono::Utc;
+use axum::{
+    Extension, Json,
+    body::Body,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
 
 use timeforged_core::api::{
-    BatchEventRequest, CreateEventRequest, ErrorResponse, ExportEventsQuery, ExportEventsResponse,
+    BatchEventRequest, BatchEventResponse, CreateEventRequest, ErrorResponse, EventResponse,
+    ExportEventsQuery, ExportEventsResponse, ExportQuery, ImportResponse,
 };
 
 use crate::app::AppState;
 use crate::auth::AuthUser;
+use crate::error::ApiError;
 use crate::service::event_service;
-use crate::storage::sqlite;
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/events",
+    tag = "events",
+    request_body = CreateEventRequest,
+    responses(
+        (status = 201, description = "Event stored", body = EventResponse),
+        (status = 400, description = "Invalid event", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
 pub async fn create_event(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Json(req): Json<CreateEventRequest>,
-) -> impl IntoResponse {
-    match event_service::create_event(&state.db, user.id, req).await {
-        Ok(resp) => (StatusCode::CREATED, Json(resp)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = event_service::create_event(&state.db, &state.bus, user.id, req).await?;
+    Ok((StatusCode::CREATED, Json(resp)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/events/batch",
+    tag = "events",
+    request_body = BatchEventRequest,
+    responses(
+        (status = 200, description = "Batch processed", body = BatchEventResponse),
+        (status = 400, description = "Invalid batch", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
 pub async fn create_batch(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Json(req): Json<BatchEventRequest>,
-) -> impl IntoResponse {
-    match event_service::create_batch(&state.db, user.id, req).await {
-        Ok(resp) => (StatusCode::OK, Json(resp)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = event_service::create_batch(&state.db, &state.bus, user.id, req).await?;
+    Ok((StatusCode::OK, Json(resp)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "events",
+    params(ExportEventsQuery),
+    responses(
+        (status = 200, description = "Events after the cursor (or since the given time)", body = ExportEventsResponse),
+        (status = 400, description = "Invalid cursor", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
 pub async fn list_events(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Query(params): Query<ExportEventsQuery>,
-) -> impl IntoResponse {
-    let since = params.since.unwrap_or_else(|| Utc::now() - chrono::Duration::days(30));
-    let limit = params.limit.clamp(1, 5000);
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = event_service::export_events(&state.db, user.id, params).await?;
+    Ok((StatusCode::OK, Json(resp)))
+}
 
-    match sqlite::list_events(&state.db, user.id, since, limit).await {
-        Ok(events) => {
-            let count = events.len();
-            (StatusCode::OK, Json(ExportEventsResponse { events, count })).into_response()
-        }
-        Err(e) => error_response(e),
-    }
+/// The full history, streamed. Unlike `GET /api/v1/events` there is no
+/// page size: the response ends after the last event.
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/export",
+    tag = "events",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Events in range, one per line (`format=jsonl`, the default) or CSV with a header row (`format=csv`)", body = String, content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid query", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn export(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(params): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let format = params.format;
+    let disposition =
+        format!("attachment; filename=\"timeforged-events.{}\"", format.as_str());
+    let lines = event_service::export_stream(state.db.clone(), user.id, params);
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(lines),
+    )
 }
 
-fn error_response(e: timeforged_core::error::AppError) -> axum::response::Response {
-    use timeforged_core::error::AppError;
-    let (status, msg) = match &e {
-        AppError::Validation(m) | AppError::BadRequest(m) => (StatusCode::BAD_REQUEST, m.clone()),
-        AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".into()),
-        AppError::NotFound(m) => (StatusCode::NOT_FOUND, m.clone()),
-        AppError::Database(m) | AppError::Internal(m) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, m.clone())
-        }
-    };
-    (status, Json(ErrorResponse { error: msg })).into_response()
+/// Import events as JSON Lines, up to 5000 lines a request. Lines that
+/// aren't valid events are rejected without failing the rest.
+#[utoipa::path(
+    post,
+    path = "/api/v1/events/import",
+    tag = "events",
+    request_body(content = String, description = "One `CreateEventRequest` per line", content_type = "application/x-ndjson"),
+    responses(
+        (status = 200, description = "Import summary", body = ImportResponse),
+        (status = 400, description = "Unreadable body, or more than 5000 lines or 64 MiB", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn import(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    body: Body,
+) -> Result<impl IntoResponse, ApiError> {
+    let body = body.into_data_stream();
+    let resp = event_service::import_events(&state.db, &state.bus, user.id, body).await?;
+    Ok((StatusCode::OK, Json(resp)))
 }
@@ -0,0 +1,105 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use timeforged_core::api::{CreateGoalRequest, ErrorResponse, GoalProgress, GoalQuery};
+use timeforged_core::error::AppError;
+use timeforged_core::models::Goal;
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::goal_service;
+
+fn parse_id(id: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(id).map_err(|_| AppError::BadRequest("invalid goal id".into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/goals",
+    tag = "goals",
+    request_body = CreateGoalRequest,
+    responses(
+        (status = 201, description = "Goal created", body = Goal),
+        (status = 400, description = "Invalid target, filter or rest days", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn create_goal(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(req): Json<CreateGoalRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let goal = goal_service::create_goal(&state.db, user.id, req).await?;
+    // Goal cards show the new goal
+    state.cards.invalidate(user.id);
+    Ok((StatusCode::CREATED, Json(goal)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/goals",
+    tag = "goals",
+    params(GoalQuery),
+    responses((status = 200, description = "Goals of the user with progress, streaks and history", body = Vec<GoalProgress>)),
+    security(("api_key" = []))
+)]
+pub async fn list_goals(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<GoalQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let idle_timeout = state.config.idle_timeout;
+    let goals = goal_service::list_goals(&state.db, user.id, query.history, idle_timeout).await?;
+    Ok(Json(goals))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/goals/{id}",
+    tag = "goals",
+    params(("id" = String, Path, description = "Goal id"), GoalQuery),
+    responses(
+        (status = 200, description = "Progress, streaks and history of the goal", body = GoalProgress),
+        (status = 400, description = "Invalid goal id", body = ErrorResponse),
+        (status = 404, description = "Goal not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn get_goal(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Query(query): Query<GoalQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (id, idle_timeout) = (parse_id(&id)?, state.config.idle_timeout);
+    let goal = goal_service::get_goal(&state.db, user.id, id, query.history, idle_timeout).await?;
+    Ok(Json(goal))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/goals/{id}",
+    tag = "goals",
+    params(("id" = String, Path, description = "Goal id")),
+    responses(
+        (status = 204, description = "Goal deleted"),
+        (status = 400, description = "Invalid goal id", body = ErrorResponse),
+        (status = 404, description = "Goal not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn delete_goal(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    goal_service::delete_goal(&state.db, user.id, parse_id(&id)?).await?;
+    state.cards.invalidate(user.id);
+    Ok(StatusCode::NO_CONTENT)
+}
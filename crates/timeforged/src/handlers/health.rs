@@ -5,6 +5,12 @@ use timeforged_core::api::{HealthResponse, StatusResponse};
 use crate::app::AppState;
 use crate::storage::sqlite;
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "status",
+    responses((status = 200, description = "Daemon is up", body = HealthResponse))
+)]
 pub async fn health() -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok".into(),
@@ -12,6 +18,12 @@ pub async fn health() -> Json<HealthResponse> {
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/status",
+    tag = "status",
+    responses((status = 200, description = "Daemon status", body = StatusResponse))
+)]
 pub async fn status(State(state): State<AppState>) -> Json<StatusResponse> {
     let user_count = sqlite::count_users(&state.db).await.unwrap_or(0);
     let event_count = sqlite::count_events(&state.db).await.unwrap_or(0);
@@ -21,5 +33,7 @@ pub async fn status(State(state): State<AppState>) -> Json<StatusResponse> {
         version: timeforged_core::VERSION.into(),
         user_count,
         event_count,
+        remotes: crate::sync::remote_statuses(&state.db, state.config.sync_alert_after).await,
+        card_cache: state.cards.stats(),
     })
 }
@@ -0,0 +1,29 @@
+use axum::{Extension, Json, extract::{Query, State}, http::StatusCode, response::IntoResponse};
+
+use timeforged_core::api::{ErrorResponse, Insights, InsightsQuery};
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::insights_service;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/insights",
+    tag = "insights",
+    params(InsightsQuery),
+    responses(
+        (status = 200, description = "Productive hours and weekdays, session lengths, context switches, focus and late-night time, unusual days", body = Insights),
+        (status = 400, description = "Range backwards or longer than a year", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn insights(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<InsightsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let insights =
+        insights_service::insights(&state.db, user.id, &query, state.config.idle_timeout).await?;
+    Ok((StatusCode::OK, Json(insights)))
+}
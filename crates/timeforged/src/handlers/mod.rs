@@ -1,7 +1,17 @@
+pub mod admin;
+pub mod billing;
 pub mod card;
+pub mod digest;
 pub mod events;
+pub mod goals;
 pub mod health;
+pub mod insights;
+pub mod profile;
 pub mod register;
 pub mod reports;
+pub mod sealed;
+pub mod stream;
+pub mod sync;
 pub mod users;
 pub mod watcher;
+pub mod webhooks;
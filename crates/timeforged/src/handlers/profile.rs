@@ -0,0 +1,32 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+
+use timeforged_core::api::{ErrorResponse, PublicProfile};
+
+use crate::app::AppState;
+use crate::error::ApiError;
+use crate::service::profile_service;
+
+/// Public profile: GET /api/v1/profile/:username
+/// The data behind the dashboard's `/u/:username` page.
+#[utoipa::path(
+    get,
+    path = "/api/v1/profile/{username}",
+    tag = "users",
+    params(("username" = String, Path, description = "Username")),
+    responses(
+        (status = 200, description = "Last year's activity; fields the user hides are left out", body = PublicProfile),
+        (status = 404, description = "No such user or profile is private", body = ErrorResponse),
+    )
+)]
+pub async fn public_profile(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+) -> Result<Json<PublicProfile>, ApiError> {
+    let (user, settings) = profile_service::public_user(&state.db, &username).await?;
+    let idle_timeout = state.config.idle_timeout;
+    let profile = profile_service::public_profile(&state.db, &user, &settings, idle_timeout).await?;
+    Ok(Json(profile))
+}
@@ -1,22 +1,30 @@
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 
 use timeforged_core::api::{ErrorResponse, RegisterRequest, RegisterResponse};
+use timeforged_core::error::AppError;
 
 use crate::app::AppState;
+use crate::error::ApiError;
 use crate::service::user_service;
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/register",
+    tag = "users",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "User created with a default API key", body = RegisterResponse),
+        (status = 400, description = "Invalid username or display name", body = ErrorResponse),
+        (status = 409, description = "Registration failed", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    )
+)]
 pub async fn register(
     State(state): State<AppState>,
     Json(req): Json<RegisterRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     if req.username.is_empty() || req.username.len() > 32 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "username must be 1-32 characters".into(),
-            }),
-        )
-            .into_response();
+        return Err(AppError::Validation("username must be 1-32 characters".into()).into());
     }
 
     // Only allow alphanumeric, hyphens, underscores
@@ -25,69 +33,36 @@ pub async fn register(
         .chars()
         .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
     {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "username must be alphanumeric (hyphens and underscores allowed)".into(),
-            }),
+        return Err(AppError::Validation(
+            "username must be alphanumeric (hyphens and underscores allowed)".into(),
         )
-            .into_response();
+        .into());
     }
 
     // Validate display_name length
-    if let Some(ref name) = req.display_name {
-        if name.len() > 128 {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "display name must be at most 128 characters".into(),
-                }),
-            )
-                .into_response();
-        }
+    if let Some(ref name) = req.display_name
+        && name.len() > 128
+    {
+        return Err(
+            AppError::Validation("display name must be at most 128 characters".into()).into(),
+        );
     }
 
-    let user = match user_service::create_user(
-        &state.db,
-        &req.username,
-        req.display_name.as_deref(),
-    )
-    .await
-    {
-        Ok(u) => u,
-        Err(_) => {
-            // Generic error to prevent username enumeration
-            return (
-                StatusCode::CONFLICT,
-                Json(ErrorResponse {
-                    error: "registration failed".into(),
-                }),
-            )
-                .into_response()
-        }
-    };
+    let user = user_service::create_user(&state.db, &req.username, req.display_name.as_deref())
+        .await
+        // Generic error to prevent username enumeration
+        .map_err(|_| AppError::Conflict("registration failed".into()))?;
 
     let raw_key = user_service::generate_api_key();
     let hash = user_service::hash_api_key(&raw_key);
 
-    if let Err(e) = crate::storage::sqlite::create_api_key(&state.db, user.id, &hash, "default")
-        .await
-    {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("failed to create api key: {e}"),
-            }),
-        )
-            .into_response();
-    }
+    crate::storage::sqlite::create_api_key(&state.db, user.id, &hash, "default").await?;
 
-    (
+    Ok((
         StatusCode::CREATED,
         Json(RegisterResponse {
             username: user.username,
             api_key: raw_key,
         }),
-    )
-        .into_response()
+    ))
 }
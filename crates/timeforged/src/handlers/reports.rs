@@ -1,56 +1,83 @@
 use axum::{Extension, Json, extract::{Query, State}, http::StatusCode, response::IntoResponse};
 
-use timeforged_core::api::ErrorResponse;
-use timeforged_core::models::ReportRequest;
+use timeforged_core::api::{CompareQuery, CompareReport, ErrorResponse};
+use timeforged_core::models::{HourlyActivity, ReportRequest, Session, Summary};
 
 use crate::app::AppState;
 use crate::auth::AuthUser;
+use crate::error::ApiError;
 use crate::service::report_service;
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/summary",
+    tag = "reports",
+    params(ReportRequest),
+    responses((status = 200, description = "Time by project, language and day", body = Summary)),
+    security(("api_key" = []))
+)]
 pub async fn summary(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Query(req): Query<ReportRequest>,
-) -> impl IntoResponse {
-    match report_service::get_summary(&state.db, user.id, &req, state.config.idle_timeout).await {
-        Ok(s) => (StatusCode::OK, Json(s)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let s = report_service::get_summary(&state.db, user.id, &req, state.config.idle_timeout).await?;
+    Ok((StatusCode::OK, Json(s)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/sessions",
+    tag = "reports",
+    params(ReportRequest),
+    responses((status = 200, description = "Coding sessions", body = Vec<Session>)),
+    security(("api_key" = []))
+)]
 pub async fn sessions(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Query(req): Query<ReportRequest>,
-) -> impl IntoResponse {
-    match report_service::get_sessions(&state.db, user.id, &req, state.config.idle_timeout).await {
-        Ok(s) => (StatusCode::OK, Json(s)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let s = report_service::get_sessions(&state.db, user.id, &req, state.config.idle_timeout)
+        .await?;
+    Ok((StatusCode::OK, Json(s)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/activity",
+    tag = "reports",
+    params(ReportRequest),
+    responses((status = 200, description = "Activity by hour of day", body = Vec<HourlyActivity>)),
+    security(("api_key" = []))
+)]
 pub async fn activity(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Query(req): Query<ReportRequest>,
-) -> impl IntoResponse {
-    match report_service::get_hourly_activity(&state.db, user.id, &req, state.config.idle_timeout)
-        .await
-    {
-        Ok(s) => (StatusCode::OK, Json(s)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let s = report_service::get_hourly_activity(&state.db, user.id, &req, state.config.idle_timeout)
+        .await?;
+    Ok((StatusCode::OK, Json(s)))
 }
 
-fn error_response(e: timeforged_core::error::AppError) -> axum::response::Response {
-    use timeforged_core::error::AppError;
-    let (status, msg) = match &e {
-        AppError::Validation(m) | AppError::BadRequest(m) => (StatusCode::BAD_REQUEST, m.clone()),
-        AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".into()),
-        AppError::NotFound(m) => (StatusCode::NOT_FOUND, m.clone()),
-        AppError::Database(m) | AppError::Internal(m) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, m.clone())
-        }
-    };
-    (status, Json(ErrorResponse { error: msg })).into_response()
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/compare",
+    tag = "reports",
+    params(CompareQuery),
+    responses(
+        (status = 200, description = "This period against an earlier one", body = CompareReport),
+        (status = 400, description = "Offset out of range", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn compare(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<CompareQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let report = report_service::compare(&state.db, user.id, &query, state.config.idle_timeout)
+        .await?;
+    Ok((StatusCode::OK, Json(report)))
 }
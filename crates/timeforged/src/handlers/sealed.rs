@@ -0,0 +1,90 @@
+use axum::{Extension, Json, extract::{Query, State}, http::StatusCode, response::IntoResponse};
+
+use timeforged_core::api::{
+    BatchEventResponse, ErrorResponse, SealedBatchRequest, SealedExportQuery,
+    SealedExportResponse, SealedKeyInfo,
+};
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::sealed_service;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sealed/key",
+    tag = "sync",
+    responses(
+        (status = 200, description = "Salt and passphrase check of the account's end-to-end key", body = SealedKeyInfo),
+        (status = 404, description = "Encryption is not set up", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn get_key(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    let info = sealed_service::get_key(&state.db, user.id).await?;
+    Ok((StatusCode::OK, Json(info)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/sealed/key",
+    tag = "sync",
+    request_body = SealedKeyInfo,
+    responses(
+        (status = 200, description = "The key info in effect: this one, or the one set before", body = SealedKeyInfo),
+        (status = 400, description = "Invalid key info", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn set_key(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(req): Json<SealedKeyInfo>,
+) -> Result<impl IntoResponse, ApiError> {
+    let info = sealed_service::set_key(&state.db, user.id, req).await?;
+    Ok((StatusCode::OK, Json(info)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/sealed/batch",
+    tag = "sync",
+    request_body = SealedBatchRequest,
+    responses(
+        (status = 200, description = "Batch processed", body = BatchEventResponse),
+        (status = 400, description = "Invalid batch, or encryption not set up", body = ErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn store_batch(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(req): Json<SealedBatchRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = sealed_service::store_batch(&state.db, user.id, req).await?;
+    Ok((StatusCode::OK, Json(resp)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sealed",
+    tag = "sync",
+    params(SealedExportQuery),
+    responses(
+        (status = 200, description = "Sealed events after the cursor", body = SealedExportResponse),
+        (status = 400, description = "Invalid cursor", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(params): Query<SealedExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = sealed_service::export(&state.db, user.id, params).await?;
+    Ok((StatusCode::OK, Json(resp)))
+}
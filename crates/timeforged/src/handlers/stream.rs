@@ -0,0 +1,106 @@
+use std::convert::Infallible;
+
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    http::HeaderMap,
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+};
+use chrono::Utc;
+use serde::Deserialize;
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+use utoipa::IntoParams;
+
+use timeforged_core::api::{ErrorResponse, StreamEvent, StreamTokenResponse};
+use timeforged_core::error::AppError;
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::{stream_service, user_service};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StreamQuery {
+    /// Stream token from `POST /api/v1/stream/token`, for clients that can't
+    /// set headers (browser `EventSource`)
+    pub token: Option<String>,
+}
+
+/// A token for opening one stream within a minute. Browsers pass it as
+/// `?token=` since `EventSource` can't send `X-Api-Key`; the API key itself
+/// never goes in a URL, where proxies, logs and history would keep it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/stream/token",
+    tag = "events",
+    responses(
+        (status = 200, description = "Single-use token, valid for a minute", body = StreamTokenResponse),
+        (status = 401, description = "Missing or invalid key", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn token(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Json<StreamTokenResponse> {
+    let (token, expires_at) = state.stream_tokens.issue(user.id, Utc::now());
+    Json(StreamTokenResponse { token, expires_at })
+}
+
+/// Live updates as Server-Sent Events: the first message is today's totals,
+/// then `event`, `totals`, `session_started` and `session_ended` as they
+/// happen. Authenticate with `X-Api-Key`, or with `?token=` from
+/// `POST /api/v1/stream/token`; the API key is not accepted in the query.
+#[utoipa::path(
+    get,
+    path = "/api/v1/stream",
+    tag = "events",
+    params(StreamQuery),
+    responses(
+        (status = 200, description = "SSE stream; each `data:` line is one message", body = StreamEvent, content_type = "text/event-stream"),
+        (status = 401, description = "Missing or invalid key, or an unknown, used or expired token", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<StreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, ApiError> {
+    let key = headers.get("X-Api-Key").and_then(|v| v.to_str().ok());
+    let user_id = match (key, params.token) {
+        (Some(key), _) => {
+            let user = user_service::authenticate(&state.db, key)
+                .await
+                .map_err(|_| AppError::Unauthorized)?;
+            user.id
+        }
+        (None, Some(token)) => {
+            state.stream_tokens.redeem(&token, Utc::now()).ok_or(AppError::Unauthorized)?
+        }
+        (None, None) => return Err(AppError::Unauthorized.into()),
+    };
+
+    // Subscribe before reading totals so nothing falls in between
+    let rx = state.bus.subscribe();
+    let totals = stream_service::today_totals(&state.db, user_id, state.config.idle_timeout).await?;
+
+    // Lagged receivers just skip what they missed; the next totals catch up
+    let live = BroadcastStream::new(rx).filter_map(move |msg| match msg {
+        Ok(msg) if msg.user_id == user_id => Some(msg.event),
+        _ => None,
+    });
+    let stream = tokio_stream::once(StreamEvent::Totals(totals))
+        .chain(live)
+        .map(|event| Ok(to_sse(&event)));
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn to_sse(event: &StreamEvent) -> SseEvent {
+    SseEvent::default()
+        .event(event.kind())
+        .json_data(event)
+        .unwrap_or_else(|_| SseEvent::default().comment("serialization failed"))
+}
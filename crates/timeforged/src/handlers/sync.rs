@@ -0,0 +1,165 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use timeforged_core::api::{
+    ErrorResponse, SyncReport, SyncRequest, SyncStatusQuery, SyncStatusResponse, SyncTrigger,
+};
+use timeforged_core::config::CliConfig;
+use timeforged_core::error::AppError;
+use timeforged_sync::remote;
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::user_service;
+use crate::sync;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/sync",
+    tag = "sync",
+    request_body = SyncRequest,
+    responses(
+        (status = 200, description = "One report per remote synced; failures are in the reports", body = Vec<SyncReport>),
+        (status = 400, description = "No remote configured", body = ErrorResponse),
+        (status = 403, description = "Not the admin user", body = ErrorResponse),
+        (status = 404, description = "No remote with that name", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn sync(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(req): Json<SyncRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    // The remotes and their keys are the operator's, and so is what they sync
+    user_service::require_admin(&state.db, &user).await?;
+    let only = req.remote.as_deref();
+    let trigger = SyncTrigger::Manual;
+    let reports = sync::sync_now(&state.db, &state.bus, user.id, req.mode, only, trigger)
+        .await
+        .map_err(sync::to_app_error)?;
+    Ok(Json(reports))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/sync/run",
+    tag = "sync",
+    request_body = SyncRequest,
+    responses(
+        (status = 202, description = "Sync started; follow it with GET /api/v1/sync/status"),
+        (status = 400, description = "No remote configured", body = ErrorResponse),
+        (status = 403, description = "Not the admin user", body = ErrorResponse),
+        (status = 404, description = "No remote with that name", body = ErrorResponse),
+        (status = 409, description = "A background sync is already running", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn run(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(req): Json<SyncRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    user_service::require_admin(&state.db, &user).await?;
+    let running = sync::BackgroundRun::claim().ok_or_else(|| {
+        AppError::Conflict("a sync is already running; see GET /api/v1/sync/status".into())
+    })?;
+    // Fail fast on a bad request; the sync itself runs in the background
+    remote::select(&CliConfig::load(), req.remote.as_deref()).map_err(sync::to_app_error)?;
+
+    tokio::spawn(async move {
+        let _running = running;
+        let only = req.remote.as_deref();
+        let trigger = SyncTrigger::Manual;
+        let run = sync::sync_now(&state.db, &state.bus, user.id, req.mode, only, trigger);
+        if let Err(e) = run.await {
+            tracing::warn!("sync: {e}");
+        }
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sync/status",
+    tag = "sync",
+    params(SyncStatusQuery),
+    responses(
+        (status = 200, description = "State of each remote and the most recent sync runs", body = SyncStatusResponse),
+        (status = 403, description = "Not the admin user", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn status(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Query(query): Query<SyncStatusQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    user_service::require_admin(&state.db, &user).await?;
+    let resp = sync::status(&state.db, state.config.sync_alert_after, query).await?;
+    Ok(Json(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use timeforged_core::models::User;
+
+    use crate::storage::sqlite;
+
+    /// A daemon, its admin and a second user.
+    async fn setup() -> (AppState, User, User) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let admin = sqlite::create_user(&pool, "admin", None).await.unwrap();
+        let other = sqlite::create_user(&pool, "guest", None).await.unwrap();
+        (AppState::for_tests(pool), admin, other)
+    }
+
+    #[tokio::test]
+    async fn only_the_admin_syncs() {
+        let (state, _, guest) = setup().await;
+        let guest = || Extension(AuthUser(guest.clone()));
+        let req = || Json(SyncRequest::default());
+
+        let Err(e) = sync(State(state.clone()), guest(), req()).await else {
+            panic!("a guest synced");
+        };
+        assert_eq!(e.status(), StatusCode::FORBIDDEN);
+        let Err(e) = run(State(state.clone()), guest(), req()).await else {
+            panic!("a guest started a sync");
+        };
+        assert_eq!(e.status(), StatusCode::FORBIDDEN);
+        let query = Query(SyncStatusQuery {
+            remote: None,
+            limit: 20,
+        });
+        let Err(e) = status(State(state), guest(), query).await else {
+            panic!("a guest saw the sync status");
+        };
+        assert_eq!(e.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn one_background_sync_at_a_time() {
+        let (state, admin, _) = setup().await;
+        let running = sync::BackgroundRun::claim().unwrap();
+        let req = Json(SyncRequest::default());
+        let Err(e) = run(State(state), Extension(AuthUser(admin)), req).await else {
+            panic!("a second background sync started");
+        };
+        assert_eq!(e.status(), StatusCode::CONFLICT);
+        drop(running);
+        assert!(sync::BackgroundRun::claim().is_some());
+    }
+}
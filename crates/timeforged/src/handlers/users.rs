@@ -6,90 +6,138 @@ use axum::{
 };
 use uuid::Uuid;
 
-use timeforged_core::api::{CreateApiKeyRequest, ErrorResponse};
-use timeforged_core::models::User;
+use timeforged_core::api::{
+    CreateApiKeyRequest, CreateApiKeyResponse, ErrorResponse, PublicProfileSettings,
+};
+use timeforged_core::error::AppError;
+use timeforged_core::models::{ApiKey, User};
 
 use crate::app::AppState;
 use crate::auth::AuthUser;
-use crate::service::user_service;
+use crate::error::ApiError;
+use crate::service::{profile_service, user_service};
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/me",
+    tag = "users",
+    responses((status = 200, description = "Authenticated user", body = User)),
+    security(("api_key" = []))
+)]
 pub async fn me(Extension(AuthUser(user)): Extension<AuthUser>) -> Json<User> {
     Json(user)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/api-keys",
+    tag = "users",
+    request_body = CreateApiKeyRequest,
+    responses((status = 201, description = "Key created; the raw key is only shown once", body = CreateApiKeyResponse)),
+    security(("api_key" = []))
+)]
 pub async fn create_api_key(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Json(req): Json<CreateApiKeyRequest>,
-) -> impl IntoResponse {
-    match user_service::create_api_key(&state.db, user.id, req).await {
-        Ok(resp) => (StatusCode::CREATED, Json(resp)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = user_service::create_api_key(&state.db, user.id, req).await?;
+    Ok((StatusCode::CREATED, Json(resp)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/api-keys",
+    tag = "users",
+    responses((status = 200, description = "API keys of the user", body = Vec<ApiKey>)),
+    security(("api_key" = []))
+)]
 pub async fn list_api_keys(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
-) -> impl IntoResponse {
-    match user_service::list_api_keys(&state.db, user.id).await {
-        Ok(keys) => Json(keys).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let keys = user_service::list_api_keys(&state.db, user.id).await?;
+    Ok(Json(keys))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/api-keys/{id}",
+    tag = "users",
+    params(("id" = String, Path, description = "API key id")),
+    responses(
+        (status = 204, description = "Key deleted"),
+        (status = 400, description = "Invalid key id", body = ErrorResponse),
+        (status = 404, description = "Key not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
 pub async fn delete_api_key(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
-    let key_id = match Uuid::parse_str(&id) {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "invalid key id".into(),
-                }),
-            )
-                .into_response()
-        }
-    };
+) -> Result<impl IntoResponse, ApiError> {
+    let key_id =
+        Uuid::parse_str(&id).map_err(|_| AppError::BadRequest("invalid key id".into()))?;
 
-    match user_service::delete_api_key(&state.db, user.id, key_id).await {
-        Ok(()) => StatusCode::NO_CONTENT.into_response(),
-        Err(e) => error_response(e),
-    }
+    user_service::delete_api_key(&state.db, user.id, key_id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-#[derive(serde::Deserialize)]
-pub struct SetPublicProfileRequest {
-    pub public: bool,
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct PublicProfileResponse {
+    /// Same as `settings.public`, for older clients
+    pub public_profile: bool,
+    pub settings: PublicProfileSettings,
+    pub card_url: String,
+    pub profile_url: String,
 }
 
-pub async fn set_public_profile(
+impl PublicProfileResponse {
+    fn new(user: &User, settings: PublicProfileSettings) -> Self {
+        Self {
+            public_profile: settings.public,
+            settings,
+            card_url: format!("/api/v1/card/{}.svg", user.username),
+            profile_url: format!("/u/{}", user.username),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/public-profile",
+    tag = "users",
+    responses((status = 200, description = "Profile visibility", body = PublicProfileResponse)),
+    security(("api_key" = []))
+)]
+pub async fn get_public_profile(
     State(state): State<AppState>,
     Extension(AuthUser(user)): Extension<AuthUser>,
-    Json(req): Json<SetPublicProfileRequest>,
-) -> impl IntoResponse {
-    match crate::storage::sqlite::set_public_profile(&state.db, user.id, req.public).await {
-        Ok(()) => Json(serde_json::json!({
-            "public_profile": req.public,
-            "card_url": format!("/api/v1/card/{}.svg", user.username),
-        })).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let settings = profile_service::settings(&state.db, user.id).await?;
+    Ok(Json(PublicProfileResponse::new(&user, settings)))
 }
 
-fn error_response(e: timeforged_core::error::AppError) -> axum::response::Response {
-    use timeforged_core::error::AppError;
-    let (status, msg) = match &e {
-        AppError::Validation(m) | AppError::BadRequest(m) => (StatusCode::BAD_REQUEST, m.clone()),
-        AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".into()),
-        AppError::NotFound(m) => (StatusCode::NOT_FOUND, m.clone()),
-        AppError::Database(m) | AppError::Internal(m) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, m.clone())
-        }
-    };
-    (status, Json(ErrorResponse { error: msg })).into_response()
+/// Replaces all settings; fields left out take their defaults.
+#[utoipa::path(
+    put,
+    path = "/api/v1/me/public-profile",
+    tag = "users",
+    request_body = PublicProfileSettings,
+    responses(
+        (status = 200, description = "Profile visibility updated", body = PublicProfileResponse),
+        (status = 400, description = "Invalid or too many hidden projects, or events are end-to-end encrypted so there is nothing to show", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn set_public_profile(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(settings): Json<PublicProfileSettings>,
+) -> Result<impl IntoResponse, ApiError> {
+    let settings = profile_service::update_settings(&state.db, user.id, settings).await?;
+    // Public cards and badges are redacted with the old settings
+    state.cards.invalidate(user.id);
+    Ok(Json(PublicProfileResponse::new(&user, settings)))
 }
@@ -13,28 +13,54 @@ use timeforged_core::config::WatchedRegistry;
 use timeforged_core::error::AppError;
 
 use crate::app::AppState;
+use crate::error::ApiError;
 use crate::watcher::WatcherCommand;
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/watch",
+    tag = "watcher",
+    request_body = WatchRequest,
+    responses(
+        (status = 200, description = "Directory watched", body = WatchActionResponse),
+        (status = 400, description = "Not a directory", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
 pub async fn watch(
     State(state): State<AppState>,
     Json(req): Json<WatchRequest>,
-) -> impl IntoResponse {
-    match do_watch(state, req).await {
-        Ok(resp) => (StatusCode::OK, Json(resp)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = do_watch(state, req).await?;
+    Ok((StatusCode::OK, Json(resp)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/watch",
+    tag = "watcher",
+    request_body = UnwatchRequest,
+    responses(
+        (status = 200, description = "Directory no longer watched", body = WatchActionResponse),
+        (status = 404, description = "Directory was not watched", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
 pub async fn unwatch(
     State(state): State<AppState>,
     Json(req): Json<UnwatchRequest>,
-) -> impl IntoResponse {
-    match do_unwatch(state, req).await {
-        Ok(resp) => (StatusCode::OK, Json(resp)).into_response(),
-        Err(e) => error_response(e),
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let resp = do_unwatch(state, req).await?;
+    Ok((StatusCode::OK, Json(resp)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/watched",
+    tag = "watcher",
+    responses((status = 200, description = "Watched directories", body = WatchedResponse)),
+    security(("api_key" = []))
+)]
 pub async fn list(State(_state): State<AppState>) -> impl IntoResponse {
     let registry = WatchedRegistry::load();
     let dirs = registry
@@ -117,15 +143,3 @@ async fn do_unwatch(
         )))
     }
 }
-
-fn error_response(e: AppError) -> axum::response::Response {
-    let (status, msg) = match &e {
-        AppError::Validation(m) | AppError::BadRequest(m) => (StatusCode::BAD_REQUEST, m.clone()),
-        AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".into()),
-        AppError::NotFound(m) => (StatusCode::NOT_FOUND, m.clone()),
-        AppError::Database(m) | AppError::Internal(m) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, m.clone())
-        }
-    };
-    (status, Json(ErrorResponse { error: msg })).into_response()
-}
@@ -0,0 +1,117 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use timeforged_core::api::{CreateWebhookRequest, CreateWebhookResponse, ErrorResponse};
+use timeforged_core::error::AppError;
+use timeforged_core::models::{Webhook, WebhookDelivery};
+
+use crate::app::AppState;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::service::webhook_service;
+
+fn parse_id(id: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(id).map_err(|_| AppError::BadRequest("invalid webhook id".into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook created; the secret is only shown once", body = CreateWebhookResponse),
+        (status = 400, description = "Invalid URL or triggers, or a receiver that isn't public", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let allow_private = state.config.webhooks.allow_private;
+    let resp = webhook_service::create_webhook(&state.db, user.id, req, allow_private).await?;
+    Ok((StatusCode::CREATED, Json(resp)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    responses((status = 200, description = "Webhooks of the user", body = Vec<Webhook>)),
+    security(("api_key" = []))
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, ApiError> {
+    let hooks = webhook_service::list_webhooks(&state.db, user.id).await?;
+    Ok(Json(hooks))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 204, description = "Webhook and its delivery log deleted"),
+        (status = 400, description = "Invalid webhook id", body = ErrorResponse),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    webhook_service::delete_webhook(&state.db, user.id, parse_id(&id)?).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "Latest deliveries, newest first", body = Vec<WebhookDelivery>),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let log = webhook_service::list_deliveries(&state.db, user.id, parse_id(&id)?).await?;
+    Ok(Json(log))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{id}/test",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 202, description = "Ping delivery queued", body = WebhookDelivery),
+        (status = 404, description = "Webhook not found", body = ErrorResponse),
+    ),
+    security(("api_key" = []))
+)]
+pub async fn test_webhook(
+    State(state): State<AppState>,
+    Extension(AuthUser(user)): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let delivery = webhook_service::send_test(&state.db, user.id, parse_id(&id)?).await?;
+    Ok((StatusCode::ACCEPTED, Json(delivery)))
+}
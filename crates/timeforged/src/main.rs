@@ -2,9 +2,17 @@
 
 mod app;
 mod auth;
+mod backup;
+mod bus;
+mod error;
 mod handlers;
+mod maintenance;
+mod openapi;
+mod pdf;
 mod rate_limit;
+mod request_id;
 mod service;
+mod smtp;
 mod storage;
 mod sync;
 mod watcher;
@@ -13,6 +21,7 @@ mod web;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use clap::{Parser, Subcommand};
 use sqlx::sqlite::SqlitePoolOptions;
 use tokio::sync::{mpsc, Mutex};
 use tracing_subscriber::EnvFilter;
@@ -20,11 +29,42 @@ use tracing_subscriber::EnvFilter;
 use timeforged_core::config::{AppConfig, WatchedRegistry, WatcherConfig};
 
 use crate::app::{AppState, build_router};
+use crate::bus::EventBus;
+use crate::rate_limit::RateLimits;
+use crate::service::card_service::RenderCache;
 use crate::service::user_service;
 use crate::storage::sqlite::init_db;
 
+#[derive(Parser)]
+#[command(name = "timeforged", about = "TimeForged daemon", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Back up the database; safe while the daemon runs
+    Backup {
+        /// Backup file (default: a timestamped file in the backup directory)
+        path: Option<PathBuf>,
+    },
+    /// Replace the database with a backup; stop the daemon first
+    Restore {
+        /// Backup file to restore
+        path: PathBuf,
+    },
+    /// Compact old events per the retention policy, then VACUUM and ANALYZE
+    Maintenance {
+        /// Only count what compaction would remove
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
     let config = AppConfig::load();
 
     tracing_subscriber::fmt()
@@ -34,12 +74,18 @@ async fn main() -> anyhow::Result<()> {
         )
         .init();
 
-    // Ensure data directory exists for SQLite
-    if let Some(path) = config.database_url.strip_prefix("sqlite:") {
-        let db_path = path.split('?').next().unwrap_or(path);
-        if let Some(parent) = std::path::Path::new(db_path).parent() {
-            std::fs::create_dir_all(parent)?;
+    match cli.command {
+        Some(Command::Backup { path }) => return backup::backup_command(&config, path).await,
+        Some(Command::Restore { path }) => return backup::restore_command(&config, &path).await,
+        Some(Command::Maintenance { dry_run }) => {
+            return maintenance::command(&config, dry_run).await;
         }
+        None => {}
+    }
+
+    // Ensure data directory exists for SQLite
+    if let Some(parent) = config.database_path().as_deref().and_then(std::path::Path::parent) {
+        std::fs::create_dir_all(parent)?;
     }
 
     let pool = SqlitePoolOptions::new()
@@ -75,6 +121,38 @@ async fn main() -> anyhow::Result<()> {
     // Create watcher command channel
     let (watcher_tx, watcher_rx) = mpsc::channel(256);
 
+    // Live event bus: watchers and the API publish, /api/v1/stream subscribes
+    let bus = EventBus::new();
+    let session_bus = bus.clone();
+    let stream_pool = pool.clone();
+    let idle_timeout = config.idle_timeout;
+    tokio::spawn(async move {
+        service::stream_service::run(stream_pool, session_bus, idle_timeout).await;
+    });
+
+    // Webhook dispatch and delivery
+    let webhook_pool = pool.clone();
+    let webhook_bus = bus.clone();
+    let allow_private = config.webhooks.allow_private;
+    tokio::spawn(async move {
+        service::webhook_service::run(webhook_pool, webhook_bus, idle_timeout, allow_private)
+            .await;
+    });
+
+    // Goal checks, for goal_reached webhooks
+    let goal_pool = pool.clone();
+    let goal_bus = bus.clone();
+    tokio::spawn(async move {
+        service::goal_service::run(goal_pool, goal_bus, idle_timeout).await;
+    });
+
+    // Scheduled digests
+    let digest_pool = pool.clone();
+    let digest_config = config.clone();
+    tokio::spawn(async move {
+        service::digest_service::run(digest_pool, digest_config, idle_timeout).await;
+    });
+
     // Get first user ID for watcher events
     let watcher_user_id = user_service::get_first_user(&pool)
         .await
@@ -84,18 +162,35 @@ async fn main() -> anyhow::Result<()> {
     // Spawn file watcher
     if let Some(user_id) = watcher_user_id {
         let watcher_pool = pool.clone();
+        let watcher_bus = bus.clone();
         let watcher_cfg = watcher_config.clone();
         let dirs = initial_dirs.clone();
         tokio::spawn(async move {
-            watcher::file_watcher::run(watcher_pool, user_id, watcher_cfg, dirs, watcher_rx).await;
+            watcher::file_watcher::run(
+                watcher_pool,
+                watcher_bus,
+                user_id,
+                watcher_cfg,
+                dirs,
+                watcher_rx,
+            )
+            .await;
         });
 
         // Spawn window tracker
         let tracker_pool = pool.clone();
+        let tracker_bus = bus.clone();
         let tracker_cfg = watcher_config.clone();
         let watched_dirs = Arc::new(Mutex::new(initial_dirs));
         tokio::spawn(async move {
-            watcher::window_tracker::run(tracker_pool, user_id, tracker_cfg, watched_dirs).await;
+            watcher::window_tracker::run(
+                tracker_pool,
+                tracker_bus,
+                user_id,
+                tracker_cfg,
+                watched_dirs,
+            )
+            .await;
         });
 
         if !registry.list().is_empty() {
@@ -116,17 +211,52 @@ async fn main() -> anyhow::Result<()> {
     // Spawn auto-sync to remote
     if let Some(user_id) = watcher_user_id {
         let sync_pool = pool.clone();
+        let sync_bus = bus.clone();
         let sync_interval = config.sync_interval;
         tokio::spawn(async move {
-            sync::run(sync_pool, user_id, sync_interval).await;
+            sync::run(sync_pool, sync_bus, user_id, sync_interval).await;
         });
     }
 
+    // Spawn rate limiter eviction
+    let rate_limits = RateLimits::from_config(&config.rate_limit);
+    let eviction_limits = rate_limits.clone();
+    let eviction_interval = config.rate_limit.eviction_interval_secs;
+    tokio::spawn(async move {
+        rate_limit::run_eviction(eviction_limits, eviction_interval).await;
+    });
+
+    // Scheduled database backups
+    let backup_pool = pool.clone();
+    let backup_config = config.clone();
+    tokio::spawn(async move {
+        backup::run(backup_pool, backup_config).await;
+    });
+
+    // Retention and database upkeep
+    let maintenance_pool = pool.clone();
+    let maintenance_config = config.clone();
+    tokio::spawn(async move {
+        maintenance::run(maintenance_pool, maintenance_config).await;
+    });
+
     let bind_addr = config.bind_addr();
+    // Card renders, dropped as their users record activity
+    let cards = RenderCache::from_config(&config.card);
+    let card_bus = bus.clone();
+    let invalidated_cards = cards.clone();
+    tokio::spawn(async move {
+        service::card_service::cache::run(invalidated_cards, card_bus).await;
+    });
+
     let state = AppState {
         db: pool,
         config,
         watcher_tx,
+        rate_limits,
+        bus,
+        cards,
+        stream_tokens: Default::default(),
     };
     let router = build_router(state);
 
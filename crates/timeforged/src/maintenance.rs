@@ -0,0 +1,628 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Days, NaiveDate, Timelike, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use sqlx::SqlitePool;
+
+use timeforged_core::api::CreateEventRequest;
+use timeforged_core::config::AppConfig;
+use timeforged_core::error::AppError;
+use timeforged_core::interchange::ExportFormat;
+use timeforged_core::models::{Event, User};
+
+use crate::backup;
+use crate::storage::sqlite;
+
+/// Events read per query while compacting.
+const PAGE: i64 = 1000;
+/// Wait after startup before the first maintenance run.
+const FIRST_RUN_SECS: u64 = 600;
+/// Kept under the idle timeout, against rounding in the reports' date
+/// arithmetic.
+const MARGIN_MS: i64 = 1000;
+
+fn io(e: std::io::Error) -> AppError {
+    AppError::Internal(e.to_string())
+}
+
+/// A stored event as compaction sees it.
+struct Beat {
+    event: Event,
+    id: i64,
+    heartbeats: i64,
+    /// `heartbeats` grew since the row was read.
+    changed: bool,
+}
+
+impl Beat {
+    fn new(event: Event, heartbeats: i64) -> Self {
+        Self {
+            id: event.id.unwrap_or_default(),
+            event,
+            heartbeats,
+            changed: false,
+        }
+    }
+
+    fn hour(&self) -> (NaiveDate, u32) {
+        (self.event.timestamp.date_naive(), self.event.timestamp.hour())
+    }
+
+    /// Timesheets bill each gap by the activity of the event ending it, so
+    /// that counts as much as the project and language.
+    fn same_kind(&self, other: &Beat) -> bool {
+        self.event.project == other.event.project
+            && self.event.language == other.event.language
+            && self.event.activity == other.event.activity
+    }
+}
+
+/// What compaction decided for one page of events.
+#[derive(Default)]
+struct Page {
+    dropped: Vec<Beat>,
+    /// Ids of the dropped rows, and of the row that took over each one.
+    merged: Vec<(i64, i64)>,
+    /// New heartbeat counts of rows kept, by id.
+    heartbeats: Vec<(i64, i64)>,
+}
+
+/// Goes through a user's events in report order and drops the ones no
+/// report needs: those whose neighbours are of the same project, language
+/// and activity, in the same hour, and close enough that the reports count the
+/// time between them anyway, so the two gaps the event splits add up to
+/// the one left. A dropped event's heartbeat count moves to the next one,
+/// which is in the same hour and session. The first and last events of a
+/// session are never dropped.
+struct Thinner {
+    max_gap_ms: i64,
+    kept: Option<Beat>,
+    pending: Option<Beat>,
+}
+
+impl Thinner {
+    fn new(idle_timeout: u64) -> Self {
+        Self {
+            max_gap_ms: (idle_timeout as i64).saturating_mul(1000) - MARGIN_MS,
+            kept: None,
+            pending: None,
+        }
+    }
+
+    fn push(&mut self, mut next: Beat, page: &mut Page) {
+        if let Some(current) = self.pending.take() {
+            if self.droppable(&current, &next) {
+                next.heartbeats += current.heartbeats;
+                next.changed = true;
+                page.merged.push((current.id, next.id));
+                page.dropped.push(current);
+            } else {
+                if current.changed {
+                    page.heartbeats.push((current.id, current.heartbeats));
+                }
+                self.kept = Some(current);
+            }
+        }
+        self.pending = Some(next);
+    }
+
+    /// The pending event's count may still grow, but write it as it stands
+    /// so that every page is consistent on its own.
+    fn flush(&self, page: &mut Page) {
+        if let Some(pending) = self.pending.as_ref().filter(|p| p.changed) {
+            page.heartbeats.push((pending.id, pending.heartbeats));
+        }
+    }
+
+    fn droppable(&self, current: &Beat, next: &Beat) -> bool {
+        let Some(prev) = &self.kept else { return false };
+        let (prev_at, at, next_at) =
+            (prev.event.timestamp, current.event.timestamp, next.event.timestamp);
+        // Ties could be ordered either way by the reports
+        prev_at < at
+            && at < next_at
+            && prev.same_kind(current)
+            && current.same_kind(next)
+            && prev.hour() == next.hour()
+            && (next_at - prev_at).num_milliseconds() < self.max_gap_ms
+    }
+}
+
+/// Heartbeats compaction removed, as gzipped JSON Lines in the export
+/// format, so `zcat <file> | tf import - --format jsonl` reads them back.
+struct Archive {
+    path: PathBuf,
+    out: GzEncoder<BufWriter<File>>,
+}
+
+impl Archive {
+    fn create(dir: &Path, username: &str) -> Result<Self, AppError> {
+        std::fs::create_dir_all(dir).map_err(io)?;
+        let name: String = username
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+        let path = dir.join(format!("{name}-{stamp}.jsonl.gz"));
+        let file = File::create_new(&path).map_err(io)?;
+        Ok(Self {
+            path,
+            out: GzEncoder::new(BufWriter::new(file), Compression::default()),
+        })
+    }
+
+    /// Flushed before the rows are deleted.
+    fn write(&mut self, beats: &[Beat]) -> Result<(), AppError> {
+        for beat in beats {
+            let line = ExportFormat::Jsonl.line(&CreateEventRequest::from_event(&beat.event));
+            self.out.write_all(line.as_bytes()).map_err(io)?;
+        }
+        self.out.flush().map_err(io)
+    }
+
+    fn finish(self) -> Result<PathBuf, AppError> {
+        self.out.finish().map_err(io)?.flush().map_err(io)?;
+        Ok(self.path)
+    }
+}
+
+/// What a maintenance run did.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Events before this were compacted; `None` if compaction is off.
+    pub before: Option<DateTime<Utc>>,
+    pub scanned: u64,
+    pub compacted: u64,
+    pub archives: Vec<PathBuf>,
+    pub size_before: u64,
+    pub size_after: u64,
+    pub vacuumed: bool,
+}
+
+/// Midnight UTC, `days` days before today.
+fn cutoff(now: DateTime<Utc>, days: u32) -> DateTime<Utc> {
+    let day = now.date_naive() - Days::new(days.into());
+    day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+async fn pragma(pool: &SqlitePool, name: &str) -> Result<i64, AppError> {
+    sqlx::query_scalar(&format!("PRAGMA {name}"))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))
+}
+
+async fn size(pool: &SqlitePool) -> Result<u64, AppError> {
+    Ok((pragma(pool, "page_count").await? * pragma(pool, "page_size").await?) as u64)
+}
+
+async fn compact_user(
+    pool: &SqlitePool,
+    user: &User,
+    before: DateTime<Utc>,
+    config: &AppConfig,
+    dry_run: bool,
+    report: &mut Report,
+) -> Result<(), AppError> {
+    let archive_dir = config.archive_dir().filter(|_| config.retention.archive);
+    let mut archive: Option<Archive> = None;
+    let mut thinner = Thinner::new(config.idle_timeout);
+    let mut after = None;
+
+    loop {
+        let rows = sqlite::list_events_for_compaction(pool, user.id, before, after, PAGE).await?;
+        let Some((last, _)) = rows.last() else { break };
+        after = Some((last.timestamp, last.id.unwrap_or_default()));
+        let full = rows.len() as i64 == PAGE;
+
+        let mut page = Page::default();
+        for (event, heartbeats) in rows {
+            report.scanned += 1;
+            thinner.push(Beat::new(event, heartbeats), &mut page);
+        }
+        thinner.flush(&mut page);
+        report.compacted += page.dropped.len() as u64;
+
+        let changed = !page.dropped.is_empty() || !page.heartbeats.is_empty();
+        if changed && !dry_run {
+            if let Some(dir) = &archive_dir
+                && !page.dropped.is_empty()
+            {
+                let out = match &mut archive {
+                    Some(out) => out,
+                    None => archive.insert(Archive::create(dir, &user.username)?),
+                };
+                out.write(&page.dropped)?;
+            }
+            sqlite::apply_compaction(pool, user.id, &page.merged, &page.heartbeats).await?;
+        }
+        if !full {
+            break;
+        }
+    }
+
+    if let Some(archive) = archive {
+        report.archives.push(archive.finish()?);
+    }
+    Ok(())
+}
+
+/// Compact events older than `retention.compact_after_days` (whole UTC
+/// days), then `VACUUM` if that or earlier deletes left free space, and
+/// `ANALYZE`. A dry run only counts what compaction would remove.
+pub async fn maintain(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    dry_run: bool,
+) -> Result<Report, AppError> {
+    let mut report = Report {
+        size_before: size(pool).await?,
+        ..Default::default()
+    };
+
+    let days = config.retention.compact_after_days;
+    if days > 0 {
+        let before = cutoff(Utc::now(), days);
+        report.before = Some(before);
+        for user in sqlite::users_with_events_before(pool, before).await? {
+            compact_user(pool, &user, before, config, dry_run, &mut report).await?;
+        }
+    }
+
+    if !dry_run {
+        let free = pragma(pool, "freelist_count").await?;
+        if report.compacted > 0 || free * 4 > pragma(pool, "page_count").await? {
+            sqlx::query("VACUUM")
+                .execute(pool)
+                .await
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            report.vacuumed = true;
+        }
+        sqlx::query("ANALYZE")
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+    }
+    report.size_after = size(pool).await?;
+    Ok(report)
+}
+
+/// Maintenance every `retention.maintenance_interval` seconds, the first
+/// run a while after startup.
+pub async fn run(pool: SqlitePool, config: AppConfig) {
+    let interval = config.retention.maintenance_interval;
+    if interval == 0 {
+        return;
+    }
+    tokio::time::sleep(Duration::from_secs(FIRST_RUN_SECS.min(interval))).await;
+
+    loop {
+        match maintain(&pool, &config, false).await {
+            Ok(report) => {
+                tracing::info!(
+                    "maintenance: compacted {} of {} old events, database {} -> {} bytes",
+                    report.compacted,
+                    report.scanned,
+                    report.size_before,
+                    report.size_after,
+                );
+            }
+            Err(e) => tracing::warn!("maintenance failed: {e}"),
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+fn mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1 << 20) as f64)
+}
+
+/// `timeforged maintenance [--dry-run]`: what the daemon does on its own
+/// every `maintenance_interval`, now. Safe while the daemon runs.
+pub async fn command(config: &AppConfig, dry_run: bool) -> anyhow::Result<()> {
+    let db = config
+        .database_path()
+        .ok_or_else(|| anyhow::anyhow!("the database is in memory, nothing to maintain"))?;
+    if !db.is_file() {
+        anyhow::bail!("no database at {}", db.display());
+    }
+    let fail = |e: AppError| anyhow::anyhow!(backup::describe(&e));
+    let pool = backup::open(&db, false).await.map_err(fail)?;
+    let result = match sqlite::init_db(&pool).await {
+        Ok(()) => maintain(&pool, config, dry_run).await,
+        Err(e) => Err(e),
+    };
+    pool.close().await;
+    let report = result.map_err(fail)?;
+
+    match report.before {
+        Some(before) => {
+            let verb = if dry_run { "Would compact" } else { "Compacted" };
+            println!(
+                "{verb} {} of {} events before {}",
+                report.compacted,
+                report.scanned,
+                before.format("%Y-%m-%d"),
+            );
+        }
+        None => println!("Compaction is off (retention.compact_after_days = 0)"),
+    }
+    for archive in &report.archives {
+        println!("  archived to {}", archive.display());
+    }
+    if dry_run {
+        println!("Database: {} (dry run, nothing changed)", mib(report.size_before));
+    } else {
+        let vacuumed = if report.vacuumed { ", vacuumed" } else { "" };
+        println!(
+            "Database: {} -> {}{vacuumed}, statistics updated",
+            mib(report.size_before),
+            mib(report.size_after),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+
+    use chrono::TimeZone;
+    use flate2::read::GzDecoder;
+    use serde_json::Value;
+    use timeforged_core::api::{ClientRequest, TimesheetQuery};
+    use timeforged_core::models::{ActivityType, EventType, ReportRequest, Rounding};
+    use uuid::Uuid;
+
+    use crate::bus::EventBus;
+    use crate::service::{billing_service, event_service, report_service};
+
+    use super::*;
+
+    fn event(user_id: Uuid, at: DateTime<Utc>, project: Option<&str>, language: &str) -> Event {
+        Event {
+            id: None,
+            user_id,
+            timestamp: at,
+            event_type: EventType::File,
+            // Stored as the API would, which infers no project from a file in `/`
+            entity: match project {
+                Some(project) => format!("/home/dev/{project}/src/lib.{language}"),
+                None => format!("/scratch.{language}"),
+            },
+            project: project.map(String::from),
+            language: Some(language.into()),
+            branch: None,
+            activity: None,
+            machine: None,
+            metadata: None,
+            created_at: None,
+        }
+    }
+
+    fn beat(id: i64, minute: i64, project: &str) -> Beat {
+        let at = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let at = at + chrono::Duration::minutes(minute);
+        let mut event = event(Uuid::nil(), at, Some(project), "rs");
+        event.id = Some(id);
+        Beat::new(event, 1)
+    }
+
+    /// Every report for `[from, to)`, with and without a project filter.
+    async fn reports(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Value {
+        let mut all = Vec::new();
+        for project in [None, Some("api".to_string())] {
+            let req = ReportRequest {
+                from: Some(from),
+                to: Some(to),
+                project,
+                language: None,
+            };
+            let summary = report_service::get_summary(pool, user_id, &req, 300).await.unwrap();
+            let sessions = report_service::get_sessions(pool, user_id, &req, 300).await.unwrap();
+            let hourly =
+                report_service::get_hourly_activity(pool, user_id, &req, 300).await.unwrap();
+            all.push(serde_json::json!([summary, sessions, hourly]));
+        }
+        Value::Array(all)
+    }
+
+    /// Equal, but for rounding in the reports' date arithmetic.
+    fn assert_close(a: &Value, b: &Value) {
+        match (a, b) {
+            (Value::Number(x), Value::Number(y)) => {
+                let (x, y) = (x.as_f64().unwrap(), y.as_f64().unwrap());
+                assert!((x - y).abs() < 1e-3, "{x} != {y}");
+            }
+            (Value::Array(x), Value::Array(y)) => {
+                assert_eq!(x.len(), y.len(), "{a} != {b}");
+                x.iter().zip(y).for_each(|(x, y)| assert_close(x, y));
+            }
+            (Value::Object(x), Value::Object(y)) => {
+                assert_eq!(x.len(), y.len(), "{a} != {b}");
+                x.iter().for_each(|(k, v)| assert_close(v, &y[k]));
+            }
+            _ => assert_eq!(a, b),
+        }
+    }
+
+    #[test]
+    fn only_events_inside_a_run_are_dropped() {
+        let mut thinner = Thinner::new(300);
+        let mut page = Page::default();
+        // Saves every minute, a break, a project switch, an hour boundary
+        for (id, minute, project) in [
+            (1, 0, "api"),
+            (2, 1, "api"),
+            (3, 2, "api"),
+            (4, 3, "api"),
+            (5, 20, "api"),
+            (6, 21, "api"),
+            (7, 22, "web"),
+            (8, 23, "web"),
+            (9, 59, "web"),
+            (10, 60, "web"),
+            (11, 61, "web"),
+        ] {
+            thinner.push(beat(id, minute, project), &mut page);
+        }
+        thinner.flush(&mut page);
+
+        let dropped: Vec<i64> = page.dropped.iter().map(|b| b.id).collect();
+        assert_eq!(dropped, [2, 3]);
+        assert_eq!(page.merged, [(2, 3), (3, 4)]);
+        assert_eq!(page.heartbeats, [(4, 3)]);
+    }
+
+    #[tokio::test]
+    async fn compaction_keeps_reports_and_archives_what_it_removes() {
+        let dir = std::env::temp_dir().join(format!("tf-maintenance-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = backup::open(&dir.join("timeforged.db"), false).await.unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+
+        // A morning of saves every 30s: two projects, breaks, files in two
+        // languages, repeats at the same instant, and one without a project
+        let day = Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap();
+        let mut at = day + chrono::Duration::hours(9);
+        for i in 0..600 {
+            at += chrono::Duration::seconds(if i % 150 == 149 { 900 } else { 30 });
+            let project = match i {
+                290..300 => None,
+                _ if (i / 40) % 2 == 0 => Some("api"),
+                _ => Some("web"),
+            };
+            let language = if i % 60 == 59 { "toml" } else { "rs" };
+            sqlite::insert_event(&pool, &event(user.id, at, project, language)).await.unwrap();
+            if i % 50 == 0 {
+                let mut twin = event(user.id, at, project, language);
+                twin.entity.push_str(".bak");
+                sqlite::insert_event(&pool, &twin).await.unwrap();
+            }
+        }
+        let recent = Utc::now() - chrono::Duration::hours(1);
+        sqlite::insert_event(&pool, &event(user.id, recent, Some("api"), "rs")).await.unwrap();
+        let stored = sqlite::count_events(&pool).await.unwrap();
+
+        let ranges = [
+            (day, day + chrono::Duration::days(1)),
+            (day + chrono::Duration::hours(10), day + chrono::Duration::hours(12)),
+        ];
+        let mut before = Vec::new();
+        for (from, to) in ranges {
+            before.push(reports(&pool, user.id, from, to).await);
+        }
+
+        let mut config = AppConfig {
+            database_url: format!("sqlite:{}", dir.join("timeforged.db").display()),
+            idle_timeout: 300,
+            ..Default::default()
+        };
+        config.retention.compact_after_days = 30;
+        let dry = maintain(&pool, &config, true).await.unwrap();
+        assert_eq!(sqlite::count_events(&pool).await.unwrap(), stored);
+        assert!(dry.archives.is_empty() && !dry.vacuumed);
+
+        let report = maintain(&pool, &config, false).await.unwrap();
+        assert_eq!(report.compacted, dry.compacted);
+        assert_eq!(report.scanned, stored as u64 - 1);
+        let left = sqlite::count_events(&pool).await.unwrap();
+        assert_eq!(left + report.compacted as i64, stored);
+        assert!(left * 4 < stored, "only {} of {stored} compacted", report.compacted);
+        assert!(report.vacuumed);
+
+        for ((from, to), before) in ranges.into_iter().zip(&before) {
+            assert_close(&reports(&pool, user.id, from, to).await, before);
+        }
+
+        // Removed heartbeats are archived; a second run has nothing left
+        let [archive] = report.archives.as_slice() else { panic!("{:?}", report.archives) };
+        let lines: Vec<String> = BufReader::new(GzDecoder::new(File::open(archive).unwrap()))
+            .lines()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines.len() as u64, report.compacted);
+        let first: CreateEventRequest = serde_json::from_str(&lines[0]).unwrap();
+        assert!(first.timestamp < day + chrono::Duration::days(1));
+
+        // Importing the archive back finds every event already counted
+        let body = Ok::<_, AppError>(axum::body::Bytes::from(lines.join("\n")));
+        let bus = EventBus::new();
+        let body = tokio_stream::iter([body]);
+        let imported = event_service::import_events(&pool, &bus, user.id, body).await.unwrap();
+        assert_eq!((imported.accepted, imported.duplicates), (0, lines.len()));
+        for ((from, to), before) in ranges.into_iter().zip(&before) {
+            assert_close(&reports(&pool, user.id, from, to).await, before);
+        }
+        assert_eq!(maintain(&pool, &config, false).await.unwrap().compacted, 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn compaction_keeps_timesheets() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        let client = ClientRequest {
+            name: "Acme".into(),
+            hourly_rate: 80.0,
+            currency: "EUR".into(),
+            rounding: Rounding::default(),
+            projects: vec![timeforged_core::models::ClientProject {
+                name: "api".into(),
+                hourly_rate: None,
+            }],
+        };
+        billing_service::create_client(&pool, user.id, client).await.unwrap();
+        sqlite::set_activity_billable(&pool, user.id, &ActivityType::Browsing, false)
+            .await
+            .unwrap();
+
+        // Coding with a few minutes of reading docs in between, every 30s
+        let day = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        for i in 0..120 {
+            let at = day + chrono::Duration::seconds(30 * i);
+            let mut event = event(user.id, at, Some("api"), "rs");
+            let reading = matches!(i % 40, 10..16) || i == 70;
+            let activity = if reading { ActivityType::Browsing } else { ActivityType::Coding };
+            event.activity = Some(activity);
+            sqlite::insert_event(&pool, &event).await.unwrap();
+        }
+
+        let query = TimesheetQuery {
+            client: "Acme".into(),
+            month: Some("2026-03".into()),
+            ..Default::default()
+        };
+        let before = billing_service::timesheet(&pool, user.id, &query, 300).await.unwrap();
+        let mut config = AppConfig {
+            idle_timeout: 300,
+            ..Default::default()
+        };
+        config.retention.compact_after_days = 30;
+        config.retention.archive = false;
+        let report = maintain(&pool, &config, false).await.unwrap();
+        assert!(report.compacted > 0);
+
+        let after = billing_service::timesheet(&pool, user.id, &query, 300).await.unwrap();
+        assert!(before.billed_seconds < 3600.0, "{}", before.billed_seconds);
+        assert!((after.billed_seconds - before.billed_seconds).abs() < 1e-3);
+        assert!((after.tracked_seconds - before.tracked_seconds).abs() < 1e-3);
+        assert_eq!(after.amount, before.amount);
+    }
+}
@@ -0,0 +1,119 @@
+use axum::{
+    body::Bytes,
+    http::header,
+    response::{Html, IntoResponse},
+};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+};
+
+/// Base document. Paths and most schemas are collected from the
+/// `#[utoipa::path]` handlers registered in `app::api_router`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "TimeForged API",
+        description = "REST API of the TimeForged time tracking daemon",
+        license(name = "MIT"),
+    ),
+    modifiers(&ApiKeyAuth),
+    tags(
+        (name = "events", description = "Recording and exporting activity events"),
+        (name = "reports", description = "Aggregated time reports"),
+        (name = "insights", description = "Productivity patterns and unusual days"),
+        (name = "users", description = "Users, registration, API keys and public profiles"),
+        (name = "watcher", description = "Directories watched by the daemon"),
+        (name = "sync", description = "Syncing with the remote server"),
+        (name = "goals", description = "Daily and weekly coding goals, their streaks and history"),
+        (name = "digests", description = "Daily and weekly digests as Markdown, HTML, email or webhook"),
+        (name = "billing", description = "Clients, rates, rounding and timesheets"),
+        (name = "webhooks", description = "Signed HTTP callbacks for sessions, summaries and goals"),
+        (name = "cards", description = "SVG profile cards"),
+        (name = "status", description = "Health and daemon status"),
+        (name = "admin", description = "Daemon maintenance, for the admin user"),
+    )
+)]
+pub struct ApiDoc;
+
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+        );
+    }
+}
+
+const DOCS_HTML: &str = include_str!("openapi_docs.html");
+
+/// GET /api/v1/docs — self-contained page rendering `openapi.json`
+pub async fn docs() -> impl IntoResponse {
+    Html(DOCS_HTML)
+}
+
+/// GET /api/v1/openapi.json — the document is rendered once at startup
+pub fn spec_response(json: Bytes) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], json)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::app::{AppState, api_router};
+
+    const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    fn generated_spec() -> String {
+        let db = SqlitePoolOptions::new().connect_lazy("sqlite::memory:").unwrap();
+        let state = AppState::for_tests(db);
+        let (_, api) = api_router(&state).split_for_parts();
+        api.to_pretty_json().unwrap() + "\n"
+    }
+
+    /// Fails when a route or API type changes without the committed spec
+    /// being regenerated. Run with `UPDATE_OPENAPI=1` to rewrite it.
+    #[tokio::test]
+    async fn spec_matches_committed_file() {
+        let generated = generated_spec();
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SPEC_PATH, &generated).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(SPEC_PATH).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "crates/timeforged/openapi.json is out of date; \
+             regenerate with `UPDATE_OPENAPI=1 cargo test -p timeforged openapi`"
+        );
+    }
+
+    #[tokio::test]
+    async fn spec_covers_all_api_routes() {
+        let spec: serde_json::Value = serde_json::from_str(&generated_spec()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+        for path in [
+            "/health",
+            "/api/v1/status",
+            "/api/v1/register",
+            "/api/v1/events",
+            "/api/v1/events/batch",
+            "/api/v1/reports/summary",
+            "/api/v1/me",
+            "/api/v1/api-keys/{id}",
+            "/api/v1/watch",
+            "/api/v1/card/{username}",
+        ] {
+            assert!(paths.contains_key(path), "missing {path}");
+        }
+        assert!(paths["/api/v1/events"].get("get").is_some());
+        assert!(paths["/api/v1/events"].get("post").is_some());
+        assert!(spec["components"]["schemas"].get("CreateEventRequest").is_some());
+        assert!(spec["components"]["securitySchemes"].get("api_key").is_some());
+    }
+}
@@ -0,0 +1,159 @@
+//! Just enough PDF for a printable table: lines of text in the standard
+//! Helvetica and Courier fonts on A4 pages, and horizontal rules. No images,
+//! no compression, and only the characters of Windows-1252.
+
+use std::fmt::Write;
+
+/// A4, in points.
+pub const PAGE_WIDTH: f64 = 595.0;
+pub const PAGE_HEIGHT: f64 = 842.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+    /// Courier: every character is 0.6 of the size wide, so columns line up
+    Mono,
+}
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+            Self::Mono => "F3",
+        }
+    }
+}
+
+const FONTS: [&str; 3] = ["Helvetica", "Helvetica-Bold", "Courier"];
+
+/// One page's drawing operators; `y` counts up from the bottom edge.
+#[derive(Default)]
+pub struct Page {
+    content: Vec<u8>,
+}
+
+impl Page {
+    pub fn text(&mut self, x: f64, y: f64, font: Font, size: f64, text: &str) {
+        let mut op = format!("BT /{} {size} Tf {x:.2} {y:.2} Td (", font.resource()).into_bytes();
+        op.extend(encode(text));
+        op.extend_from_slice(b") Tj ET\n");
+        self.content.extend(op);
+    }
+
+    pub fn rule(&mut self, x1: f64, x2: f64, y: f64) {
+        let op = format!("0.5 w {x1:.2} {y:.2} m {x2:.2} {y:.2} l S\n");
+        self.content.extend(op.into_bytes());
+    }
+}
+
+/// `pages` as a PDF file.
+pub fn document(pages: &[Page]) -> Vec<u8> {
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    let mut object = |out: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", offsets.len()).into_bytes());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    };
+
+    // 1 catalog, 2 page tree, 3-5 fonts, then a page and its content each
+    let first_page = 3 + FONTS.len();
+    let kids: String = (0..pages.len()).fold(String::new(), |mut kids, i| {
+        let _ = write!(kids, "{} 0 R ", first_page + 2 * i);
+        kids
+    });
+    object(&mut out, b"<< /Type /Catalog /Pages 2 0 R >>");
+    let tree = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.trim_end(), pages.len());
+    object(&mut out, tree.as_bytes());
+    for font in FONTS {
+        let body = format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+        );
+        object(&mut out, body.as_bytes());
+    }
+    for (i, page) in pages.iter().enumerate() {
+        let page_body = format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+            first_page + 2 * i + 1
+        );
+        object(&mut out, page_body.as_bytes());
+        let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+        stream.extend_from_slice(&page.content);
+        stream.extend_from_slice(b"endstream");
+        object(&mut out, &stream);
+    }
+
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+    for offset in &offsets {
+        let _ = writeln!(table, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        offsets.len() + 1
+    );
+    out.extend(table.into_bytes());
+    out
+}
+
+/// A string literal's bytes in WinAnsiEncoding; anything it lacks becomes "?".
+fn encode(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                c as u8
+            }
+            ' '..='~' => c as u8,
+            '€' => 0x80,
+            '–' => 0x96,
+            '—' => 0x97,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            _ => b'?',
+        };
+        out.push(byte);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_escaped_and_encoded() {
+        assert_eq!(encode("a (b) \\"), b"a \\(b\\) \\\\");
+        assert_eq!(encode("12,50 € – café ✓"), b"12,50 \x80 \x96 caf\xe9 ?");
+    }
+
+    #[test]
+    fn cross_references_point_at_their_objects() {
+        let mut page = Page::default();
+        page.text(40.0, 800.0, Font::Bold, 14.0, "Timesheet");
+        page.rule(40.0, 555.0, 790.0);
+        let pdf = document(&[page, Page::default()]);
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Kids [6 0 R 8 0 R] /Count 2"));
+        assert!(text.contains("BT /F2 14 Tf 40.00 800.00 Td (Timesheet) Tj ET"));
+
+        // Everything from the cross-reference table on is ASCII
+        let at = pdf.windows(10).rposition(|w| w == b"startxref\n").unwrap();
+        let tail = std::str::from_utf8(&pdf[at + 10..]).unwrap();
+        let startxref: usize = tail.lines().next().unwrap().parse().unwrap();
+        let xref = std::str::from_utf8(&pdf[startxref..]).unwrap();
+        assert!(xref.starts_with("xref\n0 10\n"));
+        for (n, line) in xref.lines().skip(3).take(9).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", n + 1).as_bytes()), "{n}");
+        }
+    }
+}
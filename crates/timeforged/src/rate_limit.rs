@@ -1,17 +1,38 @@
 use axum::{
-    extract::ConnectInfo,
-    http::StatusCode,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, HeaderName, HeaderValue, header},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use timeforged_core::config::RateLimitConfig;
+use timeforged_core::error::AppError;
+
+use crate::app::AppState;
+use crate::error::ApiError;
+use crate::service::user_service;
+
+/// What a bucket is keyed on: the API key (hashed) for authenticated
+/// routes, the client IP for anonymous ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateKey {
+    Ip(IpAddr),
+    ApiKey(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allowed { remaining: usize, reset: Duration },
+    Limited { retry_after: Duration },
+}
+
 #[derive(Clone)]
 pub struct RateLimiter {
-    state: Arc<Mutex<HashMap<IpAddr, Vec<Instant>>>>,
+    state: Arc<Mutex<HashMap<RateKey, Vec<Instant>>>>,
     max_requests: usize,
     window: Duration,
 }
@@ -25,46 +46,384 @@ impl RateLimiter {
         }
     }
 
-    pub fn check(&self, ip: IpAddr) -> bool {
-        let now = Instant::now();
+    pub fn check(&self, key: RateKey) -> Decision {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: RateKey, now: Instant) -> Decision {
         let mut map = self.state.lock().unwrap();
-        let entries = map.entry(ip).or_default();
+        let entries = map.entry(key).or_default();
         entries.retain(|t| now.duration_since(*t) < self.window);
+
+        // Entries are pushed in order, so the first one expires first
+        let reset = entries
+            .first()
+            .map(|t| self.window.saturating_sub(now.duration_since(*t)))
+            .unwrap_or(self.window);
+
         if entries.len() >= self.max_requests {
-            false
+            Decision::Limited { retry_after: reset }
         } else {
             entries.push(now);
-            true
+            Decision::Allowed {
+                remaining: self.max_requests - entries.len(),
+                reset,
+            }
+        }
+    }
+
+    /// Drop buckets whose requests have all left the window.
+    pub fn evict(&self) -> usize {
+        self.evict_at(Instant::now())
+    }
+
+    fn evict_at(&self, now: Instant) -> usize {
+        let mut map = self.state.lock().unwrap();
+        let before = map.len();
+        map.retain(|_, entries| {
+            entries.retain(|t| now.duration_since(*t) < self.window);
+            !entries.is_empty()
+        });
+        before - map.len()
+    }
+
+    pub fn limit(&self) -> usize {
+        self.max_requests
+    }
+}
+
+/// A trusted proxy address or network, e.g. `127.0.0.1` or `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedProxy {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl TrustedProxy {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (
+                addr.parse::<IpAddr>().ok()?,
+                Some(prefix.parse::<u8>().ok()?),
+            ),
+            None => (s.trim().parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        if prefix > max {
+            return None;
+        }
+        Some(Self { addr, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
         }
     }
 }
 
-/// Middleware: 10 requests per hour per IP (for registration)
+/// Resolve the real client address. `X-Forwarded-For` is only honoured when
+/// the direct peer is a trusted proxy; the rightmost untrusted hop wins so a
+/// client can't spoof its address by prepending entries.
+pub fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted: &[TrustedProxy]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|t| t.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    let hops: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|s| s.trim().parse().ok())
+        .collect();
+
+    hops.iter()
+        .rev()
+        .find(|ip| !is_trusted(**ip))
+        .or(hops.first())
+        .copied()
+        .unwrap_or(peer)
+}
+
+/// Limiters shared by all requests, built from `[rate_limit]` in config.toml.
+#[derive(Clone)]
+pub struct RateLimits {
+    pub register: RateLimiter,
+    pub events: RateLimiter,
+    pub trusted_proxies: Arc<Vec<TrustedProxy>>,
+}
+
+impl RateLimits {
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        let trusted_proxies = config
+            .trusted_proxies
+            .iter()
+            .filter_map(|s| {
+                let parsed = TrustedProxy::parse(s);
+                if parsed.is_none() {
+                    tracing::warn!("rate limit: ignoring invalid trusted proxy {s:?}");
+                }
+                parsed
+            })
+            .collect();
+
+        Self {
+            register: RateLimiter::new(
+                config.register_limit,
+                Duration::from_secs(config.register_window_secs),
+            ),
+            events: RateLimiter::new(
+                config.event_limit,
+                Duration::from_secs(config.event_window_secs),
+            ),
+            trusted_proxies: Arc::new(trusted_proxies),
+        }
+    }
+}
+
+/// Periodically drop idle buckets so memory doesn't grow with every IP or
+/// key ever seen.
+pub async fn run_eviction(limits: RateLimits, interval_secs: u64) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    loop {
+        ticker.tick().await;
+        let evicted = limits.register.evict() + limits.events.evict();
+        if evicted > 0 {
+            tracing::debug!("rate limit: evicted {evicted} idle buckets");
+        }
+    }
+}
+
+/// Middleware: registrations per client IP
 pub async fn register_rate_limit(
+    State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     req: axum::extract::Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    static LIMITER: std::sync::OnceLock<RateLimiter> = std::sync::OnceLock::new();
-    let limiter = LIMITER.get_or_init(|| RateLimiter::new(10, Duration::from_secs(3600)));
-
-    if !limiter.check(addr.ip()) {
-        return Err(StatusCode::TOO_MANY_REQUESTS);
-    }
-    Ok(next.run(req).await)
+) -> Response {
+    let limits = &state.rate_limits;
+    let ip = client_ip(addr.ip(), req.headers(), &limits.trusted_proxies);
+    enforce(&limits.register, RateKey::Ip(ip), req, next).await
 }
 
-/// Middleware: 120 event writes per minute per IP
+/// Middleware: event writes per API key (per client IP if no key was sent)
 pub async fn event_rate_limit(
+    State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     req: axum::extract::Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    static LIMITER: std::sync::OnceLock<RateLimiter> = std::sync::OnceLock::new();
-    let limiter = LIMITER.get_or_init(|| RateLimiter::new(120, Duration::from_secs(60)));
+) -> Response {
+    let limits = &state.rate_limits;
+    let key = match req.headers().get("X-Api-Key").and_then(|v| v.to_str().ok()) {
+        Some(key) => RateKey::ApiKey(user_service::hash_api_key(key)),
+        None => RateKey::Ip(client_ip(addr.ip(), req.headers(), &limits.trusted_proxies)),
+    };
+    enforce(&limits.events, key, req, next).await
+}
+
+async fn enforce(
+    limiter: &RateLimiter,
+    key: RateKey,
+    req: axum::extract::Request,
+    next: Next,
+) -> Response {
+    match limiter.check(key) {
+        Decision::Allowed { remaining, reset } => {
+            let mut resp = next.run(req).await;
+            set_rate_headers(resp.headers_mut(), limiter.limit(), remaining, reset);
+            resp
+        }
+        Decision::Limited { retry_after } => {
+            let mut resp = ApiError(AppError::RateLimited).into_response();
+            let headers = resp.headers_mut();
+            set_rate_headers(headers, limiter.limit(), 0, retry_after);
+            headers.insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(ceil_secs(retry_after)),
+            );
+            resp
+        }
+    }
+}
+
+fn set_rate_headers(headers: &mut HeaderMap, limit: usize, remaining: usize, reset: Duration) {
+    headers.insert(
+        HeaderName::from_static("ratelimit-limit"),
+        HeaderValue::from(limit),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-remaining"),
+        HeaderValue::from(remaining),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-reset"),
+        HeaderValue::from(ceil_secs(reset)),
+    );
+}
+
+fn ceil_secs(d: Duration) -> u64 {
+    d.as_secs() + u64::from(d.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn allows_up_to_limit_then_limits() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let key = RateKey::Ip(ip("1.2.3.4"));
+        let now = Instant::now();
+
+        assert!(matches!(
+            limiter.check_at(key.clone(), now),
+            Decision::Allowed { remaining: 1, .. }
+        ));
+        assert!(matches!(
+            limiter.check_at(key.clone(), now),
+            Decision::Allowed { remaining: 0, .. }
+        ));
+        match limiter.check_at(key, now + Duration::from_secs(10)) {
+            Decision::Limited { retry_after } => assert_eq!(retry_after, Duration::from_secs(50)),
+            other => panic!("expected limited, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn window_expiry_frees_slots() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let key = RateKey::ApiKey("abc".into());
+        let now = Instant::now();
+
+        assert!(matches!(
+            limiter.check_at(key.clone(), now),
+            Decision::Allowed { .. }
+        ));
+        assert!(matches!(
+            limiter.check_at(key.clone(), now),
+            Decision::Limited { .. }
+        ));
+        assert!(matches!(
+            limiter.check_at(key, now + Duration::from_secs(61)),
+            Decision::Allowed { .. }
+        ));
+    }
+
+    #[test]
+    fn keys_are_independent() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(matches!(
+            limiter.check_at(RateKey::ApiKey("a".into()), now),
+            Decision::Allowed { .. }
+        ));
+        assert!(matches!(
+            limiter.check_at(RateKey::ApiKey("b".into()), now),
+            Decision::Allowed { .. }
+        ));
+        assert!(matches!(
+            limiter.check_at(RateKey::Ip(ip("1.2.3.4")), now),
+            Decision::Allowed { .. }
+        ));
+        assert!(matches!(
+            limiter.check_at(RateKey::ApiKey("a".into()), now),
+            Decision::Limited { .. }
+        ));
+    }
+
+    #[test]
+    fn evict_drops_idle_buckets() {
+        let limiter = RateLimiter::new(5, Duration::from_secs(60));
+        let now = Instant::now();
+        limiter.check_at(RateKey::Ip(ip("1.1.1.1")), now);
+        limiter.check_at(RateKey::Ip(ip("2.2.2.2")), now + Duration::from_secs(30));
+
+        assert_eq!(limiter.evict_at(now + Duration::from_secs(70)), 1);
+        assert_eq!(limiter.state.lock().unwrap().len(), 1);
+        assert_eq!(limiter.evict_at(now + Duration::from_secs(100)), 1);
+        assert!(limiter.state.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn trusted_proxy_parse_and_contains() {
+        let single = TrustedProxy::parse("127.0.0.1").unwrap();
+        assert!(single.contains(ip("127.0.0.1")));
+        assert!(!single.contains(ip("127.0.0.2")));
+
+        let net = TrustedProxy::parse("10.0.0.0/8").unwrap();
+        assert!(net.contains(ip("10.20.30.40")));
+        assert!(!net.contains(ip("11.0.0.1")));
+        assert!(!net.contains(ip("::1")));
+
+        let v6 = TrustedProxy::parse("fd00::/8").unwrap();
+        assert!(v6.contains(ip("fd12::1")));
+
+        assert!(
+            TrustedProxy::parse("0.0.0.0/0")
+                .unwrap()
+                .contains(ip("8.8.8.8"))
+        );
+        assert!(TrustedProxy::parse("10.0.0.0/33").is_none());
+        assert!(TrustedProxy::parse("not-an-ip").is_none());
+    }
+
+    #[test]
+    fn forwarded_for_ignored_from_untrusted_peer() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("9.9.9.9"));
+        let trusted = [TrustedProxy::parse("127.0.0.1").unwrap()];
+
+        assert_eq!(client_ip(ip("5.5.5.5"), &headers, &trusted), ip("5.5.5.5"));
+    }
+
+    #[test]
+    fn forwarded_for_uses_rightmost_untrusted_hop() {
+        let mut headers = HeaderMap::new();
+        // Client claims to be 6.6.6.6; the proxy appended the real peer 9.9.9.9
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("6.6.6.6, 9.9.9.9, 10.0.0.5"),
+        );
+        let trusted = [
+            TrustedProxy::parse("127.0.0.1").unwrap(),
+            TrustedProxy::parse("10.0.0.0/8").unwrap(),
+        ];
+
+        assert_eq!(
+            client_ip(ip("127.0.0.1"), &headers, &trusted),
+            ip("9.9.9.9")
+        );
+    }
+
+    #[test]
+    fn forwarded_for_missing_falls_back_to_peer() {
+        let trusted = [TrustedProxy::parse("127.0.0.1").unwrap()];
+        assert_eq!(
+            client_ip(ip("127.0.0.1"), &HeaderMap::new(), &trusted),
+            ip("127.0.0.1")
+        );
+    }
 
-    if !limiter.check(addr.ip()) {
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+    #[test]
+    fn ceil_secs_rounds_up() {
+        assert_eq!(ceil_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(ceil_secs(Duration::from_secs(3)), 3);
+        assert_eq!(ceil_secs(Duration::ZERO), 0);
     }
-    Ok(next.run(req).await)
 }
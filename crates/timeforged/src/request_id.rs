@@ -0,0 +1,72 @@
+use std::future::Future;
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Request id of the request being handled on this task, if any.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+pub async fn scope<F: Future>(id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(id, f).await
+}
+
+/// Middleware: tag every request with an id (reusing a sane incoming
+/// `X-Request-Id` from a proxy), expose it to error responses and logs, and
+/// echo it back in the response header.
+pub async fn request_id(req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| is_valid(v))
+        .map(String::from)
+        .unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+
+    let span = tracing::info_span!("request", id = %id);
+    let mut resp = scope(id.clone(), next.run(req).instrument(span)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        resp.headers_mut().insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    resp
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_ids() {
+        assert!(is_valid("3f2a9c"));
+        assert!(is_valid("req_1-abc"));
+        assert!(!is_valid(""));
+        assert!(!is_valid("has space"));
+        assert!(!is_valid(&"a".repeat(65)));
+    }
+
+    #[tokio::test]
+    async fn current_is_scoped() {
+        assert_eq!(current(), None);
+        let inner = scope("abc".into(), async { current() }).await;
+        assert_eq!(inner.as_deref(), Some("abc"));
+    }
+}
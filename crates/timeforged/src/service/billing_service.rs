@@ -0,0 +1,793 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use timeforged_core::api::{
+    ActivityBilling, ClientRequest, Timesheet, TimesheetLine, TimesheetProject, TimesheetQuery,
+};
+use timeforged_core::error::AppError;
+use timeforged_core::models::{ActivityType, Client, RoundingScope};
+use timeforged_core::util::escape_markup;
+
+use crate::pdf::{self, Font, Page};
+use crate::service::event_service;
+use crate::storage::sqlite;
+
+const MAX_CLIENTS_PER_USER: usize = 100;
+const MAX_NAME_LEN: usize = 64;
+/// Longest rounding increment: a day.
+const MAX_ROUNDING_MINUTES: u32 = 24 * 60;
+const MAX_RANGE_DAYS: i64 = 366;
+
+// --- Clients ---
+
+/// Check a client, trimming its names and upper-casing its currency.
+fn validate(req: &mut ClientRequest) -> Result<(), AppError> {
+    req.name = req.name.trim().to_string();
+    if req.name.is_empty() || req.name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::Validation(format!(
+            "client name must be 1-{MAX_NAME_LEN} characters"
+        )));
+    }
+    if req.name.chars().any(char::is_control) {
+        return Err(AppError::Validation("client name cannot contain control characters".into()));
+    }
+    let rate_ok = |rate: f64| rate.is_finite() && rate >= 0.0;
+    if !rate_ok(req.hourly_rate) {
+        return Err(AppError::Validation("hourly rate must be a number of at least 0".into()));
+    }
+    req.currency = req.currency.trim().to_ascii_uppercase();
+    if req.currency.len() != 3 || !req.currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(AppError::Validation("currency must be a three-letter code like EUR".into()));
+    }
+    if req.rounding.minutes > MAX_ROUNDING_MINUTES {
+        return Err(AppError::Validation(format!(
+            "rounding must be at most {MAX_ROUNDING_MINUTES} minutes"
+        )));
+    }
+
+    for project in &mut req.projects {
+        project.name = project.name.trim().to_string();
+        if project.name.is_empty() {
+            return Err(AppError::Validation("project names cannot be empty".into()));
+        }
+        event_service::check_project(&project.name).map_err(AppError::Validation)?;
+        if project.hourly_rate.is_some_and(|rate| !rate_ok(rate)) {
+            return Err(AppError::Validation(format!(
+                "hourly rate of {} must be a number of at least 0",
+                project.name
+            )));
+        }
+    }
+    req.projects.sort_by(|a, b| a.name.cmp(&b.name));
+    if let Some(pair) = req.projects.windows(2).find(|p| p[0].name == p[1].name) {
+        return Err(AppError::Validation(format!("project {} is listed twice", pair[0].name)));
+    }
+    Ok(())
+}
+
+/// The client named `key`, ignoring case, or with id `key`.
+fn find<'a>(clients: &'a [Client], key: &str) -> Option<&'a Client> {
+    let key = key.trim();
+    clients
+        .iter()
+        .find(|c| c.id.to_string() == key)
+        .or_else(|| clients.iter().find(|c| c.name.eq_ignore_ascii_case(key)))
+}
+
+/// Name and projects clashing with another client than `id`.
+fn check_conflicts(clients: &[Client], id: Uuid, req: &ClientRequest) -> Result<(), AppError> {
+    let others = || clients.iter().filter(|c| c.id != id);
+    if others().any(|c| c.name.eq_ignore_ascii_case(&req.name)) {
+        return Err(AppError::Conflict(format!("there already is a client {}", req.name)));
+    }
+    for project in &req.projects {
+        if let Some(owner) = others().find(|c| c.projects.iter().any(|p| p.name == project.name)) {
+            return Err(AppError::Conflict(format!(
+                "project {} is already billed to {}",
+                project.name, owner.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub async fn list_clients(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<Client>, AppError> {
+    sqlite::list_clients(pool, user_id).await
+}
+
+pub async fn get_client(pool: &SqlitePool, user_id: Uuid, key: &str) -> Result<Client, AppError> {
+    let clients = sqlite::list_clients(pool, user_id).await?;
+    find(&clients, key)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("client {key} not found")))
+}
+
+pub async fn create_client(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    mut req: ClientRequest,
+) -> Result<Client, AppError> {
+    validate(&mut req)?;
+    let clients = sqlite::list_clients(pool, user_id).await?;
+    if clients.len() >= MAX_CLIENTS_PER_USER {
+        return Err(AppError::Validation(format!(
+            "at most {MAX_CLIENTS_PER_USER} clients per user"
+        )));
+    }
+    let id = Uuid::new_v4();
+    check_conflicts(&clients, id, &req)?;
+
+    let client = Client {
+        id,
+        user_id,
+        name: req.name,
+        hourly_rate: req.hourly_rate,
+        currency: req.currency,
+        rounding: req.rounding,
+        projects: req.projects,
+        created_at: Utc::now(),
+    };
+    sqlite::save_client(pool, &client).await?;
+    Ok(client)
+}
+
+/// Replace everything about the client `key` but its id.
+pub async fn update_client(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    key: &str,
+    mut req: ClientRequest,
+) -> Result<Client, AppError> {
+    validate(&mut req)?;
+    let clients = sqlite::list_clients(pool, user_id).await?;
+    let existing = find(&clients, key)
+        .ok_or_else(|| AppError::NotFound(format!("client {key} not found")))?;
+    check_conflicts(&clients, existing.id, &req)?;
+
+    let client = Client {
+        name: req.name,
+        hourly_rate: req.hourly_rate,
+        currency: req.currency,
+        rounding: req.rounding,
+        projects: req.projects,
+        ..existing.clone()
+    };
+    sqlite::save_client(pool, &client).await?;
+    Ok(client)
+}
+
+pub async fn delete_client(pool: &SqlitePool, user_id: Uuid, key: &str) -> Result<(), AppError> {
+    let client = get_client(pool, user_id, key).await?;
+    sqlite::delete_client(pool, user_id, client.id).await?;
+    Ok(())
+}
+
+// --- Billable activities ---
+
+/// Every activity type and whether its time is billed.
+pub async fn activities(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<Vec<ActivityBilling>, AppError> {
+    let non_billable = sqlite::non_billable_activities(pool, user_id).await?;
+    Ok(ActivityType::ALL
+        .into_iter()
+        .map(|activity| ActivityBilling {
+            billable: !non_billable.contains(&activity),
+            activity,
+        })
+        .collect())
+}
+
+/// Change the activity types listed; the others stay as they are.
+pub async fn set_activities(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    changes: &[ActivityBilling],
+) -> Result<Vec<ActivityBilling>, AppError> {
+    for change in changes {
+        sqlite::set_activity_billable(pool, user_id, &change.activity, change.billable).await?;
+    }
+    activities(pool, user_id).await
+}
+
+// --- Timesheets ---
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+/// First and last day of the timesheet: a month, `from` through `to`, or
+/// the month `today` is in.
+fn days(query: &TimesheetQuery, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let (from, to) = match (&query.month, query.from, query.to) {
+        (Some(month), None, None) => {
+            let first = NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+                .map_err(|_| {
+                    AppError::Validation(format!("invalid month '{month}', expected YYYY-MM"))
+                })?;
+            (first, first + Months::new(1) - Duration::days(1))
+        }
+        (None, Some(from), Some(to)) => (from, to),
+        (None, None, None) => {
+            let first = today.with_day(1).unwrap();
+            (first, first + Months::new(1) - Duration::days(1))
+        }
+        _ => {
+            return Err(AppError::Validation(
+                "give either a month or both from and to".into(),
+            ));
+        }
+    };
+    if from > to {
+        return Err(AppError::Validation("from must not be after to".into()));
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(AppError::Validation(format!(
+            "a timesheet covers at most {MAX_RANGE_DAYS} days"
+        )));
+    }
+    Ok((from, to))
+}
+
+pub async fn timesheet(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    query: &TimesheetQuery,
+    idle_timeout: u64,
+) -> Result<Timesheet, AppError> {
+    let client = get_client(pool, user_id, &query.client).await?;
+    let (from, to) = days(query, Utc::now().date_naive())?;
+    let projects: Vec<String> = client.projects.iter().map(|p| p.name.clone()).collect();
+    let (start, end) = (midnight(from), midnight(to + Duration::days(1)));
+    let points = sqlite::get_billing_timeline(pool, user_id, start, end, &projects).await?;
+    let non_billable = sqlite::non_billable_activities(pool, user_id).await?;
+    Ok(bill(&client, &points, &non_billable, from, to, idle_timeout))
+}
+
+fn cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Bill `points`, the client's events oldest first. As in the reports, the
+/// gap up to an event on the same project counts when it's shorter than
+/// `idle_timeout`, and belongs to that event's day; it's billed if the
+/// event's activity is. A longer gap ends a session.
+fn bill(
+    client: &Client,
+    points: &[(DateTime<Utc>, String, Option<ActivityType>)],
+    non_billable: &[ActivityType],
+    from: NaiveDate,
+    to: NaiveDate,
+    idle_timeout: u64,
+) -> Timesheet {
+    let idle = idle_timeout as f64;
+    let billable = |activity: &Option<ActivityType>| {
+        !non_billable.contains(activity.as_ref().unwrap_or(&ActivityType::Coding))
+    };
+
+    // Billable seconds of each session, split by day
+    let mut sessions: BTreeMap<(NaiveDate, &str), Vec<f64>> = BTreeMap::new();
+    let mut last: HashMap<&str, (DateTime<Utc>, NaiveDate)> = HashMap::new();
+    for (time, project, activity) in points {
+        let date = time.date_naive();
+        let previous = last.insert(project, (*time, date));
+        let pieces = sessions.entry((date, project)).or_default();
+        let gap = previous.map(|(t, d)| ((*time - t).num_milliseconds() as f64 / 1000.0, d));
+        match gap {
+            // Same session, same day
+            Some((gap, day)) if gap < idle && day == date && !pieces.is_empty() => {
+                if billable(activity) {
+                    *pieces.last_mut().unwrap() += gap;
+                }
+            }
+            // Same session carried past midnight, or a new one
+            Some((gap, _)) if gap < idle => pieces.push(if billable(activity) { gap } else { 0.0 }),
+            _ => pieces.push(0.0),
+        }
+    }
+
+    let mut lines = Vec::new();
+    for ((date, project), pieces) in sessions {
+        if date < from || date > to {
+            continue;
+        }
+        let tracked: f64 = pieces.iter().sum();
+        let billed = match client.rounding.per {
+            RoundingScope::Session => pieces.iter().map(|s| client.rounding.apply(*s)).sum(),
+            RoundingScope::Day => client.rounding.apply(tracked),
+        };
+        if billed <= 0.0 {
+            continue;
+        }
+        let hourly_rate = client.rate_for(project);
+        lines.push(TimesheetLine {
+            date,
+            project: project.to_string(),
+            tracked_seconds: tracked,
+            billed_seconds: billed,
+            hourly_rate,
+            amount: cents(billed / 3600.0 * hourly_rate),
+        });
+    }
+
+    let mut projects: Vec<TimesheetProject> = Vec::new();
+    for line in &lines {
+        let index = match projects.iter().position(|p| p.name == line.project) {
+            Some(index) => index,
+            None => {
+                projects.push(TimesheetProject {
+                    name: line.project.clone(),
+                    tracked_seconds: 0.0,
+                    billed_seconds: 0.0,
+                    hourly_rate: line.hourly_rate,
+                    amount: 0.0,
+                });
+                projects.len() - 1
+            }
+        };
+        let project = &mut projects[index];
+        project.tracked_seconds += line.tracked_seconds;
+        project.billed_seconds += line.billed_seconds;
+        project.amount = cents(project.amount + line.amount);
+    }
+    projects.sort_by(|a, b| b.billed_seconds.total_cmp(&a.billed_seconds));
+
+    Timesheet {
+        client: client.name.clone(),
+        currency: client.currency.clone(),
+        from,
+        to,
+        rounding: client.rounding,
+        tracked_seconds: lines.iter().map(|l| l.tracked_seconds).sum(),
+        billed_seconds: lines.iter().map(|l| l.billed_seconds).sum(),
+        amount: cents(lines.iter().map(|l| l.amount).sum()),
+        lines,
+        projects,
+    }
+}
+
+// --- Rendering ---
+
+/// Decimal hours, as invoices put them: "1.25"
+fn hours(seconds: f64) -> String {
+    format!("{:.2}", seconds / 3600.0)
+}
+
+fn money(amount: f64) -> String {
+    format!("{amount:.2}")
+}
+
+/// "September 2026" for a whole month, "2026-09-01 – 2026-09-15" otherwise.
+pub fn period(timesheet: &Timesheet) -> String {
+    let (from, to) = (timesheet.from, timesheet.to);
+    let whole_month = from.day() == 1 && (to + Duration::days(1)).day() == 1 && {
+        from.year() == to.year() && from.month() == to.month()
+    };
+    if whole_month {
+        from.format("%B %Y").to_string()
+    } else {
+        format!("{from} – {to}")
+    }
+}
+
+/// "acme-corp-2026-09.pdf", or with both days for other ranges.
+pub fn file_name(timesheet: &Timesheet, extension: &str) -> String {
+    let mut slug = String::new();
+    for c in timesheet.client.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    let slug = if slug.is_empty() { "timesheet" } else { slug };
+    let (from, to) = (timesheet.from, timesheet.to);
+    if period(timesheet) == from.format("%B %Y").to_string() {
+        format!("{slug}-{}.{extension}", from.format("%Y-%m"))
+    } else {
+        format!("{slug}-{from}-{to}.{extension}")
+    }
+}
+
+fn title(timesheet: &Timesheet) -> String {
+    format!("Timesheet — {}", timesheet.client)
+}
+
+fn rounding_note(timesheet: &Timesheet) -> String {
+    format!("Rounding: {}", timesheet.rounding.label())
+}
+
+const CSV_HEADER: [&str; 7] =
+    ["date", "project", "tracked_hours", "billed_hours", "hourly_rate", "amount", "currency"];
+
+/// One row per day and project, then a total row without a date.
+pub fn csv(timesheet: &Timesheet) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing to memory can't fail
+    let _ = writer.write_record(CSV_HEADER);
+    for line in &timesheet.lines {
+        let _ = writer.write_record([
+            line.date.to_string(),
+            line.project.clone(),
+            hours(line.tracked_seconds),
+            hours(line.billed_seconds),
+            money(line.hourly_rate),
+            money(line.amount),
+            timesheet.currency.clone(),
+        ]);
+    }
+    let _ = writer.write_record([
+        String::new(),
+        "Total".into(),
+        hours(timesheet.tracked_seconds),
+        hours(timesheet.billed_seconds),
+        String::new(),
+        money(timesheet.amount),
+        timesheet.currency.clone(),
+    ]);
+    String::from_utf8(writer.into_inner().unwrap_or_default()).unwrap_or_default()
+}
+
+pub fn html(timesheet: &Timesheet) -> String {
+    let currency = escape_markup(&timesheet.currency, false);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title>\
+         <style>{STYLE}</style></head><body>\n<h1>{title}</h1>\n\
+         <p class=\"dates\">{period} · {rounding}</p>\n",
+        title = escape_markup(&title(timesheet), false),
+        period = escape_markup(&period(timesheet), false),
+        rounding = escape_markup(&rounding_note(timesheet), false),
+    );
+    if timesheet.lines.is_empty() {
+        out += "<p>No billable time.</p>\n</body></html>\n";
+        return out;
+    }
+
+    out += "<table>\n<tr><th>Date</th><th>Project</th><th>Hours</th><th>Rate</th>\
+            <th>Amount</th></tr>\n";
+    for line in &timesheet.lines {
+        out += &format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{} {currency}</td></tr>\n",
+            line.date.format("%a %Y-%m-%d"),
+            escape_markup(&line.project, false),
+            hours(line.billed_seconds),
+            money(line.hourly_rate),
+            money(line.amount),
+        );
+    }
+    out += &format!(
+        "<tr class=\"total\"><td>Total</td><td></td><td>{}</td><td></td><td>{} {currency}</td>\
+         </tr>\n</table>\n",
+        hours(timesheet.billed_seconds),
+        money(timesheet.amount),
+    );
+
+    out += "<h2>By project</h2>\n<table>\n<tr><th>Project</th><th>Hours</th><th>Rate</th>\
+            <th>Amount</th></tr>\n";
+    for project in &timesheet.projects {
+        out += &format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{} {currency}</td></tr>\n",
+            escape_markup(&project.name, false),
+            hours(project.billed_seconds),
+            money(project.hourly_rate),
+            money(project.amount),
+        );
+    }
+    out += "</table>\n</body></html>\n";
+    out
+}
+
+const STYLE: &str = "body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;\
+color:#1f2328;max-width:720px;margin:24px auto;padding:0 16px}\
+h1{font-size:22px;margin-bottom:0}h2{font-size:16px;margin-top:24px}\
+.dates{color:#59636e;margin-top:4px}table{border-collapse:collapse;width:100%}\
+th,td{padding:4px 8px;border-bottom:1px solid #d1d9e0;text-align:right}\
+th:first-child,td:first-child,th:nth-child(2),td:nth-child(2){text-align:left}\
+tr.total td{font-weight:600;border-top:2px solid #1f2328}\
+@media print{body{margin:0;max-width:none}}";
+
+/// Widths of the PDF table's columns, in characters of the mono font.
+const PDF_COLUMNS: [usize; 5] = [16, 28, 8, 10, 16];
+const PDF_MARGIN: f64 = 50.0;
+const PDF_SIZE: f64 = 9.0;
+const PDF_LEADING: f64 = 14.0;
+
+/// A table row: the first two columns left-aligned, the rest right-aligned.
+fn pdf_row(cells: [&str; 5]) -> String {
+    let mut row = String::new();
+    for (i, (cell, width)) in cells.iter().zip(PDF_COLUMNS).enumerate() {
+        let cell: String = cell.chars().take(width - 1).collect();
+        if i < 2 {
+            row += &format!("{cell:<width$}");
+        } else {
+            row += &format!("{cell:>width$}");
+        }
+    }
+    row
+}
+
+/// The same timesheet as [`html`], as a PDF with the table in Courier.
+pub fn pdf(timesheet: &Timesheet) -> Vec<u8> {
+    let width = PDF_COLUMNS.iter().sum::<usize>() as f64 * PDF_SIZE * 0.6;
+    let right = PDF_MARGIN + width;
+    let mut pages = vec![Page::default()];
+    let mut y = pdf::PAGE_HEIGHT - PDF_MARGIN;
+
+    let page = pages.last_mut().unwrap();
+    page.text(PDF_MARGIN, y, Font::Bold, 16.0, &title(timesheet));
+    y -= 20.0;
+    let subtitle = format!("{} · {}", period(timesheet), rounding_note(timesheet));
+    page.text(PDF_MARGIN, y, Font::Regular, 10.0, &subtitle);
+    y -= 28.0;
+
+    if timesheet.lines.is_empty() {
+        page.text(PDF_MARGIN, y, Font::Regular, 10.0, "No billable time.");
+        return pdf::document(&pages);
+    }
+
+    let amount_header = format!("Amount ({})", timesheet.currency);
+    let header = pdf_row(["Date", "Project", "Hours", "Rate", &amount_header]);
+    let mut rows: Vec<(String, bool)> = timesheet
+        .lines
+        .iter()
+        .map(|line| {
+            let date = line.date.format("%a %Y-%m-%d").to_string();
+            let (billed, rate) = (hours(line.billed_seconds), money(line.hourly_rate));
+            (pdf_row([&date, &line.project, &billed, &rate, &money(line.amount)]), false)
+        })
+        .collect();
+    let (billed, amount) = (hours(timesheet.billed_seconds), money(timesheet.amount));
+    rows.push((pdf_row(["Total", "", &billed, "", &amount]), true));
+
+    let page_header = |page: &mut Page, y: &mut f64| {
+        page.text(PDF_MARGIN, *y, Font::Mono, PDF_SIZE, &header);
+        page.rule(PDF_MARGIN, right, *y - 4.0);
+        *y -= PDF_LEADING + 2.0;
+    };
+    page_header(pages.last_mut().unwrap(), &mut y);
+    for (row, total) in rows {
+        if y < PDF_MARGIN {
+            pages.push(Page::default());
+            y = pdf::PAGE_HEIGHT - PDF_MARGIN;
+            page_header(pages.last_mut().unwrap(), &mut y);
+        }
+        let page = pages.last_mut().unwrap();
+        if total {
+            page.rule(PDF_MARGIN, right, y + PDF_LEADING - 4.0);
+        }
+        page.text(PDF_MARGIN, y, Font::Mono, PDF_SIZE, &row);
+        y -= PDF_LEADING;
+    }
+
+    // The projects, if they fit under the table
+    let summary_height = (timesheet.projects.len() + 2) as f64 * PDF_LEADING + 20.0;
+    if y - summary_height < PDF_MARGIN {
+        pages.push(Page::default());
+        y = pdf::PAGE_HEIGHT - PDF_MARGIN;
+    } else {
+        y -= 16.0;
+    }
+    let page = pages.last_mut().unwrap();
+    page.text(PDF_MARGIN, y, Font::Bold, 11.0, "By project");
+    y -= 18.0;
+    for project in &timesheet.projects {
+        let (billed, rate) = (hours(project.billed_seconds), money(project.hourly_rate));
+        let row = pdf_row(["", &project.name, &billed, &rate, &money(project.amount)]);
+        page.text(PDF_MARGIN, y, Font::Mono, PDF_SIZE, &row);
+        y -= PDF_LEADING;
+    }
+    pdf::document(&pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use timeforged_core::models::{ClientProject, Rounding, RoundingDirection};
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn client(minutes: u32, per: RoundingScope) -> Client {
+        Client {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            name: "Acme".into(),
+            hourly_rate: 80.0,
+            currency: "EUR".into(),
+            rounding: Rounding { minutes, per, direction: RoundingDirection::Up },
+            projects: vec![
+                ClientProject { name: "site".into(), hourly_rate: None },
+                ClientProject { name: "audit".into(), hourly_rate: Some(120.0) },
+            ],
+            created_at: Utc::now(),
+        }
+    }
+
+    type Point = (DateTime<Utc>, String, Option<ActivityType>);
+
+    /// Events every 2 minutes from `start` for `minutes`.
+    fn stretch(start: &str, minutes: i64, project: &str, activity: ActivityType) -> Vec<Point> {
+        (0..=minutes / 2)
+            .map(|i| (at(start) + Duration::minutes(2 * i), project.into(), Some(activity.clone())))
+            .collect()
+    }
+
+    fn points() -> Vec<Point> {
+        let mut points: Vec<Point> = [
+            // Two sessions on the site: 20 and 8 minutes
+            stretch("2026-09-01T09:00:00Z", 20, "site", ActivityType::Coding),
+            stretch("2026-09-01T14:00:00Z", 8, "site", ActivityType::Coding),
+            // 10 minutes of audit, 4 of them in meetings
+            stretch("2026-09-01T16:00:00Z", 6, "audit", ActivityType::Debugging),
+            stretch("2026-09-01T16:08:00Z", 2, "audit", ActivityType::Communicating),
+            // Across midnight, and outside the month
+            stretch("2026-09-30T23:50:00Z", 20, "site", ActivityType::Coding),
+        ]
+        .concat();
+        points.sort_by_key(|(t, _, _)| *t);
+        points
+    }
+
+    fn sheet(client: &Client, non_billable: &[ActivityType]) -> Timesheet {
+        let from = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2026, 9, 30).unwrap();
+        bill(client, &points(), non_billable, from, to, 300)
+    }
+
+    /// Billed minutes and amount of `project` on day `day` of September.
+    fn line(sheet: &Timesheet, day: u32, project: &str) -> (f64, f64) {
+        let line = sheet
+            .lines
+            .iter()
+            .find(|l| l.date.day() == day && l.project == project)
+            .unwrap();
+        (line.billed_seconds / 60.0, line.amount)
+    }
+
+    #[test]
+    fn rounding_applies_per_session_or_per_day() {
+        let exact = sheet(&client(0, RoundingScope::Session), &[]);
+        // A line per day and project, by date and then name
+        let order: Vec<(u32, &str)> =
+            exact.lines.iter().map(|l| (l.date.day(), l.project.as_str())).collect();
+        assert_eq!(order, [(1, "audit"), (1, "site"), (30, "site")]);
+        assert_eq!(line(&exact, 1, "site").0, 28.0);
+        assert_eq!(line(&exact, 1, "audit").0, 10.0);
+        // Up to midnight on the 30th; the rest is October's
+        assert_eq!(line(&exact, 30, "site").0, 8.0);
+
+        let per_session = sheet(&client(15, RoundingScope::Session), &[]);
+        assert_eq!(line(&per_session, 1, "site"), (45.0, 60.0));
+        let per_day = sheet(&client(15, RoundingScope::Day), &[]);
+        assert_eq!(line(&per_day, 1, "site"), (30.0, 40.0));
+
+        // The audit at its own rate: 15 minutes at 120
+        assert_eq!(line(&per_day, 1, "audit"), (15.0, 30.0));
+        assert_eq!(per_day.projects[0].name, "site");
+        assert_eq!(per_day.projects[0].billed_seconds, 45.0 * 60.0);
+        assert_eq!(per_day.amount, 30.0 + 40.0 + 20.0);
+    }
+
+    #[test]
+    fn non_billable_activities_are_left_out() {
+        let sheet = sheet(&client(0, RoundingScope::Session), &[ActivityType::Communicating]);
+        assert_eq!(line(&sheet, 1, "audit"), (6.0, 12.0));
+        assert_eq!(line(&sheet, 1, "site").0, 28.0);
+    }
+
+    #[test]
+    fn ranges_are_months_or_days() {
+        let today = NaiveDate::from_ymd_opt(2026, 2, 14).unwrap();
+        let query = |month: Option<&str>, from: Option<&str>, to: Option<&str>| TimesheetQuery {
+            client: "Acme".into(),
+            month: month.map(Into::into),
+            from: from.map(|d| d.parse().unwrap()),
+            to: to.map(|d| d.parse().unwrap()),
+            ..Default::default()
+        };
+        let range = |q: TimesheetQuery| days(&q, today).map(|(a, b)| format!("{a} {b}"));
+
+        assert_eq!(range(query(None, None, None)).unwrap(), "2026-02-01 2026-02-28");
+        assert_eq!(range(query(Some("2024-02"), None, None)).unwrap(), "2024-02-01 2024-02-29");
+        let custom = query(None, Some("2026-01-10"), Some("2026-01-20"));
+        assert_eq!(range(custom).unwrap(), "2026-01-10 2026-01-20");
+        assert!(range(query(Some("2026-13"), None, None)).is_err());
+        assert!(range(query(None, Some("2026-01-10"), None)).is_err());
+        assert!(range(query(None, Some("2026-01-10"), Some("2026-01-09"))).is_err());
+        assert!(range(query(None, Some("2025-01-01"), Some("2026-01-09"))).is_err());
+    }
+
+    #[test]
+    fn renders_list_the_same_lines() {
+        let sheet = sheet(&client(15, RoundingScope::Day), &[]);
+        assert_eq!(period(&sheet), "September 2026");
+        assert_eq!(file_name(&sheet, "pdf"), "acme-2026-09.pdf");
+
+        let csv = csv(&sheet);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], CSV_HEADER.join(","));
+        assert_eq!(rows[2], "2026-09-01,site,0.47,0.50,80.00,40.00,EUR");
+        assert_eq!(rows.last().unwrap(), &",Total,0.77,1.00,,90.00,EUR");
+
+        let html = html(&sheet);
+        assert!(html.contains("<h1>Timesheet — Acme</h1>"));
+        assert!(html.contains("Rounding: up to 15m per day"));
+        assert!(html.contains("<td>Tue 2026-09-01</td><td>site</td><td>0.50</td>"));
+
+        let pdf = String::from_utf8_lossy(&pdf(&sheet)).to_string();
+        assert!(pdf.contains("(Tue 2026-09-01  site"));
+        assert!(pdf.contains("Amount \\(EUR\\)"));
+        assert!(pdf.contains("/Count 1"));
+    }
+
+    #[test]
+    fn long_timesheets_get_more_pages() {
+        let mut sheet = sheet(&client(0, RoundingScope::Session), &[]);
+        let line = sheet.lines[0].clone();
+        sheet.lines = vec![line; 120];
+        let pdf = String::from_utf8_lossy(&pdf(&sheet)).to_string();
+        assert!(pdf.contains("/Count 3"), "{}", &pdf[..400]);
+    }
+
+    #[tokio::test]
+    async fn clients_are_unique_and_own_their_projects() {
+        use sqlx::sqlite::SqlitePoolOptions;
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        let req = |name: &str, projects: &[&str]| ClientRequest {
+            name: name.into(),
+            hourly_rate: 80.0,
+            currency: " eur".into(),
+            rounding: Rounding::default(),
+            projects: projects
+                .iter()
+                .map(|p| ClientProject { name: p.to_string(), hourly_rate: None })
+                .collect(),
+        };
+
+        let acme = create_client(&pool, user.id, req(" Acme ", &["site", "audit"])).await.unwrap();
+        assert_eq!((acme.name.as_str(), acme.currency.as_str()), ("Acme", "EUR"));
+        let err = create_client(&pool, user.id, req("acme", &[])).await.unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+        let err = create_client(&pool, user.id, req("Globex", &["site"])).await.unwrap_err();
+        assert!(err.to_string().contains("already billed to Acme"), "{err}");
+        let err = create_client(&pool, user.id, req("Globex", &["a", "a"])).await.unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+
+        // Renamed and given a project of its own rate; still the same client
+        let mut changed = req("Acme Corp", &["site"]);
+        changed.projects[0].hourly_rate = Some(95.0);
+        let updated = update_client(&pool, user.id, "ACME", changed).await.unwrap();
+        assert_eq!(updated.id, acme.id);
+        let stored = get_client(&pool, user.id, &acme.id.to_string()).await.unwrap();
+        assert_eq!(stored.name, "Acme Corp");
+        assert_eq!(stored.rate_for("site"), 95.0);
+        assert_eq!(stored.projects.len(), 1);
+        // The audit is free to go to someone else now
+        create_client(&pool, user.id, req("Globex", &["audit"])).await.unwrap();
+
+        let changes = [ActivityBilling { activity: ActivityType::Browsing, billable: false }];
+        let activities = set_activities(&pool, user.id, &changes).await.unwrap();
+        assert_eq!(activities.len(), ActivityType::ALL.len());
+        assert!(activities.iter().all(|a| a.billable == (a.activity != ActivityType::Browsing)));
+
+        delete_client(&pool, user.id, "acme corp").await.unwrap();
+        assert!(matches!(
+            get_client(&pool, user.id, "Acme Corp").await,
+            Err(AppError::NotFound(_))
+        ));
+        assert_eq!(list_clients(&pool, user.id).await.unwrap().len(), 1);
+    }
+}
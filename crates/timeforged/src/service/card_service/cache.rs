@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::http::{HeaderMap, header};
+use chrono::{DateTime, SubsecRound, Utc};
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use timeforged_core::api::{CardCacheStats, StreamEvent};
+use timeforged_core::config::CardConfig;
+
+use crate::bus::EventBus;
+
+/// A rendered card or badge, ready to send.
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    pub body: Bytes,
+    pub content_type: &'static str,
+    /// Strong validator: a hash of the body, quoted
+    pub etag: String,
+    /// When this body was first rendered, in whole seconds like HTTP dates
+    pub modified: DateTime<Utc>,
+}
+
+impl Rendered {
+    pub fn new(body: impl Into<Bytes>, content_type: &'static str) -> Self {
+        let body = body.into();
+        let digest = format!("{:x}", Sha256::digest(&body));
+        Self {
+            etag: format!("\"{}\"", &digest[..32]),
+            body,
+            content_type,
+            modified: Utc::now().trunc_subsecs(0),
+        }
+    }
+
+    /// `modified` as an HTTP date, for `Last-Modified`.
+    pub fn last_modified(&self) -> String {
+        self.modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+    }
+
+    /// Whether a conditional request says the client already has this
+    /// render. `If-None-Match` decides when it's sent; `If-Modified-Since`
+    /// only counts without it.
+    pub fn not_modified(&self, headers: &HeaderMap) -> bool {
+        let value = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        if let Some(tags) = value(header::IF_NONE_MATCH) {
+            return self.matches(tags);
+        }
+        value(header::IF_MODIFIED_SINCE)
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .is_some_and(|since| self.modified <= since)
+    }
+
+    /// Whether an `If-None-Match` header value names this render.
+    fn matches(&self, if_none_match: &str) -> bool {
+        if_none_match.trim() == "*"
+            || if_none_match
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == self.etag)
+    }
+}
+
+/// The outcome of [`RenderCache::get`].
+pub enum Lookup {
+    Hit(Rendered),
+    Miss(Slot),
+}
+
+/// Where a render that missed the cache goes once it's made. It remembers
+/// when it was handed out, so a render that raced new activity isn't kept.
+pub struct Slot {
+    user_id: Uuid,
+    key: String,
+    generation: u64,
+}
+
+struct Entry {
+    at: Instant,
+    rendered: Rendered,
+}
+
+#[derive(Default)]
+struct UserRenders {
+    renders: HashMap<String, Entry>,
+    /// Generation of the user's last invalidation
+    invalidated: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    users: HashMap<Uuid, UserRenders>,
+    len: usize,
+    /// Counts invalidations; slots are stamped with it
+    generation: u64,
+    /// Generation of the last [`RenderCache::clear`]
+    cleared: u64,
+    hits: u64,
+    misses: u64,
+    invalidations: u64,
+}
+
+impl Inner {
+    fn remove(&mut self, user_id: Uuid, key: &str) {
+        if let Some(user) = self.users.get_mut(&user_id)
+            && user.renders.remove(key).is_some()
+        {
+            self.len -= 1;
+        }
+    }
+
+    /// Make room for one more render: drop the expired ones, and if every
+    /// render is still fresh, the oldest.
+    fn evict(&mut self, now: Instant, ttl: Duration) {
+        for user in self.users.values_mut() {
+            user.renders.retain(|_, entry| now.duration_since(entry.at) < ttl);
+        }
+        self.len = self.users.values().map(|user| user.renders.len()).sum();
+
+        let oldest = self
+            .users
+            .iter()
+            .flat_map(|(user_id, user)| {
+                user.renders.iter().map(move |(key, entry)| (entry.at, user_id, key))
+            })
+            .min_by_key(|(at, _, _)| *at)
+            .map(|(_, user_id, key)| (*user_id, key.clone()));
+        if let Some((user_id, key)) = oldest {
+            self.remove(user_id, &key);
+        }
+    }
+}
+
+/// Renders kept in memory, per user, for `[card] cache_secs`, so a card
+/// linked from a busy README isn't recomputed from a year of events on
+/// every view. A user's renders are dropped as soon as they record
+/// activity (see [`run`]), so the expiry only matters for windows that
+/// move with the clock, like "today".
+#[derive(Clone)]
+pub struct RenderCache {
+    inner: Arc<Mutex<Inner>>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl RenderCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner::default())),
+            ttl,
+            capacity,
+        }
+    }
+
+    pub fn from_config(config: &CardConfig) -> Self {
+        Self::new(Duration::from_secs(config.cache_secs), config.cache_entries)
+    }
+
+    /// How long a render is reused, which is also how long clients may
+    /// keep one before asking again.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The user's render for `key`, or the slot to put a new one in.
+    pub fn get(&self, user_id: Uuid, key: String) -> Lookup {
+        self.get_at(user_id, key, Instant::now())
+    }
+
+    fn get_at(&self, user_id: Uuid, key: String, now: Instant) -> Lookup {
+        let mut inner = self.inner.lock().unwrap();
+        let hit = inner
+            .users
+            .get(&user_id)
+            .and_then(|user| user.renders.get(&key))
+            .filter(|entry| now.duration_since(entry.at) < self.ttl)
+            .map(|entry| entry.rendered.clone());
+        match hit {
+            Some(rendered) => {
+                inner.hits += 1;
+                Lookup::Hit(rendered)
+            }
+            None => {
+                inner.misses += 1;
+                Lookup::Miss(Slot {
+                    user_id,
+                    key,
+                    generation: inner.generation,
+                })
+            }
+        }
+    }
+
+    /// Keep a render, unless the user recorded activity since `slot` was
+    /// handed out. Returns what to send: if the body is the one that just
+    /// expired, it keeps that render's `modified`, so `Last-Modified`
+    /// doesn't move while the ETag stays.
+    pub fn insert(&self, slot: Slot, rendered: Rendered) -> Rendered {
+        self.insert_at(slot, rendered, Instant::now())
+    }
+
+    fn insert_at(&self, slot: Slot, mut rendered: Rendered, now: Instant) -> Rendered {
+        if self.ttl.is_zero() || self.capacity == 0 {
+            return rendered;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let stale = inner.cleared > slot.generation
+            || inner
+                .users
+                .get(&slot.user_id)
+                .is_some_and(|user| user.invalidated > slot.generation);
+        if stale {
+            return rendered;
+        }
+
+        let previous = inner
+            .users
+            .get(&slot.user_id)
+            .and_then(|user| user.renders.get(&slot.key));
+        if let Some(previous) = previous.filter(|p| p.rendered.etag == rendered.etag) {
+            rendered.modified = previous.rendered.modified;
+        }
+        let replaces = previous.is_some();
+        inner.remove(slot.user_id, &slot.key);
+        if !replaces && inner.len >= self.capacity {
+            inner.evict(now, self.ttl);
+        }
+
+        let entry = Entry {
+            at: now,
+            rendered: rendered.clone(),
+        };
+        inner.users.entry(slot.user_id).or_default().renders.insert(slot.key, entry);
+        inner.len += 1;
+        rendered
+    }
+
+    /// Drop the user's renders; renders of theirs already under way won't
+    /// be kept either.
+    pub fn invalidate(&self, user_id: Uuid) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.generation += 1;
+        let generation = inner.generation;
+        let user = inner.users.entry(user_id).or_default();
+        user.invalidated = generation;
+        let dropped = std::mem::take(&mut user.renders).len();
+        if dropped > 0 {
+            inner.len -= dropped;
+            inner.invalidations += 1;
+        }
+    }
+
+    /// Drop every render, for when activity may have been missed.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.generation += 1;
+        inner.cleared = inner.generation;
+        inner.users.clear();
+        inner.len = 0;
+    }
+
+    pub fn stats(&self) -> CardCacheStats {
+        let inner = self.inner.lock().unwrap();
+        let requests = inner.hits + inner.misses;
+        CardCacheStats {
+            entries: inner.len,
+            hits: inner.hits,
+            misses: inner.misses,
+            hit_rate: if requests == 0 {
+                0.0
+            } else {
+                inner.hits as f64 / requests as f64
+            },
+            invalidations: inner.invalidations,
+        }
+    }
+}
+
+/// Background task: drops a user's renders when they record activity, so
+/// their cards show it on the next request rather than after `cache_secs`.
+pub async fn run(cards: RenderCache, bus: EventBus) {
+    let mut rx = bus.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(msg) => {
+                if matches!(msg.event, StreamEvent::Event(_) | StreamEvent::BatchIngested { .. }) {
+                    cards.invalidate(msg.user_id);
+                }
+            }
+            Err(RecvError::Lagged(n)) => {
+                // Some of the missed messages may have been activity
+                tracing::debug!("cards: lagged by {n} messages, dropping every render");
+                cards.clear();
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn card(s: &'static str) -> Rendered {
+        Rendered::new(s, "image/svg+xml")
+    }
+
+    /// Fill a miss with `rendered` and return what was sent.
+    fn fill(cache: &RenderCache, user: Uuid, key: &str, card: Rendered, now: Instant) -> Rendered {
+        match cache.get_at(user, key.into(), now) {
+            Lookup::Miss(slot) => cache.insert_at(slot, card, now),
+            Lookup::Hit(_) => panic!("{key} is cached"),
+        }
+    }
+
+    fn hit(cache: &RenderCache, user: Uuid, key: &str, now: Instant) -> bool {
+        matches!(cache.get_at(user, key.into(), now), Lookup::Hit(_))
+    }
+
+    #[test]
+    fn etags_match_conditional_requests() {
+        let rendered = card("<svg/>");
+        assert_eq!(rendered.etag.len(), 34);
+        assert_eq!(card("<svg/>").etag, rendered.etag);
+        assert_ne!(card("<svg />").etag, rendered.etag);
+
+        assert!(rendered.matches(&rendered.etag));
+        assert!(rendered.matches(&format!("\"other\", W/{}", rendered.etag)));
+        assert!(rendered.matches("*"));
+        assert!(!rendered.matches("\"other\""));
+    }
+
+    #[test]
+    fn last_modified_answers_if_modified_since() {
+        let rendered = card("<svg/>");
+        let date = rendered.last_modified();
+        assert!(date.ends_with(" GMT") && date.len() == 29, "{date}");
+
+        let conditional = |name, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            rendered.not_modified(&headers)
+        };
+        assert!(conditional(header::IF_MODIFIED_SINCE, &date));
+        assert!(!conditional(header::IF_MODIFIED_SINCE, "Mon, 01 Jan 2024 00:00:00 GMT"));
+        assert!(!conditional(header::IF_MODIFIED_SINCE, "yesterday"));
+        assert!(!rendered.not_modified(&HeaderMap::new()));
+
+        // A stale ETag wins over a matching date
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        headers.insert(header::IF_MODIFIED_SINCE, HeaderValue::from_str(&date).unwrap());
+        assert!(!rendered.not_modified(&headers));
+    }
+
+    #[test]
+    fn entries_expire_and_the_oldest_make_room() {
+        let cache = RenderCache::new(Duration::from_secs(60), 2);
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        fill(&cache, alice, "a", card("a"), start);
+        fill(&cache, bob, "b", card("b"), at(1));
+        assert!(hit(&cache, alice, "a", at(59)));
+        assert!(!hit(&cache, alice, "a", at(60)));
+        assert!(!hit(&cache, bob, "a", at(1)));
+
+        fill(&cache, alice, "c", card("c"), at(2));
+        assert!(!hit(&cache, alice, "a", at(3)));
+        assert!(hit(&cache, bob, "b", at(3)) && hit(&cache, alice, "c", at(3)));
+        assert_eq!(cache.stats().entries, 2);
+
+        let off = RenderCache::new(Duration::ZERO, 10);
+        fill(&off, alice, "a", card("a"), start);
+        assert!(!hit(&off, alice, "a", start));
+    }
+
+    #[test]
+    fn activity_drops_only_that_users_renders() {
+        let cache = RenderCache::new(Duration::from_secs(60), 10);
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = Instant::now();
+        fill(&cache, alice, "a", card("a"), now);
+        fill(&cache, bob, "b", card("b"), now);
+
+        // Alice's render started before her new events were stored
+        let Lookup::Miss(slot) = cache.get_at(alice, "c".into(), now) else { panic!() };
+        cache.invalidate(alice);
+        cache.insert_at(slot, card("c"), now);
+        assert!(!hit(&cache, alice, "a", now) && !hit(&cache, alice, "c", now));
+        assert!(hit(&cache, bob, "b", now));
+
+        fill(&cache, alice, "a", card("a"), now);
+        assert!(hit(&cache, alice, "a", now));
+        cache.clear();
+        assert!(!hit(&cache, alice, "a", now) && !hit(&cache, bob, "b", now));
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (0, 2, 8));
+        assert_eq!((stats.hit_rate, stats.invalidations), (0.2, 1));
+    }
+
+    #[test]
+    fn unchanged_renders_keep_their_date() {
+        let cache = RenderCache::new(Duration::from_secs(60), 10);
+        let user = Uuid::new_v4();
+        let start = Instant::now();
+        let mut first = card("a");
+        first.modified -= chrono::Duration::hours(1);
+        let first = fill(&cache, user, "k", first, start);
+
+        let later = start + Duration::from_secs(61);
+        assert_eq!(fill(&cache, user, "k", card("a"), later).modified, first.modified);
+        let later = later + Duration::from_secs(61);
+        assert!(fill(&cache, user, "k", card("b"), later).modified > first.modified);
+    }
+}
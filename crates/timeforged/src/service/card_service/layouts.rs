@@ -0,0 +1,689 @@
+use std::f64::consts::PI;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use timeforged_core::api::GoalProgress;
+use timeforged_core::models::{HourlyActivity, Summary};
+
+use super::svg::{El, px};
+use super::{
+    HEADER_Y, PAD_X, Theme, all_days, card, format_duration, header, heat_color, lang_color,
+    logo, month_abbr, period, text, top_languages, truncate, tspan,
+};
+
+/// Days with at least this much time count as active.
+const ACTIVE_SECS: f64 = 60.0;
+
+/// The activity heatmap with month and weekday labels, a stats line and the
+/// top languages.
+pub fn full(summary: &Summary, theme: &Theme) -> El {
+    let all_days = all_days(summary);
+    let max_secs = all_days.iter().map(|(_, s)| *s).fold(0.0_f64, f64::max);
+
+    // Align to start of week (Monday = 0)
+    let start_weekday = summary.from.date_naive().weekday().num_days_from_monday() as i32;
+    let total_cells = start_weekday as usize + all_days.len();
+    let num_cols = total_cells.div_ceil(7);
+
+    // Cell sizing — adaptive based on column count
+    let (cell_size, cell_gap) = if num_cols <= 10 {
+        (13, 3)
+    } else if num_cols <= 20 {
+        (11, 2)
+    } else {
+        // Year view: ~53 columns, need compact cells
+        (10, 3)
+    };
+    let cell_stride = cell_size + cell_gap;
+
+    let day_label_width: i32 = 30;
+    let grid_x = PAD_X + day_label_width;
+    let grid_width = num_cols as i32 * cell_stride - cell_gap;
+    let width = grid_x + grid_width + PAD_X;
+
+    // Sections vertical layout
+    let month_labels_y = 48;
+    let grid_y = month_labels_y + 14;
+    let grid_height = 7 * cell_stride - cell_gap;
+    let legend_y = grid_y + grid_height + 14;
+    let stats_y = legend_y + 18;
+    let separator_y = stats_y + 10;
+
+    let top_langs = top_languages(summary, 5);
+    let lang_bar_y = separator_y + 18;
+    let lang_bar_height = 8;
+    let lang_label_start = lang_bar_y + lang_bar_height + 16;
+    let lang_row_height = 20;
+    let lang_rows = top_langs.len().div_ceil(3) as i32;
+    let height = if top_langs.is_empty() {
+        stats_y + 20
+    } else {
+        separator_y + 18 + lang_bar_height + 16 + lang_rows * lang_row_height + 14
+    };
+
+    let total_str = format_duration(summary.total_seconds);
+    let mut svg = card(width, height, theme)
+        .children(header(width, &format!("{total_str} {}", period(summary)), theme));
+
+    // Month labels above the grid
+    let mut last_month = 0u32;
+    for col in 0..num_cols {
+        let Some(actual_idx) = (col * 7).checked_sub(start_weekday as usize) else {
+            continue;
+        };
+        let Some((date, _)) = all_days.get(actual_idx) else {
+            continue;
+        };
+        if date.month() != last_month {
+            last_month = date.month();
+            let x = grid_x + col as i32 * cell_stride;
+            svg.push(text(x, month_labels_y, 10, &theme.muted).text(month_abbr(last_month)));
+        }
+    }
+
+    for (row, label) in [(1, "Mon"), (3, "Wed"), (5, "Fri")] {
+        let y = grid_y + row * cell_stride + cell_size - 2;
+        svg.push(text(PAD_X, y, 10, &theme.muted).text(label));
+    }
+
+    for (i, &(_, secs)) in all_days.iter().enumerate() {
+        let cell_index = i + start_weekday as usize;
+        let x = grid_x + (cell_index / 7) as i32 * cell_stride;
+        let y = grid_y + (cell_index % 7) as i32 * cell_stride;
+        svg.push(cell(x, y, cell_size, heat_color(theme, secs, max_secs)));
+    }
+
+    // Legend: Less □□□□□ More
+    let box_size = 10;
+    let box_gap = 3;
+    let total_legend_w = 5 * (box_size + box_gap) - box_gap;
+    let legend_start_x = width - PAD_X - total_legend_w - 35;
+    svg.push(text(legend_start_x - 28, legend_y, 10, &theme.muted).text("Less"));
+    let colors = std::iter::once(&theme.heat_empty).chain(&theme.heat);
+    for (j, color) in colors.enumerate() {
+        let x = legend_start_x + j as i32 * (box_size + box_gap);
+        svg.push(cell(x, legend_y - 9, box_size, color));
+    }
+    let more_x = legend_start_x + 5 * (box_size + box_gap) + 3;
+    svg.push(text(more_x, legend_y, 10, &theme.muted).text("More"));
+
+    let projects_label = match summary.projects.len() {
+        0 => "no projects".to_string(),
+        1 => "1 project".to_string(),
+        n => format!("{n} projects"),
+    };
+    svg.push(
+        text(PAD_X, stats_y, 11, &theme.muted)
+            .text("Total: ")
+            .child(tspan(&theme.accent).attr("font-weight", 600).text(&total_str))
+            .text("  ·  ")
+            .child(tspan(&theme.muted).text(projects_label)),
+    );
+
+    if top_langs.is_empty() {
+        return svg;
+    }
+
+    svg.push(
+        El::new("line")
+            .attr("x1", PAD_X)
+            .attr("y1", separator_y)
+            .attr("x2", width - PAD_X)
+            .attr("y2", separator_y)
+            .attr("stroke", &theme.separator)
+            .attr("stroke-width", 1),
+    );
+
+    // Stacked language bar
+    let bar_width = width - 2 * PAD_X;
+    let bar = |fill: &str| {
+        El::new("rect")
+            .attr("x", PAD_X)
+            .attr("y", lang_bar_y)
+            .attr("width", bar_width)
+            .attr("height", lang_bar_height)
+            .attr("rx", 4)
+            .attr("fill", fill)
+    };
+    svg.push(El::new("clipPath").attr("id", "lc").child(bar("#000")));
+    svg.push(bar(&theme.bar_bg));
+    let total_lang_secs: f64 = top_langs.iter().map(|l| l.total_seconds).sum();
+    let mut segments = El::new("g").attr("clip-path", "url(#lc)");
+    let mut offset = 0.0_f64;
+    for lang in &top_langs {
+        let seg_w = if total_lang_secs > 0.0 {
+            (lang.total_seconds / total_lang_secs) * bar_width as f64
+        } else {
+            0.0
+        };
+        if seg_w > 0.5 {
+            segments.push(
+                El::new("rect")
+                    .attr("x", px(PAD_X as f64 + offset))
+                    .attr("y", lang_bar_y)
+                    .attr("width", px(seg_w))
+                    .attr("height", lang_bar_height)
+                    .attr("fill", lang_color(&lang.name)),
+            );
+        }
+        offset += seg_w;
+    }
+    svg.push(segments);
+
+    // Language labels (3 per row)
+    let col_width = bar_width / 3;
+    for (i, lang) in top_langs.iter().enumerate() {
+        let x = PAD_X + (i % 3) as i32 * col_width;
+        let y = lang_label_start + (i / 3) as i32 * lang_row_height;
+        svg.push(dot(x + 4, y - 4, lang_color(&lang.name)));
+        svg.push(
+            text(x + 14, y, 11, &theme.text)
+                .child(El::new("tspan").attr("font-weight", 500).text(truncate(&lang.name, 16)))
+                .text(" ")
+                .child(tspan(&theme.muted).text(format!("{}%", lang.percent.round() as u32))),
+        );
+    }
+    svg
+}
+
+/// A one-line badge in the style of shields.io: logo and name, then the
+/// total for the period.
+pub fn badge(summary: &Summary, theme: &Theme) -> El {
+    let value = format!("{} {}", format_duration(summary.total_seconds), period(summary));
+    shield("TimeForged", &value, theme)
+}
+
+/// The logo and `label` on the theme's background, then `value` on the
+/// accent.
+pub fn shield(label: &str, value: &str, theme: &Theme) -> El {
+    // Rough widths for 11px sans-serif; badges don't need to be exact.
+    let text_width = |s: &str| (s.chars().count() as f64 * 6.5).round() as i32;
+    let label_width = 22 + text_width(label) + 6;
+    let value_width = text_width(value) + 14;
+    let width = label_width + value_width;
+    let height = 20;
+
+    let rect = |x: i32, w: i32, fill: &str| {
+        El::new("rect")
+            .attr("x", x)
+            .attr("width", w)
+            .attr("height", height)
+            .attr("fill", fill)
+    };
+    let clip = El::new("rect")
+        .attr("width", width)
+        .attr("height", height)
+        .attr("rx", 3)
+        .attr("fill", "#000");
+    El::new("svg")
+        .attr("xmlns", "http://www.w3.org/2000/svg")
+        .attr("xmlns:xlink", "http://www.w3.org/1999/xlink")
+        .attr("width", width)
+        .attr("height", height)
+        .attr("viewBox", format!("0 0 {width} {height}"))
+        .attr("role", "img")
+        .attr("aria-label", format!("{label}: {value}"))
+        .child(El::new("title").text(format!("{label}: {value}")))
+        .child(El::new("clipPath").attr("id", "r").child(clip))
+        .child(
+            El::new("g")
+                .attr("clip-path", "url(#r)")
+                .child(rect(0, label_width, &theme.bg))
+                .child(rect(label_width, value_width, &theme.accent)),
+        )
+        .child(logo(4, 3, 14))
+        .child(text(22, 14, 11, &theme.text).text(label))
+        .child(
+            text(label_width + value_width / 2, 14, 11, &theme.bg)
+                .attr("text-anchor", "middle")
+                .attr("font-weight", 600)
+                .text(value),
+        )
+}
+
+/// Languages as a donut chart with a legend.
+pub fn donut(summary: &Summary, theme: &Theme) -> El {
+    let width = 340;
+    let height = 180;
+    let (cx, cy, r) = (85.0, 108.0, 48.0);
+    let circumference = 2.0 * PI * r;
+    let langs = top_languages(summary, 5);
+
+    let ring = |stroke: &str| {
+        El::new("circle")
+            .attr("cx", px(cx))
+            .attr("cy", px(cy))
+            .attr("r", px(r))
+            .attr("stroke", stroke)
+            .attr("stroke-width", 16)
+            .attr("fill", "none")
+    };
+
+    let mut svg = card(width, height, theme)
+        .children(header(width, period(summary), theme))
+        .child(ring(&theme.bar_bg));
+
+    // Each language is the ring's stroke dashed to its share, rotated so the
+    // first one starts at twelve o'clock.
+    let mut offset = 0.0;
+    for lang in &langs {
+        let len = lang.percent / 100.0 * circumference;
+        if len > 0.5 {
+            svg.push(
+                ring(lang_color(&lang.name))
+                    .attr("stroke-dasharray", format!("{} {}", px(len), px(circumference)))
+                    .attr("stroke-dashoffset", px(-offset))
+                    .attr("transform", format!("rotate(-90 {} {})", px(cx), px(cy))),
+            );
+        }
+        offset += len;
+    }
+
+    svg.push(
+        text(px(cx), px(cy + 2.0), 13, &theme.text)
+            .attr("text-anchor", "middle")
+            .attr("font-weight", 700)
+            .text(format_duration(summary.total_seconds)),
+    );
+    svg.push(
+        text(px(cx), px(cy + 16.0), 10, &theme.muted)
+            .attr("text-anchor", "middle")
+            .text("total"),
+    );
+
+    if langs.is_empty() {
+        svg.push(text(170, 112, 11, &theme.muted).text("No languages yet"));
+    }
+    for (i, lang) in langs.iter().enumerate() {
+        let y = 70 + i as i32 * 20;
+        svg.push(dot(174, y - 4, lang_color(&lang.name)));
+        svg.push(
+            text(184, y, 11, &theme.text)
+                .child(El::new("tspan").attr("font-weight", 500).text(truncate(&lang.name, 18)))
+                .text(" ")
+                .child(tspan(&theme.muted).text(format!("{}%", lang.percent.round() as u32))),
+        );
+    }
+    svg
+}
+
+/// The top projects as horizontal bars.
+pub fn projects(summary: &Summary, theme: &Theme) -> El {
+    let width = 400;
+    let row_height = 34;
+    let top = 58;
+    let projects: Vec<_> = summary.projects.iter().take(5).collect();
+    let rows = projects.len().max(1) as i32;
+    let height = top + rows * row_height + 4;
+    let max_secs = projects.first().map_or(0.0, |p| p.total_seconds);
+    let bar_width = (width - 2 * PAD_X) as f64;
+
+    let mut svg = card(width, height, theme).children(header(width, period(summary), theme));
+    if projects.is_empty() {
+        svg.push(text(PAD_X, top + 14, 11, &theme.muted).text("No projects yet"));
+    }
+    for (i, project) in projects.iter().enumerate() {
+        let y = top + i as i32 * row_height;
+        svg.push(
+            text(PAD_X, y + 8, 11, &theme.text)
+                .attr("font-weight", 500)
+                .text(truncate(&project.name, 40)),
+        );
+        svg.push(
+            text(width - PAD_X, y + 8, 11, &theme.muted)
+                .attr("text-anchor", "end")
+                .text(format_duration(project.total_seconds)),
+        );
+        let fill = if max_secs > 0.0 {
+            project.total_seconds / max_secs * bar_width
+        } else {
+            0.0
+        };
+        svg.push(bar(PAD_X as f64, (y + 15) as f64, bar_width, 6.0, &theme.bar_bg));
+        if fill > 0.5 {
+            svg.push(bar(PAD_X as f64, (y + 15) as f64, fill, 6.0, &theme.accent));
+        }
+    }
+    svg
+}
+
+/// Time per week (Monday to Sunday) over the last 12 weeks of the range.
+pub fn weekly(summary: &Summary, theme: &Theme) -> El {
+    let width = 400;
+    let height = 180;
+    let (chart_top, chart_height) = (62.0, 84.0);
+    let baseline = chart_top + chart_height;
+
+    let mut weeks: Vec<(NaiveDate, f64)> = Vec::new();
+    for (date, secs) in all_days(summary) {
+        let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+        match weeks.last_mut() {
+            Some((start, total)) if *start == monday => *total += secs,
+            _ => weeks.push((monday, secs)),
+        }
+    }
+    let weeks = &weeks[weeks.len().saturating_sub(12)..];
+    let max_secs = weeks.iter().map(|(_, s)| *s).fold(0.0_f64, f64::max);
+    let average = weeks.iter().map(|(_, s)| s).sum::<f64>() / weeks.len().max(1) as f64;
+
+    let slot = (width - 2 * PAD_X) as f64 / weeks.len().max(1) as f64;
+    let bar_width = (slot * 0.6).min(28.0);
+    let mut svg = card(width, height, theme)
+        .children(header(width, &format!("{} / week", format_duration(average)), theme))
+        .child(
+            El::new("line")
+                .attr("x1", PAD_X)
+                .attr("y1", px(baseline))
+                .attr("x2", width - PAD_X)
+                .attr("y2", px(baseline))
+                .attr("stroke", &theme.separator)
+                .attr("stroke-width", 1),
+        );
+
+    // Label every week when they fit, otherwise every other one.
+    let label_every = if weeks.len() > 6 { 2 } else { 1 };
+    for (i, &(start, secs)) in weeks.iter().enumerate() {
+        let center = PAD_X as f64 + slot * (i as f64 + 0.5);
+        let h = if max_secs > 0.0 {
+            secs / max_secs * chart_height
+        } else {
+            0.0
+        };
+        if h >= 1.0 {
+            let fill = heat_color(theme, secs, max_secs);
+            svg.push(bar(center - bar_width / 2.0, baseline - h, bar_width, h, fill));
+        }
+        if secs > 0.0 && secs == max_secs {
+            svg.push(
+                text(px(center), px(baseline - h - 5.0), 10, &theme.text)
+                    .attr("text-anchor", "middle")
+                    .text(format_duration(secs)),
+            );
+        }
+        if (weeks.len() - 1 - i).is_multiple_of(label_every) {
+            svg.push(
+                text(px(center), px(baseline + 16.0), 10, &theme.muted)
+                    .attr("text-anchor", "middle")
+                    .text(format!("{} {}", month_abbr(start.month()), start.day())),
+            );
+        }
+    }
+    svg
+}
+
+/// A run of consecutive active days.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Streak {
+    pub days: u32,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// The current and longest streaks and the number of active days. The
+/// current streak ends on the last day of `days`, or the day before when
+/// that day has no activity yet.
+pub fn streaks(days: &[(NaiveDate, f64)]) -> (Streak, Streak, usize) {
+    let mut longest = Streak::default();
+    let mut run = Streak::default();
+    for &(date, secs) in days {
+        if secs >= ACTIVE_SECS {
+            run.days += 1;
+            run.from = run.from.or(Some(date));
+            run.to = Some(date);
+            if run.days > longest.days {
+                longest = run;
+            }
+        } else {
+            run = Streak::default();
+        }
+    }
+
+    let active = days.iter().filter(|(_, s)| *s >= ACTIVE_SECS).count();
+    let today_idle = days.last().is_none_or(|(_, s)| *s < ACTIVE_SECS);
+    let end = days.len().saturating_sub(usize::from(today_idle));
+    let mut current = Streak::default();
+    for &(date, secs) in days[..end].iter().rev() {
+        if secs < ACTIVE_SECS {
+            break;
+        }
+        current.days += 1;
+        current.from = Some(date);
+        current.to = current.to.or(Some(date));
+    }
+    (current, longest, active)
+}
+
+/// Current and longest streaks and active days, side by side.
+pub fn streak(summary: &Summary, theme: &Theme) -> El {
+    let width = 400;
+    let height = 130;
+    let days = all_days(summary);
+    let (current, longest, active) = streaks(&days);
+
+    let day = |d: NaiveDate| format!("{} {}", month_abbr(d.month()), d.day());
+    let range = |s: &Streak| match (s.from, s.to) {
+        (Some(from), Some(to)) if from == to => day(from),
+        (Some(from), Some(to)) => format!("{} – {}", day(from), day(to)),
+        _ => "—".to_string(),
+    };
+    let columns = [
+        (current.days as usize, "Current streak", range(&current)),
+        (longest.days as usize, "Longest streak", range(&longest)),
+        (active, "Active days", format!("of {}", days.len())),
+    ];
+
+    let mut svg = card(width, height, theme).children(header(width, period(summary), theme));
+    let column_width = (width - 2 * PAD_X) / 3;
+    for (i, (value, label, detail)) in columns.into_iter().enumerate() {
+        let x = PAD_X + column_width * i as i32 + column_width / 2;
+        if i > 0 {
+            let sep_x = PAD_X + column_width * i as i32;
+            svg.push(
+                El::new("line")
+                    .attr("x1", sep_x)
+                    .attr("y1", HEADER_Y + 36)
+                    .attr("x2", sep_x)
+                    .attr("y2", height - 16)
+                    .attr("stroke", &theme.separator)
+                    .attr("stroke-width", 1),
+            );
+        }
+        svg.push(
+            text(x, 80, 24, &theme.accent)
+                .attr("text-anchor", "middle")
+                .attr("font-weight", 700)
+                .text(value),
+        );
+        svg.push(
+            text(x, 98, 11, &theme.text)
+                .attr("text-anchor", "middle")
+                .text(label),
+        );
+        svg.push(
+            text(x, 113, 10, &theme.muted)
+                .attr("text-anchor", "middle")
+                .text(detail),
+        );
+    }
+    svg
+}
+
+/// Up to five goals: progress in the current day or week and streaks.
+pub fn goals(goals: &[GoalProgress], theme: &Theme) -> El {
+    let width = 400;
+    let row_height = 46;
+    let top = 58;
+    let goals = &goals[..goals.len().min(5)];
+    let rows = goals.len().max(1) as i32;
+    let height = top + rows * row_height;
+    let bar_width = (width - 2 * PAD_X) as f64;
+    let met = goals.iter().filter(|g| g.current.met).count();
+    let badge = match goals.len() {
+        0 => "goals".to_string(),
+        n => format!("{met} of {n} met"),
+    };
+
+    let mut svg = card(width, height, theme).children(header(width, &badge, theme));
+    if goals.is_empty() {
+        svg.push(text(PAD_X, top + 14, 11, &theme.muted).text("No goals yet"));
+    }
+    for (i, goal) in goals.iter().enumerate() {
+        let y = top + i as i32 * row_height;
+        svg.push(
+            text(PAD_X, y + 8, 11, &theme.text)
+                .attr("font-weight", 500)
+                .text(truncate(&goal.label, 40)),
+        );
+        let done = format_duration(goal.current.seconds);
+        let target = format_duration(goal.goal.target_seconds as f64);
+        svg.push(
+            text(width - PAD_X, y + 8, 11, &theme.muted)
+                .attr("text-anchor", "end")
+                .text(format!("{done} / {target}")),
+        );
+        let fill = (goal.percent / 100.0).clamp(0.0, 1.0) * bar_width;
+        svg.push(bar(PAD_X as f64, (y + 15) as f64, bar_width, 6.0, &theme.bar_bg));
+        if fill > 0.5 {
+            svg.push(bar(PAD_X as f64, (y + 15) as f64, fill, 6.0, &theme.accent));
+        }
+
+        let unit = goal.goal.period.as_str();
+        let plural = |n: u32| if n == 1 { unit.to_string() } else { format!("{unit}s") };
+        let streak = goal.streak;
+        svg.push(text(PAD_X, y + 33, 10, &theme.muted).text(format!(
+            "streak {} {} · best {}",
+            streak.current,
+            plural(streak.current),
+            streak.longest
+        )));
+        let status = if goal.current.met {
+            "met".to_string()
+        } else {
+            format!("{}%", goal.percent.floor() as u32)
+        };
+        svg.push(
+            text(width - PAD_X, y + 33, 10, &theme.accent)
+                .attr("text-anchor", "end")
+                .attr("font-weight", 600)
+                .text(status),
+        );
+    }
+    svg
+}
+
+/// A 24-hour dial with one spoke per hour, longer for busier hours.
+pub fn clock(summary: &Summary, hourly: &[HourlyActivity], theme: &Theme) -> El {
+    let width = 340;
+    let height = 200;
+    let (cx, cy) = (100.0, 120.0);
+    let (inner, reach) = (18.0, 34.0);
+
+    let mut hours = [0.0_f64; 24];
+    for h in hourly {
+        if let Some(slot) = hours.get_mut(h.hour as usize) {
+            *slot += h.total_seconds;
+        }
+    }
+    let max_secs = hours.iter().copied().fold(0.0_f64, f64::max);
+
+    let point = |hour: f64, radius: f64| {
+        let angle = hour / 24.0 * 2.0 * PI - PI / 2.0;
+        (cx + radius * angle.cos(), cy + radius * angle.sin())
+    };
+
+    let mut svg = card(width, height, theme)
+        .children(header(width, period(summary), theme))
+        .child(
+            El::new("circle")
+                .attr("cx", px(cx))
+                .attr("cy", px(cy))
+                .attr("r", px(inner - 6.0))
+                .attr("stroke", &theme.separator)
+                .attr("stroke-width", 1)
+                .attr("fill", "none"),
+        );
+
+    for (hour, &secs) in hours.iter().enumerate() {
+        let share = if max_secs > 0.0 { secs / max_secs } else { 0.0 };
+        let (x1, y1) = point(hour as f64, inner);
+        let (x2, y2) = point(hour as f64, inner + 3.0 + share * reach);
+        let stroke = if secs < ACTIVE_SECS {
+            theme.bar_bg.as_str()
+        } else {
+            heat_color(theme, secs, max_secs)
+        };
+        svg.push(
+            El::new("line")
+                .attr("x1", px(x1))
+                .attr("y1", px(y1))
+                .attr("x2", px(x2))
+                .attr("y2", px(y2))
+                .attr("stroke", stroke)
+                .attr("stroke-width", 6)
+                .attr("stroke-linecap", "round"),
+        );
+    }
+
+    for hour in [0, 6, 12, 18] {
+        let (x, y) = point(hour as f64, inner + reach + 14.0);
+        svg.push(
+            text(px(x), px(y + 3.5), 10, &theme.muted)
+                .attr("text-anchor", "middle")
+                .text(format!("{hour:02}")),
+        );
+    }
+
+    let peak = hours
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| **s >= ACTIVE_SECS)
+        .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(&a.0)))
+        .map(|(hour, _)| hour);
+    let (label_x, value_size) = (200, 14);
+    let peak_text = match peak {
+        Some(hour) => format!("{hour:02}:00 – {:02}:00", (hour + 1) % 24),
+        None => "—".to_string(),
+    };
+    let stats = [
+        ("Most active", peak_text),
+        ("Total", format_duration(summary.total_seconds)),
+    ];
+    for (i, (label, value)) in stats.into_iter().enumerate() {
+        let y = 84 + i as i32 * 44;
+        svg.push(text(label_x, y, 11, &theme.muted).text(label));
+        svg.push(
+            text(label_x, y + 19, value_size, &theme.text)
+                .attr("font-weight", 600)
+                .text(value),
+        );
+    }
+    svg.push(text(label_x, 180, 10, &theme.muted).text("Hours in UTC"));
+    svg
+}
+
+fn cell(x: i32, y: i32, size: i32, fill: &str) -> El {
+    El::new("rect")
+        .attr("x", x)
+        .attr("y", y)
+        .attr("width", size)
+        .attr("height", size)
+        .attr("rx", 2)
+        .attr("fill", fill)
+}
+
+fn dot(cx: i32, cy: i32, fill: &str) -> El {
+    El::new("circle")
+        .attr("cx", cx)
+        .attr("cy", cy)
+        .attr("r", 4)
+        .attr("fill", fill)
+}
+
+fn bar(x: f64, y: f64, width: f64, height: f64, fill: &str) -> El {
+    El::new("rect")
+        .attr("x", px(x))
+        .attr("y", px(y))
+        .attr("width", px(width))
+        .attr("height", px(height))
+        .attr("rx", px((height / 2.0).min(3.0)))
+        .attr("fill", fill)
+}
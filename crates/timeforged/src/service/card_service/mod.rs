@@ -0,0 +1,664 @@
+use chrono::{Duration, NaiveDate};
+use serde::Deserialize;
+use std::collections::HashMap;
+use timeforged_core::api::GoalProgress;
+use timeforged_core::models::{CategorySummary, HourlyActivity, Summary};
+use utoipa::ToSchema;
+
+pub mod cache;
+mod layouts;
+pub mod raster;
+mod svg;
+mod theme;
+
+pub use cache::{Lookup, RenderCache, Rendered};
+pub use layouts::Streak;
+use svg::El;
+pub use theme::{Theme, ThemeColors};
+
+const LANG_COLORS: &[(&str, &str)] = &[
+    ("Rust", "#dea584"),
+    ("TypeScript", "#3178c6"),
+    ("JavaScript", "#f1e05a"),
+    ("Python", "#3572A5"),
+    ("Go", "#00ADD8"),
+    ("Java", "#b07219"),
+    ("C", "#555555"),
+    ("C++", "#f34b7d"),
+    ("C#", "#178600"),
+    ("Ruby", "#701516"),
+    ("PHP", "#4F5D95"),
+    ("Swift", "#F05138"),
+    ("Kotlin", "#A97BFF"),
+    ("Dart", "#00B4AB"),
+    ("Lua", "#000080"),
+    ("Shell", "#89e051"),
+    ("HTML", "#e34c26"),
+    ("CSS", "#563d7c"),
+    ("SCSS", "#c6538c"),
+    ("Vue", "#41b883"),
+    ("Svelte", "#ff3e00"),
+    ("JSON", "#292929"),
+    ("YAML", "#cb171e"),
+    ("TOML", "#9c4221"),
+    ("Markdown", "#083fa1"),
+    ("SQL", "#e38c00"),
+    ("Dockerfile", "#384d54"),
+    ("Zig", "#ec915c"),
+    ("Elixir", "#6e4a7e"),
+    ("Haskell", "#5e5086"),
+    ("OCaml", "#3be133"),
+    ("Scala", "#c22d40"),
+    ("R", "#198CE7"),
+    ("Nix", "#7e7eff"),
+];
+
+fn lang_color(name: &str) -> &str {
+    LANG_COLORS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, c)| *c)
+        .unwrap_or("#8b949e")
+}
+
+/// Data formats and configs — not real programming languages
+const EXCLUDED_LANGS: &[&str] = &[
+    "JSON", "YAML", "TOML", "Markdown", "XML", "CSV", "INI",
+    "Env", "Git Config", "EditorConfig", "Properties", "Plain Text",
+    "SVG", "Lock",
+];
+
+fn is_programming_lang(name: &str) -> bool {
+    !EXCLUDED_LANGS.iter().any(|e| e.eq_ignore_ascii_case(name))
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total_min = (seconds / 60.0).round() as u64;
+    let h = total_min / 60;
+    let m = total_min % 60;
+    if h > 0 {
+        format!("{}h {:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m", m)
+    } else {
+        "0m".to_string()
+    }
+}
+
+fn month_abbr(month: u32) -> &'static str {
+    match month {
+        1 => "Jan",
+        2 => "Feb",
+        3 => "Mar",
+        4 => "Apr",
+        5 => "May",
+        6 => "Jun",
+        7 => "Jul",
+        8 => "Aug",
+        9 => "Sep",
+        10 => "Oct",
+        11 => "Nov",
+        12 => "Dec",
+        _ => "",
+    }
+}
+
+/// Embedded TimeForged logo (favicon-48.png, base64-encoded)
+const LOGO_PNG_B64: &str = "iVBORw0KGgoAAAANSUhEUgAAADAAAAAwCAYAAABXAvmHAAAQlElEQVR42u1YeZRU1Zn/7ttq7a6qXukFmmZRQBQySSYxCWgEUUDUzDFiGNyFcYxKjJnkoHE7HsW4BOIYjWA4MYLLcTIZHUR0FAOoCIIDBHADoRvoHbqqa3/13rvz+25VtyiaYMaj/JE653ZV13t137f8vt/3+y7R319f7kscC0Z4K/6J2vYnSGiCBP64ttT0EMn6S473pFxJfasHU2TSmk/8rXZMRHF6HXlCIxdLSiEd0l1P17zbqRwOPUZGbeZTf6sdG0DIkgsweFguTHKlGIz/yz2yqZFaSR7rEJLyQjr0UKsBcxyy9IdEwbtSmET65aOFl+8l94VDZKU84ealIImY67ok6Ur2zDgmEtB1AunUosIpNaNWiDxJR6PEf1WQliMK9vURlRUkykMCYqShVvidHdC/8Giv/TkluuO0/t1OujR2G82/UYeBNmV60h4ZOmmWb33BldtRx4/WNkXeoeFNVLZrCzlhX1h6wgLqg5rObknUh/jiISS3XEe9azcoxjEDAc0tODqZPsdzC5KtET4/udk86oCoetQE6trworo3UOnf49reUAmjHc24SnjuQ9jO+BIg5JY8EYwADwZ5CjtgIOACy2NsMDJkLrnL1Z0sCWTGdawyXFNlCxw5QqrSll88CwlJOWmKrGeCY6yzbTJfcHRjtGuYHFmtde8HUqBIYZjItLcZMlqmuYZFuLQf4CdehiEy+B+1LL6EItZtCghbh5WOIfWFmucOI4dmSs+9lc3zXJWRYpq4SAMGxc/7N6ruem58sj1LClupOOXa4qSFLPmZauDflyyhP725jRprK6mprlZDm0TdGbK/kjQNRQiMuh4YXSh+VKjgYuN7PHB8niz6B7lVTDYf9dLWN6eQbc9xTGt+xNu/K5URtKMjTcMGN8+UBXeQhvDqfvmuk0iuqj5nIiV3tJGXtEkm4uQVHEKz+2wZMJHmfT0dVF1ZhvRpnuAIAZ/KQHZAhwNIseYWHZAK01R0RhRx79Nc2iNHyyshEa4q/PbFSrfrxZ1yFF3qe4aWH5pKI3wZsjz7ScmtjfOQBVSyrog/+wa55aBVXVHodS7pfR7JuPjN75aifSBSNr4KBckpFKjgFMhxXDIsk3TWJ4is6zooJJeyeZvm/+gaZOORkCcpa5qmxyyh0g1O4Ptdtz8DJdiL0h+VjpL+AYOHZJp8lNVTMhiyfKG+WYPPp9xLdZSrHdNDnlfJt3qa3kqpRBMFAoTbOM1DtGy2BeXP10rbNTdT/Zgm0vYcoDw87N71Hn1jwkSyNB88tigcsCiRzlEGjDByeDN999Qp1NveKVWESZAQH23ust9y2f/nwxvEEaAtZsnvM+fvylff9UB6JiXrJtuu65rqp7pWkPGEJf0+kgE/O9Co24V9nH2JjBszL7yCWpIHqawmCoyUE1kGFYaNpMa6OkTcUx3PQsWX4/I7Lbuot7ODdmzdSoOqavZj+0aFc2WGPNweZWg/tPge/sz3eF5/XXzUDdv1DsW8Lpom/ocejywtF6mDlMvLkGlqjtFUT3ZvVg+U+1xXaoeQ5eUotA441yHuWrioC5tW4wFx7Mrb8946P4v/59pjviYlOyQ6ocjjhl7P9UbIw2RWiZaVoaiDvYZp3uGzrGcty+pybBCm5aO8nR+et+3LnYIz33Hdfsf4mZrf7z/dLjgvZaWfTgodoPOC/009qSgZwQA5+QJagCRHlJFl9pCeL9adBFw1fFrlAN9YUdcpVLiOEwPWy13PjQL3FY7jVOG9hj8D22G8V+K7EZ70VJEevsBCbjAYPK6lpbW5YNuP4L4uX9jg6qa+RALPcHdnMpkb/nXuHBEI+OeypvE8T+Pf2nZ+ht9v1Uct59wOp+pUmv2/VPXVOMLmt1DOU6FSp2q6NApOBUfWwG909cifzbv2IhRiRhbz6pQozyMqrcM/f7gGeLrYPEGnhrF+/nXz8ADn/VJtmOlMjjr3H5qQSCSk6bPCnstP1EQ5olooFJZUV1UKAyzG9wOu12ZSmQOZbPaPyVzhFTHzBaL3bSrk82f4ZWGln+yVbiF7BrkZstGoo1d+1Y2dVutpYBOqra4JGSgIwMJQkMZz1KKPL9n/We/HukQmDNPYtnnTxm89vWIFA874yvjx7IBj6JxgOsi+wrEUskr+QFC+unUbxRN9lEqlqalxMJwwqPhYhEIZIHaDBlGTcSpIIy0VULAMSgmQjG4I4Pw+EiOfIC2TTlMf5GplrEqY2MjjVsjw4LiUoPGJE0WJ55n3R44aMW7KmWdSy94WpVMmf+dkCoeD8vTJp1NnZ/vOG6+/TvT1Jbl3UFl5OX1n3ElUWRHrwrYnd3R3Ubgs3KAwje1EUevkf3LSNnrDm0v1dbnVWlks5gQiFSIaXCPLgiTLQ+7ARJbL5SirVoZGjx4tUGzrFE+7nigZ70nqx3kJMv2O4XvLtKa9u/M96ursQrHl+afDRow8jgL+AK1bt5ZOPHEcvbZlG0WjEaqqrqZ/ufCfacHCX0m7UKiuq69fv27Nn6inq7sNwXuMSnnghwyqDtGrHeV0bmYVhUU2HpTpXs1zWGuQwPuAA+Bb4NEmG4vTerCna2I4FP4mYNHF0eAiw+o32OtPB/+vozgvuuSy52/48XWUy2apL5Xiq7sX/HKRnHPRbAqGQtTW1ibWvrJaa2xspB/NuxbGL1KZXfrIEtHT00PfnngqRWMxQvFfpJW4FTgak83YMo/BYEL7A3Ovbp1JK5PjrUovQZUyTlUyMeCAceK48bT7/ffQbTVKJvuUM2CPDZs3baqdeMqpTdlsZgH+/wE/FHSr1KumJDCjR1vy4AP3s4QwAqEwVVRUOaFQSBzY3yrZ0Ei0nI2UjOzzz55Bd/5yoeQ+8PiTy8T8G36uoIsAat09vV5Hezsdf9zIFs9xmxQ8ETTm73zebq4TnfTndK087+q1Rw7158+YTmPHfYX8/iCwfAIt+sXdXJgiwbTnOC2pVGrWmBPGikAwWO+zzLv0EvUVxZv2XACMohkGBhLXSQOayb4EvbVpI9SF6Pxg9x4JhpuNCepmwCbHv1m/caO45ofzKB6Pk24a6EeuF4vGaOjQJs7eimKz84rayzLeRGZuDyOTAb/lfOqpxIxJp9I1cy6D5IizxqGmoc3y8suvYEc0ZhlwOtdEO2plfk3tIAHNk1UQMvQdHa17OWBLQBO/1nvzNGn4N+jp5cups6t7EJhjFWrsMUTxEvjna2gcIqZMnkSpdFozIQ0MwwwDhveBOK5nnYVEtipiAOMAfmPS6cw/4lrGxvPRo+RfPVY5b8ZUunbuHKqoqUYbt+mNN1737rjpJjBJZ1FK8pCRSdPxo44P6qBdcPmgx5c+zJC6AhevApOUPff2GrrngQfN+oZ6dF/zbtXWNW0678/NTBQnL1HI5Smfy45Gln8Mo+/lZipYrOJPKBQckc/n32YYDTCglH/bscqmTW/Sjl0f0PYdOwAzP/l8Ph1GuOD4+wDtFB5+S8ehQxTEtaqKSpV+v6nRKaecTK372mn79ne2IronmZZ1Wzzeeytfj6Pghw0ZokigE9qqJ5mjwZXlgIz1MhwYls3mmhF5uuPmmwbmkJr6RrrgrGlH2PdX54Gvfe3r6n35H/5Iu3fvoopozEV0mIUWImp3cuqjwSCMytDhfWPtuo1KryR6e8ctuO1Wuv3ue2jBLTer6+fNnUtDGhrUvgd7e6kMUpnluk/zn4ym9t0wq0zTUIJ8ybLlQMXcz+dga9nTT1MmZ1M4VIYCL1Dbgf3rUPwTguEgH0lRXtrU+34HBWsjKGxdYXlfy1467YzptHH9Orrw4jm0f99e2rF9G0lMVNlclnxwnimcX7lMRtbXNQgu5BLu6Ydzrvh8D3d/99R/0Ja33mQooatGHGiZKaDG1agJUV1dLW1kJ1IziHa//XbxAaBnGGICYgWGj4sMQDMBFr8lvhc0zX3GQ0Ffj4zei4Youru7acLECdR83EiaPnHiX7TnM51KPPfSajSsjNL0PLHhuTreVx1EQ9q7Z4+srq6hc88+l3o72tVczJIZRcr3FRjTLFHYiV8/shQDVkDV1PkzL/Bu/tlPOaP3cqFmuZtDcvCslYfM+VyP1+9/eDHGTIumnXUmhcIheuj+30g2CFw9G3BYnockOX7UKDTEJPX2JoSnDgIpAg30SKQs/H12CJSq9BM78NbmzVRXN4j10WIU7hyer8+aNl1gZqBt27bx/KBY8XNxYPGjj6E/DKMtWzajNgWmOb0mlUx1sgMs0mDQRGj9dWuhbU6bdLowTEtD9F0Yexrw/DKGmwju7cOgoyGLcvy48fLV116lqqqqn0BC38P9him2LByqR3du51ln3rxr5BNPPEmXzvrB/w9Ci3+/jNa//hqdccq3Ido6JUMG2ucJTz1UdWbKZLJrAYlro9FK/k7WNzW6F18wC5RqvILufXcun+sbPXYsVGrI4+uBQIhisdhTyng+hmEVinc0vkcP9hyk7q4uOWnSJNq6bSsteWz535aBp1Y8S/tbDtAgzMezvncO3XLnAqhVS2KdjGi/XprpqXQEpOZcdOlun8///dqGujVrXnqZoDi543LTwiz9Z5p2ztmUSMTnFOzCYq6Pj1hR2gPSYSygtgPspN12w3zvxfUb8NstNHzYcDp7yuSjc2DDG+vp+dVr1FEJDKZW0GHtoDrePJZMJQ8NHJ0cftxQGvA5K9ykALPt+PwBMsRH4wbexyPKDQPqdmDo//D0QkkUQDISiQjoMOJsN6BnKFpFDd2Egj+qRvbMzlbu60VjmBV0A9RpTe1L9q0snryJD7t7fwokDRxocXQLTmEsdhhLh50RUfFI3FOnuVJ+bFYqnhtxsUPsyWAg8HXU0CYuenWUfuSZzKfXQE04QKZpCRjNkYxWVVftgQhbqSJfmgfkYQMIlQYcPm7kGZk/q1M6PjlQGRHqmFwNLSWROBB4SUdoHo52Kp15M1ZRsQPjbsTv8zMSxFE7cFJDFWWyGb0PlAha/BUmqKGydLL2SbJKlpziKJmmsQuN7krMBo2R8nIRCgZFNBrVAL8Rfp/vp5DYvaL/pO7jmxwGSQ4OKHVMMp26MYFZJZ3J6EfPQjzAuK70+EjRdZL9EPn4UcqH+kedTFB5WVnDgf0HRtp24WFE8QBkOEtxjqgEq+7O5fL3PL9qRUVZOPwtVrTy8OMZkqVEftQxlFAf1w3X0mdwQBBSJwxg3zJNlyWBKNWEppZWWsXv2PgRzUMFZoc2rhneG0ZpwLPAtCPgAM/YfEHwAJPP5ddHy8vU/v34VvsqqJUwT/3faZEiHDVx1GrUxKqMVTi8iWmZ8zL53C8QgThC4GJ7ic2k6mjFvMcqYhWdGGI40uLBRQvl6g2bvM0bN1AdZotV//kHmj5zFu1rbZHNaIYzIDeeW/EMxFyeamtrRDKVrsAeabYZ7KVyUTzW9ATG1Qq/32rjmmBuOOo+sGbNq7QJ3MsOYDChNHhcllhDo2JBCnV8VMxqLBKh7h7lgNI8Q5uH0sUzLzhi36XLltHOne8oag4E/MTZSGJIEqVDYs6qVLBRDigi8GN6Uw4AQddfc/XRQWivm1dUyLIZmkSwcAOWNV6gUc1V7y6UJC9PQ7ELxjQb1jB4yCcaz6/LZs+m4SOGqbNSlAUmv5yAYWoPt7R3/57FJXXMHAxBJRD//jrWXv8H1/41XLtWKx0AAAAASUVORK5CYII=";
+
+
+/// Card designs, picked with `layout=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// Activity heatmap, total and top languages
+    #[default]
+    Full,
+    /// One-line badge with the total
+    Badge,
+    /// Languages as a donut chart
+    Donut,
+    /// Top projects as bars
+    Projects,
+    /// Time per week
+    Weekly,
+    /// Current and longest daily streaks
+    Streak,
+    /// Time per hour of the day
+    Clock,
+    /// Progress towards goals, and their streaks
+    Goals,
+}
+
+impl Layout {
+    /// Whether the card needs hourly activity besides the summary.
+    pub fn needs_hourly(self) -> bool {
+        matches!(self, Layout::Clock)
+    }
+
+    /// Whether the card shows the user's goals.
+    pub fn needs_goals(self) -> bool {
+        matches!(self, Layout::Goals)
+    }
+}
+
+/// What a card is drawn from.
+pub struct CardData<'a> {
+    pub summary: &'a Summary,
+    /// Only fetched for layouts that [need it](Layout::needs_hourly).
+    pub hourly: &'a [HourlyActivity],
+    /// Only fetched for layouts that [need them](Layout::needs_goals).
+    pub goals: &'a [GoalProgress],
+}
+
+pub fn render_svg(data: &CardData, layout: Layout, theme: &Theme) -> String {
+    let card = match layout {
+        Layout::Full => layouts::full(data.summary, theme),
+        Layout::Badge => layouts::badge(data.summary, theme),
+        Layout::Donut => layouts::donut(data.summary, theme),
+        Layout::Projects => layouts::projects(data.summary, theme),
+        Layout::Weekly => layouts::weekly(data.summary, theme),
+        Layout::Streak => layouts::streak(data.summary, theme),
+        Layout::Clock => layouts::clock(data.summary, data.hourly, theme),
+        Layout::Goals => layouts::goals(data.goals, theme),
+    };
+    card.render()
+}
+
+/// What a badge shows, from the last segment of its URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// "coded 12h 30m this week"
+    Total,
+    /// Time on the last day of the range
+    Today,
+    /// Time per day over the range
+    Average,
+    /// The top language and its share, e.g. "Rust 64%"
+    Language,
+    /// The top project and its time
+    Project,
+    /// Days in a row with activity
+    Streak,
+}
+
+impl Metric {
+    pub const NAMES: [&str; 6] = ["total", "today", "average", "language", "project", "streak"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "total" => Some(Self::Total),
+            "today" => Some(Self::Today),
+            "average" => Some(Self::Average),
+            "language" => Some(Self::Language),
+            "project" => Some(Self::Project),
+            "streak" => Some(Self::Streak),
+            _ => None,
+        }
+    }
+}
+
+/// A shields.io-style badge for one metric; `label` replaces the left-hand
+/// text.
+pub fn render_badge(summary: &Summary, metric: Metric, label: Option<&str>, theme: &Theme) -> String {
+    let days = all_days(summary);
+    let (default_label, value) = match metric {
+        Metric::Total => (
+            "coded".to_string(),
+            format!("{} {}", format_duration(summary.total_seconds), period(summary)),
+        ),
+        Metric::Today => {
+            let today = days.last().map_or(0.0, |(_, secs)| *secs);
+            ("today".to_string(), format_duration(today))
+        }
+        Metric::Average => {
+            let average = summary.total_seconds / days.len().max(1) as f64;
+            ("daily average".to_string(), format_duration(average))
+        }
+        Metric::Language => match top_languages(summary, 1).pop() {
+            Some(lang) => (lang.name, format!("{}%", lang.percent.round() as u32)),
+            None => ("language".to_string(), "none yet".to_string()),
+        },
+        Metric::Project => match summary.projects.first() {
+            Some(project) => (project.name.clone(), format_duration(project.total_seconds)),
+            None => ("project".to_string(), "none yet".to_string()),
+        },
+        Metric::Streak => {
+            let (current, _, _) = layouts::streaks(&days);
+            let unit = if current.days == 1 { "day" } else { "days" };
+            ("streak".to_string(), format!("{} {unit}", current.days))
+        }
+    };
+    let label = truncate(label.unwrap_or(&default_label), 32);
+    layouts::shield(&label, &value, theme).render()
+}
+
+const FONT: &str = "'Segoe UI',Ubuntu,Roboto,sans-serif";
+const PAD_X: i32 = 25;
+const HEADER_Y: i32 = 18;
+const LOGO_SIZE: i32 = 28;
+
+/// The TimeForged logo as an inline PNG.
+fn logo(x: i32, y: i32, size: i32) -> El {
+    let href = format!("data:image/png;base64,{LOGO_PNG_B64}");
+    El::new("image")
+        .attr("x", x)
+        .attr("y", y)
+        .attr("width", size)
+        .attr("height", size)
+        .attr("xlink:href", &href)
+        .attr("href", href)
+}
+
+fn text(x: impl std::fmt::Display, y: impl std::fmt::Display, size: u32, fill: &str) -> El {
+    El::new("text")
+        .attr("x", x)
+        .attr("y", y)
+        .attr("fill", fill)
+        .attr("font-family", FONT)
+        .attr("font-size", size)
+}
+
+fn tspan(fill: &str) -> El {
+    El::new("tspan").attr("fill", fill)
+}
+
+/// The card's root element and its rounded background.
+fn card(width: i32, height: i32, theme: &Theme) -> El {
+    El::new("svg")
+        .attr("xmlns", "http://www.w3.org/2000/svg")
+        .attr("xmlns:xlink", "http://www.w3.org/1999/xlink")
+        .attr("width", width)
+        .attr("height", height)
+        .attr("viewBox", format!("0 0 {width} {height}"))
+        .attr("fill", "none")
+        .child(
+            El::new("rect")
+                .attr("x", 0.5)
+                .attr("y", 0.5)
+                .attr("width", width - 1)
+                .attr("height", height - 1)
+                .attr("rx", 6)
+                .attr("fill", &theme.bg)
+                .attr("stroke", &theme.border),
+        )
+}
+
+/// Logo and title on the left, `badge` on the right.
+fn header(width: i32, badge: &str, theme: &Theme) -> [El; 3] {
+    [
+        logo(PAD_X, HEADER_Y - 8, LOGO_SIZE),
+        text(PAD_X + LOGO_SIZE + 6, HEADER_Y + 9, 14, &theme.accent)
+            .attr("font-weight", 700)
+            .text("TimeForged"),
+        text(width - PAD_X - 8, HEADER_Y + 8, 11, &theme.accent)
+            .attr("text-anchor", "end")
+            .attr("font-weight", 600)
+            .text(badge),
+    ]
+}
+
+/// "this week", "this month" or "this year", from the summary's range.
+fn period(summary: &Summary) -> &'static str {
+    let days = (summary.to.date_naive() - summary.from.date_naive()).num_days().max(1);
+    if days <= 7 {
+        "this week"
+    } else if days <= 31 {
+        "this month"
+    } else {
+        "this year"
+    }
+}
+
+/// The current and longest streaks of the summary's range, and its active
+/// days (a minute or more).
+pub fn streaks(summary: &Summary) -> (Streak, Streak, usize) {
+    layouts::streaks(&all_days(summary))
+}
+
+/// Every day of the summary's range with its total, including empty days.
+fn all_days(summary: &Summary) -> Vec<(NaiveDate, f64)> {
+    let day_map: HashMap<NaiveDate, f64> = summary
+        .days
+        .iter()
+        .map(|d| (d.date, d.total_seconds))
+        .collect();
+    let end_date = summary.to.date_naive();
+    let mut days = Vec::new();
+    let mut d = summary.from.date_naive();
+    while d <= end_date {
+        days.push((d, day_map.get(&d).copied().unwrap_or(0.0)));
+        d += Duration::days(1);
+    }
+    days
+}
+
+/// Up to `n` programming languages, with percentages of their own total
+/// (JSON, Markdown, YAML and the like are left out).
+fn top_languages(summary: &Summary, n: usize) -> Vec<CategorySummary> {
+    let prog_langs: Vec<_> = summary
+        .languages
+        .iter()
+        .filter(|l| is_programming_lang(&l.name))
+        .collect();
+    let prog_total: f64 = prog_langs.iter().map(|l| l.total_seconds).sum();
+    prog_langs
+        .iter()
+        .take(n)
+        .map(|l| CategorySummary {
+            name: l.name.clone(),
+            total_seconds: l.total_seconds,
+            percent: if prog_total > 0.0 {
+                (l.total_seconds / prog_total) * 100.0
+            } else {
+                0.0
+            },
+        })
+        .collect()
+}
+
+/// The heatmap shade for `secs` out of `max_secs`.
+fn heat_color(theme: &Theme, secs: f64, max_secs: f64) -> &str {
+    if secs < 60.0 || max_secs <= 0.0 {
+        return &theme.heat_empty;
+    }
+    let ratio = secs / max_secs;
+    if ratio < 0.15 {
+        &theme.heat[0]
+    } else if ratio < 0.35 {
+        &theme.heat[1]
+    } else if ratio < 0.65 {
+        &theme.heat[2]
+    } else {
+        &theme.heat[3]
+    }
+}
+
+/// `name` cut to `max` characters.
+fn truncate(name: &str, max: usize) -> String {
+    if name.chars().count() <= max {
+        name.to_string()
+    } else {
+        let cut: String = name.chars().take(max - 1).collect();
+        format!("{cut}…")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use proptest::prelude::*;
+    use timeforged_core::api::{GoalPeriodResult, GoalStreak};
+    use timeforged_core::models::{DaySummary, Goal, GoalPeriod};
+    use uuid::Uuid;
+
+    const SNAPSHOT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/cards");
+
+    fn category(name: &str, total_seconds: f64, total: f64) -> CategorySummary {
+        CategorySummary {
+            name: name.into(),
+            total_seconds,
+            percent: total_seconds / total * 100.0,
+        }
+    }
+
+    /// 91 days with a streak still going, and a project name that needs
+    /// escaping.
+    fn fixture() -> (Summary, Vec<HourlyActivity>) {
+        let from = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let to = from + Duration::days(90);
+        let days: Vec<DaySummary> = (0..=90)
+            .filter(|i| i % 7 != 3 && (i % 11 != 5 || *i > 80))
+            .map(|i| DaySummary {
+                date: from.date_naive() + Duration::days(i),
+                total_seconds: ((i * 37) % 180 + 20) as f64 * 60.0,
+            })
+            .collect();
+        let total: f64 = days.iter().map(|d| d.total_seconds).sum();
+        let projects = vec![
+            category("timeforged", total * 0.52, total),
+            category("tools & <scripts>", total * 0.21, total),
+            category("dotfiles", total * 0.14, total),
+            category("blog", total * 0.09, total),
+            category("a-very-long-project-name-that-does-not-fit-anywhere", total * 0.04, total),
+        ];
+        let languages = vec![
+            category("Rust", total * 0.48, total),
+            category("TypeScript", total * 0.2, total),
+            category("Markdown", total * 0.12, total),
+            category("Python", total * 0.1, total),
+            category("Shell", total * 0.06, total),
+            category("Go", total * 0.04, total),
+        ];
+        let hourly = (7..24)
+            .map(|hour| HourlyActivity {
+                hour,
+                total_seconds: total * ((hour as f64 - 14.0).abs() + 1.0).recip() / 4.0,
+                event_count: 10,
+            })
+            .collect();
+        let summary = Summary {
+            total_seconds: total,
+            from,
+            to,
+            projects,
+            languages,
+            days,
+        };
+        (summary, hourly)
+    }
+
+    /// A daily goal under way and a weekly one already met.
+    fn goals_fixture() -> Vec<GoalProgress> {
+        let goal = |period, target_seconds, project: Option<&str>, language: Option<&str>| Goal {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            period,
+            target_seconds,
+            project: project.map(String::from),
+            language: language.map(String::from),
+            rest_days: Vec::new(),
+            created_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+        };
+        let progress = |goal: Goal, seconds: f64, current: u32, longest: u32| GoalProgress {
+            label: goal.label(),
+            current: GoalPeriodResult {
+                start: NaiveDate::from_ymd_opt(2026, 3, 30).unwrap(),
+                seconds,
+                met: seconds >= goal.target_seconds as f64,
+                rest: false,
+            },
+            percent: seconds / goal.target_seconds as f64 * 100.0,
+            streak: GoalStreak { current, longest },
+            history: Vec::new(),
+            goal,
+        };
+        vec![
+            progress(goal(GoalPeriod::Day, 7200, Some("timeforged"), None), 4700.0, 4, 12),
+            progress(goal(GoalPeriod::Week, 36000, None, Some("Rust")), 37400.0, 1, 3),
+        ]
+    }
+
+    /// Each layout is rendered from the same fixture and compared with the
+    /// committed SVG. Run with `UPDATE_CARDS=1` to rewrite them.
+    #[test]
+    fn cards_match_snapshots() {
+        let (summary, hourly) = fixture();
+        let goals = goals_fixture();
+        let data = CardData {
+            summary: &summary,
+            hourly: &hourly,
+            goals: &goals,
+        };
+        let custom = ThemeColors {
+            bg: Some("#282a36".into()),
+            text: Some("f8f8f2".into()),
+            accent: Some("#bd93f9".into()),
+            ..Default::default()
+        };
+        let custom = Theme::dark().with(&custom).unwrap();
+        let cases = [
+            ("full", Layout::Full, Theme::dark()),
+            ("full-light", Layout::Full, Theme::light()),
+            ("badge", Layout::Badge, Theme::dark()),
+            ("badge-custom", Layout::Badge, custom),
+            ("donut", Layout::Donut, Theme::dark()),
+            ("projects", Layout::Projects, Theme::dark()),
+            ("weekly", Layout::Weekly, Theme::dark()),
+            ("streak", Layout::Streak, Theme::dark()),
+            ("clock", Layout::Clock, Theme::dark()),
+            ("goals", Layout::Goals, Theme::dark()),
+        ];
+
+        let mut cards: Vec<(String, String)> = cases
+            .into_iter()
+            .map(|(name, layout, theme)| (name.into(), render_svg(&data, layout, &theme)))
+            .collect();
+        for name in Metric::NAMES {
+            let metric = Metric::parse(name).unwrap();
+            let svg = render_badge(&summary, metric, None, &Theme::dark());
+            cards.push((format!("badge-{name}"), svg));
+        }
+        let svg = render_badge(&summary, Metric::Total, Some("time <spent>"), &Theme::light());
+        cards.push(("badge-label".into(), svg));
+
+        let update = std::env::var_os("UPDATE_CARDS").is_some();
+        let mut stale = Vec::new();
+        for (name, svg) in cards {
+            assert!(!svg.contains("<scripts>"), "{name}: unescaped project name");
+            let path = format!("{SNAPSHOT_DIR}/{name}.svg");
+            if update {
+                std::fs::create_dir_all(SNAPSHOT_DIR).unwrap();
+                std::fs::write(&path, &svg).unwrap();
+            } else if std::fs::read_to_string(&path).unwrap_or_default() != svg {
+                stale.push(name);
+            }
+        }
+        assert!(
+            stale.is_empty(),
+            "cards changed: {stale:?}; if intended, regenerate with \
+             `UPDATE_CARDS=1 cargo test -p timeforged cards_match_snapshots`"
+        );
+    }
+
+    #[test]
+    fn empty_summaries_render() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let summary = Summary {
+            total_seconds: 0.0,
+            from: now - Duration::days(7),
+            to: now,
+            projects: Vec::new(),
+            languages: Vec::new(),
+            days: Vec::new(),
+        };
+        let data = CardData {
+            summary: &summary,
+            hourly: &[],
+            goals: &[],
+        };
+        for layout in ALL_LAYOUTS {
+            let svg = render_svg(&data, layout, &Theme::light());
+            assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"), "{layout:?}");
+            // The logo's base64 may spell anything; only the drawing counts
+            assert!(!svg.replace(LOGO_PNG_B64, "").contains("NaN"), "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn streaks_count_back_from_yesterday() {
+        let day = |i: i64| NaiveDate::from_ymd_opt(2026, 3, 1).unwrap() + Duration::days(i);
+        let secs = [600.0, 600.0, 600.0, 0.0, 600.0, 30.0, 600.0, 600.0, 0.0];
+        let mut days: Vec<_> = secs.iter().enumerate().map(|(i, s)| (day(i as i64), *s)).collect();
+
+        let (current, longest, active) = layouts::streaks(&days);
+        assert_eq!((current.days, current.from, current.to), (2, Some(day(6)), Some(day(7))));
+        assert_eq!((longest.days, longest.from, longest.to), (3, Some(day(0)), Some(day(2))));
+        assert_eq!(active, 6);
+
+        // A second idle day ends the streak
+        days.push((day(9), 0.0));
+        assert_eq!(layouts::streaks(&days).0.days, 0);
+        assert_eq!(layouts::streaks(&[]).0.days, 0);
+    }
+
+    const ALL_LAYOUTS: [Layout; 8] = [
+        Layout::Full,
+        Layout::Badge,
+        Layout::Donut,
+        Layout::Projects,
+        Layout::Weekly,
+        Layout::Streak,
+        Layout::Clock,
+        Layout::Goals,
+    ];
+
+    /// Names as a hostile or careless client might send them.
+    fn name() -> impl Strategy<Value = String> {
+        prop_oneof![
+            any::<String>(),
+            "[<>&\"' a-z\\x00-\\x1f]{0,80}",
+            Just("</text><script>alert(1)</script>".to_string()),
+            Just("]]><!--".to_string()),
+            Just("&amp;&#0;&lt;".to_string()),
+        ]
+    }
+
+    fn categories() -> impl Strategy<Value = Vec<CategorySummary>> {
+        prop::collection::vec((name(), 0.0..1e6_f64), 0..8).prop_map(|items| {
+            let total: f64 = items.iter().map(|(_, s)| s).sum::<f64>().max(1.0);
+            items
+                .into_iter()
+                .map(|(name, secs)| category(&name, secs, total))
+                .collect()
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        /// Every layout is well-formed XML made only of the elements the
+        /// layouts draw, whatever the projects and languages are called,
+        /// and no name runs on past the card.
+        #[test]
+        fn cards_are_well_formed(
+            projects in categories(),
+            languages in categories(),
+            days in 1..400_i64,
+        ) {
+            let to = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+            let summary = Summary {
+                total_seconds: projects.iter().map(|p| p.total_seconds).sum(),
+                from: to - Duration::days(days),
+                to,
+                projects,
+                languages,
+                days: Vec::new(),
+            };
+            let data = CardData {
+                summary: &summary,
+                hourly: &[],
+                goals: &[],
+            };
+            let allowed = [
+                "svg", "rect", "image", "text", "tspan", "line", "circle", "g", "clipPath",
+                "title",
+            ];
+            for layout in ALL_LAYOUTS {
+                let svg = render_svg(&data, layout, &Theme::dark());
+                let doc = roxmltree::Document::parse(&svg)
+                    .map_err(|e| TestCaseError::fail(format!("{layout:?}: {e}")))?;
+                for node in doc.descendants() {
+                    if node.is_element() {
+                        let tag = node.tag_name().name();
+                        prop_assert!(allowed.contains(&tag), "{:?}: <{}>", layout, tag);
+                    } else if node.is_text() {
+                        let len = node.text().unwrap_or("").chars().count();
+                        prop_assert!(len <= 64, "{:?}: {} characters", layout, len);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::{Arc, OnceLock};
+
+use resvg::{tiny_skia, usvg};
+
+/// PNGs are drawn at twice the SVG's size so they stay sharp on high-DPI
+/// screens; `<img width=…>` scales them back.
+const SCALE: f32 = 2.0;
+
+/// Fonts a card's `sans-serif` may fall back to, in order of preference.
+const SANS_SERIF: &[&str] = &["Segoe UI", "Ubuntu", "Roboto", "DejaVu Sans", "Liberation Sans"];
+
+/// System fonts, loaded on the first PNG and shared after that.
+fn fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut db = usvg::fontdb::Database::new();
+            db.load_system_fonts();
+            let installed = |name: &str| {
+                db.faces()
+                    .any(|face| face.families.iter().any(|(family, _)| family == name))
+            };
+            if let Some(family) = SANS_SERIF.iter().find(|name| installed(name)) {
+                db.set_sans_serif_family(*family);
+            } else {
+                tracing::warn!("cards: no sans-serif font found, PNG cards will have no text");
+            }
+            Arc::new(db)
+        })
+        .clone()
+}
+
+/// Rasterize a card. CPU-bound; call it off the async runtime.
+pub fn png(svg: &str) -> Result<Vec<u8>, String> {
+    let options = usvg::Options {
+        fontdb: fonts(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| e.to_string())?;
+    let size = tree.size().to_int_size().scale_by(SCALE).ok_or("card is too large")?;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("card is too large")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(SCALE, SCALE),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_rasterize_at_twice_their_size() {
+        let svg = "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"20\">\
+                   <rect width=\"40\" height=\"20\" fill=\"#ef973e\"/></svg>";
+        let image = png(svg).unwrap();
+        assert_eq!(&image[..8], b"\x89PNG\r\n\x1a\n");
+        // IHDR: width and height, big-endian, right after the chunk header
+        assert_eq!(&image[16..24], &[0, 0, 0, 80, 0, 0, 0, 40]);
+
+        assert!(png("<svg").is_err());
+    }
+}
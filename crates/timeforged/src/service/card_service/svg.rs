@@ -0,0 +1,173 @@
+use std::fmt::{Display, Write};
+
+use timeforged_core::util::escape_markup;
+
+/// An SVG element: attributes in insertion order, then children. Values
+/// and text are escaped when the tree is written, so project and language
+/// names from events can't break the markup.
+#[derive(Debug, Clone)]
+pub struct El {
+    name: &'static str,
+    attrs: Vec<(&'static str, String)>,
+    children: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    El(El),
+    Text(String),
+}
+
+impl El {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            attrs: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn attr(mut self, name: &'static str, value: impl Display) -> Self {
+        self.attrs.push((name, value.to_string()));
+        self
+    }
+
+    pub fn child(mut self, child: El) -> Self {
+        self.push(child);
+        self
+    }
+
+    pub fn children(mut self, children: impl IntoIterator<Item = El>) -> Self {
+        self.children.extend(children.into_iter().map(Node::El));
+        self
+    }
+
+    pub fn text(mut self, text: impl Display) -> Self {
+        self.children.push(Node::Text(text.to_string()));
+        self
+    }
+
+    pub fn push(&mut self, child: El) {
+        self.children.push(Node::El(child));
+    }
+
+    /// The element as a document, one element per line. Elements holding
+    /// text are kept on one line, since whitespace inside them would show.
+    pub fn render(&self) -> String {
+        let mut out = String::with_capacity(16384);
+        self.write(&mut out, 0);
+        out
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&indent);
+        self.write_inline_open(out);
+        if self.children.is_empty() {
+            out.push_str("/>\n");
+        } else if self.children.iter().any(|c| matches!(c, Node::Text(_))) {
+            out.push('>');
+            self.write_inline_children(out);
+            let _ = writeln!(out, "</{}>", self.name);
+        } else {
+            out.push_str(">\n");
+            for child in &self.children {
+                if let Node::El(el) = child {
+                    el.write(out, depth + 1);
+                }
+            }
+            let _ = writeln!(out, "{indent}</{}>", self.name);
+        }
+    }
+
+    fn write_inline_open(&self, out: &mut String) {
+        let _ = write!(out, "<{}", self.name);
+        for (name, value) in &self.attrs {
+            let _ = write!(out, " {name}=\"{}\"", escape_markup(value, true));
+        }
+    }
+
+    fn write_inline(&self, out: &mut String) {
+        self.write_inline_open(out);
+        if self.children.is_empty() {
+            out.push_str("/>");
+        } else {
+            out.push('>');
+            self.write_inline_children(out);
+            let _ = write!(out, "</{}>", self.name);
+        }
+    }
+
+    fn write_inline_children(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                Node::El(el) => el.write_inline(out),
+                Node::Text(text) => out.push_str(&escape_markup(text, false)),
+            }
+        }
+    }
+}
+
+/// A coordinate with at most one decimal, and none when it is whole.
+pub fn px(value: f64) -> String {
+    let rounded = format!("{value:.1}");
+    match rounded.strip_suffix(".0") {
+        Some("-0") => "0".into(),
+        Some(whole) => whole.into(),
+        None => rounded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use timeforged_core::util::allowed_in_xml;
+
+    #[test]
+    fn names_are_escaped_and_text_stays_inline() {
+        let svg = El::new("svg")
+            .attr("width", 10)
+            .child(El::new("rect").attr("fill", "#fff"))
+            .child(
+                El::new("text")
+                    .attr("data-name", "say \"hi\"")
+                    .text("tools & <scripts> ")
+                    .child(El::new("tspan").text("42%")),
+            )
+            .render();
+        assert_eq!(
+            svg,
+            "<svg width=\"10\">\n  <rect fill=\"#fff\"/>\n  \
+             <text data-name=\"say &quot;hi&quot;\">tools &amp; &lt;scripts&gt; \
+             <tspan>42%</tspan></text>\n</svg>\n"
+        );
+    }
+
+    #[test]
+    fn coordinates_are_short() {
+        assert_eq!(px(12.0), "12");
+        assert_eq!(px(12.34), "12.3");
+        assert_eq!(px(-0.01), "0");
+        assert_eq!(px(0.1 + 0.2), "0.3");
+    }
+
+    proptest! {
+        /// Whatever a client names a project or language, the text and the
+        /// attribute parse back as what was written, minus the characters
+        /// XML can't hold.
+        #[test]
+        fn any_string_round_trips(value in any::<String>()) {
+            let svg = El::new("svg")
+                .child(El::new("text").attr("data-name", &value).text(&value))
+                .render();
+            let doc = roxmltree::Document::parse(&svg).unwrap();
+            let text = doc.descendants().find(|n| n.has_tag_name("text")).unwrap();
+            let expected: String = value.chars().filter(|c| allowed_in_xml(*c)).collect();
+            prop_assert_eq!(text.attribute("data-name"), Some(expected.as_str()));
+            // Parsers normalize CRLF and lone CR to LF in text content
+            let text_expected = expected.replace("\r\n", "\n").replace('\r', "\n");
+            prop_assert_eq!(text.text().unwrap_or(""), text_expected.as_str());
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Card colors, as `#rrggbb` (or `#rgb`, with optional alpha) strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub bg: String,
+    pub border: String,
+    pub text: String,
+    pub muted: String,
+    pub accent: String,
+    pub bar_bg: String,
+    pub separator: String,
+    pub heat_empty: String,
+    pub heat: [String; 4],
+}
+
+/// Colors a theme file or the card's query can set; the rest come from the
+/// base theme. Given an accent but no heat levels, the heatmap is shaded
+/// from the background to the accent.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeColors {
+    pub bg: Option<String>,
+    pub border: Option<String>,
+    pub text: Option<String>,
+    pub muted: Option<String>,
+    pub accent: Option<String>,
+    pub bar_bg: Option<String>,
+    pub separator: Option<String>,
+    pub heat_empty: Option<String>,
+    pub heat: Option<Vec<String>>,
+}
+
+fn strings<const N: usize>(colors: [&str; N]) -> [String; N] {
+    colors.map(String::from)
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            bg: "#0d1117".into(),
+            border: "#30363d".into(),
+            text: "#c9d1d9".into(),
+            muted: "#6e7681".into(),
+            accent: "#ef973e".into(),
+            bar_bg: "#161b22".into(),
+            separator: "#21262d".into(),
+            heat_empty: "#161b22".into(),
+            heat: strings(["#4a2a0a", "#7c4a15", "#c46d1a", "#ef973e"]),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            bg: "#ffffff".into(),
+            border: "#d0d7de".into(),
+            text: "#1f2328".into(),
+            muted: "#656d76".into(),
+            accent: "#c46d1a".into(),
+            bar_bg: "#eaeef2".into(),
+            separator: "#d8dee4".into(),
+            heat_empty: "#ebedf0".into(),
+            heat: strings(["#fde5c8", "#f5c78e", "#e8a04e", "#d97b16"]),
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    /// A built-in theme, or the theme file `<name>.toml` in `dir`: the
+    /// colors of [`ThemeColors`], and `base`, the built-in theme it starts
+    /// from (`dark` by default).
+    pub async fn named(name: &str, dir: &Path) -> Result<Self, String> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+        let valid = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        let unknown = || format!("unknown theme '{name}'");
+        if !valid {
+            return Err(unknown());
+        }
+        let content = tokio::fs::read_to_string(dir.join(format!("{name}.toml")))
+            .await
+            .map_err(|_| unknown())?;
+
+        let invalid = |e: String| format!("theme '{name}': {e}");
+        let mut file: toml::Table =
+            toml::from_str(&content).map_err(|e| invalid(e.message().into()))?;
+        let base = match file.remove("base") {
+            Some(toml::Value::String(base)) => base,
+            Some(_) => return Err(invalid("base must be a theme name".into())),
+            None => "dark".into(),
+        };
+        let theme = Self::builtin(&base)
+            .ok_or_else(|| invalid(format!("unknown base theme '{base}'")))?;
+        let colors: ThemeColors = toml::Value::Table(file)
+            .try_into()
+            .map_err(|e: toml::de::Error| invalid(e.message().into()))?;
+        theme.with(&colors).map_err(invalid)
+    }
+
+    /// This theme with some colors replaced.
+    pub fn with(mut self, colors: &ThemeColors) -> Result<Self, String> {
+        for (slot, value) in [
+            (&mut self.bg, &colors.bg),
+            (&mut self.border, &colors.border),
+            (&mut self.text, &colors.text),
+            (&mut self.muted, &colors.muted),
+            (&mut self.accent, &colors.accent),
+            (&mut self.bar_bg, &colors.bar_bg),
+            (&mut self.separator, &colors.separator),
+            (&mut self.heat_empty, &colors.heat_empty),
+        ] {
+            if let Some(value) = value {
+                *slot = parse_color(value)?;
+            }
+        }
+
+        match &colors.heat {
+            Some(levels) => {
+                let levels: Vec<String> =
+                    levels.iter().map(|c| parse_color(c)).collect::<Result<_, _>>()?;
+                self.heat = levels
+                    .try_into()
+                    .map_err(|_| "heat takes exactly 4 colors".to_string())?;
+            }
+            None if colors.accent.is_some() => {
+                self.heat = [0.25, 0.45, 0.7, 1.0].map(|t| mix(&self.bg, &self.accent, t));
+            }
+            None => {}
+        }
+        Ok(self)
+    }
+}
+
+/// `#`-less or not, 3, 4, 6 or 8 hex digits; lowercased, with the `#`.
+pub fn parse_color(value: &str) -> Result<String, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(format!("#{}", hex.to_ascii_lowercase()))
+    } else {
+        Err(format!("invalid color '{value}', expected hex like ef973e"))
+    }
+}
+
+fn rgb(color: &str) -> [u8; 3] {
+    let hex = color.trim_start_matches('#');
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap_or(0);
+    if hex.len() <= 4 {
+        [0, 1, 2].map(|i| digit(i) * 17)
+    } else {
+        [0, 2, 4].map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0))
+    }
+}
+
+/// `t` of the way from `from` to `to`.
+fn mix(from: &str, to: &str, t: f64) -> String {
+    let (a, b) = (rgb(from), rgb(to));
+    let c: Vec<String> = (0..3)
+        .map(|i| {
+            let v = a[i] as f64 + (b[i] as f64 - a[i] as f64) * t;
+            format!("{:02x}", v.round() as u8)
+        })
+        .collect();
+    format!("#{}", c.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_checked() {
+        assert_eq!(parse_color("EF973E").unwrap(), "#ef973e");
+        assert_eq!(parse_color("#fff").unwrap(), "#fff");
+        assert!(parse_color("red").is_err());
+        assert!(parse_color("#12345").is_err());
+        assert_eq!(mix("#000000", "#fff", 0.5), "#808080");
+    }
+
+    #[test]
+    fn accent_shades_the_heatmap() {
+        let colors = ThemeColors {
+            bg: Some("000000".into()),
+            accent: Some("ff0000".into()),
+            ..Default::default()
+        };
+        let theme = Theme::dark().with(&colors).unwrap();
+        assert_eq!(theme.heat[3], "#ff0000");
+        assert_eq!(theme.heat[0], "#400000");
+        assert_eq!(theme.text, Theme::dark().text);
+
+        let bad = ThemeColors {
+            heat: Some(vec!["fff".into()]),
+            ..Default::default()
+        };
+        assert!(Theme::dark().with(&bad).is_err());
+    }
+
+    #[tokio::test]
+    async fn themes_load_from_files() {
+        let dir = std::env::temp_dir().join(format!("tf-themes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("dracula.toml"),
+            "base = \"dark\"\nbg = \"#282a36\"\naccent = \"#bd93f9\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.toml"), "bg = \"#zzz\"\n").unwrap();
+
+        let theme = Theme::named("dracula", &dir).await.unwrap();
+        assert_eq!((theme.bg.as_str(), theme.accent.as_str()), ("#282a36", "#bd93f9"));
+        assert_eq!(Theme::named("light", &dir).await.unwrap(), Theme::light());
+        assert!(Theme::named("broken", &dir).await.unwrap_err().contains("invalid color"));
+        let escape = Theme::named("../dracula", &dir).await.unwrap_err();
+        assert_eq!(escape, "unknown theme '../dracula'");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,653 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use timeforged_core::api::{
+    Digest, DigestEntry, DigestGoal, DigestPeriod, DigestSent, DigestSettings,
+};
+use timeforged_core::config::AppConfig;
+use timeforged_core::error::AppError;
+use timeforged_core::models::{CategorySummary, ReportRequest, User, WebhookTrigger};
+use timeforged_core::util::escape_markup;
+
+use crate::service::{goal_service, report_service, webhook_service};
+use crate::smtp::{self, Mail};
+use crate::storage::sqlite;
+
+/// Projects and languages listed.
+const TOP: usize = 5;
+const CHECK_INTERVAL: StdDuration = StdDuration::from_secs(60);
+
+pub async fn settings(pool: &SqlitePool, user_id: Uuid) -> Result<DigestSettings, AppError> {
+    sqlite::get_digest_settings(pool, user_id).await
+}
+
+/// Check and store new settings. The next scheduled digest is the first one
+/// due after now, so changing the schedule never sends one right away.
+pub async fn update_settings(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    user_id: Uuid,
+    mut settings: DigestSettings,
+) -> Result<DigestSettings, AppError> {
+    if settings.hour > 23 {
+        return Err(AppError::Validation("hour must be 0-23 (UTC)".into()));
+    }
+    settings.email = settings.email.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+    if let Some(email) = &settings.email {
+        if !valid_email(email) {
+            return Err(AppError::Validation(format!("invalid email address '{email}'")));
+        }
+        if config.digest.smtp.is_none() {
+            return Err(AppError::Validation(
+                "emailed digests need an SMTP server in the daemon config ([digest.smtp])".into(),
+            ));
+        }
+    }
+
+    let sent_through = send_day(&settings, Utc::now()) - Duration::days(1);
+    sqlite::set_digest_settings(pool, user_id, &settings, sent_through).await?;
+    Ok(settings)
+}
+
+/// Something SMTP will take as a recipient, and nothing that could end a
+/// command or a header.
+fn valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    email.len() <= 254
+        && !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+        && email.chars().all(|c| c.is_ascii_graphic() && !"<>()[],;:\\\"".contains(c))
+}
+
+/// The day the latest digest due by `now` goes out on: the last day at or
+/// after the settings' hour, and on their weekday for weekly digests. A
+/// digest covers the days before it.
+pub fn send_day(settings: &DigestSettings, now: DateTime<Utc>) -> NaiveDate {
+    let mut day = now.date_naive();
+    if now.hour() < settings.hour as u32 {
+        day -= Duration::days(1);
+    }
+    if settings.period == DigestPeriod::Weekly {
+        let back = (day.weekday().num_days_from_monday() + 7
+            - settings.weekday.num_days_from_monday())
+            % 7;
+        day -= Duration::days(back as i64);
+    }
+    day
+}
+
+/// The digest of the day or seven days through `through`, compared with
+/// the period before.
+pub async fn build(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    period: DigestPeriod,
+    through: NaiveDate,
+    idle_timeout: u64,
+) -> Result<Digest, AppError> {
+    let days = period.days();
+    let from = through - Duration::days(days - 1);
+    let range = |from: NaiveDate| ReportRequest {
+        from: Some(from.and_hms_opt(0, 0, 0).unwrap().and_utc()),
+        to: Some((from + Duration::days(days)).and_hms_opt(0, 0, 0).unwrap().and_utc()),
+        project: None,
+        language: None,
+    };
+    let req = range(from);
+    let summary = report_service::get_summary(pool, user_id, &req, idle_timeout).await?;
+    let previous = range(from - Duration::days(days));
+    let previous = report_service::get_summary(pool, user_id, &previous, idle_timeout).await?;
+    let sessions = report_service::get_sessions(pool, user_id, &req, idle_timeout).await?;
+
+    let mut goals = Vec::new();
+    let history = days as usize + 1;
+    for progress in goal_service::list_goals(pool, user_id, history, idle_timeout).await? {
+        let period_days = goal_service::period_days(progress.goal.period);
+        let ended_in_range: Vec<_> = progress
+            .history
+            .iter()
+            .chain([&progress.current])
+            .filter(|p| p.start >= from && p.start + Duration::days(period_days - 1) <= through)
+            .filter(|p| p.met || !p.rest)
+            .collect();
+        goals.push(DigestGoal {
+            label: progress.label,
+            met: ended_in_range.iter().filter(|p| p.met).count(),
+            periods: ended_in_range.len(),
+            streak: progress.streak,
+        });
+    }
+
+    Ok(Digest {
+        period,
+        from,
+        to: through,
+        total_seconds: summary.total_seconds,
+        previous_seconds: previous.total_seconds,
+        // A minute or more, as on cards
+        active_days: summary.days.iter().filter(|d| d.total_seconds >= 60.0).count(),
+        projects: entries(&summary.projects, &previous.projects),
+        languages: entries(&summary.languages, &previous.languages),
+        longest_session: sessions
+            .into_iter()
+            .max_by(|a, b| a.duration_seconds.total_cmp(&b.duration_seconds)),
+        goals,
+    })
+}
+
+/// The digest through yesterday, whatever the schedule: what previews and
+/// digests sent on demand show.
+pub async fn latest(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    period: DigestPeriod,
+    idle_timeout: u64,
+) -> Result<Digest, AppError> {
+    let yesterday = Utc::now().date_naive() - Duration::days(1);
+    build(pool, user_id, period, yesterday, idle_timeout).await
+}
+
+/// The top entries of `current` with a minute or more, and their time in
+/// `previous`.
+fn entries(current: &[CategorySummary], previous: &[CategorySummary]) -> Vec<DigestEntry> {
+    let before: HashMap<&str, f64> =
+        previous.iter().map(|c| (c.name.as_str(), c.total_seconds)).collect();
+    current
+        .iter()
+        .filter(|c| c.total_seconds >= 60.0)
+        .take(TOP)
+        .map(|c| DigestEntry {
+            name: c.name.clone(),
+            seconds: c.total_seconds,
+            previous_seconds: before.get(c.name.as_str()).copied().unwrap_or(0.0),
+        })
+        .collect()
+}
+
+// --- Rendering ---
+
+/// "12h 05m", "45m"
+fn hm(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as u64;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m:02}m"),
+    }
+}
+
+/// "+1h 05m", "-20m", or "new" for time where there was none.
+fn change(seconds: f64, previous: f64) -> String {
+    match (seconds - previous) / 60.0 {
+        _ if previous < 60.0 && seconds >= 60.0 => "new".into(),
+        d if d.abs() < 1.0 => "±0m".into(),
+        d if d > 0.0 => format!("+{}", hm(d * 60.0)),
+        d => format!("-{}", hm(-d * 60.0)),
+    }
+}
+
+/// "Sat, Oct 17, 2026" or "Oct 12 – Oct 18, 2026".
+fn dates(digest: &Digest) -> String {
+    match digest.period {
+        DigestPeriod::Daily => digest.to.format("%a, %b %-d, %Y").to_string(),
+        DigestPeriod::Weekly => format!(
+            "{} – {}",
+            digest.from.format("%b %-d"),
+            digest.to.format("%b %-d, %Y")
+        ),
+    }
+}
+
+fn title(digest: &Digest) -> &'static str {
+    match digest.period {
+        DigestPeriod::Daily => "Daily digest",
+        DigestPeriod::Weekly => "Weekly digest",
+    }
+}
+
+pub fn subject(digest: &Digest) -> String {
+    format!("TimeForged {}: {}", title(digest).to_lowercase(), dates(digest))
+}
+
+/// The lines under the title, as label and text.
+fn overview(digest: &Digest) -> Vec<(&'static str, String)> {
+    let before = match digest.period {
+        DigestPeriod::Daily => "the day before",
+        DigestPeriod::Weekly => "the week before",
+    };
+    let mut total = hm(digest.total_seconds);
+    if digest.previous_seconds >= 60.0 {
+        let percent = (digest.total_seconds / digest.previous_seconds - 1.0) * 100.0;
+        total += &match change(digest.total_seconds, digest.previous_seconds) {
+            same if same == "±0m" => format!(", the same as {before}"),
+            change => format!(", {change} ({percent:+.0}%) on {before}"),
+        };
+    }
+    let mut lines = vec![("Total", total)];
+    if digest.period == DigestPeriod::Weekly {
+        lines.push(("Active days", format!("{} of 7", digest.active_days)));
+    }
+    if let Some(session) = &digest.longest_session {
+        let mut text = hm(session.duration_seconds);
+        if let Some(project) = &session.project {
+            text += &format!(" on {project}");
+        }
+        text += &format!(
+            ", {} {}–{} UTC",
+            session.start.format("%a %b %-d"),
+            session.start.format("%H:%M"),
+            session.end.format("%H:%M")
+        );
+        lines.push(("Longest session", text));
+    }
+    lines
+}
+
+fn goal_line(goal: &DigestGoal, period: DigestPeriod) -> String {
+    let streak = format!("streak {} (best {})", goal.streak.current, goal.streak.longest);
+    match (goal.periods, period) {
+        (0, _) => format!("{}: {streak}", goal.label),
+        (1, DigestPeriod::Daily) => {
+            let met = if goal.met == 1 { "met" } else { "missed" };
+            format!("{}: {met}, {streak}", goal.label)
+        }
+        (n, _) => format!("{}: met {} of {n}, {streak}", goal.label, goal.met),
+    }
+}
+
+/// Backslash the characters that would change how a name renders.
+fn md_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\|*`<>[]#".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub fn markdown(digest: &Digest) -> String {
+    let mut out = format!("# {}\n\n{}\n\n", title(digest), dates(digest));
+    if digest.total_seconds < 60.0 {
+        out += "No coding time recorded.\n";
+    } else {
+        for (label, text) in overview(digest) {
+            out += &format!("- **{label}:** {}\n", md_escape(&text));
+        }
+        for (heading, entries) in [("Projects", &digest.projects), ("Languages", &digest.languages)]
+        {
+            if entries.is_empty() {
+                continue;
+            }
+            out += &format!("\n## {heading}\n\n| Name | Time | Change |\n|---|---:|---:|\n");
+            for e in entries {
+                let change = change(e.seconds, e.previous_seconds);
+                out += &format!("| {} | {} | {change} |\n", md_escape(&e.name), hm(e.seconds));
+            }
+        }
+    }
+    if !digest.goals.is_empty() {
+        out += "\n## Goals\n\n";
+        for goal in &digest.goals {
+            out += &format!("- {}\n", md_escape(&goal_line(goal, digest.period)));
+        }
+    }
+    out
+}
+
+pub fn html(digest: &Digest) -> String {
+    let page_title = format!("{} · {}", title(digest), dates(digest));
+    let mut body = format!("<h1>{}</h1>\n", title(digest));
+    body += &format!("<p class=\"dates\">{}</p>\n", dates(digest));
+    if digest.total_seconds < 60.0 {
+        body += "<p>No coding time recorded.</p>\n";
+    } else {
+        body += "<ul>\n";
+        for (label, text) in overview(digest) {
+            let text = escape_markup(&text, false);
+            body += &format!("<li><strong>{label}:</strong> {text}</li>\n");
+        }
+        body += "</ul>\n";
+        for (heading, entries) in [("Projects", &digest.projects), ("Languages", &digest.languages)]
+        {
+            if entries.is_empty() {
+                continue;
+            }
+            body += &format!("<h2>{heading}</h2>\n<table>\n");
+            body += "<tr><th>Name</th><th>Time</th><th>Change</th></tr>\n";
+            for e in entries {
+                body += &format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape_markup(&e.name, false),
+                    hm(e.seconds),
+                    change(e.seconds, e.previous_seconds)
+                );
+            }
+            body += "</table>\n";
+        }
+    }
+    if !digest.goals.is_empty() {
+        body += "<h2>Goals</h2>\n<ul>\n";
+        for goal in &digest.goals {
+            let line = escape_markup(&goal_line(goal, digest.period), false);
+            body += &format!("<li>{line}</li>\n");
+        }
+        body += "</ul>\n";
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{STYLE}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escape_markup(&page_title, false)
+    )
+}
+
+/// Inline so the page looks the same as a file and in mail clients.
+const STYLE: &str = "body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;\
+color:#1f2328;max-width:640px;margin:24px auto;padding:0 16px}\
+h1{font-size:22px;margin-bottom:0}h2{font-size:16px;margin-top:24px}\
+.dates{color:#59636e;margin-top:4px}table{border-collapse:collapse;width:100%}\
+th,td{padding:4px 8px;border-bottom:1px solid #d1d9e0;text-align:right}\
+th:first-child,td:first-child{text-align:left}";
+
+// --- Delivery ---
+
+/// Send `digest` everywhere the settings ask for, and to webhooks
+/// subscribed to `digest`. A failed delivery doesn't stop the others.
+pub async fn deliver(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    user: &User,
+    settings: &DigestSettings,
+    digest: &Digest,
+) -> DigestSent {
+    let (text, html) = (markdown(digest), html(digest));
+    let mut sent = DigestSent::default();
+
+    if settings.directory {
+        match config.digest_dir() {
+            Some(dir) => match write_files(&dir, &user.username, digest, &text, &html) {
+                Ok(files) => sent.files = files,
+                Err(e) => sent.errors.push(format!("writing files: {e}")),
+            },
+            None => sent.errors.push("no digest directory for an in-memory database".into()),
+        }
+    }
+
+    if let Some(to) = &settings.email {
+        let mail = Mail {
+            to,
+            subject: &subject(digest),
+            text: &text,
+            html: &html,
+        };
+        match &config.digest.smtp {
+            Some(smtp) => match smtp::send(smtp, &mail).await {
+                Ok(()) => sent.emailed = Some(to.clone()),
+                Err(e) => sent.errors.push(e.to_string()),
+            },
+            None => sent.errors.push("no SMTP server configured".into()),
+        }
+    }
+
+    let data = serde_json::json!({ "digest": digest, "markdown": text, "html": html });
+    match webhook_service::dispatch(pool, Some(user.id), WebhookTrigger::Digest, data).await {
+        Ok(n) => sent.webhooks = n,
+        Err(e) => sent.errors.push(format!("queueing webhooks: {e}")),
+    }
+    sent
+}
+
+/// `<dir>/<username>/<period>-<last day>.md` and `.html`, replacing any
+/// earlier render of the same digest.
+fn write_files(
+    dir: &Path,
+    username: &str,
+    digest: &Digest,
+    text: &str,
+    html: &str,
+) -> Result<Vec<String>, std::io::Error> {
+    let name: String = username
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let dir = dir.join(name);
+    std::fs::create_dir_all(&dir)?;
+    let stem = format!("{}-{}", digest.period.as_str(), digest.to);
+    let mut files = Vec::new();
+    for (ext, content) in [("md", text), ("html", html)] {
+        let path = dir.join(format!("{stem}.{ext}"));
+        std::fs::write(&path, content)?;
+        files.push(path.to_string_lossy().to_string());
+    }
+    Ok(files)
+}
+
+/// Build and deliver every scheduled digest that is due and not sent yet.
+/// A digest is marked sent before delivery, so a failing one isn't retried
+/// every minute; webhooks retry on their own.
+pub async fn send_due(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    now: DateTime<Utc>,
+    idle_timeout: u64,
+) -> Result<usize, AppError> {
+    let mut sent = 0;
+    for (user_id, settings) in sqlite::scheduled_digests(pool).await? {
+        let through = send_day(&settings, now) - Duration::days(1);
+        if !sqlite::mark_digest_sent(pool, user_id, through).await? {
+            continue;
+        }
+        let Some(user) = sqlite::get_user_by_id(pool, user_id).await? else {
+            continue;
+        };
+        let digest = build(pool, user_id, settings.period, through, idle_timeout).await?;
+        let result = deliver(pool, config, &user, &settings, &digest).await;
+        for error in &result.errors {
+            let period = settings.period.as_str();
+            tracing::warn!("digests: {period} digest for {}: {error}", user.username);
+        }
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+/// Background task: sends scheduled digests as they come due.
+pub async fn run(pool: SqlitePool, config: AppConfig, idle_timeout: u64) {
+    let mut tick = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        tick.tick().await;
+        match send_due(&pool, &config, Utc::now(), idle_timeout).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("digests: sent {n}"),
+            Err(e) => tracing::warn!("digests: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use timeforged_core::api::CreateGoalRequest;
+    use timeforged_core::models::{Event, EventType, GoalPeriod};
+
+    use crate::smtp;
+
+    async fn setup() -> (SqlitePool, Uuid) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        (pool, user.id)
+    }
+
+    /// An event a minute for `minutes` minutes from `hour` o'clock on `day`.
+    async fn work(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        day: NaiveDate,
+        hour: u32,
+        minutes: i64,
+        project: &str,
+        language: &str,
+    ) {
+        let start = day.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        for minute in 0..=minutes {
+            let event = Event {
+                id: None,
+                user_id,
+                timestamp: start + Duration::minutes(minute),
+                event_type: EventType::File,
+                entity: format!("/src/{project}/main"),
+                project: Some(project.into()),
+                language: Some(language.into()),
+                branch: None,
+                activity: None,
+                machine: None,
+                metadata: None,
+                created_at: None,
+            };
+            sqlite::insert_event(pool, &event).await.unwrap();
+        }
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn digests_go_out_after_the_hour_on_their_day() {
+        let daily = DigestSettings {
+            period: DigestPeriod::Daily,
+            ..Default::default()
+        };
+        assert_eq!(send_day(&daily, at("2026-03-04T07:59:00Z")).to_string(), "2026-03-03");
+        assert_eq!(send_day(&daily, at("2026-03-04T08:00:00Z")).to_string(), "2026-03-04");
+
+        // Mondays at 8 by default; 2026-03-02 is a Monday
+        let weekly = DigestSettings::default();
+        assert_eq!(send_day(&weekly, at("2026-03-04T10:00:00Z")).to_string(), "2026-03-02");
+        assert_eq!(send_day(&weekly, at("2026-03-02T07:00:00Z")).to_string(), "2026-02-23");
+        let fridays = DigestSettings {
+            weekday: Weekday::Fri,
+            hour: 0,
+            ..Default::default()
+        };
+        assert_eq!(send_day(&fridays, at("2026-03-06T00:00:00Z")).to_string(), "2026-03-06");
+
+        assert!(valid_email("dev@example.com"));
+        for bad in ["dev", "@example.com", "dev@localhost", "a b@example.com", "a@b.c>\r\nDATA"] {
+            assert!(!valid_email(bad), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn digests_compare_with_the_period_before() {
+        let (pool, user_id) = setup().await;
+        let through = Utc::now().date_naive() - Duration::days(1);
+        let from = through - Duration::days(6);
+        work(&pool, user_id, from - Duration::days(3), 10, 30, "app", "Rust").await;
+        work(&pool, user_id, from + Duration::days(1), 10, 60, "app", "Rust").await;
+        work(&pool, user_id, from + Duration::days(3), 14, 20, "<web|site>", "TypeScript").await;
+        let goal = CreateGoalRequest {
+            period: GoalPeriod::Day,
+            target_seconds: 50 * 60,
+            project: None,
+            language: None,
+            rest_days: Vec::new(),
+        };
+        goal_service::create_goal(&pool, user_id, goal).await.unwrap();
+
+        let digest = build(&pool, user_id, DigestPeriod::Weekly, through, 300).await.unwrap();
+        assert_eq!((digest.from, digest.to), (from, through));
+        assert!((digest.total_seconds - 4800.0).abs() < 1.0, "{}", digest.total_seconds);
+        assert!((digest.previous_seconds - 1800.0).abs() < 1.0);
+        assert_eq!(digest.active_days, 2);
+        let projects: Vec<_> = digest.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(projects, ["app", "<web|site>"]);
+        assert_eq!(digest.projects[1].previous_seconds, 0.0);
+        let longest = digest.longest_session.as_ref().unwrap();
+        assert_eq!(longest.project.as_deref(), Some("app"));
+        assert_eq!((digest.goals[0].met, digest.goals[0].periods), (1, 7));
+
+        let md = markdown(&digest);
+        assert!(md.starts_with("# Weekly digest\n"), "{md}");
+        assert!(md.contains("- **Total:** 1h 20m, +50m (+167%) on the week before\n"), "{md}");
+        assert!(md.contains("- **Active days:** 2 of 7\n"), "{md}");
+        assert!(md.contains("| app | 1h 00m | +30m |\n"), "{md}");
+        assert!(md.contains("| \\<web\\|site\\> | 20m | new |\n"), "{md}");
+        assert!(md.contains("- 50m/day: met 1 of 7, streak 0 (best 1)\n"), "{md}");
+
+        let html = html(&digest);
+        assert!(html.contains("<td>&lt;web|site&gt;</td>"), "{html}");
+        assert!(!html.contains("<web|"));
+    }
+
+    #[tokio::test]
+    async fn scheduled_digests_go_out_once() {
+        let (pool, user_id) = setup().await;
+        let (smtp_config, mut mails) = smtp::mock::server().await;
+        let dir = std::env::temp_dir().join(format!("tf-digests-{}", std::process::id()));
+        let mut config = AppConfig::default();
+        config.digest.dir = Some(dir.to_string_lossy().to_string());
+        let triggers = [WebhookTrigger::Digest];
+        sqlite::create_webhook(&pool, user_id, "http://127.0.0.1:9/", "s", &triggers)
+            .await
+            .unwrap();
+
+        let mut settings = DigestSettings {
+            enabled: true,
+            period: DigestPeriod::Daily,
+            hour: 0,
+            email: Some(" dev@example.com ".into()),
+            ..Default::default()
+        };
+        // Nowhere to send mail yet
+        let err = update_settings(&pool, &config, user_id, settings.clone()).await;
+        assert!(matches!(err, Err(AppError::Validation(_))));
+        config.digest.smtp = Some(smtp_config);
+        settings.hour = 24;
+        let err = update_settings(&pool, &config, user_id, settings.clone()).await;
+        assert!(matches!(err, Err(AppError::Validation(_))));
+        settings.hour = 0;
+        let stored = update_settings(&pool, &config, user_id, settings).await.unwrap();
+        assert_eq!(stored.email.as_deref(), Some("dev@example.com"));
+        assert_eq!(super::settings(&pool, user_id).await.unwrap(), stored);
+
+        // Today's digest was due when the settings changed, so it is skipped
+        let now = Utc::now();
+        assert_eq!(send_due(&pool, &config, now, 300).await.unwrap(), 0);
+        let tomorrow = now + Duration::days(1);
+        assert_eq!(send_due(&pool, &config, tomorrow, 300).await.unwrap(), 1);
+        assert_eq!(send_due(&pool, &config, tomorrow, 300).await.unwrap(), 0);
+
+        let today = now.date_naive();
+        let file = dir.join("dev").join(format!("daily-{today}.md"));
+        let md = std::fs::read_to_string(&file).unwrap();
+        assert!(md.contains("No coding time recorded."), "{md}");
+        assert!(dir.join("dev").join(format!("daily-{today}.html")).exists());
+
+        let (commands, data) = mails.recv().await.unwrap();
+        assert!(commands.contains(&"RCPT TO:<dev@example.com>".to_string()));
+        assert!(data.contains("Subject: TimeForged daily digest: "), "{data}");
+
+        let hooks = sqlite::list_webhooks(&pool, user_id).await.unwrap();
+        let log = sqlite::list_deliveries(&pool, hooks[0].id, 10).await.unwrap();
+        assert_eq!(log.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
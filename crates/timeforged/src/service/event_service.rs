@@ -1,21 +1,52 @@
+use chrono::Utc;
 use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
 use uuid::Uuid;
 
-use timeforged_core::api::{BatchEventRequest, BatchEventResponse, CreateEventRequest, EventResponse};
+use timeforged_core::api::{
+    BatchEventRequest, BatchEventResponse, CreateEventRequest, EventCursor, EventResponse,
+    ExportEventsQuery, ExportEventsResponse, ExportQuery, ImportError, ImportResponse,
+    MAX_BATCH_EVENTS, MAX_IMPORT_LINES, StreamEvent,
+};
 use timeforged_core::error::AppError;
 use timeforged_core::models::Event;
 use timeforged_core::util::infer_language_from_path;
 
+use crate::bus::EventBus;
 use crate::storage::sqlite;
 
 pub async fn create_event(
     pool: &SqlitePool,
+    bus: &EventBus,
     user_id: Uuid,
     req: CreateEventRequest,
 ) -> Result<EventResponse, AppError> {
+    let (event, _) = ingest(pool, bus, user_id, req).await?;
+
+    Ok(EventResponse {
+        id: event.id.unwrap_or_default(),
+        timestamp: event.timestamp,
+        event_type: event.event_type,
+        entity: event.entity,
+    })
+}
+
+/// Validate, normalize and record a client-submitted event.
+async fn ingest(
+    pool: &SqlitePool,
+    bus: &EventBus,
+    user_id: Uuid,
+    req: CreateEventRequest,
+) -> Result<(Event, bool), AppError> {
+    record_event(pool, bus, prepare(user_id, req)?).await
+}
+
+fn prepare(user_id: Uuid, req: CreateEventRequest) -> Result<Event, AppError> {
     validate_event(&req)?;
 
-    let event = Event {
+    Ok(Event {
         id: None,
         user_id,
         timestamp: req.timestamp,
@@ -28,40 +59,262 @@ pub async fn create_event(
         machine: req.machine.clone(),
         metadata: req.metadata.clone(),
         created_at: None,
-    };
-
-    let id = sqlite::insert_event(pool, &event).await?;
-
-    Ok(EventResponse {
-        id,
-        timestamp: event.timestamp,
-        event_type: event.event_type,
-        entity: event.entity,
     })
 }
 
+/// Store an event and announce it on the bus. Used by the API and the
+/// watchers so live subscribers see every source. An event identical to
+/// one already stored is not stored or announced again; the flag says
+/// whether it was new.
+pub async fn record_event(
+    pool: &SqlitePool,
+    bus: &EventBus,
+    mut event: Event,
+) -> Result<(Event, bool), AppError> {
+    let inserted = sqlite::insert_event(pool, &event).await?;
+    event.id = Some(inserted.id);
+    if !inserted.duplicate {
+        bus.publish(event.user_id, StreamEvent::Event(event.clone()));
+    }
+    Ok((event, !inserted.duplicate))
+}
+
 pub async fn create_batch(
     pool: &SqlitePool,
+    bus: &EventBus,
     user_id: Uuid,
     req: BatchEventRequest,
 ) -> Result<BatchEventResponse, AppError> {
-    if req.events.len() > 100 {
-        return Err(AppError::BadRequest("batch size exceeds 100".into()));
+    if req.events.len() > MAX_BATCH_EVENTS {
+        let message = format!("batch size exceeds {MAX_BATCH_EVENTS}");
+        return Err(AppError::BadRequest(message));
     }
 
     let mut accepted = 0usize;
     let mut rejected = 0usize;
+    let mut duplicates = 0usize;
 
     for event_req in req.events {
-        match create_event(pool, user_id, event_req).await {
-            Ok(_) => accepted += 1,
+        match ingest(pool, bus, user_id, event_req).await {
+            Ok((_, true)) => accepted += 1,
+            Ok((_, false)) => duplicates += 1,
             Err(_) => rejected += 1,
         }
     }
 
-    Ok(BatchEventResponse { accepted, rejected })
+    if accepted > 0 {
+        bus.publish(user_id, StreamEvent::BatchIngested { accepted, rejected });
+    }
+
+    Ok(BatchEventResponse {
+        accepted,
+        rejected,
+        duplicates,
+    })
 }
 
+/// `GET /api/v1/events`: cursor mode when `cursor` is given, otherwise
+/// the legacy timestamp watermark (default: last 30 days).
+pub async fn export_events(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    query: ExportEventsQuery,
+) -> Result<ExportEventsResponse, AppError> {
+    let limit = query.limit.clamp(1, 5000);
+
+    let Some(cursor) = query.cursor else {
+        let since = query.since.unwrap_or_else(|| Utc::now() - chrono::Duration::days(30));
+        let events = sqlite::list_events(pool, user_id, since, limit).await?;
+        let count = events.len();
+        return Ok(ExportEventsResponse {
+            events,
+            count,
+            next_cursor: None,
+            has_more: count as i64 == limit,
+        });
+    };
+
+    let after = EventCursor::decode(&cursor)
+        .ok_or_else(|| AppError::BadRequest("invalid cursor".into()))?;
+    // One extra row tells whether another page is waiting
+    let mut events = sqlite::list_events_after(pool, user_id, after.0, limit + 1).await?;
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+
+    let next = events.last().and_then(|e| e.id).map(EventCursor).unwrap_or(after);
+    Ok(ExportEventsResponse {
+        count: events.len(),
+        events,
+        next_cursor: Some(next.encode()),
+        has_more,
+    })
+}
+
+/// Events read per query while exporting.
+const EXPORT_PAGE: i64 = 1000;
+
+/// `GET /api/v1/events/export`: the export, one page of events per item.
+/// Pages are read as the client takes them, so an export is never held in
+/// memory whole, and reading stops if the client goes away.
+pub fn export_stream(
+    pool: SqlitePool,
+    user_id: Uuid,
+    query: ExportQuery,
+) -> impl Stream<Item = Result<String, AppError>> {
+    let (tx, rx) = mpsc::channel(2);
+    tokio::spawn(async move {
+        let format = query.format;
+        if let Some(header) = format.header()
+            && tx.send(Ok(header)).await.is_err()
+        {
+            return;
+        }
+
+        let mut after = 0;
+        loop {
+            let page = sqlite::list_events_in_range_after(
+                &pool,
+                user_id,
+                after,
+                query.from,
+                query.to,
+                EXPORT_PAGE,
+            )
+            .await;
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    tracing::warn!("export failed: {e}");
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+            let Some(last) = page.last() else { return };
+            after = last.id.unwrap_or_default();
+
+            let chunk = page
+                .iter()
+                .map(|e| format.line(&CreateEventRequest::from_event(e)))
+                .collect();
+            if tx.send(Ok(chunk)).await.is_err() || (page.len() as i64) < EXPORT_PAGE {
+                return;
+            }
+        }
+    });
+    ReceiverStream::new(rx)
+}
+
+/// Rejected lines an [`ImportResponse`] explains.
+const MAX_IMPORT_ERRORS: usize = 20;
+/// Longest line an import reads; a longer one fails the whole import.
+const MAX_IMPORT_LINE: usize = 1 << 20;
+/// Largest import body, in bytes.
+const MAX_IMPORT_BYTES: usize = 64 << 20;
+
+/// `POST /api/v1/events/import`: store the JSON Lines events of `body` in
+/// one transaction, so a request that fails halfway stores nothing. The
+/// whole body (at most [`MAX_IMPORT_LINES`] lines and [`MAX_IMPORT_BYTES`])
+/// is read and checked first, so a slow upload never holds the database's
+/// write lock. Events already stored count as duplicates, so importing a
+/// file twice is harmless. Imported events aren't announced one by one
+/// (they are history, not activity); subscribers get one `BatchIngested`.
+pub async fn import_events<B, E>(
+    pool: &SqlitePool,
+    bus: &EventBus,
+    user_id: Uuid,
+    mut body: B,
+) -> Result<ImportResponse, AppError>
+where
+    B: Stream<Item = Result<axum::body::Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let mut resp = ImportResponse::default();
+    let mut events = Vec::new();
+    let mut buf = Vec::new();
+    let mut line = 0;
+    let mut read = 0;
+
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| AppError::BadRequest(format!("reading body: {e}")))?;
+        read += chunk.len();
+        if read > MAX_IMPORT_BYTES {
+            return Err(AppError::BadRequest(format!(
+                "body exceeds {MAX_IMPORT_BYTES} bytes; split the import"
+            )));
+        }
+        buf.extend_from_slice(&chunk);
+        let mut start = 0;
+        while let Some(end) = buf[start..].iter().position(|&b| b == b'\n') {
+            line += 1;
+            import_line(user_id, &buf[start..start + end], line, &mut events, &mut resp)?;
+            start += end + 1;
+        }
+        buf.drain(..start);
+        if buf.len() > MAX_IMPORT_LINE {
+            return Err(AppError::BadRequest(format!("line {} is too long", line + 1)));
+        }
+    }
+    if !buf.is_empty() {
+        import_line(user_id, &buf, line + 1, &mut events, &mut resp)?;
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+    for event in &events {
+        if sqlite::insert_event_in(&mut tx, event).await?.duplicate {
+            resp.duplicates += 1;
+        } else {
+            resp.accepted += 1;
+        }
+    }
+    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
+
+    if resp.accepted > 0 {
+        let (accepted, rejected) = (resp.accepted, resp.rejected);
+        bus.publish(user_id, StreamEvent::BatchIngested { accepted, rejected });
+    }
+    Ok(resp)
+}
+
+/// Check one line and queue its event. Too many lines abort the import;
+/// anything wrong with the line itself only rejects it.
+fn import_line(
+    user_id: Uuid,
+    line: &[u8],
+    number: usize,
+    events: &mut Vec<Event>,
+    resp: &mut ImportResponse,
+) -> Result<(), AppError> {
+    if number > MAX_IMPORT_LINES {
+        return Err(AppError::BadRequest(format!(
+            "more than {MAX_IMPORT_LINES} lines; split the import"
+        )));
+    }
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(());
+    }
+    let event = serde_json::from_slice::<CreateEventRequest>(line)
+        .map_err(|e| e.to_string())
+        .and_then(|req| prepare(user_id, req).map_err(|e| e.to_string()));
+    match event {
+        Ok(event) => events.push(event),
+        Err(error) => {
+            resp.rejected += 1;
+            if resp.errors.len() < MAX_IMPORT_ERRORS {
+                resp.errors.push(ImportError {
+                    line: number,
+                    error,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Longest project name accepted, in characters.
+const MAX_PROJECT_LEN: usize = 128;
+/// Longest language name accepted, in characters.
+const MAX_LANGUAGE_LEN: usize = 64;
+
 fn validate_event(req: &CreateEventRequest) -> Result<(), AppError> {
     if req.entity.is_empty() {
         return Err(AppError::Validation("entity cannot be empty".into()));
@@ -69,24 +322,55 @@ fn validate_event(req: &CreateEventRequest) -> Result<(), AppError> {
     if req.entity.len() > 1024 {
         return Err(AppError::Validation("entity too long".into()));
     }
+    if let Some(project) = &req.project {
+        check_project(project).map_err(AppError::Validation)?;
+    }
+    if let Some(language) = &req.language {
+        check_language(language).map_err(AppError::Validation)?;
+    }
+    Ok(())
+}
+
+/// Project names end up in reports, cards and file names of exports, so
+/// they are kept short and printable.
+pub(crate) fn check_project(project: &str) -> Result<(), String> {
+    if project.chars().count() > MAX_PROJECT_LEN {
+        return Err(format!("project too long (max {MAX_PROJECT_LEN} characters)"));
+    }
+    if project.chars().any(char::is_control) {
+        return Err("project cannot contain control characters".into());
+    }
+    Ok(())
+}
+
+/// Language names are words like `C++`, `F#` or `Visual Basic .NET`.
+pub(crate) fn check_language(language: &str) -> Result<(), String> {
+    if language.chars().count() > MAX_LANGUAGE_LEN {
+        return Err(format!("language too long (max {MAX_LANGUAGE_LEN} characters)"));
+    }
+    let allowed = |c: char| c.is_alphanumeric() || " +#-._/()'*!".contains(c);
+    if let Some(c) = language.chars().find(|c| !allowed(*c)) {
+        return Err(format!("language cannot contain {c:?}"));
+    }
     Ok(())
 }
 
 fn normalize_project(req: &CreateEventRequest) -> Option<String> {
-    if let Some(ref p) = req.project {
-        if !p.is_empty() {
-            return Some(p.clone());
-        }
+    if let Some(ref p) = req.project
+        && !p.is_empty()
+    {
+        return Some(p.clone());
     }
-    // Try to infer project from entity path
-    infer_project_from_path(&req.entity)
+    // Try to infer project from entity path; a directory name that wouldn't
+    // pass as a project is left out rather than failing the event
+    infer_project_from_path(&req.entity).filter(|p| check_project(p).is_ok())
 }
 
 fn normalize_language(req: &CreateEventRequest) -> Option<String> {
-    if let Some(ref l) = req.language {
-        if !l.is_empty() {
-            return Some(l.clone());
-        }
+    if let Some(ref l) = req.language
+        && !l.is_empty()
+    {
+        return Some(l.clone());
     }
     infer_language_from_path(&req.entity)
 }
@@ -114,3 +398,220 @@ fn infer_project_from_path(entity: &str) -> Option<String> {
     None
 }
 
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use timeforged_core::models::EventType;
+
+    use super::*;
+
+    async fn setup() -> (SqlitePool, Uuid) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        (pool, user.id)
+    }
+
+    fn request(entity: &str, at: chrono::DateTime<Utc>) -> CreateEventRequest {
+        CreateEventRequest {
+            timestamp: at,
+            event_type: EventType::File,
+            entity: entity.into(),
+            project: Some("app".into()),
+            language: None,
+            branch: None,
+            activity: None,
+            machine: None,
+            metadata: None,
+        }
+    }
+
+    fn request_without_project(entity: &str, at: chrono::DateTime<Utc>) -> CreateEventRequest {
+        CreateEventRequest {
+            project: None,
+            ..request(entity, at)
+        }
+    }
+
+    fn cursor_query(cursor: &str, limit: i64) -> ExportEventsQuery {
+        ExportEventsQuery {
+            cursor: Some(cursor.into()),
+            since: None,
+            limit,
+        }
+    }
+
+    #[tokio::test]
+    async fn duplicates_are_stored_once() {
+        let (pool, user_id) = setup().await;
+        let bus = EventBus::new();
+        let now = Utc::now();
+
+        let first = create_event(&pool, &bus, user_id, request("/a.rs", now)).await.unwrap();
+        let again = create_event(&pool, &bus, user_id, request("/a.rs", now)).await.unwrap();
+        assert_eq!(first.id, again.id);
+
+        let batch = BatchEventRequest {
+            events: vec![request("/a.rs", now), request("/b.rs", now), request("", now)],
+        };
+        let resp = create_batch(&pool, &bus, user_id, batch).await.unwrap();
+        assert_eq!((resp.accepted, resp.duplicates, resp.rejected), (1, 1, 1));
+    }
+
+    #[test]
+    fn names_are_checked_on_ingest() {
+        let at = Utc::now();
+        let with = |project: &str, language: &str| CreateEventRequest {
+            project: Some(project.into()),
+            language: Some(language.into()),
+            ..request("/home/dev/app/src/main.rs", at)
+        };
+
+        for (project, language) in [("tools & scripts", "C++"), ("app", "Visual Basic .NET")] {
+            assert!(prepare(Uuid::nil(), with(project, language)).is_ok(), "{language}");
+        }
+        for (project, language) in [
+            ("app", "</text><script>alert(1)</script>"),
+            ("app", "Rust\n"),
+            ("a\u{0}b", "Rust"),
+            (&"x".repeat(MAX_PROJECT_LEN + 1), "Rust"),
+            ("app", &"x".repeat(MAX_LANGUAGE_LEN + 1)),
+        ] {
+            let err = prepare(Uuid::nil(), with(project, language)).unwrap_err();
+            assert!(matches!(err, AppError::Validation(_)), "{project:?} {language:?}");
+        }
+
+        // An inferred project that wouldn't pass is dropped, not rejected
+        let event = prepare(Uuid::nil(), request_without_project("/home/a\u{7}b/x.rs", at));
+        assert_eq!(event.unwrap().project, None);
+    }
+
+    #[tokio::test]
+    async fn export_pages_by_cursor_without_gaps() {
+        let (pool, user_id) = setup().await;
+        let bus = EventBus::new();
+        let now = Utc::now();
+        for i in 0..5 {
+            create_event(&pool, &bus, user_id, request(&format!("/{i}.rs"), now)).await.unwrap();
+        }
+
+        let mut cursor = String::new();
+        let mut seen = Vec::new();
+        let mut pages = Vec::new();
+        loop {
+            let page = export_events(&pool, user_id, cursor_query(&cursor, 2)).await.unwrap();
+            seen.extend(page.events.iter().map(|e| e.entity.clone()));
+            pages.push((page.count, page.has_more));
+            cursor = page.next_cursor.unwrap();
+            if !page.has_more {
+                break;
+            }
+        }
+        assert_eq!(pages, [(2, true), (2, true), (1, false)]);
+        assert_eq!(seen, ["/0.rs", "/1.rs", "/2.rs", "/3.rs", "/4.rs"]);
+
+        // Caught up: the cursor stays put until something new arrives
+        let idle = export_events(&pool, user_id, cursor_query(&cursor, 2)).await.unwrap();
+        assert_eq!((idle.count, idle.next_cursor.as_deref()), (0, Some(cursor.as_str())));
+
+        let err = export_events(&pool, user_id, cursor_query("bogus", 2)).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(_)));
+    }
+
+    #[tokio::test]
+    async fn full_export_streams_every_page_in_range() {
+        use timeforged_core::interchange::{ExportFormat, read_csv};
+
+        let (pool, user_id) = setup().await;
+        let bus = EventBus::new();
+        let start = Utc::now() - chrono::Duration::days(1);
+        let count = EXPORT_PAGE as usize + 5;
+        let events: Vec<_> = (0..count)
+            .map(|i| request(&format!("/{i}.rs"), start + chrono::Duration::seconds(i as i64)))
+            .collect();
+        for chunk in events.chunks(100) {
+            let batch = BatchEventRequest {
+                events: chunk.to_vec(),
+            };
+            create_batch(&pool, &bus, user_id, batch).await.unwrap();
+        }
+
+        let query = ExportQuery {
+            format: ExportFormat::Csv,
+            from: Some(start + chrono::Duration::seconds(2)),
+            to: None,
+        };
+        let text: String = export_stream(pool.clone(), user_id, query)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await
+            .concat();
+        let parsed: Vec<_> = read_csv(text.as_bytes()).unwrap().map(|(_, e)| e.unwrap()).collect();
+        assert_eq!(parsed.len(), count - 2);
+        assert_eq!(parsed[0].entity, "/2.rs");
+        assert_eq!(parsed.last().unwrap().entity, format!("/{}.rs", count - 1));
+    }
+
+    #[tokio::test]
+    async fn import_reads_lines_across_chunks_and_is_idempotent() {
+        let (pool, user_id) = setup().await;
+        let bus = EventBus::new();
+        let now = Utc::now();
+        let line = |entity: &str| serde_json::to_string(&request(entity, now)).unwrap();
+        let body = format!("{}\n\nnot json\n{}\n{}", line("/a.rs"), line(""), line("/b.rs"));
+        // Split mid-line, as a network would
+        let (head, tail) = body.split_at(body.len() / 2);
+        let chunks = || {
+            let chunk = |c: &str| Ok::<_, AppError>(axum::body::Bytes::from(c.to_owned()));
+            tokio_stream::iter([chunk(head), chunk(tail)])
+        };
+
+        let resp = import_events(&pool, &bus, user_id, chunks()).await.unwrap();
+        assert_eq!((resp.accepted, resp.duplicates, resp.rejected), (2, 0, 2));
+        let lines: Vec<_> = resp.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [3, 4]);
+
+        let again = import_events(&pool, &bus, user_id, chunks()).await.unwrap();
+        assert_eq!((again.accepted, again.duplicates), (0, 2));
+    }
+
+    #[tokio::test]
+    async fn failed_import_stores_nothing() {
+        let (pool, user_id) = setup().await;
+        let bus = EventBus::new();
+        let line = serde_json::to_string(&request("/a.rs", Utc::now())).unwrap();
+        let chunks = tokio_stream::iter([
+            Ok(axum::body::Bytes::from(format!("{line}\n"))),
+            Err(AppError::BadRequest("connection reset".into())),
+        ]);
+
+        assert!(import_events(&pool, &bus, user_id, chunks).await.is_err());
+        let stored = sqlite::list_events_in_range_after(&pool, user_id, 0, None, None, 10);
+        assert!(stored.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn oversized_import_stores_nothing() {
+        let (pool, user_id) = setup().await;
+        let bus = EventBus::new();
+        let now = Utc::now();
+        let body: String = (0..=MAX_IMPORT_LINES)
+            .map(|i| {
+                let event = request(&format!("/{i}.rs"), now + chrono::Duration::seconds(i as i64));
+                serde_json::to_string(&event).unwrap() + "\n"
+            })
+            .collect();
+        let chunks = tokio_stream::iter([Ok::<_, AppError>(axum::body::Bytes::from(body))]);
+
+        let err = import_events(&pool, &bus, user_id, chunks).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(_)), "{err}");
+        let stored = sqlite::list_events_in_range_after(&pool, user_id, 0, None, None, 10);
+        assert!(stored.await.unwrap().is_empty());
+    }
+}
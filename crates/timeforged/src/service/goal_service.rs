@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use sqlx::SqlitePool;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use timeforged_core::api::{
+    CreateGoalRequest, GoalPeriodResult, GoalProgress, GoalReached, GoalStreak, StreamEvent,
+};
+use timeforged_core::error::AppError;
+use timeforged_core::models::{DaySummary, Goal, GoalPeriod, ReportRequest, WebhookTrigger};
+
+use crate::bus::EventBus;
+use crate::service::{event_service, report_service, webhook_service};
+use crate::storage::sqlite;
+
+/// Days back goal history and streaks are worked out over.
+const HISTORY_DAYS: i64 = 365;
+const MAX_GOALS_PER_USER: usize = 20;
+/// Shortest target; anything less is met by opening a file.
+const MIN_TARGET_SECS: u64 = 60;
+
+/// Days in a period, and so the most a target can ask for.
+pub fn period_days(period: GoalPeriod) -> i64 {
+    match period {
+        GoalPeriod::Day => 1,
+        GoalPeriod::Week => 7,
+    }
+}
+
+/// First day of the period `date` falls in.
+pub fn period_start(period: GoalPeriod, date: NaiveDate) -> NaiveDate {
+    match period {
+        GoalPeriod::Day => date,
+        GoalPeriod::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+    }
+}
+
+/// Check a new goal, trimming its filters and sorting its rest days.
+fn validate(req: &mut CreateGoalRequest) -> Result<(), AppError> {
+    let max = period_days(req.period) as u64 * 86400;
+    if !(MIN_TARGET_SECS..=max).contains(&req.target_seconds) {
+        return Err(AppError::Validation(format!(
+            "target must be between {MIN_TARGET_SECS} and {max} seconds for a {} goal",
+            req.period.as_str()
+        )));
+    }
+
+    let trim = |s: Option<String>| Some(s?.trim().to_string()).filter(|s| !s.is_empty());
+    req.project = trim(req.project.take());
+    req.language = trim(req.language.take());
+    if let Some(project) = &req.project {
+        event_service::check_project(project).map_err(AppError::Validation)?;
+    }
+    if let Some(language) = &req.language {
+        event_service::check_language(language).map_err(AppError::Validation)?;
+    }
+
+    if req.period == GoalPeriod::Week && !req.rest_days.is_empty() {
+        return Err(AppError::Validation("only daily goals have rest days".into()));
+    }
+    req.rest_days.sort_by_key(|d| d.num_days_from_monday());
+    req.rest_days.dedup();
+    if req.rest_days.len() == 7 {
+        return Err(AppError::Validation(
+            "a goal needs at least one day that isn't a rest day".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn create_goal(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    mut req: CreateGoalRequest,
+) -> Result<Goal, AppError> {
+    validate(&mut req)?;
+    if sqlite::list_goals(pool, user_id).await?.len() >= MAX_GOALS_PER_USER {
+        return Err(AppError::Validation(format!(
+            "at most {MAX_GOALS_PER_USER} goals per user"
+        )));
+    }
+    sqlite::create_goal(pool, user_id, &req).await
+}
+
+/// Every goal of the user with its progress and the last `history` periods.
+pub async fn list_goals(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    history: usize,
+    idle_timeout: u64,
+) -> Result<Vec<GoalProgress>, AppError> {
+    let today = Utc::now().date_naive();
+    let mut goals = Vec::new();
+    for goal in sqlite::list_goals(pool, user_id).await? {
+        goals.push(progress(pool, goal, today, history, idle_timeout).await?);
+    }
+    Ok(goals)
+}
+
+pub async fn get_goal(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    goal_id: Uuid,
+    history: usize,
+    idle_timeout: u64,
+) -> Result<GoalProgress, AppError> {
+    let goal = sqlite::get_goal(pool, user_id, goal_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("goal not found".into()))?;
+    progress(pool, goal, Utc::now().date_naive(), history, idle_timeout).await
+}
+
+pub async fn delete_goal(pool: &SqlitePool, user_id: Uuid, goal_id: Uuid) -> Result<(), AppError> {
+    if !sqlite::delete_goal(pool, user_id, goal_id).await? {
+        return Err(AppError::NotFound("goal not found".into()));
+    }
+    Ok(())
+}
+
+/// Time per day from `from` to now, counting only what the goal counts.
+async fn days(
+    pool: &SqlitePool,
+    goal: &Goal,
+    from: NaiveDate,
+    idle_timeout: u64,
+) -> Result<Vec<DaySummary>, AppError> {
+    let req = ReportRequest {
+        from: Some(from.and_hms_opt(0, 0, 0).unwrap().and_utc()),
+        to: Some(Utc::now()),
+        project: goal.project.clone(),
+        language: goal.language.clone(),
+    };
+    Ok(report_service::get_summary(pool, goal.user_id, &req, idle_timeout).await?.days)
+}
+
+/// How a goal is going as of `today`, from the last year of reports.
+async fn progress(
+    pool: &SqlitePool,
+    goal: Goal,
+    today: NaiveDate,
+    history: usize,
+    idle_timeout: u64,
+) -> Result<GoalProgress, AppError> {
+    let first = period_start(goal.period, today - Duration::days(HISTORY_DAYS));
+    let days = days(pool, &goal, first, idle_timeout).await?;
+    Ok(evaluate(goal, &days, first, today, history))
+}
+
+/// Sum `days` into the goal's periods from `first` through the one `today`
+/// is in, and work out the streaks.
+fn evaluate(
+    goal: Goal,
+    days: &[DaySummary],
+    first: NaiveDate,
+    today: NaiveDate,
+    history: usize,
+) -> GoalProgress {
+    let mut by_period: HashMap<NaiveDate, f64> = HashMap::new();
+    for day in days {
+        *by_period.entry(period_start(goal.period, day.date)).or_default() += day.total_seconds;
+    }
+
+    let target = goal.target_seconds as f64;
+    let current_start = period_start(goal.period, today);
+    let mut periods = Vec::new();
+    let mut start = first;
+    while start <= current_start {
+        let seconds = by_period.get(&start).copied().unwrap_or(0.0);
+        periods.push(GoalPeriodResult {
+            start,
+            seconds,
+            met: seconds >= target,
+            rest: goal.period == GoalPeriod::Day && goal.rest_days.contains(&start.weekday()),
+        });
+        start += Duration::days(period_days(goal.period));
+    }
+    let current = periods.pop().expect("the current period is always included");
+    let streak = streak(&periods, &current);
+
+    GoalProgress {
+        label: goal.label(),
+        percent: current.seconds / target * 100.0,
+        current,
+        streak,
+        history: periods.split_off(periods.len().saturating_sub(history)),
+        goal,
+    }
+}
+
+/// Met periods in a row. A missed rest day neither breaks nor extends a
+/// streak, and the period in progress can only add to it.
+fn streak(finished: &[GoalPeriodResult], current: &GoalPeriodResult) -> GoalStreak {
+    let (mut run, mut longest) = (0, 0);
+    for period in finished {
+        if period.met {
+            run += 1;
+            longest = longest.max(run);
+        } else if !period.rest {
+            run = 0;
+        }
+    }
+    let run = run + u32::from(current.met);
+    GoalStreak {
+        current: run,
+        longest: longest.max(run),
+    }
+}
+
+/// Queue `goal_reached` webhooks for the user's goals met in the current
+/// period, once per goal and period. Returns how many were reached.
+pub async fn check_reached(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    idle_timeout: u64,
+) -> Result<usize, AppError> {
+    let today = Utc::now().date_naive();
+    let week = period_start(GoalPeriod::Week, today);
+    let mut reached = 0;
+    for goal in sqlite::unreached_goals(pool, user_id, today, week).await? {
+        // The current period alone settles it; the year behind it is only
+        // needed for the streak once it is met
+        let start = period_start(goal.period, today);
+        let seconds: f64 = days(pool, &goal, start, idle_timeout)
+            .await?
+            .iter()
+            .map(|d| d.total_seconds)
+            .sum();
+        if seconds < goal.target_seconds as f64
+            || !sqlite::mark_goal_reached(pool, goal.id, start).await?
+        {
+            continue;
+        }
+
+        let progress = progress(pool, goal, today, 0, idle_timeout).await?;
+        let data = GoalReached {
+            label: progress.label,
+            period_start: start,
+            seconds: progress.current.seconds,
+            streak: progress.streak.current,
+            goal: progress.goal,
+        };
+        let data = serde_json::to_value(&data).map_err(|e| AppError::Internal(e.to_string()))?;
+        webhook_service::dispatch(pool, Some(user_id), WebhookTrigger::GoalReached, data).await?;
+        reached += 1;
+    }
+    Ok(reached)
+}
+
+/// Background task: checks a user's goals whenever their totals for today
+/// change, which the stream service debounces.
+pub async fn run(pool: SqlitePool, bus: EventBus, idle_timeout: u64) {
+    let mut rx = bus.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(msg) => {
+                if matches!(msg.event, StreamEvent::Totals(_))
+                    && let Err(e) = check_reached(&pool, msg.user_id, idle_timeout).await
+                {
+                    tracing::warn!("goals: failed to check goals: {e}");
+                }
+            }
+            Err(RecvError::Lagged(n)) => {
+                tracing::debug!("goals: missed {n} bus messages");
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use timeforged_core::models::{Event, EventType};
+
+    async fn setup() -> (SqlitePool, Uuid) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        (pool, user.id)
+    }
+
+    fn request(period: GoalPeriod, target_seconds: u64) -> CreateGoalRequest {
+        CreateGoalRequest {
+            period,
+            target_seconds,
+            project: None,
+            language: None,
+            rest_days: Vec::new(),
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn goal(period: GoalPeriod, target_seconds: u64) -> Goal {
+        Goal {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            period,
+            target_seconds,
+            project: None,
+            language: None,
+            rest_days: Vec::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// A daily one-hour goal, checked against `secs` per day from Monday
+    /// 2026-03-02 on, the last day being today.
+    fn daily(secs: &[f64], rest_days: Vec<Weekday>, history: usize) -> GoalProgress {
+        let first = date("2026-03-02");
+        let days: Vec<_> = secs
+            .iter()
+            .enumerate()
+            .map(|(i, s)| DaySummary {
+                date: first + Duration::days(i as i64),
+                total_seconds: *s,
+            })
+            .collect();
+        let goal = Goal {
+            rest_days,
+            ..goal(GoalPeriod::Day, 3600)
+        };
+        let today = first + Duration::days(secs.len() as i64 - 1);
+        evaluate(goal, &days, first, today, history)
+    }
+
+    #[test]
+    fn missed_days_break_streaks_unless_they_are_rest_days() {
+        // Mon-Fri met, weekend off, Mon met, Tue in progress
+        let week = [3600.0, 4000.0, 3600.0, 5000.0, 3700.0, 0.0, 0.0, 3600.0, 1800.0];
+        let progress = daily(&week, vec![], 30);
+        assert_eq!(progress.streak, GoalStreak { current: 1, longest: 5 });
+        assert_eq!(progress.percent, 50.0);
+        assert!(!progress.current.met);
+        assert_eq!(progress.history.len(), 8);
+
+        let progress = daily(&week, vec![Weekday::Sat, Weekday::Sun], 3);
+        assert_eq!(progress.streak, GoalStreak { current: 6, longest: 6 });
+        let rest: Vec<_> = progress.history.iter().map(|p| (p.start, p.rest)).collect();
+        assert_eq!(
+            rest,
+            [(date("2026-03-07"), true), (date("2026-03-08"), true), (date("2026-03-09"), false)]
+        );
+
+        // Meeting today's target extends the streak right away
+        let mut done = week;
+        done[8] = 3600.0;
+        let progress = daily(&done, vec![Weekday::Sat, Weekday::Sun], 0);
+        assert_eq!(progress.streak, GoalStreak { current: 7, longest: 7 });
+        assert!(progress.history.is_empty());
+    }
+
+    #[test]
+    fn weekly_goals_add_up_monday_to_sunday() {
+        let goal = goal(GoalPeriod::Week, 10 * 3600);
+        let days = [("2026-03-01", 36000.0), ("2026-03-02", 20000.0), ("2026-03-08", 16000.0)]
+            .map(|(d, s)| DaySummary { date: date(d), total_seconds: s });
+
+        let first = period_start(GoalPeriod::Week, date("2026-02-25"));
+        assert_eq!(first, date("2026-02-23"));
+        let progress = evaluate(goal, &days, first, date("2026-03-10"), 10);
+        let weeks: Vec<_> = progress.history.iter().map(|p| (p.start, p.met)).collect();
+        assert_eq!(weeks, [(date("2026-02-23"), true), (date("2026-03-02"), true)]);
+        assert_eq!(progress.current.start, date("2026-03-09"));
+        assert_eq!(progress.streak, GoalStreak { current: 2, longest: 2 });
+    }
+
+    #[tokio::test]
+    async fn goals_are_checked() {
+        let (pool, user_id) = setup().await;
+        assert!(create_goal(&pool, user_id, request(GoalPeriod::Day, 30)).await.is_err());
+        assert!(create_goal(&pool, user_id, request(GoalPeriod::Day, 86401)).await.is_err());
+        let mut weekly = request(GoalPeriod::Week, 86401);
+        weekly.rest_days = vec![Weekday::Sun];
+        assert!(create_goal(&pool, user_id, weekly).await.is_err());
+
+        let mut req = request(GoalPeriod::Day, 7200);
+        req.project = Some("  timeforged ".into());
+        req.language = Some(String::new());
+        req.rest_days = vec![Weekday::Sun, Weekday::Sat, Weekday::Sun];
+        let goal = create_goal(&pool, user_id, req).await.unwrap();
+        assert_eq!(goal.project.as_deref(), Some("timeforged"));
+        assert_eq!(goal.language, None);
+        assert_eq!(goal.rest_days, [Weekday::Sat, Weekday::Sun]);
+
+        let stored = sqlite::get_goal(&pool, user_id, goal.id).await.unwrap().unwrap();
+        assert_eq!(stored.label(), "2h/day on timeforged");
+        assert_eq!(stored.rest_days, goal.rest_days);
+
+        delete_goal(&pool, user_id, goal.id).await.unwrap();
+        assert!(delete_goal(&pool, user_id, goal.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reaching_a_goal_is_announced_once_per_period() {
+        let (pool, user_id) = setup().await;
+        let mut req = request(GoalPeriod::Day, 540);
+        req.language = Some("Rust".into());
+        let goal = create_goal(&pool, user_id, req).await.unwrap();
+        let triggers = [WebhookTrigger::GoalReached];
+        sqlite::create_webhook(&pool, user_id, "http://127.0.0.1:9/", "s", &triggers)
+            .await
+            .unwrap();
+
+        // Ten minutes of Rust and twenty of Python, ending now
+        let now = Utc::now();
+        let start = now - Duration::minutes(30);
+        for minute in 0..=30 {
+            let event = Event {
+                id: None,
+                user_id,
+                timestamp: start + Duration::minutes(minute),
+                event_type: EventType::File,
+                entity: "/src/app/main".into(),
+                project: Some("app".into()),
+                language: Some(if minute <= 10 { "Rust" } else { "Python" }.into()),
+                branch: None,
+                activity: None,
+                machine: None,
+                metadata: None,
+                created_at: None,
+            };
+            sqlite::insert_event(&pool, &event).await.unwrap();
+        }
+
+        // Only the Rust time counts, and the day may have started mid-session
+        let progress = get_goal(&pool, user_id, goal.id, 0, 300).await.unwrap();
+        let rust_today = progress.current.seconds;
+        assert!(rust_today < 601.0, "{rust_today}");
+        let expected = usize::from(rust_today >= 540.0);
+        assert_eq!(check_reached(&pool, user_id, 300).await.unwrap(), expected);
+        assert_eq!(check_reached(&pool, user_id, 300).await.unwrap(), 0);
+
+        let hooks = sqlite::list_webhooks(&pool, user_id).await.unwrap();
+        let log = sqlite::list_deliveries(&pool, hooks[0].id, 10).await.unwrap();
+        assert_eq!(log.len(), expected);
+    }
+}
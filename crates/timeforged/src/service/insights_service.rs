@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc, Weekday};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use timeforged_core::api::{
+    ContextSwitches, FocusInsight, HourInsight, Insights, InsightsQuery, LateNightInsight,
+    SessionInsights, SessionTrend, UnusualDay, UnusualKind, WeekdayInsight,
+};
+use timeforged_core::error::AppError;
+
+use crate::storage::sqlite;
+
+const DEFAULT_RANGE_DAYS: i64 = 30;
+const MAX_RANGE_DAYS: i64 = 366;
+/// Days before each day its time is compared with.
+const BASELINE_DAYS: i64 = 28;
+/// Days with time a baseline needs before anything stands out from it.
+const MIN_BASELINE_ACTIVE_DAYS: usize = 5;
+/// Standard deviations off the baseline for a day to be unusual...
+const UNUSUAL_Z_SCORE: f64 = 2.0;
+/// ...and by at least this much, so a steady routine doesn't flag minutes.
+const UNUSUAL_MIN_DELTA_SECS: f64 = 1800.0;
+/// Shortest stretch on one project that counts as focus time.
+const FOCUS_MIN_BLOCK_SECS: u64 = 25 * 60;
+/// Late night is from 22:00 up to 05:00.
+const LATE_NIGHT_HOURS: (u32, u32) = (22, 5);
+const LATE_NIGHT_FREQUENT_RATIO: f64 = 0.15;
+
+type Point = (DateTime<Utc>, Option<String>);
+
+pub async fn insights(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    query: &InsightsQuery,
+    idle_timeout: u64,
+) -> Result<Insights, AppError> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::days(DEFAULT_RANGE_DAYS));
+    if from >= to {
+        return Err(AppError::Validation("from must be before to".into()));
+    }
+    if to - from > Duration::days(MAX_RANGE_DAYS) {
+        return Err(AppError::Validation(format!(
+            "range must be at most {MAX_RANGE_DAYS} days"
+        )));
+    }
+
+    let baseline_from = from - Duration::days(BASELINE_DAYS);
+    let points = sqlite::get_timeline(
+        pool,
+        user_id,
+        baseline_from,
+        to,
+        query.project.as_deref(),
+        query.language.as_deref(),
+    )
+    .await?;
+    Ok(analyze(&points, from, to, idle_timeout))
+}
+
+fn is_late(time: DateTime<Utc>) -> bool {
+    let (start, end) = LATE_NIGHT_HOURS;
+    time.hour() >= start || time.hour() < end
+}
+
+/// The day a night belongs to: 02:00 on Tuesday is Monday night.
+fn night_of(time: DateTime<Utc>) -> NaiveDate {
+    let date = time.date_naive();
+    if time.hour() < LATE_NIGHT_HOURS.1 { date - Duration::days(1) } else { date }
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Work everything out from event `points`, oldest first, reaching back
+/// [`BASELINE_DAYS`] before `from`. Like the reports, the gap up to an
+/// event counts as active time when it's shorter than `idle_timeout`, and
+/// belongs to that event's hour and day.
+fn analyze(
+    points: &[Point],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    idle_timeout: u64,
+) -> Insights {
+    let idle = idle_timeout as f64;
+    let mut daily: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    let mut hours = [0.0; 24];
+    let mut weekdays = [0.0; 7];
+    let mut active = 0.0;
+    let (mut switches, mut late, mut late_nights) = (0, 0.0, HashSet::new());
+    let mut sessions: Vec<(DateTime<Utc>, f64)> = Vec::new();
+    let (mut focus, mut block) = (0.0, 0.0);
+    let mut flush_block = |block: &mut f64| {
+        if *block >= FOCUS_MIN_BLOCK_SECS as f64 {
+            focus += *block;
+        }
+        *block = 0.0;
+    };
+
+    let mut session_start: Option<DateTime<Utc>> = None;
+    for (i, (time, project)) in points.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|p| &points[p]);
+        let gap = previous.map(|(t, _)| (*time - *t).num_milliseconds() as f64 / 1000.0);
+        let in_range = *time >= from;
+
+        let Some(gap) = gap.filter(|gap| *gap < idle) else {
+            // A new session
+            if let (Some(start), Some((end, _))) = (session_start, previous)
+                && start >= from
+            {
+                sessions.push((start, (*end - start).num_milliseconds() as f64 / 1000.0));
+            }
+            session_start = Some(*time);
+            flush_block(&mut block);
+            continue;
+        };
+
+        *daily.entry(time.date_naive()).or_default() += gap;
+        if !in_range {
+            continue;
+        }
+        active += gap;
+        hours[time.hour() as usize] += gap;
+        weekdays[time.weekday().num_days_from_monday() as usize] += gap;
+        if is_late(*time) {
+            late += gap;
+            late_nights.insert(night_of(*time));
+        }
+
+        let previous_project = previous.and_then(|(_, p)| p.as_ref());
+        if previous_project != project.as_ref() {
+            flush_block(&mut block);
+            if previous_project.is_some() && project.is_some() {
+                switches += 1;
+            }
+        } else {
+            block += gap;
+        }
+    }
+    if let (Some(start), Some((end, _))) = (session_start, points.last())
+        && start >= from
+    {
+        sessions.push((start, (*end - start).num_milliseconds() as f64 / 1000.0));
+    }
+    flush_block(&mut block);
+
+    let ratio = |seconds: f64| if active > 0.0 { seconds / active } else { 0.0 };
+    let active_days = daily.range(from.date_naive()..).filter(|(_, s)| **s > 0.0).count();
+    let weekdays = weekday_insights(&weekdays, from.date_naive(), to.date_naive());
+    let best_weekday = weekdays
+        .iter()
+        .filter(|w| w.average_seconds > 0.0)
+        .max_by(|a, b| a.average_seconds.total_cmp(&b.average_seconds))
+        .map(|w| w.weekday);
+
+    Insights {
+        from,
+        to,
+        active_seconds: active,
+        top_hours: top_hours(&hours),
+        hours: (0..24).map(|h| HourInsight { hour: h as u8, seconds: hours[h] }).collect(),
+        weekdays,
+        best_weekday,
+        sessions: session_insights(&sessions, from.date_naive(), to.date_naive()),
+        context_switches: ContextSwitches {
+            total: switches,
+            per_hour: if active > 0.0 { switches as f64 / (active / 3600.0) } else { 0.0 },
+        },
+        focus: FocusInsight {
+            min_block_seconds: FOCUS_MIN_BLOCK_SECS,
+            seconds: focus,
+            ratio: ratio(focus),
+        },
+        late_night: LateNightInsight {
+            seconds: late,
+            ratio: ratio(late),
+            days: late_nights.len() as u32,
+            frequent: ratio(late) >= LATE_NIGHT_FREQUENT_RATIO
+                || (late_nights.len() >= 3 && late_nights.len() * 4 >= active_days),
+        },
+        unusual_days: unusual_days(&daily, from, to),
+    }
+}
+
+/// The three hours with most time, best first; earlier hours win ties.
+fn top_hours(hours: &[f64; 24]) -> Vec<u8> {
+    let mut ranked: Vec<usize> = (0..24).filter(|h| hours[*h] > 0.0).collect();
+    ranked.sort_by(|a, b| hours[*b].total_cmp(&hours[*a]).then(a.cmp(b)));
+    ranked.into_iter().take(3).map(|h| h as u8).collect()
+}
+
+fn weekday_insights(seconds: &[f64; 7], first: NaiveDate, last: NaiveDate) -> Vec<WeekdayInsight> {
+    let mut occurrences = [0u32; 7];
+    for date in first.iter_days().take_while(|d| *d <= last) {
+        occurrences[date.weekday().num_days_from_monday() as usize] += 1;
+    }
+    (0..7)
+        .map(|i| WeekdayInsight {
+            weekday: Weekday::try_from(i as u8).unwrap(),
+            seconds: seconds[i],
+            average_seconds: seconds[i] / occurrences[i].max(1) as f64,
+        })
+        .collect()
+}
+
+/// `sessions` as (start, length): overall figures and one entry per week
+/// from `first` to `last`, quiet weeks included.
+fn session_insights(
+    sessions: &[(DateTime<Utc>, f64)],
+    first: NaiveDate,
+    last: NaiveDate,
+) -> SessionInsights {
+    let total: f64 = sessions.iter().map(|(_, s)| s).sum();
+    let mut weeks: BTreeMap<NaiveDate, (u32, f64)> = BTreeMap::new();
+    let mut week = monday_of(first);
+    while week <= last {
+        weeks.insert(week, (0, 0.0));
+        week += Duration::days(7);
+    }
+    for (start, seconds) in sessions {
+        let entry = weeks.entry(monday_of(start.date_naive())).or_default();
+        entry.0 += 1;
+        entry.1 += seconds;
+    }
+    SessionInsights {
+        count: sessions.len() as u32,
+        average_seconds: if sessions.is_empty() { 0.0 } else { total / sessions.len() as f64 },
+        longest_seconds: sessions.iter().map(|(_, s)| *s).fold(0.0, f64::max),
+        weekly: weeks
+            .into_iter()
+            .map(|(week, (sessions, seconds))| SessionTrend {
+                week,
+                sessions,
+                average_seconds: if sessions == 0 { 0.0 } else { seconds / sessions as f64 },
+            })
+            .collect(),
+    }
+}
+
+/// Whole days in range whose time is far off the [`BASELINE_DAYS`] before
+/// them, idle days included in the baseline.
+fn unusual_days(
+    daily: &BTreeMap<NaiveDate, f64>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<UnusualDay> {
+    let seconds = |date: NaiveDate| daily.get(&date).copied().unwrap_or(0.0);
+    let mut unusual = Vec::new();
+    // Only days that are over: today isn't low, just not done yet
+    let days = from.date_naive().iter_days().take_while(|d| {
+        d.succ_opt().is_some_and(|next| next.and_hms_opt(0, 0, 0).unwrap().and_utc() <= to)
+    });
+    for date in days {
+        let baseline: Vec<f64> =
+            (1..=BASELINE_DAYS).map(|n| seconds(date - Duration::days(n))).collect();
+        if baseline.iter().filter(|s| **s > 0.0).count() < MIN_BASELINE_ACTIVE_DAYS {
+            continue;
+        }
+        let (mean, deviation) = mean_and_deviation(&baseline);
+        let value = seconds(date);
+        if deviation == 0.0 || (value - mean).abs() < UNUSUAL_MIN_DELTA_SECS {
+            continue;
+        }
+        let z_score = (value - mean) / deviation;
+        if z_score.abs() >= UNUSUAL_Z_SCORE {
+            unusual.push(UnusualDay {
+                date,
+                seconds: value,
+                baseline_seconds: mean,
+                z_score,
+                kind: if z_score > 0.0 { UnusualKind::High } else { UnusualKind::Low },
+            });
+        }
+    }
+    unusual
+}
+
+/// Mean and population standard deviation.
+fn mean_and_deviation(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    /// Events every 2 minutes from `start` for `minutes`.
+    fn stretch(start: &str, minutes: i64, project: &str) -> Vec<Point> {
+        (0..=minutes / 2)
+            .map(|i| (at(start) + Duration::minutes(2 * i), Some(project.to_string())))
+            .collect()
+    }
+
+    fn join(parts: Vec<Vec<Point>>) -> Vec<Point> {
+        let mut points: Vec<Point> = parts.into_iter().flatten().collect();
+        points.sort_by_key(|(t, _)| *t);
+        points
+    }
+
+    #[test]
+    fn statistics_are_plain() {
+        assert_eq!(mean_and_deviation(&[]), (0.0, 0.0));
+        assert_eq!(mean_and_deviation(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), (5.0, 2.0));
+
+        let mut hours = [0.0; 24];
+        hours[9] = 100.0;
+        hours[14] = 300.0;
+        hours[10] = 100.0;
+        assert_eq!(top_hours(&hours), [14, 9, 10]);
+        assert_eq!(top_hours(&[0.0; 24]), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn patterns_come_from_the_timeline() {
+        let points = join(vec![
+            // Monday: an hour of focus on one project
+            stretch("2026-03-02T09:00:00Z", 60, "app"),
+            // then later, 20 minutes hopping between two
+            stretch("2026-03-02T14:00:00Z", 10, "app"),
+            stretch("2026-03-02T14:12:00Z", 8, "docs"),
+            // Tuesday night past midnight
+            stretch("2026-03-03T23:30:00Z", 60, "app"),
+        ]);
+        let (from, to) = (at("2026-03-02T00:00:00Z"), at("2026-03-09T00:00:00Z"));
+        let insights = analyze(&points, from, to, 300);
+
+        // The two minutes between the afternoon's projects count too
+        assert_eq!(insights.active_seconds, (60.0 + 10.0 + 2.0 + 8.0 + 60.0) * 60.0);
+        assert_eq!(insights.top_hours, [9, 0, 23]);
+        assert_eq!(insights.hours[14].seconds, 20.0 * 60.0);
+        assert_eq!(insights.best_weekday, Some(Weekday::Mon));
+        // Tuesday night runs into Wednesday
+        assert_eq!(insights.weekdays[1].seconds, 28.0 * 60.0);
+        assert_eq!(insights.weekdays[2].seconds, 32.0 * 60.0);
+
+        assert_eq!(insights.sessions.count, 3);
+        assert_eq!(insights.sessions.longest_seconds, 3600.0);
+        assert_eq!(insights.sessions.weekly.len(), 2);
+        assert_eq!(insights.sessions.weekly[0].sessions, 3);
+        assert_eq!(insights.sessions.weekly[1].sessions, 0);
+
+        assert_eq!(insights.context_switches.total, 1);
+        // Both hours count, the short afternoon stretches don't
+        assert_eq!(insights.focus.seconds, 7200.0);
+        assert_eq!(insights.late_night.seconds, 3600.0);
+        assert_eq!(insights.late_night.days, 1);
+        assert!(insights.late_night.frequent);
+        // No baseline before March
+        assert!(insights.unusual_days.is_empty());
+    }
+
+    #[test]
+    fn days_far_off_the_baseline_stand_out() {
+        let mut parts = Vec::new();
+        // Four weeks of about an hour a day, give or take ten minutes
+        for day in 0..28 {
+            let start = at("2026-02-01T10:00:00Z") + Duration::days(day);
+            let minutes = 60 + [-10, 0, 10][day as usize % 3];
+            parts.push(stretch(&start.to_rfc3339(), minutes, "app"));
+        }
+        // Then a day with nothing, an ordinary one and a five-hour one
+        parts.push(stretch("2026-03-02T10:00:00Z", 60, "app"));
+        parts.push(stretch("2026-03-03T10:00:00Z", 300, "app"));
+        let points = join(parts);
+        let (from, to) = (at("2026-03-01T00:00:00Z"), at("2026-03-04T00:00:00Z"));
+        let unusual = analyze(&points, from, to, 300).unusual_days;
+
+        let dates: Vec<_> = unusual.iter().map(|d| (d.date.to_string(), d.kind)).collect();
+        assert_eq!(
+            dates,
+            [("2026-03-01".into(), UnusualKind::Low), ("2026-03-03".into(), UnusualKind::High)]
+        );
+        assert_eq!(unusual[0].seconds, 0.0);
+        assert!((unusual[0].baseline_seconds - 3600.0).abs() < 60.0);
+        assert_eq!(unusual[1].seconds, 300.0 * 60.0);
+        assert!(unusual[1].z_score > 10.0);
+    }
+
+    #[tokio::test]
+    async fn insights_read_the_database() {
+        use sqlx::sqlite::SqlitePoolOptions;
+        use timeforged_core::models::{Event, EventType};
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        for (timestamp, project) in stretch("2026-03-02T09:00:00Z", 30, "app") {
+            let event = Event {
+                id: None,
+                user_id: user.id,
+                timestamp,
+                event_type: EventType::File,
+                entity: "src/main.rs".into(),
+                project,
+                language: Some("Rust".into()),
+                branch: None,
+                activity: None,
+                machine: None,
+                metadata: None,
+                created_at: None,
+            };
+            sqlite::insert_event(&pool, &event).await.unwrap();
+        }
+
+        let query = InsightsQuery {
+            from: Some(at("2026-03-02T00:00:00Z")),
+            to: Some(at("2026-03-03T00:00:00Z")),
+            ..Default::default()
+        };
+        let found = insights(&pool, user.id, &query, 300).await.unwrap();
+        assert_eq!(found.active_seconds, 1800.0);
+        assert_eq!(found.focus.ratio, 1.0);
+
+        let other = InsightsQuery { project: Some("other".into()), ..query.clone() };
+        assert_eq!(insights(&pool, user.id, &other, 300).await.unwrap().active_seconds, 0.0);
+
+        let backwards = InsightsQuery { from: query.to, to: query.from, ..Default::default() };
+        assert!(insights(&pool, user.id, &backwards, 300).await.is_err());
+    }
+
+}
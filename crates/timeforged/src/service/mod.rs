@@ -1,4 +1,12 @@
+pub mod billing_service;
 pub mod card_service;
+pub mod digest_service;
 pub mod event_service;
+pub mod goal_service;
+pub mod insights_service;
+pub mod profile_service;
 pub mod report_service;
+pub mod sealed_service;
+pub mod stream_service;
 pub mod user_service;
+pub mod webhook_service;
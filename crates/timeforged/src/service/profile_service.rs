@@ -0,0 +1,289 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use timeforged_core::api::{PublicProfile, PublicProfileSettings, ProfileStreak, ProfileTotals};
+use timeforged_core::error::AppError;
+use timeforged_core::models::{CategorySummary, ReportRequest, Summary, User};
+
+use crate::service::card_service::{Layout, Metric};
+use crate::service::{card_service, event_service, report_service, sealed_service};
+use crate::storage::sqlite;
+
+/// Days a public profile covers.
+const PROFILE_DAYS: i64 = 365;
+/// Languages and projects listed on a profile.
+const PROFILE_TOP: usize = 10;
+/// Most projects a user can hide.
+const MAX_HIDDEN_PROJECTS: usize = 100;
+
+pub async fn settings(pool: &SqlitePool, user_id: Uuid) -> Result<PublicProfileSettings, AppError> {
+    sqlite::get_profile_settings(pool, user_id).await
+}
+
+/// Check and store a user's settings. Hidden projects are trimmed and
+/// deduplicated; what was stored is returned.
+pub async fn update_settings(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    mut settings: PublicProfileSettings,
+) -> Result<PublicProfileSettings, AppError> {
+    if settings.public && sealed_service::is_encrypted(pool, user_id).await? {
+        return Err(AppError::BadRequest(
+            "events are end-to-end encrypted, so there is no public profile to show".into(),
+        ));
+    }
+
+    let mut hidden: Vec<String> = Vec::new();
+    for project in &settings.hidden_projects {
+        let project = project.trim();
+        event_service::check_project(project).map_err(AppError::Validation)?;
+        let seen = hidden.iter().any(|p| p.to_lowercase() == project.to_lowercase());
+        if !project.is_empty() && !seen {
+            hidden.push(project.to_string());
+        }
+    }
+    if hidden.len() > MAX_HIDDEN_PROJECTS {
+        return Err(AppError::Validation(format!(
+            "too many hidden projects (max {MAX_HIDDEN_PROJECTS})"
+        )));
+    }
+    settings.hidden_projects = hidden;
+
+    sqlite::set_profile_settings(pool, user_id, &settings).await?;
+    Ok(settings)
+}
+
+/// The user behind a public profile, card or badge, and what they show.
+/// Non-existent, private and encrypted users (the server can't read their
+/// events) all get the same 404, so names can't be probed.
+pub async fn public_user(
+    pool: &SqlitePool,
+    username: &str,
+) -> Result<(User, PublicProfileSettings), AppError> {
+    let not_found = || AppError::NotFound("not found".into());
+    let user = match sqlite::get_user_by_username(pool, username).await {
+        Ok(Some(u)) if u.public_profile => u,
+        _ => return Err(not_found()),
+    };
+    if !matches!(sealed_service::is_encrypted(pool, user.id).await, Ok(false)) {
+        return Err(not_found());
+    }
+    let settings = settings(pool, user.id).await?;
+    Ok((user, settings))
+}
+
+/// Take out of `summary` the projects, languages and total the settings
+/// hide, so public cards and badges drawn from it show no more than the
+/// profile. The days stay for the heatmap; streaks come from them, so cards
+/// and badges that show a hidden streak are refused with
+/// [`shows_layout`] and [`shows_metric`] instead.
+pub fn redact(summary: &mut Summary, settings: &PublicProfileSettings) {
+    summary.projects.retain(|p| settings.shows_project(&p.name));
+    if !settings.show_languages {
+        summary.languages.clear();
+    }
+    if !settings.show_totals {
+        summary.total_seconds = 0.0;
+    }
+}
+
+/// Whether a public card may use `layout`: the badge and weekly cards are
+/// all totals, and the streak card is all streak.
+pub fn shows_layout(settings: &PublicProfileSettings, layout: Layout) -> bool {
+    match layout {
+        Layout::Badge | Layout::Weekly => settings.show_totals,
+        Layout::Streak => settings.show_streak,
+        _ => true,
+    }
+}
+
+/// Whether a public badge may show `metric`.
+pub fn shows_metric(settings: &PublicProfileSettings, metric: Metric) -> bool {
+    match metric {
+        Metric::Total | Metric::Today | Metric::Average => settings.show_totals,
+        Metric::Language => settings.show_languages,
+        Metric::Project => settings.show_projects,
+        Metric::Streak => settings.show_streak,
+    }
+}
+
+/// The profile of a public user over the last year, as their settings allow.
+pub async fn public_profile(
+    pool: &SqlitePool,
+    user: &User,
+    settings: &PublicProfileSettings,
+    idle_timeout: u64,
+) -> Result<PublicProfile, AppError> {
+    let now = Utc::now();
+    let req = ReportRequest {
+        from: Some(now - chrono::Duration::days(PROFILE_DAYS)),
+        to: Some(now),
+        project: None,
+        language: None,
+    };
+    let mut summary = report_service::get_summary(pool, user.id, &req, idle_timeout).await?;
+    redact(&mut summary, settings);
+    Ok(build(user, settings, summary))
+}
+
+fn build(user: &User, settings: &PublicProfileSettings, summary: Summary) -> PublicProfile {
+    let (current, longest, active) = card_service::streaks(&summary);
+    let top = |mut list: Vec<CategorySummary>| {
+        list.truncate(PROFILE_TOP);
+        list
+    };
+    let totals = ProfileTotals {
+        total_seconds: summary.total_seconds,
+        active_days: active as u32,
+        daily_average_seconds: if active == 0 {
+            0.0
+        } else {
+            summary.total_seconds / active as f64
+        },
+    };
+    let streak = ProfileStreak {
+        current: current.days,
+        longest: longest.days,
+        longest_from: longest.from,
+        longest_to: longest.to,
+    };
+
+    PublicProfile {
+        username: user.username.clone(),
+        display_name: user.display_name.clone(),
+        from: summary.from,
+        to: summary.to,
+        totals: settings.show_totals.then_some(totals),
+        languages: settings.show_languages.then(|| top(summary.languages)),
+        projects: settings.show_projects.then(|| top(summary.projects)),
+        streak: settings.show_streak.then_some(streak),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use timeforged_core::models::{Event, EventType};
+
+    async fn setup() -> (SqlitePool, User) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        (pool, user)
+    }
+
+    /// Ten minutes of work on `project` yesterday, a heartbeat a minute.
+    async fn work(pool: &SqlitePool, user_id: Uuid, project: &str, hour: u32) {
+        let yesterday = (Utc::now() - chrono::Duration::days(1)).date_naive();
+        let start = yesterday.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        for minute in 0..=10 {
+            let event = Event {
+                id: None,
+                user_id,
+                timestamp: start + chrono::Duration::minutes(minute),
+                event_type: EventType::File,
+                entity: format!("/src/{project}/main.rs"),
+                project: Some(project.into()),
+                language: Some("Rust".into()),
+                branch: None,
+                activity: None,
+                machine: None,
+                metadata: None,
+                created_at: None,
+            };
+            sqlite::insert_event(pool, &event).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn profiles_show_only_what_the_settings_allow() {
+        let (pool, user) = setup().await;
+        work(&pool, user.id, "api", 9).await;
+        work(&pool, user.id, "acme-billing", 14).await;
+
+        // Private until the user says otherwise
+        assert!(public_user(&pool, "dev").await.is_err());
+        let settings = PublicProfileSettings {
+            public: true,
+            hidden_projects: vec![" Acme-Billing ".into(), "acme-billing".into()],
+            ..Default::default()
+        };
+        let stored = update_settings(&pool, user.id, settings).await.unwrap();
+        assert_eq!(stored.hidden_projects, ["Acme-Billing"]);
+
+        let (user, settings) = public_user(&pool, "dev").await.unwrap();
+        assert_eq!(settings, stored);
+        let profile = public_profile(&pool, &user, &settings, 300).await.unwrap();
+        let projects: Vec<_> = profile.projects.unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(projects, ["api"]);
+        // Hidden projects still count towards the totals
+        let totals = profile.totals.unwrap();
+        assert!((totals.total_seconds - 1200.0).abs() < 1e-3, "{}", totals.total_seconds);
+        assert_eq!(totals.active_days, 1);
+        assert_eq!(profile.streak.unwrap().current, 1);
+
+        let only_languages = PublicProfileSettings {
+            public: true,
+            show_totals: false,
+            show_projects: false,
+            show_streak: false,
+            ..Default::default()
+        };
+        let settings = update_settings(&pool, user.id, only_languages).await.unwrap();
+        let profile = public_profile(&pool, &user, &settings, 300).await.unwrap();
+        let json = serde_json::to_value(&profile).unwrap();
+        let mut keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["from", "languages", "to", "username"]);
+    }
+
+    #[tokio::test]
+    async fn redacting_zeroes_a_hidden_total_and_keeps_the_days() {
+        let (pool, user) = setup().await;
+        work(&pool, user.id, "api", 9).await;
+        let now = Utc::now();
+        let req = ReportRequest {
+            from: Some(now - chrono::Duration::days(7)),
+            to: Some(now),
+            project: None,
+            language: None,
+        };
+        let summary = report_service::get_summary(&pool, user.id, &req, 300).await.unwrap();
+        assert!(summary.total_seconds > 0.0);
+
+        let mut shown = summary.clone();
+        redact(&mut shown, &PublicProfileSettings::default());
+        assert_eq!(shown.total_seconds, summary.total_seconds);
+
+        let settings = PublicProfileSettings {
+            show_totals: false,
+            ..Default::default()
+        };
+        let mut hidden = summary.clone();
+        redact(&mut hidden, &settings);
+        assert_eq!(hidden.total_seconds, 0.0);
+        assert_eq!(hidden.days.len(), summary.days.len());
+        assert_eq!(hidden.projects.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn settings_are_checked() {
+        let (pool, user) = setup().await;
+        assert_eq!(settings(&pool, user.id).await.unwrap(), PublicProfileSettings::default());
+
+        let bad = |hidden: Vec<String>| PublicProfileSettings {
+            hidden_projects: hidden,
+            ..Default::default()
+        };
+        let too_many = (0..=MAX_HIDDEN_PROJECTS).map(|i| format!("p{i}")).collect();
+        assert!(update_settings(&pool, user.id, bad(too_many)).await.is_err());
+        assert!(update_settings(&pool, user.id, bad(vec!["a\nb".into()])).await.is_err());
+        assert_eq!(settings(&pool, user.id).await.unwrap(), PublicProfileSettings::default());
+    }
+}
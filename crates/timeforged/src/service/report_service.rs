@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
+use timeforged_core::api::{CompareEntry, ComparePeriod, CompareQuery, CompareReport};
 use timeforged_core::error::AppError;
-use timeforged_core::models::{HourlyActivity, ReportRequest, Session, Summary};
+use timeforged_core::models::{CategorySummary, HourlyActivity, ReportRequest, Session, Summary};
 
 use crate::storage::sqlite;
 
@@ -32,3 +36,174 @@ pub async fn get_hourly_activity(
 ) -> Result<Vec<HourlyActivity>, AppError> {
     sqlite::get_hourly_activity(pool, user_id, req, idle_timeout).await
 }
+
+/// Furthest back a comparison can reach, in periods.
+const MAX_COMPARE_OFFSET: u32 = 366;
+
+type Range = (DateTime<Utc>, DateTime<Utc>);
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+/// First day of the period `date` is in.
+fn period_start(period: ComparePeriod, date: NaiveDate) -> NaiveDate {
+    match period {
+        ComparePeriod::Day => date,
+        ComparePeriod::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        ComparePeriod::Month => date.with_day(1).unwrap(),
+        ComparePeriod::Year => date.with_ordinal(1).unwrap(),
+    }
+}
+
+/// `date` moved `n` periods back (or on, for negative `n`).
+fn shift(period: ComparePeriod, date: NaiveDate, n: i64) -> NaiveDate {
+    let months = |m: i64| {
+        let by = Months::new(m.unsigned_abs() as u32);
+        if m >= 0 { date - by } else { date + by }
+    };
+    match period {
+        ComparePeriod::Day => date - Duration::days(n),
+        ComparePeriod::Week => date - Duration::days(7 * n),
+        ComparePeriod::Month => months(n),
+        ComparePeriod::Year => months(12 * n),
+    }
+}
+
+/// The period `now` is in, up to `now`, and the one `offset` periods
+/// before it: as far into it as `now` is into the current one, or all of
+/// it with `full`.
+fn compare_ranges(
+    period: ComparePeriod,
+    offset: u32,
+    full: bool,
+    now: DateTime<Utc>,
+) -> (Range, Range) {
+    let start = period_start(period, now.date_naive());
+    let previous_start = shift(period, start, offset as i64);
+    let previous_end = midnight(shift(period, previous_start, -1));
+    let previous_to = if full {
+        previous_end
+    } else {
+        (midnight(previous_start) + (now - midnight(start))).min(previous_end)
+    };
+    ((midnight(start), now), (midnight(previous_start), previous_to))
+}
+
+/// Everything in either list with its time in both, most time now first.
+fn compare_entries(current: &[CategorySummary], previous: &[CategorySummary]) -> Vec<CompareEntry> {
+    let mut before: HashMap<&str, f64> =
+        previous.iter().map(|c| (c.name.as_str(), c.total_seconds)).collect();
+    let mut entries: Vec<CompareEntry> = current
+        .iter()
+        .map(|c| {
+            let previous = before.remove(c.name.as_str()).unwrap_or(0.0);
+            CompareEntry::new(&c.name, c.total_seconds, previous)
+        })
+        .collect();
+    // Gone since: kept in the earlier period's order
+    entries.extend(
+        previous
+            .iter()
+            .filter(|c| before.contains_key(c.name.as_str()))
+            .map(|c| CompareEntry::new(&c.name, 0.0, c.total_seconds)),
+    );
+    entries
+}
+
+pub async fn compare(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    query: &CompareQuery,
+    idle_timeout: u64,
+) -> Result<CompareReport, AppError> {
+    if !(1..=MAX_COMPARE_OFFSET).contains(&query.offset) {
+        return Err(AppError::Validation(format!(
+            "offset must be between 1 and {MAX_COMPARE_OFFSET}"
+        )));
+    }
+    let (current, previous) = compare_ranges(query.period, query.offset, query.full, Utc::now());
+    let summary = |(from, to): Range| ReportRequest {
+        from: Some(from),
+        to: Some(to),
+        project: query.project.clone(),
+        language: query.language.clone(),
+    };
+    let current = get_summary(pool, user_id, &summary(current), idle_timeout).await?;
+    let previous = get_summary(pool, user_id, &summary(previous), idle_timeout).await?;
+
+    Ok(CompareReport {
+        period: query.period,
+        offset: query.offset,
+        total: CompareEntry::new("total", current.total_seconds, previous.total_seconds),
+        projects: compare_entries(&current.projects, &previous.projects),
+        languages: compare_entries(&current.languages, &previous.languages),
+        current,
+        previous,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn ranges(period: ComparePeriod, offset: u32, full: bool, now: &str) -> [String; 4] {
+        let ((from, to), (prev_from, prev_to)) = compare_ranges(period, offset, full, at(now));
+        [from, to, prev_from, prev_to].map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+    }
+
+    #[test]
+    fn earlier_periods_are_cut_at_the_same_point() {
+        // Wednesday noon against last Wednesday noon
+        assert_eq!(
+            ranges(ComparePeriod::Week, 1, false, "2026-03-04T12:00:00Z"),
+            ["2026-03-02 00:00", "2026-03-04 12:00", "2026-02-23 00:00", "2026-02-25 12:00"]
+        );
+        assert_eq!(
+            ranges(ComparePeriod::Week, 1, true, "2026-03-04T12:00:00Z")[3],
+            "2026-03-02 00:00"
+        );
+        assert_eq!(
+            ranges(ComparePeriod::Day, 7, false, "2026-03-04T09:30:00Z"),
+            ["2026-03-04 00:00", "2026-03-04 09:30", "2026-02-25 00:00", "2026-02-25 09:30"]
+        );
+        // The same month last year
+        assert_eq!(
+            ranges(ComparePeriod::Month, 12, true, "2026-03-31T23:00:00Z"),
+            ["2026-03-01 00:00", "2026-03-31 23:00", "2025-03-01 00:00", "2025-04-01 00:00"]
+        );
+        // March 31st against a February that has no 31st
+        assert_eq!(
+            ranges(ComparePeriod::Month, 1, false, "2026-03-31T10:00:00Z")[2..],
+            ["2026-02-01 00:00".to_string(), "2026-03-01 00:00".to_string()]
+        );
+        assert_eq!(
+            ranges(ComparePeriod::Year, 1, false, "2026-02-01T00:00:00Z")[2..],
+            ["2025-01-01 00:00".to_string(), "2025-02-01 00:00".to_string()]
+        );
+    }
+
+    #[test]
+    fn entries_cover_both_periods() {
+        let category = |name: &str, total_seconds: f64| CategorySummary {
+            name: name.into(),
+            total_seconds,
+            percent: 0.0,
+        };
+        let now = [category("app", 5400.0), category("new", 600.0)];
+        let before = [category("old", 1200.0), category("app", 3600.0)];
+        let entries = compare_entries(&now, &before);
+
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["app", "new", "old"]);
+        assert_eq!(entries[0].delta_seconds, 1800.0);
+        assert_eq!(entries[0].percent_change, Some(50.0));
+        assert_eq!(entries[1].percent_change, None);
+        assert_eq!(entries[2], CompareEntry::new("old", 0.0, 1200.0));
+        assert_eq!(entries[2].percent_change, Some(-100.0));
+    }
+}
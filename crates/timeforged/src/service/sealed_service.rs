@@ -0,0 +1,109 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use timeforged_core::api::{
+    BatchEventResponse, EventCursor, SealedBatchRequest, SealedEvent, SealedExportQuery,
+    SealedExportResponse, SealedKeyInfo,
+};
+use timeforged_core::error::AppError;
+
+use crate::storage::sqlite;
+
+/// Largest blob accepted, base64. Far above any event the API takes in the
+/// clear, sealed.
+const MAX_BLOB_LEN: usize = 64 * 1024;
+
+/// Whether the user keeps their events here sealed. Their cards and reports
+/// would be computed from nothing, so they aren't offered.
+pub async fn is_encrypted(pool: &SqlitePool, user_id: Uuid) -> Result<bool, AppError> {
+    Ok(sqlite::get_sealed_key(pool, user_id).await?.is_some())
+}
+
+pub async fn get_key(pool: &SqlitePool, user_id: Uuid) -> Result<SealedKeyInfo, AppError> {
+    sqlite::get_sealed_key(pool, user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("encryption is not set up".into()))
+}
+
+/// Set the user's key info once. If another machine got there first, its
+/// info is kept and returned, so the caller checks its passphrase against
+/// that.
+pub async fn set_key(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    info: SealedKeyInfo,
+) -> Result<SealedKeyInfo, AppError> {
+    if info.salt.is_empty() || info.check.is_empty() {
+        return Err(AppError::Validation("salt and check are required".into()));
+    }
+    if info.salt.len() > 256 || info.check.len() > 1024 {
+        return Err(AppError::Validation("salt or check too long".into()));
+    }
+    sqlite::insert_sealed_key(pool, user_id, &info).await?;
+    get_key(pool, user_id).await
+}
+
+pub async fn store_batch(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    req: SealedBatchRequest,
+) -> Result<BatchEventResponse, AppError> {
+    if req.events.len() > 100 {
+        return Err(AppError::BadRequest("batch size exceeds 100".into()));
+    }
+    // Blobs nobody else could ever open are worse than none
+    if !is_encrypted(pool, user_id).await? {
+        return Err(AppError::BadRequest(
+            "encryption is not set up (PUT /api/v1/sealed/key first)".into(),
+        ));
+    }
+
+    let mut resp = BatchEventResponse {
+        accepted: 0,
+        rejected: 0,
+        duplicates: 0,
+    };
+    for event in &req.events {
+        if validate(event).is_err() {
+            resp.rejected += 1;
+        } else if sqlite::insert_sealed_event(pool, user_id, event).await? {
+            resp.accepted += 1;
+        } else {
+            resp.duplicates += 1;
+        }
+    }
+    Ok(resp)
+}
+
+fn validate(event: &SealedEvent) -> Result<(), AppError> {
+    if event.key.is_empty() || event.key.len() > 128 {
+        return Err(AppError::Validation("invalid key".into()));
+    }
+    if event.blob.is_empty() || event.blob.len() > MAX_BLOB_LEN {
+        return Err(AppError::Validation("invalid blob".into()));
+    }
+    Ok(())
+}
+
+/// `GET /api/v1/sealed`: pages by cursor, like the plain event export.
+pub async fn export(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    query: SealedExportQuery,
+) -> Result<SealedExportResponse, AppError> {
+    let limit = query.limit.clamp(1, 5000);
+    let after = EventCursor::decode(query.cursor.as_deref().unwrap_or_default())
+        .ok_or_else(|| AppError::BadRequest("invalid cursor".into()))?;
+
+    // One extra row tells whether another page is waiting
+    let mut events = sqlite::list_sealed_events_after(pool, user_id, after.0, limit + 1).await?;
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+
+    let next = events.last().and_then(|e| e.id).map(EventCursor).unwrap_or(after);
+    Ok(SealedExportResponse {
+        events,
+        next_cursor: next.encode(),
+        has_more,
+    })
+}
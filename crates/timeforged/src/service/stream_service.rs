@@ -0,0 +1,309 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use timeforged_core::api::{StreamEvent, TodayTotals};
+use timeforged_core::error::AppError;
+use timeforged_core::models::{Event, ReportRequest, Session};
+
+use crate::bus::EventBus;
+use crate::storage::sqlite;
+
+/// Totals are recomputed at most this often per user, however many events
+/// arrive (a 100-event batch causes one query, not 100).
+const TOTALS_DEBOUNCE: Duration = Duration::from_secs(2);
+/// How often open sessions are checked for having gone idle.
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(15);
+/// How long a stream token can be redeemed for.
+const TOKEN_TTL: Duration = Duration::from_secs(60);
+
+/// Today's totals (UTC day, same as the tray and Waybar module).
+pub async fn today_totals(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    idle_timeout: u64,
+) -> Result<TodayTotals, AppError> {
+    let now = Utc::now();
+    let date = now.date_naive();
+    let req = ReportRequest {
+        from: Some(date.and_hms_opt(0, 0, 0).unwrap().and_utc()),
+        to: Some(now),
+        project: None,
+        language: None,
+    };
+    let summary = sqlite::get_summary(pool, user_id, &req, idle_timeout).await?;
+
+    Ok(TodayTotals {
+        date,
+        total_seconds: summary.total_seconds,
+        projects: summary.projects,
+        languages: summary.languages,
+    })
+}
+
+struct OpenSession {
+    start: DateTime<Utc>,
+    last: DateTime<Utc>,
+    project: Option<String>,
+    event_count: i64,
+}
+
+impl OpenSession {
+    fn close(self) -> Session {
+        Session {
+            start: self.start,
+            end: self.last,
+            duration_seconds: (self.last - self.start).num_milliseconds() as f64 / 1000.0,
+            project: self.project,
+            event_count: self.event_count,
+        }
+    }
+}
+
+/// Live session boundaries, using the same idle-gap rule as the sessions
+/// report: a gap of `idle_timeout` or more ends a session.
+pub struct SessionTracker {
+    idle_timeout: chrono::Duration,
+    open: HashMap<Uuid, OpenSession>,
+}
+
+impl SessionTracker {
+    pub fn new(idle_timeout: u64) -> Self {
+        Self {
+            idle_timeout: chrono::Duration::seconds(idle_timeout as i64),
+            open: HashMap::new(),
+        }
+    }
+
+    /// Feed a newly recorded event. Backfilled events (sync pulls, imports)
+    /// that are already older than the idle timeout don't affect live
+    /// sessions.
+    pub fn observe(&mut self, event: &Event, now: DateTime<Utc>) -> Vec<StreamEvent> {
+        if now - event.timestamp >= self.idle_timeout {
+            return Vec::new();
+        }
+
+        let mut out = Vec::new();
+        if let Some(session) = self.open.get_mut(&event.user_id) {
+            if event.timestamp - session.last < self.idle_timeout {
+                session.last = session.last.max(event.timestamp);
+                session.event_count += 1;
+                return out;
+            }
+            let ended = self.open.remove(&event.user_id).unwrap();
+            out.push(StreamEvent::SessionEnded(ended.close()));
+        }
+
+        self.open.insert(
+            event.user_id,
+            OpenSession {
+                start: event.timestamp,
+                last: event.timestamp,
+                project: event.project.clone(),
+                event_count: 1,
+            },
+        );
+        out.push(StreamEvent::SessionStarted {
+            start: event.timestamp,
+            project: event.project.clone(),
+        });
+        out
+    }
+
+    /// Close sessions that have been idle for the timeout.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<(Uuid, StreamEvent)> {
+        let idle: Vec<Uuid> = self
+            .open
+            .iter()
+            .filter(|(_, s)| now - s.last >= self.idle_timeout)
+            .map(|(user_id, _)| *user_id)
+            .collect();
+
+        idle.into_iter()
+            .filter_map(|user_id| {
+                let session = self.open.remove(&user_id)?;
+                Some((user_id, StreamEvent::SessionEnded(session.close())))
+            })
+            .collect()
+    }
+}
+
+/// Background task: turns raw events on the bus into session start/end
+/// notifications and debounced totals for today.
+pub async fn run(pool: SqlitePool, bus: EventBus, idle_timeout: u64) {
+    let mut rx = bus.subscribe();
+    let mut tracker = SessionTracker::new(idle_timeout);
+    let mut dirty: HashSet<Uuid> = HashSet::new();
+    let mut totals_tick = tokio::time::interval(TOTALS_DEBOUNCE);
+    let mut expire_tick = tokio::time::interval(EXPIRE_INTERVAL);
+
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(msg) => {
+                    if let StreamEvent::Event(event) = &msg.event {
+                        for out in tracker.observe(event, Utc::now()) {
+                            bus.publish(msg.user_id, out);
+                        }
+                        dirty.insert(msg.user_id);
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    tracing::debug!("stream: session tracker lagged by {n} messages");
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = totals_tick.tick() => {
+                for user_id in dirty.drain() {
+                    match today_totals(&pool, user_id, idle_timeout).await {
+                        Ok(totals) => bus.publish(user_id, StreamEvent::Totals(totals)),
+                        Err(e) => tracing::warn!("stream: failed to compute totals: {e}"),
+                    }
+                }
+            }
+            _ = expire_tick.tick() => {
+                for (user_id, ended) in tracker.expire(Utc::now()) {
+                    bus.publish(user_id, ended);
+                }
+            }
+        }
+    }
+}
+
+/// Stream tokens, so browsers can open a stream without putting their API
+/// key in the URL (where proxies and logs would keep it). Each is good for
+/// one stream opened within [`TOKEN_TTL`]; they live in memory only, so a
+/// restart voids them.
+#[derive(Clone, Default)]
+pub struct StreamTokens {
+    tokens: Arc<Mutex<HashMap<String, Issued>>>,
+}
+
+struct Issued {
+    user_id: Uuid,
+    expires_at: DateTime<Utc>,
+}
+
+impl StreamTokens {
+    /// A new token for `user_id`, and when it expires.
+    pub fn issue(&self, user_id: Uuid, now: DateTime<Utc>) -> (String, DateTime<Utc>) {
+        let token = format!("st_{}", Uuid::new_v4().simple());
+        let expires_at = now + TOKEN_TTL;
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, issued| issued.expires_at > now);
+        tokens.insert(token.clone(), Issued { user_id, expires_at });
+        (token, expires_at)
+    }
+
+    /// The user `token` was issued to, if it's unused and unexpired. Either
+    /// way it can't be redeemed again.
+    pub fn redeem(&self, token: &str, now: DateTime<Utc>) -> Option<Uuid> {
+        let issued = self.tokens.lock().unwrap().remove(token)?;
+        (issued.expires_at > now).then_some(issued.user_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use timeforged_core::models::EventType;
+
+    fn event(user_id: Uuid, at: DateTime<Utc>) -> Event {
+        Event {
+            id: Some(1),
+            user_id,
+            timestamp: at,
+            event_type: EventType::File,
+            entity: "main.rs".into(),
+            project: Some("app".into()),
+            language: None,
+            branch: None,
+            activity: None,
+            machine: None,
+            metadata: None,
+            created_at: None,
+        }
+    }
+
+    fn kinds(events: &[StreamEvent]) -> Vec<&'static str> {
+        events.iter().map(|e| e.kind()).collect()
+    }
+
+    #[test]
+    fn first_event_starts_session() {
+        let mut tracker = SessionTracker::new(300);
+        let now = Utc::now();
+        let out = tracker.observe(&event(Uuid::nil(), now), now);
+        assert_eq!(kinds(&out), ["session_started"]);
+
+        // Within the timeout: same session, nothing new
+        let later = now + chrono::Duration::seconds(60);
+        assert!(tracker.observe(&event(Uuid::nil(), later), later).is_empty());
+    }
+
+    #[test]
+    fn gap_ends_and_restarts_session() {
+        let mut tracker = SessionTracker::new(300);
+        let t0 = Utc::now();
+        tracker.observe(&event(Uuid::nil(), t0), t0);
+        let t1 = t0 + chrono::Duration::seconds(120);
+        tracker.observe(&event(Uuid::nil(), t1), t1);
+
+        let t2 = t1 + chrono::Duration::seconds(600);
+        let out = tracker.observe(&event(Uuid::nil(), t2), t2);
+        assert_eq!(kinds(&out), ["session_ended", "session_started"]);
+        match &out[0] {
+            StreamEvent::SessionEnded(s) => {
+                assert_eq!(s.duration_seconds, 120.0);
+                assert_eq!(s.event_count, 2);
+                assert_eq!(s.project.as_deref(), Some("app"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn expire_closes_idle_sessions_only() {
+        let mut tracker = SessionTracker::new(300);
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let t0 = Utc::now();
+        tracker.observe(&event(a, t0), t0);
+        let t1 = t0 + chrono::Duration::seconds(200);
+        tracker.observe(&event(b, t1), t1);
+
+        let ended = tracker.expire(t0 + chrono::Duration::seconds(310));
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].0, a);
+        assert!(tracker.expire(t0 + chrono::Duration::seconds(310)).is_empty());
+    }
+
+    #[test]
+    fn backfilled_events_are_ignored() {
+        let mut tracker = SessionTracker::new(300);
+        let now = Utc::now();
+        let old = now - chrono::Duration::hours(3);
+        assert!(tracker.observe(&event(Uuid::nil(), old), now).is_empty());
+        assert!(tracker.expire(now).is_empty());
+    }
+
+    #[test]
+    fn stream_tokens_are_single_use_and_expire() {
+        let tokens = StreamTokens::default();
+        let user_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        let (token, expires_at) = tokens.issue(user_id, now);
+        assert_eq!(expires_at, now + TOKEN_TTL);
+        assert_eq!(tokens.redeem(&token, now), Some(user_id));
+        assert_eq!(tokens.redeem(&token, now), None);
+
+        let (token, expires_at) = tokens.issue(user_id, now);
+        assert_eq!(tokens.redeem(&token, expires_at), None);
+        assert_eq!(tokens.redeem("st_guess", now), None);
+    }
+}
@@ -78,6 +78,14 @@ pub async fn get_first_user(pool: &SqlitePool) -> Result<User, AppError> {
         .ok_or(AppError::NotFound("no users".into()))
 }
 
+/// Daemon maintenance is for the first user, the admin created on first run.
+pub async fn require_admin(pool: &SqlitePool, user: &User) -> Result<(), AppError> {
+    if get_first_user(pool).await?.id != user.id {
+        return Err(AppError::Forbidden("only the admin can do this".into()));
+    }
+    Ok(())
+}
+
 pub async fn ensure_admin(pool: &SqlitePool) -> Result<Option<String>, AppError> {
     let count = sqlite::count_users(pool).await?;
     if count > 0 {
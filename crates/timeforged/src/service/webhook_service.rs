@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use timeforged_core::api::{
+    CreateWebhookRequest, CreateWebhookResponse, StreamEvent, WebhookPayload,
+};
+use timeforged_core::error::AppError;
+use timeforged_core::models::{
+    DeliveryStatus, ReportRequest, Webhook, WebhookDelivery, WebhookTrigger,
+};
+
+use crate::bus::EventBus;
+use crate::storage::sqlite;
+
+/// Attempts per delivery before it is marked failed.
+pub const MAX_ATTEMPTS: i64 = 8;
+/// Delay after the first failed attempt; doubles each time (30s, 1m, 2m, ...).
+const BASE_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 6 * 3600;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the queue is checked for due deliveries.
+const DELIVER_INTERVAL: Duration = Duration::from_secs(5);
+const DELIVERY_BATCH: i64 = 50;
+/// Delivered and failed entries are dropped from the log after this long.
+const DELIVERY_RETENTION_DAYS: i64 = 30;
+const MAX_WEBHOOKS_PER_USER: usize = 20;
+const DELIVERY_LOG_LIMIT: i64 = 50;
+
+pub fn generate_secret() -> String {
+    format!("whsec_{}", Uuid::new_v4().simple())
+}
+
+/// Hex HMAC-SHA256 of `<timestamp>.<body>`. Including the timestamp lets
+/// receivers reject replays of old deliveries.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Delay before the next attempt, after `attempts` failed ones.
+pub fn backoff(attempts: i64) -> chrono::Duration {
+    let exp = (attempts - 1).clamp(0, 20) as u32;
+    chrono::Duration::seconds((BASE_BACKOFF_SECS << exp).min(MAX_BACKOFF_SECS))
+}
+
+/// Whether `ip` is on the public internet: not loopback, private,
+/// link-local (where cloud metadata services listen) or otherwise reserved
+/// for local use.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space of carrier-grade NAT
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(v4.into());
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local and link-local
+                || first & 0xfe00 == 0xfc00
+                || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+/// Check a receiver URL. Unless `allow_private`, a host given as an
+/// address must be public; names are checked when they're resolved, at
+/// delivery.
+fn validate_url(url: &str, allow_private: bool) -> Result<(), AppError> {
+    if url.len() > 2048 {
+        return Err(AppError::Validation("url must be at most 2048 characters".into()));
+    }
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| AppError::Validation(format!("invalid url: {e}")))?;
+    let Some(host) = parsed.host_str().filter(|_| matches!(parsed.scheme(), "http" | "https"))
+    else {
+        return Err(AppError::Validation("url must be an http(s) URL".into()));
+    };
+    if allow_private {
+        return Ok(());
+    }
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let local = match host.parse::<IpAddr>() {
+        Ok(ip) => !is_public(ip),
+        Err(_) => host.eq_ignore_ascii_case("localhost") || host.ends_with(".localhost"),
+    };
+    if local {
+        return Err(AppError::Validation(
+            "url must point to a public address; set [webhooks] allow_private to post to \
+             local receivers"
+                .into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves receiver names to their public addresses only, so a name can't
+/// lead a delivery into the local network.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} has no public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The client deliveries are sent with. Redirects aren't followed, since
+/// they could lead anywhere; unless `allow_private`, names only resolve to
+/// public addresses.
+pub fn http_client(allow_private: bool) -> Client {
+    let builder = Client::builder().redirect(Policy::none());
+    let builder = if allow_private {
+        builder
+    } else {
+        builder.dns_resolver(Arc::new(PublicResolver))
+    };
+    builder.build().expect("webhook HTTP client")
+}
+
+pub async fn create_webhook(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    req: CreateWebhookRequest,
+    allow_private: bool,
+) -> Result<CreateWebhookResponse, AppError> {
+    validate_url(&req.url, allow_private)?;
+
+    let mut triggers = if req.triggers.is_empty() {
+        WebhookTrigger::SUBSCRIBABLE.to_vec()
+    } else {
+        req.triggers
+    };
+    if triggers.contains(&WebhookTrigger::Ping) {
+        return Err(AppError::Validation(
+            "ping is sent on demand and can't be subscribed to".into(),
+        ));
+    }
+    triggers.sort_by_key(|t| t.as_str());
+    triggers.dedup();
+
+    if sqlite::list_webhooks(pool, user_id).await?.len() >= MAX_WEBHOOKS_PER_USER {
+        return Err(AppError::Validation(format!(
+            "at most {MAX_WEBHOOKS_PER_USER} webhooks per user"
+        )));
+    }
+
+    let secret = generate_secret();
+    let hook = sqlite::create_webhook(pool, user_id, &req.url, &secret, &triggers).await?;
+
+    Ok(CreateWebhookResponse {
+        id: hook.id,
+        url: hook.url,
+        triggers: hook.triggers,
+        secret,
+    })
+}
+
+pub async fn list_webhooks(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<Webhook>, AppError> {
+    sqlite::list_webhooks(pool, user_id).await
+}
+
+pub async fn delete_webhook(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<(), AppError> {
+    if !sqlite::delete_webhook(pool, user_id, webhook_id).await? {
+        return Err(AppError::NotFound("webhook not found".into()));
+    }
+    Ok(())
+}
+
+async fn owned_webhook(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<Webhook, AppError> {
+    sqlite::get_webhook(pool, user_id, webhook_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("webhook not found".into()))
+}
+
+/// Most recent deliveries first.
+pub async fn list_deliveries(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<Vec<WebhookDelivery>, AppError> {
+    owned_webhook(pool, user_id, webhook_id).await?;
+    sqlite::list_deliveries(pool, webhook_id, DELIVERY_LOG_LIMIT).await
+}
+
+/// Queue a `ping` delivery so a receiver can be checked end to end.
+pub async fn send_test(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<WebhookDelivery, AppError> {
+    let hook = owned_webhook(pool, user_id, webhook_id).await?;
+    let data = serde_json::json!({ "message": "TimeForged webhook test" });
+    let (id, created_at) = enqueue(pool, &hook, WebhookTrigger::Ping, data).await?;
+
+    Ok(WebhookDelivery {
+        id,
+        webhook_id,
+        trigger: WebhookTrigger::Ping,
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        response_status: None,
+        error: None,
+        created_at,
+        last_attempt_at: None,
+        next_attempt_at: Some(created_at),
+    })
+}
+
+async fn enqueue(
+    pool: &SqlitePool,
+    hook: &Webhook,
+    trigger: WebhookTrigger,
+    data: serde_json::Value,
+) -> Result<(Uuid, DateTime<Utc>), AppError> {
+    let payload = WebhookPayload {
+        id: Uuid::new_v4(),
+        event: trigger,
+        created_at: Utc::now(),
+        data,
+    };
+    let body = serde_json::to_string(&payload).map_err(|e| AppError::Internal(e.to_string()))?;
+    sqlite::insert_delivery(pool, payload.id, hook.id, trigger, &body, payload.created_at).await?;
+    Ok((payload.id, payload.created_at))
+}
+
+/// Queue `data` for every webhook subscribed to `trigger` (of one user, or
+/// of everyone when `user_id` is `None`).
+pub async fn dispatch(
+    pool: &SqlitePool,
+    user_id: Option<Uuid>,
+    trigger: WebhookTrigger,
+    data: serde_json::Value,
+) -> Result<usize, AppError> {
+    let hooks = sqlite::webhooks_for_trigger(pool, user_id, trigger).await?;
+    for hook in &hooks {
+        enqueue(pool, hook, trigger, data.clone()).await?;
+    }
+    Ok(hooks.len())
+}
+
+/// Bus messages that fire a webhook trigger.
+fn trigger_for(event: &StreamEvent) -> Option<(WebhookTrigger, serde_json::Value)> {
+    match event {
+        StreamEvent::SessionEnded(session) => {
+            Some((WebhookTrigger::SessionEnded, serde_json::to_value(session).ok()?))
+        }
+        StreamEvent::BatchIngested { accepted, rejected } => Some((
+            WebhookTrigger::EventsIngested,
+            serde_json::json!({ "accepted": accepted, "rejected": rejected }),
+        )),
+        _ => None,
+    }
+}
+
+/// Send every due delivery once, with a client from [`http_client`].
+/// Returns how many were attempted.
+pub async fn deliver_due(
+    pool: &SqlitePool,
+    http: &Client,
+    allow_private: bool,
+) -> Result<usize, AppError> {
+    let due = sqlite::due_deliveries(pool, Utc::now(), DELIVERY_BATCH).await?;
+
+    for delivery in &due {
+        // Webhooks may predate the rules, or the config may have changed
+        let result = match validate_url(&delivery.url, allow_private) {
+            Ok(()) => post(http, delivery).await,
+            Err(e) => Err((None, e.to_string())),
+        };
+        let now = Utc::now();
+        let attempts = delivery.attempts + 1;
+
+        let (status, response_status, error, next) = match result {
+            Ok(code) => (DeliveryStatus::Delivered, Some(code), None, None),
+            Err((code, error)) if attempts >= MAX_ATTEMPTS => {
+                tracing::warn!("webhook delivery {} failed for good: {error}", delivery.id);
+                (DeliveryStatus::Failed, code, Some(error), None)
+            }
+            Err((code, error)) => {
+                tracing::debug!("webhook delivery {} failed: {error}", delivery.id);
+                (DeliveryStatus::Pending, code, Some(error), Some(now + backoff(attempts)))
+            }
+        };
+
+        sqlite::record_delivery_attempt(
+            pool,
+            delivery.id,
+            status,
+            response_status,
+            error.as_deref(),
+            now,
+            next,
+        )
+        .await?;
+    }
+
+    Ok(due.len())
+}
+
+async fn post(http: &Client, delivery: &sqlite::DueDelivery) -> Result<u16, (Option<u16>, String)> {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(&delivery.secret, timestamp, &delivery.payload);
+
+    let resp = http
+        .post(&delivery.url)
+        .timeout(DELIVERY_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", format!("TimeForged/{}", timeforged_core::VERSION))
+        .header("X-TimeForged-Event", delivery.trigger.as_str())
+        .header("X-TimeForged-Delivery", delivery.id.to_string())
+        .header("X-TimeForged-Timestamp", timestamp.to_string())
+        .header("X-TimeForged-Signature", format!("sha256={signature}"))
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+
+    let status = resp.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err((Some(status.as_u16()), format!("HTTP {status}")))
+    }
+}
+
+/// Queue yesterday's summary for every `daily_summary` webhook.
+async fn send_daily_summaries(
+    pool: &SqlitePool,
+    date: NaiveDate,
+    idle_timeout: u64,
+) -> Result<(), AppError> {
+    let hooks = sqlite::webhooks_for_trigger(pool, None, WebhookTrigger::DailySummary).await?;
+
+    let mut by_user: HashMap<Uuid, Vec<Webhook>> = HashMap::new();
+    for hook in hooks {
+        by_user.entry(hook.user_id).or_default().push(hook);
+    }
+
+    let from = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let req = ReportRequest {
+        from: Some(from),
+        to: Some(from + chrono::Duration::days(1)),
+        project: None,
+        language: None,
+    };
+
+    for (user_id, hooks) in by_user {
+        let summary = sqlite::get_summary(pool, user_id, &req, idle_timeout).await?;
+        let data = serde_json::json!({ "date": date, "summary": summary });
+        for hook in &hooks {
+            enqueue(pool, hook, WebhookTrigger::DailySummary, data.clone()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Background task: turns bus messages and the daily schedule into queued
+/// deliveries, and sends due deliveries with retries.
+pub async fn run(pool: SqlitePool, bus: EventBus, idle_timeout: u64, allow_private: bool) {
+    let http = http_client(allow_private);
+    let mut rx = bus.subscribe();
+    let mut deliver_tick = tokio::time::interval(DELIVER_INTERVAL);
+    let mut daily_tick = tokio::time::interval(Duration::from_secs(60));
+    let mut today = Utc::now().date_naive();
+
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(msg) => {
+                    if let Some((trigger, data)) = trigger_for(&msg.event)
+                        && let Err(e) = dispatch(&pool, Some(msg.user_id), trigger, data).await
+                    {
+                        tracing::warn!("webhooks: failed to queue {}: {e}", trigger.as_str());
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    tracing::warn!("webhooks: missed {n} bus messages");
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = deliver_tick.tick() => {
+                if let Err(e) = deliver_due(&pool, &http, allow_private).await {
+                    tracing::warn!("webhooks: delivery failed: {e}");
+                }
+            }
+            _ = daily_tick.tick() => {
+                let now = Utc::now();
+                if now.date_naive() > today {
+                    if let Err(e) = send_daily_summaries(&pool, today, idle_timeout).await {
+                        tracing::warn!("webhooks: failed to queue daily summaries: {e}");
+                    }
+                    let cutoff = now - chrono::Duration::days(DELIVERY_RETENTION_DAYS);
+                    if let Err(e) = sqlite::prune_deliveries(&pool, cutoff).await {
+                        tracing::warn!("webhooks: failed to prune delivery log: {e}");
+                    }
+                    today = now.date_naive();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{Router, extract::State, http::HeaderMap, http::StatusCode, routing::post};
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use timeforged_core::models::Session;
+
+    use super::*;
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+    /// Local receiver that records requests and answers with `status`.
+    async fn mock_receiver(status: StatusCode) -> (String, Received) {
+        let received: Received = Arc::default();
+        let record = move |State(rx): State<Received>, headers: HeaderMap, body: String| {
+            rx.lock().unwrap().push((headers, body));
+            async move { status }
+        };
+        let app = Router::new()
+            .route("/hook", post(record))
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}/hook"), received)
+    }
+
+    async fn setup() -> (SqlitePool, Uuid) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        (pool, user.id)
+    }
+
+    fn request(url: &str, triggers: Vec<WebhookTrigger>) -> CreateWebhookRequest {
+        CreateWebhookRequest { url: url.into(), triggers }
+    }
+
+    fn session() -> Session {
+        let end = Utc::now();
+        Session {
+            start: end - chrono::Duration::minutes(90),
+            end,
+            duration_seconds: 5400.0,
+            project: Some("app".into()),
+            event_count: 42,
+        }
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload() {
+        let (pool, user_id) = setup().await;
+        let (url, received) = mock_receiver(StatusCode::OK).await;
+        let hook = create_webhook(&pool, user_id, request(&url, vec![]), true).await.unwrap();
+
+        let (trigger, data) = trigger_for(&StreamEvent::SessionEnded(session())).unwrap();
+        assert_eq!(dispatch(&pool, Some(user_id), trigger, data).await.unwrap(), 1);
+        assert_eq!(deliver_due(&pool, &http_client(true), true).await.unwrap(), 1);
+
+        let (headers, body) = received.lock().unwrap()[0].clone();
+        let header = |name: &str| headers.get(name).unwrap().to_str().unwrap();
+        assert_eq!(header("x-timeforged-event"), "session_ended");
+        let timestamp: i64 = header("x-timeforged-timestamp").parse().unwrap();
+        assert_eq!(
+            header("x-timeforged-signature"),
+            format!("sha256={}", sign(&hook.secret, timestamp, &body))
+        );
+
+        let payload: WebhookPayload = serde_json::from_str(&body).unwrap();
+        assert_eq!(payload.event, WebhookTrigger::SessionEnded);
+        assert_eq!(payload.id.to_string(), header("x-timeforged-delivery"));
+        assert_eq!(payload.data["event_count"], 42);
+
+        let log = list_deliveries(&pool, user_id, hook.id).await.unwrap();
+        assert_eq!(log[0].status, DeliveryStatus::Delivered);
+        assert_eq!(log[0].attempts, 1);
+        assert_eq!(log[0].response_status, Some(200));
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried_later() {
+        let (pool, user_id) = setup().await;
+        let (url, received) = mock_receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+        let hook = create_webhook(&pool, user_id, request(&url, vec![]), true).await.unwrap();
+
+        send_test(&pool, user_id, hook.id).await.unwrap();
+        assert_eq!(deliver_due(&pool, &http_client(true), true).await.unwrap(), 1);
+        // Backing off: nothing due right away
+        assert_eq!(deliver_due(&pool, &http_client(true), true).await.unwrap(), 0);
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let log = list_deliveries(&pool, user_id, hook.id).await.unwrap();
+        assert_eq!(log[0].trigger, WebhookTrigger::Ping);
+        assert_eq!(log[0].status, DeliveryStatus::Pending);
+        assert_eq!(log[0].response_status, Some(500));
+        assert!(log[0].next_attempt_at.unwrap() > Utc::now() + chrono::Duration::seconds(20));
+    }
+
+    #[tokio::test]
+    async fn dispatch_only_reaches_subscribers() {
+        let (pool, user_id) = setup().await;
+        let (url, received) = mock_receiver(StatusCode::OK).await;
+        let daily = request(&url, vec![WebhookTrigger::DailySummary]);
+        create_webhook(&pool, user_id, daily, true).await.unwrap();
+
+        let (trigger, data) =
+            trigger_for(&StreamEvent::BatchIngested { accepted: 3, rejected: 0 }).unwrap();
+        assert_eq!(trigger, WebhookTrigger::EventsIngested);
+        assert_eq!(dispatch(&pool, Some(user_id), trigger, data).await.unwrap(), 0);
+
+        send_daily_summaries(&pool, Utc::now().date_naive(), 300).await.unwrap();
+        deliver_due(&pool, &http_client(true), true).await.unwrap();
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let payload: WebhookPayload = serde_json::from_str(&received[0].1).unwrap();
+        assert_eq!(payload.event, WebhookTrigger::DailySummary);
+        assert_eq!(payload.data["summary"]["total_seconds"], 0.0);
+    }
+
+    #[tokio::test]
+    async fn create_validates_input() {
+        let (pool, user_id) = setup().await;
+        for url in ["ftp://example.com/hook", "not a url", "http://"] {
+            assert!(create_webhook(&pool, user_id, request(url, vec![]), true).await.is_err());
+        }
+        let ping = request("https://example.com", vec![WebhookTrigger::Ping]);
+        assert!(create_webhook(&pool, user_id, ping, false).await.is_err());
+
+        let all = create_webhook(&pool, user_id, request("https://example.com", vec![]), false)
+            .await
+            .unwrap();
+        assert_eq!(all.triggers.len(), WebhookTrigger::SUBSCRIBABLE.len());
+    }
+
+    #[tokio::test]
+    async fn local_receivers_need_opting_in() {
+        let (pool, user_id) = setup().await;
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.10/hook",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://localhost:9000/hook",
+            "http://api.localhost/hook",
+        ] {
+            let err = create_webhook(&pool, user_id, request(url, vec![]), false).await;
+            assert!(matches!(err, Err(AppError::Validation(_))), "{url}");
+            create_webhook(&pool, user_id, request(url, vec![]), true).await.unwrap();
+        }
+        assert!(is_public("93.184.215.14".parse().unwrap()));
+        assert!(is_public("2606:4700::1111".parse().unwrap()));
+
+        // Names are checked once resolved
+        let resolved = PublicResolver.resolve("localhost".parse().unwrap()).await;
+        assert!(resolved.is_err());
+    }
+
+    #[tokio::test]
+    async fn delivery_to_local_receivers_fails_unless_allowed() {
+        let (pool, user_id) = setup().await;
+        let (url, received) = mock_receiver(StatusCode::OK).await;
+        let hook = create_webhook(&pool, user_id, request(&url, vec![]), true).await.unwrap();
+        send_test(&pool, user_id, hook.id).await.unwrap();
+
+        // Created while allowed, delivered after the config changed
+        assert_eq!(deliver_due(&pool, &http_client(false), false).await.unwrap(), 1);
+        assert!(received.lock().unwrap().is_empty());
+        let log = list_deliveries(&pool, user_id, hook.id).await.unwrap();
+        assert_eq!(log[0].status, DeliveryStatus::Pending);
+        assert!(log[0].error.as_deref().unwrap().contains("public address"));
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff(1).num_seconds(), 30);
+        assert_eq!(backoff(2).num_seconds(), 60);
+        assert_eq!(backoff(4).num_seconds(), 240);
+        assert_eq!(backoff(MAX_ATTEMPTS + 20).num_seconds(), MAX_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn signature_is_hmac_sha256() {
+        // Same as: printf '1700000000.{"id":1}' | openssl dgst -sha256 -hmac whsec_test
+        assert_eq!(
+            sign("whsec_test", 1_700_000_000, r#"{"id":1}"#),
+            "2f441ba4b3b2d50d28a9ab9d9fd8880376ecd1eb5d0435401553f5d8d0a5dcf8"
+        );
+        assert_ne!(sign("a", 1, "body"), sign("a", 2, "body"));
+    }
+}
@@ -0,0 +1,312 @@
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use uuid::Uuid;
+
+use timeforged_core::config::SmtpConfig;
+use timeforged_core::error::AppError;
+
+/// A whole exchange, from connecting to QUIT.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A mail with a plain text and an HTML version of the same content.
+pub struct Mail<'a> {
+    pub to: &'a str,
+    pub subject: &'a str,
+    pub text: &'a str,
+    pub html: &'a str,
+}
+
+/// Hand `mail` to the configured server. Just enough SMTP for a local relay:
+/// EHLO, AUTH PLAIN when a username is set, one recipient, no TLS. Without
+/// TLS the password would cross the network in the clear, so AUTH is only
+/// sent to a server on a loopback address.
+pub async fn send(config: &SmtpConfig, mail: &Mail<'_>) -> Result<(), AppError> {
+    tokio::time::timeout(TIMEOUT, exchange(config, mail))
+        .await
+        .map_err(|_| AppError::Internal("smtp: timed out".into()))?
+        .map_err(|e| AppError::Internal(format!("smtp: {e}")))
+}
+
+async fn exchange(config: &SmtpConfig, mail: &Mail<'_>) -> Result<(), String> {
+    let stream = TcpStream::connect((config.host.as_str(), config.port))
+        .await
+        .map_err(|e| format!("connecting to {}:{}: {e}", config.host, config.port))?;
+    if config.username.is_some() {
+        let peer = stream.peer_addr().map_err(|e| e.to_string())?;
+        if !peer.ip().is_loopback() {
+            return Err(format!(
+                "not logging in to {peer} over an unencrypted connection; \
+                 use a relay on this machine or drop the username"
+            ));
+        }
+    }
+    let (read, write) = stream.into_split();
+    let mut conn = Connection {
+        read: BufReader::new(read),
+        write,
+    };
+
+    conn.expect(220).await?;
+    conn.command("EHLO timeforged", 250).await?;
+    if let Some(username) = &config.username {
+        let password = config.password.as_deref().unwrap_or_default();
+        let token = STANDARD.encode(format!("\0{username}\0{password}"));
+        conn.command(&format!("AUTH PLAIN {token}"), 235).await?;
+    }
+    conn.command(&format!("MAIL FROM:<{}>", config.from), 250).await?;
+    conn.command(&format!("RCPT TO:<{}>", mail.to), 250).await?;
+    conn.command("DATA", 354).await?;
+    let message = format_message(&config.from, mail, Utc::now(), Uuid::new_v4());
+    conn.send(&format!("{}\r\n.", dot_stuff(&message))).await?;
+    conn.expect(250).await?;
+    // The server has the mail; a failed goodbye doesn't matter
+    let _ = conn.command("QUIT", 221).await;
+    Ok(())
+}
+
+struct Connection {
+    read: BufReader<OwnedReadHalf>,
+    write: OwnedWriteHalf,
+}
+
+impl Connection {
+    async fn send(&mut self, line: &str) -> Result<(), String> {
+        self.write
+            .write_all(format!("{line}\r\n").as_bytes())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Read a reply, all lines of it, and check its code.
+    async fn expect(&mut self, code: u16) -> Result<(), String> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            if self.read.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+                return Err("connection closed".into());
+            }
+            reply.push_str(line.trim_end());
+            // "250-" continues, "250 " ends
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+            reply.push(' ');
+        }
+        match reply.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+            Some(got) if got == code => Ok(()),
+            _ => Err(format!("expected {code}, got \"{reply}\"")),
+        }
+    }
+
+    async fn command(&mut self, line: &str, code: u16) -> Result<(), String> {
+        self.send(line).await?;
+        self.expect(code).await
+    }
+}
+
+/// The message as sent after DATA: headers, then `multipart/alternative`
+/// text and HTML parts, base64-encoded so no line gets too long.
+fn format_message(from: &str, mail: &Mail<'_>, date: DateTime<Utc>, id: Uuid) -> String {
+    let boundary = format!("=_{}", id.simple());
+    let domain = from.rsplit_once('@').map_or("localhost", |(_, d)| d);
+    let mut out = String::new();
+    out += &format!("From: {from}\r\n");
+    out += &format!("To: {}\r\n", mail.to);
+    out += &format!("Subject: {}\r\n", encode_header(mail.subject));
+    out += &format!("Date: {}\r\n", date.to_rfc2822());
+    out += &format!("Message-ID: <{}@{domain}>\r\n", id.simple());
+    out += "MIME-Version: 1.0\r\n";
+    out += &format!("Content-Type: multipart/alternative; boundary=\"{boundary}\"\r\n\r\n");
+    for (content_type, body) in [("text/plain", mail.text), ("text/html", mail.html)] {
+        out += &format!("--{boundary}\r\n");
+        out += &format!("Content-Type: {content_type}; charset=utf-8\r\n");
+        out += "Content-Transfer-Encoding: base64\r\n\r\n";
+        let encoded = STANDARD.encode(body);
+        for line in encoded.as_bytes().chunks(76) {
+            out += std::str::from_utf8(line).unwrap_or_default();
+            out += "\r\n";
+        }
+    }
+    out += &format!("--{boundary}--");
+    out
+}
+
+/// RFC 2047 for subjects that aren't plain ASCII.
+fn encode_header(value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        value.to_string()
+    } else {
+        format!("=?utf-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+/// A line that starts with a dot gets another, so it can't end the data.
+fn dot_stuff(message: &str) -> String {
+    message
+        .split("\r\n")
+        .map(|line| if line.starts_with('.') { format!(".{line}") } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    use timeforged_core::config::SmtpConfig;
+
+    /// A server that accepts every mail and passes on the commands and
+    /// message data of each session.
+    pub async fn server() -> (SmtpConfig, mpsc::UnboundedReceiver<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (read, mut write) = stream.into_split();
+                let mut read = BufReader::new(read);
+                write.write_all(b"220 mock ready\r\n").await.unwrap();
+                let (mut commands, mut data) = (Vec::new(), String::new());
+                let mut line = String::new();
+                while read.read_line(&mut line).await.unwrap_or(0) > 0 {
+                    let command = line.trim_end().to_string();
+                    line.clear();
+                    let reply: &[u8] = match command.split(' ').next().unwrap_or_default() {
+                        "EHLO" => b"250-mock\r\n250 AUTH PLAIN\r\n",
+                        "AUTH" => b"235 ok\r\n",
+                        "DATA" => {
+                            write.write_all(b"354 go ahead\r\n").await.unwrap();
+                            while read.read_line(&mut line).await.unwrap() > 0 && line != ".\r\n" {
+                                data.push_str(&line);
+                                line.clear();
+                            }
+                            line.clear();
+                            b"250 queued\r\n"
+                        }
+                        "QUIT" => b"221 bye\r\n",
+                        _ => b"250 ok\r\n",
+                    };
+                    write.write_all(reply).await.unwrap();
+                    commands.push(command);
+                }
+                let _ = tx.send((commands, data));
+            }
+        });
+        let config = SmtpConfig {
+            host: "127.0.0.1".into(),
+            port,
+            from: "timeforged@example.com".into(),
+            username: Some("tf".into()),
+            password: Some("secret".into()),
+        };
+        (config, rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mail() -> Mail<'static> {
+        Mail {
+            to: "dev@example.com",
+            subject: "Weekly digest – Oct 12",
+            text: ".starts with a dot\n",
+            html: "<p>hi</p>",
+        }
+    }
+
+    #[test]
+    fn messages_carry_both_versions() {
+        let id = Uuid::nil();
+        let message = format_message("tf@example.com", &mail(), Utc::now(), id);
+        assert!(message.contains("Subject: =?utf-8?B?"));
+        assert!(message.contains("Message-ID: <00000000000000000000000000000000@example.com>"));
+        assert!(message.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(message.contains("Content-Type: text/html; charset=utf-8"));
+        assert!(message.contains(&STANDARD.encode("<p>hi</p>")));
+        assert!(message.ends_with(&format!("--=_{}--", id.simple())));
+        assert!(message.split("\r\n").all(|line| line.len() <= 998));
+
+        assert_eq!(dot_stuff(".a\r\nb\r\n."), "..a\r\nb\r\n..");
+    }
+
+    #[tokio::test]
+    async fn mail_goes_to_the_server() {
+        let (config, mut sessions) = mock::server().await;
+        send(&config, &mail()).await.unwrap();
+
+        let (commands, data) = sessions.recv().await.unwrap();
+        let token = STANDARD.encode("\0tf\0secret");
+        assert_eq!(
+            commands,
+            [
+                "EHLO timeforged".to_string(),
+                format!("AUTH PLAIN {token}"),
+                "MAIL FROM:<timeforged@example.com>".into(),
+                "RCPT TO:<dev@example.com>".into(),
+                "DATA".into(),
+                "QUIT".into(),
+            ]
+        );
+        assert!(data.starts_with("From: timeforged@example.com\r\n"));
+        assert!(data.contains(&STANDARD.encode(".starts with a dot\n")));
+    }
+
+    #[tokio::test]
+    async fn refusals_are_errors() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(b"554 go away\r\n").await.unwrap();
+        });
+        let config = SmtpConfig {
+            host: "127.0.0.1".into(),
+            port,
+            from: "tf@example.com".into(),
+            username: None,
+            password: None,
+        };
+        let err = send(&config, &mail()).await.unwrap_err().to_string();
+        assert!(err.contains("expected 220, got \"554 go away\""), "{err}");
+    }
+
+    #[tokio::test]
+    async fn no_login_over_the_network() {
+        // Any address that isn't loopback; the check comes before a word is sent
+        let Some(ip) = local_ip() else { return };
+        let listener = tokio::net::TcpListener::bind((ip, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(b"220 mock ready\r\n").await.unwrap();
+            let mut received = Vec::new();
+            let _ = tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut received).await;
+            let _ = tx.send(received);
+        });
+        let (mut config, _) = mock::server().await;
+        config.host = ip.to_string();
+        config.port = port;
+        let err = send(&config, &mail()).await.unwrap_err().to_string();
+        assert!(err.contains("unencrypted connection"), "{err}");
+        assert!(rx.await.unwrap().is_empty());
+    }
+
+    /// This machine's address on its default route, if it has one.
+    fn local_ip() -> Option<std::net::IpAddr> {
+        let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+        socket.connect("192.0.2.1:9").ok()?;
+        let ip = socket.local_addr().ok()?.ip();
+        (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+    }
+}
@@ -0,0 +1,4 @@
+-- Content hash of an event (see sqlite::dedup_key); the same event pushed
+-- twice, or bounced back by sync, is stored once. Rows that predate the
+-- column are backfilled at startup; older duplicates keep a NULL key.
+ALTER TABLE events ADD COLUMN dedup_key TEXT;
@@ -0,0 +1 @@
+CREATE UNIQUE INDEX IF NOT EXISTS idx_events_dedup ON events(user_id, dedup_key);
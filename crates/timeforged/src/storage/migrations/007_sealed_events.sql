@@ -0,0 +1,22 @@
+-- End-to-end encrypted events. The server can't read them: it only knows
+-- whose they are and when they happened, and recognises repeats by the
+-- client's keyed hash.
+CREATE TABLE IF NOT EXISTS sealed_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    timestamp TEXT NOT NULL,
+    dedup_key TEXT NOT NULL,
+    blob TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sealed_events_dedup ON sealed_events(user_id, dedup_key);
+
+-- Salt and passphrase check of an encrypted account; its presence marks
+-- the account as encrypted.
+CREATE TABLE IF NOT EXISTS sealed_keys (
+    user_id TEXT PRIMARY KEY NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    salt TEXT NOT NULL,
+    check_value TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
@@ -0,0 +1,4 @@
+-- Raw heartbeats a row stands for. Compaction (see maintenance.rs) drops
+-- heartbeats that don't change any report and adds them to the next row
+-- kept, so event counts in reports stay the same.
+ALTER TABLE events ADD COLUMN heartbeats INTEGER NOT NULL DEFAULT 1;
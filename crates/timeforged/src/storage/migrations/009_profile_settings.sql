@@ -0,0 +1,3 @@
+-- What a public profile shows (PublicProfileSettings as JSON); NULL means
+-- the defaults. Whether it is public at all stays in public_profile.
+ALTER TABLE users ADD COLUMN profile_settings TEXT;
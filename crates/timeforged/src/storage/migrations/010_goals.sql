@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS goals (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    period TEXT NOT NULL,
+    target_seconds INTEGER NOT NULL,
+    project TEXT,
+    language TEXT,
+    rest_days TEXT NOT NULL DEFAULT '',
+    -- First day of the last period a goal_reached webhook went out for
+    reached_period TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_goals_user ON goals(user_id);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS digests (
+    user_id TEXT PRIMARY KEY NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- DigestSettings as JSON; enabled is copied out to find scheduled ones
+    settings TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 0,
+    -- Last day covered by the last scheduled digest
+    sent_through TEXT
+);
@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS clients (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    hourly_rate REAL NOT NULL,
+    currency TEXT NOT NULL,
+    -- Rounding as JSON
+    rounding TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    UNIQUE (user_id, name)
+);
+
+-- Projects billed to a client; each project to one client at most
+CREATE TABLE IF NOT EXISTS client_projects (
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    project TEXT NOT NULL,
+    client_id TEXT NOT NULL REFERENCES clients(id) ON DELETE CASCADE,
+    -- The client's rate when NULL
+    hourly_rate REAL,
+    PRIMARY KEY (user_id, project)
+);
+
+CREATE INDEX IF NOT EXISTS idx_client_projects_client ON client_projects(client_id);
+
+-- Activity types whose time isn't billed; everything else is
+CREATE TABLE IF NOT EXISTS non_billable_activities (
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    activity TEXT NOT NULL,
+    PRIMARY KEY (user_id, activity)
+);
@@ -0,0 +1,6 @@
+-- Data migrations done in Rust at startup (see sqlite::init_db), recorded
+-- so each runs once rather than on every start
+CREATE TABLE IF NOT EXISTS data_migrations (
+    name TEXT PRIMARY KEY NOT NULL,
+    done_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
@@ -0,0 +1,11 @@
+-- Events compaction folded into a later one: their dedup key, and the row
+-- that carries their heartbeats now. The same event arriving again (a
+-- re-imported archive, a late push) is a duplicate of that row, not new time.
+CREATE TABLE IF NOT EXISTS compacted_events (
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    dedup_key TEXT NOT NULL,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, dedup_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_compacted_events_event ON compacted_events(event_id);
@@ -1,11 +1,17 @@
-use chrono::{DateTime, Utc};
-use sqlx::{Row, SqlitePool};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqliteConnection, SqlitePool};
 use uuid::Uuid;
 
+use timeforged_core::api::{
+    CreateGoalRequest, DigestSettings, PublicProfileSettings, SealedEvent, SealedKeyInfo, SyncMode,
+    SyncRun, SyncTrigger,
+};
 use timeforged_core::error::AppError;
 use timeforged_core::models::{
-    ApiKey, CategorySummary, DaySummary, Event, HourlyActivity, ReportRequest, Session, Summary,
-    User,
+    ActivityType, ApiKey, CategorySummary, Client, ClientProject, DaySummary, DeliveryStatus, Event,
+    Goal, GoalPeriod, HourlyActivity, ReportRequest, Session, Summary, User, Webhook,
+    WebhookDelivery, WebhookTrigger,
 };
 
 pub async fn init_db(pool: &SqlitePool) -> Result<(), AppError> {
@@ -17,9 +23,145 @@ pub async fn init_db(pool: &SqlitePool) -> Result<(), AppError> {
         .execute(pool)
         .await
         .ok(); // ignore if column already exists
+    sqlx::raw_sql(include_str!("migrations/003_webhooks.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/004_event_dedup.sql"))
+        .execute(pool)
+        .await
+        .ok(); // ignore if column already exists
+    sqlx::raw_sql(include_str!("migrations/005_event_dedup_index.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/006_sync_runs.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/007_sealed_events.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/008_event_heartbeats.sql"))
+        .execute(pool)
+        .await
+        .ok(); // ignore if column already exists
+    sqlx::raw_sql(include_str!("migrations/009_profile_settings.sql"))
+        .execute(pool)
+        .await
+        .ok(); // ignore if column already exists
+    sqlx::raw_sql(include_str!("migrations/010_goals.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/011_digests.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/012_billing.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/013_data_migrations.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::raw_sql(include_str!("migrations/014_compacted_events.sql"))
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    if !data_migration_done(pool, "dedup_keys").await? {
+        backfill_dedup_keys(pool).await?;
+        record_data_migration(pool, "dedup_keys").await?;
+    }
     Ok(())
 }
 
+async fn data_migration_done(pool: &SqlitePool, name: &str) -> Result<bool, AppError> {
+    sqlx::query("SELECT 1 FROM data_migrations WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map(|row| row.is_some())
+        .map_err(|e| AppError::Database(e.to_string()))
+}
+
+async fn record_data_migration(pool: &SqlitePool, name: &str) -> Result<(), AppError> {
+    sqlx::query("INSERT OR IGNORE INTO data_migrations (name) VALUES (?)")
+        .bind(name)
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| AppError::Database(e.to_string()))
+}
+
+/// Identity of an event for deduplication: everything except the metadata,
+/// which clients may reshape.
+pub fn dedup_key(event: &Event) -> String {
+    let mut hasher = Sha256::new();
+    let ts = event.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+    for field in [
+        Some(ts.as_str()),
+        Some(event.event_type.as_str()),
+        Some(event.entity.as_str()),
+        event.project.as_deref(),
+        event.language.as_deref(),
+        event.branch.as_deref(),
+        event.activity.as_ref().map(|a| a.as_str()),
+        event.machine.as_deref(),
+    ] {
+        // Tag absent fields so None and Some("") differ
+        match field {
+            Some(v) => {
+                hasher.update(b"s");
+                hasher.update(v.as_bytes());
+            }
+            None => hasher.update(b"n"),
+        }
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Give rows stored before deduplication their key. When old duplicates
+/// exist only the first one gets it; the rest are left alone, which is why
+/// this runs once instead of on every start.
+async fn backfill_dedup_keys(pool: &SqlitePool) -> Result<(), AppError> {
+    let mut after = 0i64;
+    loop {
+        let rows = sqlx::query(&format!(
+            "SELECT {EVENT_COLUMNS} FROM events WHERE dedup_key IS NULL AND id > ? ORDER BY id LIMIT 1000"
+        ))
+        .bind(after)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+        for row in &rows {
+            let event = parse_event_row(row)?;
+            let key = dedup_key(&event);
+            after = event.id.unwrap_or(after);
+            sqlx::query(
+                "UPDATE events SET dedup_key = ? WHERE id = ?
+                 AND NOT EXISTS (SELECT 1 FROM events WHERE user_id = ? AND dedup_key = ?)",
+            )
+            .bind(&key)
+            .bind(after)
+            .bind(event.user_id.to_string())
+            .bind(&key)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+        tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
+    }
+}
+
 // --- Users ---
 
 pub async fn create_user(
@@ -86,6 +228,16 @@ pub async fn get_first_user(pool: &SqlitePool) -> Result<Option<User>, AppError>
     row.map(|r| parse_user_row(&r)).transpose()
 }
 
+pub async fn get_user_by_id(pool: &SqlitePool, user_id: Uuid) -> Result<Option<User>, AppError> {
+    let row = sqlx::query("SELECT id, username, display_name, created_at FROM users WHERE id = ?")
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    row.map(|r| parse_user_row(&r)).transpose()
+}
+
 pub async fn get_user_by_username(pool: &SqlitePool, username: &str) -> Result<Option<User>, AppError> {
     let row = sqlx::query("SELECT id, username, display_name, public_profile, created_at FROM users WHERE username = ?")
         .bind(username)
@@ -96,133 +248,1042 @@ pub async fn get_user_by_username(pool: &SqlitePool, username: &str) -> Result<O
     row.map(|r| parse_user_row(&r)).transpose()
 }
 
-pub async fn set_public_profile(pool: &SqlitePool, user_id: Uuid, public: bool) -> Result<(), AppError> {
-    sqlx::query("UPDATE users SET public_profile = ? WHERE id = ?")
-        .bind(public as i32)
-        .bind(user_id.to_string())
+pub async fn get_profile_settings(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<PublicProfileSettings, AppError> {
+    let row = sqlx::query("SELECT public_profile, profile_settings FROM users WHERE id = ?")
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound("user not found".into()))?;
+
+    let json: Option<String> = row.get("profile_settings");
+    let mut settings = match json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| AppError::Database(e.to_string()))?,
+        None => PublicProfileSettings::default(),
+    };
+    settings.public = row.get::<i32, _>("public_profile") != 0;
+    Ok(settings)
+}
+
+pub async fn set_profile_settings(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    settings: &PublicProfileSettings,
+) -> Result<(), AppError> {
+    let json = serde_json::to_string(settings).map_err(|e| AppError::Internal(e.to_string()))?;
+    sqlx::query("UPDATE users SET public_profile = ?, profile_settings = ? WHERE id = ?")
+        .bind(settings.public as i32)
+        .bind(json)
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(())
+}
+
+// --- API Keys ---
+
+pub async fn create_api_key(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    key_hash: &str,
+    label: &str,
+) -> Result<ApiKey, AppError> {
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO api_keys (id, user_id, key_hash, label, created_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(id.to_string())
+    .bind(user_id.to_string())
+    .bind(key_hash)
+    .bind(label)
+    .bind(now.to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(ApiKey {
+        id,
+        user_id,
+        key_hash: key_hash.to_string(),
+        label: label.to_string(),
+        created_at: now,
+        last_used_at: None,
+    })
+}
+
+pub async fn find_user_by_api_key_hash(
+    pool: &SqlitePool,
+    key_hash: &str,
+) -> Result<Option<User>, AppError> {
+    let row = sqlx::query(
+        "SELECT u.id, u.username, u.display_name, u.created_at
+         FROM users u JOIN api_keys ak ON u.id = ak.user_id
+         WHERE ak.key_hash = ?",
+    )
+    .bind(key_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    match row {
+        Some(row) => {
+            // Update last_used_at
+            let _ = sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE key_hash = ?")
+                .bind(Utc::now().to_rfc3339())
+                .bind(key_hash)
+                .execute(pool)
+                .await;
+            Ok(Some(parse_user_row(&row)?))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn list_api_keys(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<Vec<ApiKey>, AppError> {
+    let rows = sqlx::query(
+        "SELECT id, user_id, key_hash, label, created_at, last_used_at FROM api_keys WHERE user_id = ? ORDER BY created_at DESC",
+    )
+    .bind(user_id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_api_key_row).collect()
+}
+
+pub async fn delete_api_key(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    key_id: Uuid,
+) -> Result<bool, AppError> {
+    let result =
+        sqlx::query("DELETE FROM api_keys WHERE id = ? AND user_id = ?")
+            .bind(key_id.to_string())
+            .bind(user_id.to_string())
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.rows_affected() > 0)
+}
+
+fn parse_api_key_row(row: &sqlx::sqlite::SqliteRow) -> Result<ApiKey, AppError> {
+    let id_str: String = row.get("id");
+    let user_id_str: String = row.get("user_id");
+    let created_str: String = row.get("created_at");
+    let last_used: Option<String> = row.get("last_used_at");
+
+    Ok(ApiKey {
+        id: Uuid::parse_str(&id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        user_id: Uuid::parse_str(&user_id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        key_hash: row.get("key_hash"),
+        label: row.get("label"),
+        created_at: DateTime::parse_from_rfc3339(&created_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| AppError::Database(e.to_string()))?,
+        last_used_at: last_used
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)),
+    })
+}
+
+// --- Webhooks ---
+
+pub async fn create_webhook(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    url: &str,
+    secret: &str,
+    triggers: &[WebhookTrigger],
+) -> Result<Webhook, AppError> {
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO webhooks (id, user_id, url, secret, triggers, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(id.to_string())
+    .bind(user_id.to_string())
+    .bind(url)
+    .bind(secret)
+    .bind(join_triggers(triggers))
+    .bind(now.to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(Webhook {
+        id,
+        user_id,
+        url: url.to_string(),
+        triggers: triggers.to_vec(),
+        secret: secret.to_string(),
+        created_at: now,
+    })
+}
+
+pub async fn list_webhooks(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<Webhook>, AppError> {
+    let rows = sqlx::query(
+        "SELECT id, user_id, url, secret, triggers, created_at FROM webhooks WHERE user_id = ? ORDER BY created_at",
+    )
+    .bind(user_id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_webhook_row).collect()
+}
+
+pub async fn get_webhook(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<Option<Webhook>, AppError> {
+    let row = sqlx::query(
+        "SELECT id, user_id, url, secret, triggers, created_at FROM webhooks WHERE id = ? AND user_id = ?",
+    )
+    .bind(webhook_id.to_string())
+    .bind(user_id.to_string())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    row.as_ref().map(parse_webhook_row).transpose()
+}
+
+pub async fn delete_webhook(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = ? AND user_id = ?")
+        .bind(webhook_id.to_string())
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.rows_affected() > 0)
+}
+
+fn join_triggers(triggers: &[WebhookTrigger]) -> String {
+    triggers.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(",")
+}
+
+fn parse_webhook_row(row: &sqlx::sqlite::SqliteRow) -> Result<Webhook, AppError> {
+    let id_str: String = row.get("id");
+    let user_id_str: String = row.get("user_id");
+    let triggers_str: String = row.get("triggers");
+    let created_str: String = row.get("created_at");
+
+    Ok(Webhook {
+        id: Uuid::parse_str(&id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        user_id: Uuid::parse_str(&user_id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        url: row.get("url"),
+        triggers: triggers_str.split(',').filter_map(WebhookTrigger::parse).collect(),
+        secret: row.get("secret"),
+        created_at: DateTime::parse_from_rfc3339(&created_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| AppError::Database(e.to_string()))?,
+    })
+}
+
+/// Queue a delivery; it is picked up by `due_deliveries` right away.
+pub async fn insert_delivery(
+    pool: &SqlitePool,
+    id: Uuid,
+    webhook_id: Uuid,
+    trigger: WebhookTrigger,
+    payload: &str,
+    created_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO webhook_deliveries (id, webhook_id, kind, payload, status, created_at, next_attempt_at)
+         VALUES (?, ?, ?, ?, 'pending', ?, ?)",
+    )
+    .bind(id.to_string())
+    .bind(webhook_id.to_string())
+    .bind(trigger.as_str())
+    .bind(payload)
+    .bind(created_at.to_rfc3339())
+    .bind(created_at.to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(())
+}
+
+/// A pending delivery together with where and how to send it.
+pub struct DueDelivery {
+    pub id: Uuid,
+    pub trigger: WebhookTrigger,
+    pub payload: String,
+    pub attempts: i64,
+    pub url: String,
+    pub secret: String,
+}
+
+pub async fn due_deliveries(
+    pool: &SqlitePool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<DueDelivery>, AppError> {
+    let rows = sqlx::query(
+        "SELECT d.id, d.kind, d.payload, d.attempts, w.url, w.secret
+         FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id
+         WHERE d.status = 'pending' AND d.next_attempt_at <= ?
+         ORDER BY d.next_attempt_at LIMIT ?",
+    )
+    .bind(now.to_rfc3339())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter()
+        .map(|row| {
+            let id_str: String = row.get("id");
+            let kind: String = row.get("kind");
+            Ok(DueDelivery {
+                id: Uuid::parse_str(&id_str).map_err(|e| AppError::Database(e.to_string()))?,
+                trigger: WebhookTrigger::parse(&kind)
+                    .ok_or_else(|| AppError::Database(format!("unknown webhook trigger {kind}")))?,
+                payload: row.get("payload"),
+                attempts: row.get("attempts"),
+                url: row.get("url"),
+                secret: row.get("secret"),
+            })
+        })
+        .collect()
+}
+
+/// Record the outcome of one attempt. `next_attempt_at` is only set while
+/// the delivery stays pending.
+pub async fn record_delivery_attempt(
+    pool: &SqlitePool,
+    id: Uuid,
+    status: DeliveryStatus,
+    response_status: Option<u16>,
+    error: Option<&str>,
+    attempted_at: DateTime<Utc>,
+    next_attempt_at: Option<DateTime<Utc>>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE webhook_deliveries
+         SET status = ?, attempts = attempts + 1, response_status = ?, error = ?,
+             last_attempt_at = ?, next_attempt_at = ?
+         WHERE id = ?",
+    )
+    .bind(status.as_str())
+    .bind(response_status.map(|s| s as i64))
+    .bind(error)
+    .bind(attempted_at.to_rfc3339())
+    .bind(next_attempt_at.map(|t| t.to_rfc3339()))
+    .bind(id.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(())
+}
+
+pub async fn list_deliveries(
+    pool: &SqlitePool,
+    webhook_id: Uuid,
+    limit: i64,
+) -> Result<Vec<WebhookDelivery>, AppError> {
+    let rows = sqlx::query(
+        "SELECT id, webhook_id, kind, status, attempts, response_status, error, created_at,
+                last_attempt_at, next_attempt_at
+         FROM webhook_deliveries WHERE webhook_id = ? ORDER BY created_at DESC LIMIT ?",
+    )
+    .bind(webhook_id.to_string())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    let parse_opt = |s: Option<String>| {
+        s.and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+
+    rows.iter()
+        .map(|row| {
+            let id_str: String = row.get("id");
+            let kind: String = row.get("kind");
+            let status: String = row.get("status");
+            let response_status: Option<i64> = row.get("response_status");
+            let created_str: String = row.get("created_at");
+            Ok(WebhookDelivery {
+                id: Uuid::parse_str(&id_str).map_err(|e| AppError::Database(e.to_string()))?,
+                webhook_id,
+                trigger: WebhookTrigger::parse(&kind)
+                    .ok_or_else(|| AppError::Database(format!("unknown webhook trigger {kind}")))?,
+                status: DeliveryStatus::from_str_lossy(&status),
+                attempts: row.get("attempts"),
+                response_status: response_status.map(|s| s as u16),
+                error: row.get("error"),
+                created_at: DateTime::parse_from_rfc3339(&created_str)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .map_err(|e| AppError::Database(e.to_string()))?,
+                last_attempt_at: parse_opt(row.get("last_attempt_at")),
+                next_attempt_at: parse_opt(row.get("next_attempt_at")),
+            })
+        })
+        .collect()
+}
+
+/// Drop finished deliveries older than `before`; pending ones are kept.
+pub async fn prune_deliveries(pool: &SqlitePool, before: DateTime<Utc>) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        "DELETE FROM webhook_deliveries WHERE status != 'pending' AND created_at < ?",
+    )
+    .bind(before.to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.rows_affected())
+}
+
+/// Webhooks subscribed to `trigger`, optionally limited to one user.
+pub async fn webhooks_for_trigger(
+    pool: &SqlitePool,
+    user_id: Option<Uuid>,
+    trigger: WebhookTrigger,
+) -> Result<Vec<Webhook>, AppError> {
+    let rows = sqlx::query(
+        "SELECT id, user_id, url, secret, triggers, created_at FROM webhooks
+         WHERE (? IS NULL OR user_id = ?) AND (',' || triggers || ',') LIKE ?",
+    )
+    .bind(user_id.map(|u| u.to_string()))
+    .bind(user_id.map(|u| u.to_string()))
+    .bind(format!("%,{},%", trigger.as_str()))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_webhook_row).collect()
+}
+
+// --- Goals ---
+
+const GOAL_COLUMNS: &str = "id, user_id, period, target_seconds, project, language, rest_days, created_at";
+
+pub async fn create_goal(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    req: &CreateGoalRequest,
+) -> Result<Goal, AppError> {
+    let goal = Goal {
+        id: Uuid::new_v4(),
+        user_id,
+        period: req.period,
+        target_seconds: req.target_seconds,
+        project: req.project.clone(),
+        language: req.language.clone(),
+        rest_days: req.rest_days.clone(),
+        created_at: Utc::now(),
+    };
+    let rest_days: Vec<String> = goal.rest_days.iter().map(|d| d.to_string()).collect();
+
+    sqlx::query(
+        "INSERT INTO goals (id, user_id, period, target_seconds, project, language, rest_days, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(goal.id.to_string())
+    .bind(user_id.to_string())
+    .bind(goal.period.as_str())
+    .bind(goal.target_seconds as i64)
+    .bind(&goal.project)
+    .bind(&goal.language)
+    .bind(rest_days.join(","))
+    .bind(goal.created_at.to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    Ok(goal)
+}
+
+pub async fn list_goals(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<Goal>, AppError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {GOAL_COLUMNS} FROM goals WHERE user_id = ? ORDER BY created_at"
+    ))
+    .bind(user_id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_goal_row).collect()
+}
+
+pub async fn get_goal(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    goal_id: Uuid,
+) -> Result<Option<Goal>, AppError> {
+    let row = sqlx::query(&format!("SELECT {GOAL_COLUMNS} FROM goals WHERE id = ? AND user_id = ?"))
+        .bind(goal_id.to_string())
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    row.as_ref().map(parse_goal_row).transpose()
+}
+
+pub async fn delete_goal(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    goal_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM goals WHERE id = ? AND user_id = ?")
+        .bind(goal_id.to_string())
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// A user's goals not yet reached in the periods starting on `day` (daily
+/// goals) and `week` (weekly goals).
+pub async fn unreached_goals(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    day: NaiveDate,
+    week: NaiveDate,
+) -> Result<Vec<Goal>, AppError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {GOAL_COLUMNS} FROM goals WHERE user_id = ?
+         AND ((period = 'day' AND reached_period IS NOT ?) OR (period = 'week' AND reached_period IS NOT ?))
+         ORDER BY created_at"
+    ))
+    .bind(user_id.to_string())
+    .bind(day.to_string())
+    .bind(week.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_goal_row).collect()
+}
+
+/// Record that a goal was reached in the period starting on `period_start`.
+/// Returns false if that was already recorded.
+pub async fn mark_goal_reached(
+    pool: &SqlitePool,
+    goal_id: Uuid,
+    period_start: NaiveDate,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE goals SET reached_period = ? WHERE id = ? AND reached_period IS NOT ?",
+    )
+    .bind(period_start.to_string())
+    .bind(goal_id.to_string())
+    .bind(period_start.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.rows_affected() > 0)
+}
+
+fn parse_goal_row(row: &sqlx::sqlite::SqliteRow) -> Result<Goal, AppError> {
+    let id_str: String = row.get("id");
+    let user_id_str: String = row.get("user_id");
+    let period_str: String = row.get("period");
+    let rest_days_str: String = row.get("rest_days");
+    let created_str: String = row.get("created_at");
+
+    Ok(Goal {
+        id: Uuid::parse_str(&id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        user_id: Uuid::parse_str(&user_id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        period: GoalPeriod::parse(&period_str)
+            .ok_or_else(|| AppError::Database(format!("unknown goal period '{period_str}'")))?,
+        target_seconds: row.get::<i64, _>("target_seconds").max(0) as u64,
+        project: row.get("project"),
+        language: row.get("language"),
+        rest_days: rest_days_str.split(',').filter_map(|d| d.parse().ok()).collect(),
+        created_at: DateTime::parse_from_rfc3339(&created_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| AppError::Database(e.to_string()))?,
+    })
+}
+
+// --- Digests ---
+
+pub async fn get_digest_settings(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<DigestSettings, AppError> {
+    let json: Option<String> = sqlx::query_scalar("SELECT settings FROM digests WHERE user_id = ?")
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    match json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| AppError::Database(e.to_string())),
+        None => Ok(DigestSettings::default()),
+    }
+}
+
+/// Store the settings, counting digests through `sent_through` as sent so
+/// the next one goes out on the new schedule.
+pub async fn set_digest_settings(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    settings: &DigestSettings,
+    sent_through: NaiveDate,
+) -> Result<(), AppError> {
+    let json = serde_json::to_string(settings).map_err(|e| AppError::Internal(e.to_string()))?;
+    sqlx::query(
+        "INSERT INTO digests (user_id, settings, enabled, sent_through) VALUES (?, ?, ?, ?)
+         ON CONFLICT(user_id) DO UPDATE SET settings = excluded.settings,
+             enabled = excluded.enabled, sent_through = excluded.sent_through",
+    )
+    .bind(user_id.to_string())
+    .bind(json)
+    .bind(settings.enabled as i32)
+    .bind(sent_through.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(())
+}
+
+/// Users with scheduled digests turned on, and their settings.
+pub async fn scheduled_digests(
+    pool: &SqlitePool,
+) -> Result<Vec<(Uuid, DigestSettings)>, AppError> {
+    let rows = sqlx::query("SELECT user_id, settings FROM digests WHERE enabled = 1")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter()
+        .map(|row| {
+            let user_id: String = row.get("user_id");
+            let settings: String = row.get("settings");
+            Ok((
+                Uuid::parse_str(&user_id).map_err(|e| AppError::Database(e.to_string()))?,
+                serde_json::from_str(&settings).map_err(|e| AppError::Database(e.to_string()))?,
+            ))
+        })
+        .collect()
+}
+
+/// Record that the scheduled digest through `through` went out. Returns
+/// false if that was already recorded.
+pub async fn mark_digest_sent(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    through: NaiveDate,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE digests SET sent_through = ? WHERE user_id = ? AND sent_through IS NOT ?",
+    )
+    .bind(through.to_string())
+    .bind(user_id.to_string())
+    .bind(through.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.rows_affected() > 0)
+}
+
+// --- Billing ---
+
+/// Insert the client, or replace it and its projects.
+pub async fn save_client(pool: &SqlitePool, client: &Client) -> Result<(), AppError> {
+    let rounding =
+        serde_json::to_string(&client.rounding).map_err(|e| AppError::Internal(e.to_string()))?;
+    let (id, user_id) = (client.id.to_string(), client.user_id.to_string());
+    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::query(
+        "INSERT INTO clients (id, user_id, name, hourly_rate, currency, rounding, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, hourly_rate = excluded.hourly_rate,
+             currency = excluded.currency, rounding = excluded.rounding",
+    )
+    .bind(&id)
+    .bind(&user_id)
+    .bind(&client.name)
+    .bind(client.hourly_rate)
+    .bind(&client.currency)
+    .bind(rounding)
+    .bind(client.created_at.to_rfc3339())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    sqlx::query("DELETE FROM client_projects WHERE client_id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    for project in &client.projects {
+        sqlx::query(
+            "INSERT INTO client_projects (user_id, project, client_id, hourly_rate) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&user_id)
+        .bind(&project.name)
+        .bind(&id)
+        .bind(project.hourly_rate)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    }
+    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))
+}
+
+/// A user's clients by name, each with its projects by name.
+pub async fn list_clients(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<Client>, AppError> {
+    let rows = sqlx::query(
+        "SELECT id, user_id, name, hourly_rate, currency, rounding, created_at FROM clients
+         WHERE user_id = ? ORDER BY name COLLATE NOCASE",
+    )
+    .bind(user_id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    let mut clients: Vec<Client> = rows.iter().map(parse_client_row).collect::<Result<_, _>>()?;
+
+    let projects = sqlx::query(
+        "SELECT client_id, project, hourly_rate FROM client_projects WHERE user_id = ?
+         ORDER BY project COLLATE NOCASE",
+    )
+    .bind(user_id.to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    for row in &projects {
+        let client_id: String = row.get("client_id");
+        if let Some(client) = clients.iter_mut().find(|c| c.id.to_string() == client_id) {
+            client.projects.push(ClientProject {
+                name: row.get("project"),
+                hourly_rate: row.get("hourly_rate"),
+            });
+        }
+    }
+    Ok(clients)
+}
+
+pub async fn delete_client(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    client_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM clients WHERE id = ? AND user_id = ?")
+        .bind(client_id.to_string())
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.rows_affected() > 0)
+}
+
+fn parse_client_row(row: &sqlx::sqlite::SqliteRow) -> Result<Client, AppError> {
+    let id_str: String = row.get("id");
+    let user_id_str: String = row.get("user_id");
+    let rounding: String = row.get("rounding");
+    let created_str: String = row.get("created_at");
+
+    Ok(Client {
+        id: Uuid::parse_str(&id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        user_id: Uuid::parse_str(&user_id_str).map_err(|e| AppError::Database(e.to_string()))?,
+        name: row.get("name"),
+        hourly_rate: row.get("hourly_rate"),
+        currency: row.get("currency"),
+        rounding: serde_json::from_str(&rounding).map_err(|e| AppError::Database(e.to_string()))?,
+        projects: Vec::new(),
+        created_at: DateTime::parse_from_rfc3339(&created_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| AppError::Database(e.to_string()))?,
+    })
+}
+
+pub async fn non_billable_activities(
+    pool: &SqlitePool,
+    user_id: Uuid,
+) -> Result<Vec<ActivityType>, AppError> {
+    let rows: Vec<String> =
+        sqlx::query_scalar("SELECT activity FROM non_billable_activities WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(rows.iter().map(|a| ActivityType::from_str_lossy(a)).collect())
+}
+
+pub async fn set_activity_billable(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    activity: &ActivityType,
+    billable: bool,
+) -> Result<(), AppError> {
+    let query = if billable {
+        "DELETE FROM non_billable_activities WHERE user_id = ? AND activity = ?"
+    } else {
+        "INSERT OR IGNORE INTO non_billable_activities (user_id, activity) VALUES (?, ?)"
+    };
+    sqlx::query(query)
+        .bind(user_id.to_string())
+        .bind(activity.as_str())
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(())
+}
+
+// --- Sync runs ---
+
+/// Run times are stored fixed-width so they compare as strings.
+fn run_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+pub async fn insert_sync_run(pool: &SqlitePool, run: &SyncRun) -> Result<i64, AppError> {
+    let result = sqlx::query(
+        "INSERT INTO sync_runs (remote, direction, triggered_by, started_at, duration_ms, accepted, duplicates, rejected, skipped, error)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&run.remote)
+    .bind(run.direction.as_str())
+    .bind(run.trigger.as_str())
+    .bind(run_time(run.started_at))
+    .bind(run.duration_ms as i64)
+    .bind(run.accepted as i64)
+    .bind(run.duplicates as i64)
+    .bind(run.rejected as i64)
+    .bind(run.skipped as i64)
+    .bind(&run.error)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Most recent runs first, optionally with one remote only.
+pub async fn list_sync_runs(
+    pool: &SqlitePool,
+    remote: Option<&str>,
+    limit: i64,
+) -> Result<Vec<SyncRun>, AppError> {
+    let rows = sqlx::query(
+        "SELECT id, remote, direction, triggered_by, started_at, duration_ms, accepted, duplicates, rejected, skipped, error
+         FROM sync_runs WHERE ? IS NULL OR remote = ? ORDER BY started_at DESC, id DESC LIMIT ?",
+    )
+    .bind(remote)
+    .bind(remote)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter()
+        .map(|row| {
+            let direction: String = row.get("direction");
+            let trigger: String = row.get("triggered_by");
+            let started_str: String = row.get("started_at");
+            let count = |col: &str| row.get::<i64, _>(col) as usize;
+            Ok(SyncRun {
+                id: row.get("id"),
+                remote: row.get("remote"),
+                direction: SyncMode::parse(&direction).unwrap_or_default(),
+                trigger: SyncTrigger::from_str_lossy(&trigger),
+                started_at: DateTime::parse_from_rfc3339(&started_str)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .map_err(|e| AppError::Database(e.to_string()))?,
+                duration_ms: row.get::<i64, _>("duration_ms") as u64,
+                accepted: count("accepted"),
+                duplicates: count("duplicates"),
+                rejected: count("rejected"),
+                skipped: count("skipped"),
+                error: row.get("error"),
+            })
+        })
+        .collect()
+}
+
+/// How syncing with one remote has been going, from its recorded runs.
+pub struct SyncHealth {
+    pub remote: String,
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// Oldest failure that no later success in the same direction followed
+    pub failing_since: Option<DateTime<Utc>>,
+}
+
+pub async fn sync_health(pool: &SqlitePool) -> Result<Vec<SyncHealth>, AppError> {
+    let rows = sqlx::query(
+        "SELECT remote,
+                MAX(CASE WHEN error IS NULL THEN started_at END) AS last_success_at,
+                MIN(CASE WHEN error IS NOT NULL AND NOT EXISTS (
+                        SELECT 1 FROM sync_runs s
+                        WHERE s.remote = f.remote AND s.direction = f.direction
+                          AND s.error IS NULL AND s.started_at > f.started_at
+                    ) THEN started_at END) AS failing_since
+         FROM sync_runs f GROUP BY remote",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    let parse_opt = |s: Option<String>| {
+        s.and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    Ok(rows
+        .iter()
+        .map(|row| SyncHealth {
+            remote: row.get("remote"),
+            last_success_at: parse_opt(row.get("last_success_at")),
+            failing_since: parse_opt(row.get("failing_since")),
+        })
+        .collect())
+}
+
+pub async fn prune_sync_runs(pool: &SqlitePool, before: DateTime<Utc>) -> Result<u64, AppError> {
+    let result = sqlx::query("DELETE FROM sync_runs WHERE started_at < ?")
+        .bind(run_time(before))
         .execute(pool)
         .await
         .map_err(|e| AppError::Database(e.to_string()))?;
-    Ok(())
+    Ok(result.rows_affected())
 }
 
-// --- API Keys ---
+// --- Sealed events ---
 
-pub async fn create_api_key(
+/// Store a sealed event unless one with the same key is there; `false` for
+/// a repeat.
+pub async fn insert_sealed_event(
     pool: &SqlitePool,
     user_id: Uuid,
-    key_hash: &str,
-    label: &str,
-) -> Result<ApiKey, AppError> {
-    let id = Uuid::new_v4();
-    let now = Utc::now();
-
-    sqlx::query(
-        "INSERT INTO api_keys (id, user_id, key_hash, label, created_at) VALUES (?, ?, ?, ?, ?)",
+    event: &SealedEvent,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "INSERT INTO sealed_events (user_id, timestamp, dedup_key, blob) VALUES (?, ?, ?, ?)
+         ON CONFLICT(user_id, dedup_key) DO NOTHING",
     )
-    .bind(id.to_string())
     .bind(user_id.to_string())
-    .bind(key_hash)
-    .bind(label)
-    .bind(now.to_rfc3339())
+    .bind(event.timestamp.to_rfc3339())
+    .bind(&event.key)
+    .bind(&event.blob)
     .execute(pool)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
-
-    Ok(ApiKey {
-        id,
-        user_id,
-        key_hash: key_hash.to_string(),
-        label: label.to_string(),
-        created_at: now,
-        last_used_at: None,
-    })
-}
-
-pub async fn find_user_by_api_key_hash(
-    pool: &SqlitePool,
-    key_hash: &str,
-) -> Result<Option<User>, AppError> {
-    let row = sqlx::query(
-        "SELECT u.id, u.username, u.display_name, u.created_at
-         FROM users u JOIN api_keys ak ON u.id = ak.user_id
-         WHERE ak.key_hash = ?",
-    )
-    .bind(key_hash)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| AppError::Database(e.to_string()))?;
-
-    match row {
-        Some(row) => {
-            // Update last_used_at
-            let _ = sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE key_hash = ?")
-                .bind(Utc::now().to_rfc3339())
-                .bind(key_hash)
-                .execute(pool)
-                .await;
-            Ok(Some(parse_user_row(&row)?))
-        }
-        None => Ok(None),
-    }
+    Ok(result.rows_affected() > 0)
 }
 
-pub async fn list_api_keys(
+pub async fn list_sealed_events_after(
     pool: &SqlitePool,
     user_id: Uuid,
-) -> Result<Vec<ApiKey>, AppError> {
+    after_id: i64,
+    limit: i64,
+) -> Result<Vec<SealedEvent>, AppError> {
     let rows = sqlx::query(
-        "SELECT id, user_id, key_hash, label, created_at, last_used_at FROM api_keys WHERE user_id = ? ORDER BY created_at DESC",
+        "SELECT id, timestamp, dedup_key, blob FROM sealed_events
+         WHERE user_id = ? AND id > ? ORDER BY id ASC LIMIT ?",
     )
     .bind(user_id.to_string())
+    .bind(after_id)
+    .bind(limit)
     .fetch_all(pool)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
 
-    rows.iter().map(parse_api_key_row).collect()
+    rows.iter()
+        .map(|row| {
+            let ts_str: String = row.get("timestamp");
+            Ok(SealedEvent {
+                id: Some(row.get("id")),
+                timestamp: DateTime::parse_from_rfc3339(&ts_str)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .map_err(|e| AppError::Database(e.to_string()))?,
+                key: row.get("dedup_key"),
+                blob: row.get("blob"),
+            })
+        })
+        .collect()
 }
 
-pub async fn delete_api_key(
+pub async fn get_sealed_key(
     pool: &SqlitePool,
     user_id: Uuid,
-    key_id: Uuid,
+) -> Result<Option<SealedKeyInfo>, AppError> {
+    let row = sqlx::query("SELECT salt, check_value FROM sealed_keys WHERE user_id = ?")
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(row.map(|row| SealedKeyInfo {
+        salt: row.get("salt"),
+        check: row.get("check_value"),
+    }))
+}
+
+/// Set the key info unless the user has some already; `false` if they had.
+pub async fn insert_sealed_key(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    info: &SealedKeyInfo,
 ) -> Result<bool, AppError> {
-    let result =
-        sqlx::query("DELETE FROM api_keys WHERE id = ? AND user_id = ?")
-            .bind(key_id.to_string())
-            .bind(user_id.to_string())
-            .execute(pool)
-            .await
-            .map_err(|e| AppError::Database(e.to_string()))?;
+    let result = sqlx::query(
+        "INSERT INTO sealed_keys (user_id, salt, check_value) VALUES (?, ?, ?)
+         ON CONFLICT(user_id) DO NOTHING",
+    )
+    .bind(user_id.to_string())
+    .bind(&info.salt)
+    .bind(&info.check)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
     Ok(result.rows_affected() > 0)
 }
 
-fn parse_api_key_row(row: &sqlx::sqlite::SqliteRow) -> Result<ApiKey, AppError> {
-    let id_str: String = row.get("id");
-    let user_id_str: String = row.get("user_id");
-    let created_str: String = row.get("created_at");
-    let last_used: Option<String> = row.get("last_used_at");
+// --- Events ---
 
-    Ok(ApiKey {
-        id: Uuid::parse_str(&id_str).map_err(|e| AppError::Database(e.to_string()))?,
-        user_id: Uuid::parse_str(&user_id_str).map_err(|e| AppError::Database(e.to_string()))?,
-        key_hash: row.get("key_hash"),
-        label: row.get("label"),
-        created_at: DateTime::parse_from_rfc3339(&created_str)
-            .map(|dt| dt.with_timezone(&Utc))
-            .map_err(|e| AppError::Database(e.to_string()))?,
-        last_used_at: last_used
-            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-            .map(|dt| dt.with_timezone(&Utc)),
-    })
+/// Result of storing an event.
+pub struct Inserted {
+    pub id: i64,
+    /// An identical event was already stored; `id` is the existing row, or
+    /// the row compaction folded it into.
+    pub duplicate: bool,
 }
 
-// --- Events ---
+pub async fn insert_event(pool: &SqlitePool, event: &Event) -> Result<Inserted, AppError> {
+    let mut conn = pool.acquire().await.map_err(|e| AppError::Database(e.to_string()))?;
+    insert_event_in(&mut conn, event).await
+}
 
-pub async fn insert_event(pool: &SqlitePool, event: &Event) -> Result<i64, AppError> {
+/// [`insert_event`] on `conn`, e.g. as part of a transaction.
+pub async fn insert_event_in(
+    conn: &mut SqliteConnection,
+    event: &Event,
+) -> Result<Inserted, AppError> {
+    let key = dedup_key(event);
+    let compacted: Option<i64> = sqlx::query_scalar(
+        "SELECT event_id FROM compacted_events WHERE user_id = ? AND dedup_key = ?",
+    )
+    .bind(event.user_id.to_string())
+    .bind(&key)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+    if let Some(id) = compacted {
+        return Ok(Inserted {
+            id,
+            duplicate: true,
+        });
+    }
     let result = sqlx::query(
-        "INSERT INTO events (user_id, timestamp, event_type, entity, project, language, branch, activity, machine, metadata)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO events (user_id, timestamp, event_type, entity, project, language, branch, activity, machine, metadata, dedup_key)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(user_id, dedup_key) DO NOTHING",
     )
     .bind(event.user_id.to_string())
     .bind(event.timestamp.to_rfc3339())
@@ -234,11 +1295,28 @@ pub async fn insert_event(pool: &SqlitePool, event: &Event) -> Result<i64, AppEr
     .bind(event.activity.as_ref().map(|a| a.as_str()))
     .bind(&event.machine)
     .bind(event.metadata.as_ref().map(|m| m.to_string()))
-    .execute(pool)
+    .bind(&key)
+    .execute(&mut *conn)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
 
-    Ok(result.last_insert_rowid())
+    if result.rows_affected() > 0 {
+        return Ok(Inserted {
+            id: result.last_insert_rowid(),
+            duplicate: false,
+        });
+    }
+
+    let row = sqlx::query("SELECT id FROM events WHERE user_id = ? AND dedup_key = ?")
+        .bind(event.user_id.to_string())
+        .bind(&key)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(Inserted {
+        id: row.get("id"),
+        duplicate: true,
+    })
 }
 
 pub async fn count_events(pool: &SqlitePool) -> Result<i64, AppError> {
@@ -249,54 +1327,204 @@ pub async fn count_events(pool: &SqlitePool) -> Result<i64, AppError> {
     Ok(row.get::<i64, _>("cnt"))
 }
 
+const EVENT_COLUMNS: &str = "id, user_id, timestamp, event_type, entity, project, language, branch, activity, machine, metadata, created_at";
+
+/// Legacy export: events with `timestamp > since`, oldest first.
 pub async fn list_events(
     pool: &SqlitePool,
     user_id: Uuid,
     since: DateTime<Utc>,
     limit: i64,
 ) -> Result<Vec<Event>, AppError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {EVENT_COLUMNS} FROM events WHERE user_id = ? AND timestamp > ? ORDER BY timestamp ASC LIMIT ?"
+    ))
+    .bind(user_id.to_string())
+    .bind(since.to_rfc3339())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_event_row).collect()
+}
+
+/// Events inserted after row `after_id`, in insertion order. Unlike
+/// timestamps the rowid only grows, so late or same-instant events can't
+/// slip past a cursor.
+pub async fn list_events_after(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    after_id: i64,
+    limit: i64,
+) -> Result<Vec<Event>, AppError> {
+    let rows = sqlx::query(&format!(
+        "SELECT {EVENT_COLUMNS} FROM events WHERE user_id = ? AND id > ? ORDER BY id ASC LIMIT ?"
+    ))
+    .bind(user_id.to_string())
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_event_row).collect()
+}
+
+/// Like [`list_events_after`], limited to timestamps in `[from, to)`;
+/// a missing bound is open.
+pub async fn list_events_in_range_after(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    after_id: i64,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    limit: i64,
+) -> Result<Vec<Event>, AppError> {
+    let (from, to) = (from.map(|t| t.to_rfc3339()), to.map(|t| t.to_rfc3339()));
+    let rows = sqlx::query(&format!(
+        "SELECT {EVENT_COLUMNS} FROM events WHERE user_id = ?1 AND id > ?2 AND (?3 IS NULL OR timestamp >= ?3) AND (?4 IS NULL OR timestamp < ?4) ORDER BY id ASC LIMIT ?5"
+    ))
+    .bind(user_id.to_string())
+    .bind(after_id)
+    .bind(from)
+    .bind(to)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_event_row).collect()
+}
+
+// --- Compaction ---
+
+/// Users with events before `before`.
+pub async fn users_with_events_before(
+    pool: &SqlitePool,
+    before: DateTime<Utc>,
+) -> Result<Vec<User>, AppError> {
     let rows = sqlx::query(
-        "SELECT id, user_id, timestamp, event_type, entity, project, language, branch, activity, machine, metadata, created_at
-         FROM events WHERE user_id = ? AND timestamp > ? ORDER BY timestamp ASC LIMIT ?",
+        "SELECT id, username, display_name, public_profile, created_at FROM users
+         WHERE EXISTS (SELECT 1 FROM events WHERE events.user_id = users.id AND timestamp < ?)
+         ORDER BY created_at ASC",
     )
+    .bind(before.to_rfc3339())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter().map(parse_user_row).collect()
+}
+
+/// Events before `before` in report order (timestamp, then id), after the
+/// event `after` (a timestamp and id), each with its heartbeat count.
+pub async fn list_events_for_compaction(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    before: DateTime<Utc>,
+    after: Option<(DateTime<Utc>, i64)>,
+    limit: i64,
+) -> Result<Vec<(Event, i64)>, AppError> {
+    let (after_ts, after_id) = after.map_or((String::new(), 0), |(ts, id)| (ts.to_rfc3339(), id));
+    let rows = sqlx::query(&format!(
+        "SELECT {EVENT_COLUMNS}, heartbeats FROM events WHERE user_id = ? AND timestamp < ? AND (timestamp, id) > (?, ?) ORDER BY timestamp, id LIMIT ?"
+    ))
     .bind(user_id.to_string())
-    .bind(since.to_rfc3339())
+    .bind(before.to_rfc3339())
+    .bind(after_ts)
+    .bind(after_id)
     .bind(limit)
     .fetch_all(pool)
     .await
     .map_err(|e| AppError::Database(e.to_string()))?;
 
-    rows.iter().map(|row| {
-        let id: i64 = row.get("id");
-        let uid_str: String = row.get("user_id");
-        let uid = Uuid::parse_str(&uid_str).map_err(|e| AppError::Database(e.to_string()))?;
-        let ts_str: String = row.get("timestamp");
-        let timestamp = DateTime::parse_from_rfc3339(&ts_str)
-            .map(|dt| dt.with_timezone(&Utc))
+    rows.iter()
+        .map(|row| Ok((parse_event_row(row)?, row.get("heartbeats"))))
+        .collect()
+}
+
+/// Delete the `merged` rows, each `(id, id of the row that took it over)`,
+/// and set the heartbeat counts of the rows that took them over, in one
+/// transaction. Deleted rows leave their dedup key in `compacted_events`,
+/// pointing at the row that took them over.
+pub async fn apply_compaction(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    merged: &[(i64, i64)],
+    heartbeats: &[(i64, i64)],
+) -> Result<(), AppError> {
+    let user_id = user_id.to_string();
+    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+    for (id, into) in merged {
+        sqlx::query(
+            "INSERT OR REPLACE INTO compacted_events (user_id, dedup_key, event_id)
+             SELECT user_id, dedup_key, ? FROM events
+             WHERE user_id = ? AND id = ? AND dedup_key IS NOT NULL",
+        )
+        .bind(into)
+        .bind(&user_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        // Events folded into this one earlier move along with it
+        sqlx::query("UPDATE compacted_events SET event_id = ? WHERE user_id = ? AND event_id = ?")
+            .bind(into)
+            .bind(&user_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
             .map_err(|e| AppError::Database(e.to_string()))?;
-        let et_str: String = row.get("event_type");
-        let activity_str: Option<String> = row.get("activity");
-        let metadata_str: Option<String> = row.get("metadata");
-        let created_str: Option<String> = row.get("created_at");
-        let created_at = created_str.and_then(|s| {
-            DateTime::parse_from_rfc3339(&s).ok().map(|dt| dt.with_timezone(&Utc))
-        });
+        sqlx::query("DELETE FROM events WHERE user_id = ? AND id = ?")
+            .bind(&user_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+    }
+    for (id, count) in heartbeats {
+        sqlx::query("UPDATE events SET heartbeats = ? WHERE user_id = ? AND id = ?")
+            .bind(count)
+            .bind(&user_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+    }
+    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))
+}
 
-        Ok(Event {
-            id: Some(id),
-            user_id: uid,
-            timestamp,
-            event_type: timeforged_core::models::EventType::from_str_lossy(&et_str),
-            entity: row.get("entity"),
-            project: row.get("project"),
-            language: row.get("language"),
-            branch: row.get("branch"),
-            activity: activity_str.map(|a| timeforged_core::models::ActivityType::from_str_lossy(&a)),
-            machine: row.get("machine"),
-            metadata: metadata_str.and_then(|s| serde_json::from_str(&s).ok()),
-            created_at,
-        })
-    }).collect()
+fn parse_event_row(row: &sqlx::sqlite::SqliteRow) -> Result<Event, AppError> {
+    let id: i64 = row.get("id");
+    let uid_str: String = row.get("user_id");
+    let uid = Uuid::parse_str(&uid_str).map_err(|e| AppError::Database(e.to_string()))?;
+    let ts_str: String = row.get("timestamp");
+    let timestamp = DateTime::parse_from_rfc3339(&ts_str)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    let et_str: String = row.get("event_type");
+    let activity_str: Option<String> = row.get("activity");
+    let metadata_str: Option<String> = row.get("metadata");
+    let created_str: Option<String> = row.get("created_at");
+    let created_at = created_str.and_then(|s| {
+        DateTime::parse_from_rfc3339(&s).ok().map(|dt| dt.with_timezone(&Utc))
+    });
+
+    Ok(Event {
+        id: Some(id),
+        user_id: uid,
+        timestamp,
+        event_type: timeforged_core::models::EventType::from_str_lossy(&et_str),
+        entity: row.get("entity"),
+        project: row.get("project"),
+        language: row.get("language"),
+        branch: row.get("branch"),
+        activity: activity_str.map(|a| timeforged_core::models::ActivityType::from_str_lossy(&a)),
+        machine: row.get("machine"),
+        metadata: metadata_str.and_then(|s| serde_json::from_str(&s).ok()),
+        created_at,
+    })
 }
 
 // --- Reports ---
@@ -316,21 +1544,23 @@ pub async fn get_summary(
     let from_str = from.to_rfc3339();
     let to_str = to.to_rfc3339();
 
+    let (project, language) = (req.project.as_deref(), req.language.as_deref());
+
     // Total time via session gaps
-    let total = compute_total_seconds(pool, &user_id_str, &from_str, &to_str, idle_timeout, req.project.as_deref()).await?;
+    let total = compute_total_seconds(pool, &user_id_str, &from_str, &to_str, idle_timeout, project, language).await?;
 
     // By project
     let projects = query_category_summary(
-        pool, &user_id_str, &from_str, &to_str, "project", req.project.as_deref(), idle_timeout,
+        pool, &user_id_str, &from_str, &to_str, "project", req, idle_timeout,
     ).await?;
 
     // By language
     let languages = query_category_summary(
-        pool, &user_id_str, &from_str, &to_str, "language", req.project.as_deref(), idle_timeout,
+        pool, &user_id_str, &from_str, &to_str, "language", req, idle_timeout,
     ).await?;
 
     // By day
-    let days = query_day_summary(pool, &user_id_str, &from_str, &to_str, idle_timeout, req.project.as_deref()).await?;
+    let days = query_day_summary(pool, &user_id_str, &from_str, &to_str, idle_timeout, project, language).await?;
 
     Ok(Summary {
         total_seconds: total,
@@ -349,6 +1579,7 @@ async fn compute_total_seconds(
     to: &str,
     idle_timeout: u64,
     project: Option<&str>,
+    language: Option<&str>,
 ) -> Result<f64, AppError> {
     // Use window function to compute gaps between consecutive events.
     // If gap < idle_timeout, it's active time. Otherwise, count a flat heartbeat (e.g. 2 min).
@@ -362,6 +1593,9 @@ async fn compute_total_seconds(
     if project.is_some() {
         query.push_str(" AND project = ?");
     }
+    if language.is_some() {
+        query.push_str(" AND language = ?");
+    }
     query.push_str(
         ")
         SELECT CAST(COALESCE(SUM(
@@ -382,6 +1616,9 @@ async fn compute_total_seconds(
     if let Some(p) = project {
         q = q.bind(p);
     }
+    if let Some(l) = language {
+        q = q.bind(l);
+    }
     q = q.bind(idle_timeout as f64);
 
     let row = q.fetch_one(pool).await.map_err(|e| AppError::Database(e.to_string()))?;
@@ -394,9 +1631,11 @@ async fn query_category_summary(
     from: &str,
     to: &str,
     column: &str,
-    project_filter: Option<&str>,
+    filter: &ReportRequest,
     idle_timeout: u64,
 ) -> Result<Vec<CategorySummary>, AppError> {
+    let project_filter = filter.project.as_deref();
+    let language_filter = filter.language.as_deref();
     // Simpler approach: count events per category, weight by avg gap
     let mut query = format!(
         "WITH ordered AS (
@@ -409,6 +1648,9 @@ async fn query_category_summary(
     if project_filter.is_some() && column != "project" {
         query.push_str(" AND project = ?");
     }
+    if language_filter.is_some() && column != "language" {
+        query.push_str(" AND language = ?");
+    }
     query.push_str(&format!(
         ")
         SELECT {col} as name,
@@ -430,10 +1672,15 @@ async fn query_category_summary(
         .bind(user_id)
         .bind(from)
         .bind(to);
-    if let Some(p) = project_filter {
-        if column != "project" {
-            q = q.bind(p);
-        }
+    if let Some(p) = project_filter
+        && column != "project"
+    {
+        q = q.bind(p);
+    }
+    if let Some(l) = language_filter
+        && column != "language"
+    {
+        q = q.bind(l);
     }
     q = q.bind(idle_timeout as f64);
 
@@ -461,6 +1708,7 @@ async fn query_day_summary(
     to: &str,
     idle_timeout: u64,
     project: Option<&str>,
+    language: Option<&str>,
 ) -> Result<Vec<DaySummary>, AppError> {
     let mut query = String::from(
         "WITH ordered AS (
@@ -472,6 +1720,9 @@ async fn query_day_summary(
     if project.is_some() {
         query.push_str(" AND project = ?");
     }
+    if language.is_some() {
+        query.push_str(" AND language = ?");
+    }
     query.push_str(
         ")
         SELECT day,
@@ -495,6 +1746,9 @@ async fn query_day_summary(
     if let Some(p) = project {
         q = q.bind(p);
     }
+    if let Some(l) = language {
+        q = q.bind(l);
+    }
     q = q.bind(idle_timeout as f64);
 
     let rows = q.fetch_all(pool).await.map_err(|e| AppError::Database(e.to_string()))?;
@@ -523,7 +1777,7 @@ pub async fn get_sessions(
 
     let mut query = String::from(
         "WITH ordered AS (
-            SELECT timestamp, project,
+            SELECT timestamp, project, heartbeats,
                    LAG(timestamp) OVER (ORDER BY timestamp) as prev_ts
             FROM events
             WHERE user_id = ? AND timestamp >= ? AND timestamp <= ?",
@@ -534,13 +1788,13 @@ pub async fn get_sessions(
     query.push_str(
         "),
         gaps AS (
-            SELECT timestamp, project, prev_ts,
+            SELECT timestamp, project, heartbeats, prev_ts,
                    CASE WHEN prev_ts IS NULL OR (julianday(timestamp) - julianday(prev_ts)) * 86400 >= ?
                         THEN 1 ELSE 0 END as new_session
             FROM ordered
         ),
         sessions AS (
-            SELECT timestamp, project,
+            SELECT timestamp, project, heartbeats,
                    SUM(new_session) OVER (ORDER BY timestamp) as session_id
             FROM gaps
         )
@@ -548,7 +1802,7 @@ pub async fn get_sessions(
                MAX(timestamp) as end_ts,
                CAST((julianday(MAX(timestamp)) - julianday(MIN(timestamp))) * 86400 AS REAL) as duration,
                project,
-               COUNT(*) as event_count
+               SUM(heartbeats) as event_count
         FROM sessions
         GROUP BY session_id
         ORDER BY start_ts",
@@ -596,7 +1850,7 @@ pub async fn get_hourly_activity(
     let mut query = String::from(
         "WITH ordered AS (
             SELECT CAST(strftime('%H', timestamp) AS INTEGER) as hour,
-                   timestamp,
+                   timestamp, heartbeats,
                    LAG(timestamp) OVER (ORDER BY timestamp) as prev_ts
             FROM events
             WHERE user_id = ? AND timestamp >= ? AND timestamp <= ?",
@@ -615,7 +1869,7 @@ pub async fn get_hourly_activity(
                        ELSE 0.0
                    END
                ), 0.0) AS REAL) as total,
-               COUNT(*) as event_count
+               SUM(heartbeats) as event_count
         FROM ordered
         GROUP BY hour
         ORDER BY hour",
@@ -641,3 +1895,110 @@ pub async fn get_hourly_activity(
         })
         .collect())
 }
+
+/// Times and projects of events in `[from, to]`, oldest first, for working
+/// out patterns no single report query gives.
+pub async fn get_timeline(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    project: Option<&str>,
+    language: Option<&str>,
+) -> Result<Vec<(DateTime<Utc>, Option<String>)>, AppError> {
+    let rows = sqlx::query(
+        "SELECT timestamp, project FROM events WHERE user_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3 AND (?4 IS NULL OR project = ?4) AND (?5 IS NULL OR language = ?5) ORDER BY timestamp",
+    )
+    .bind(user_id.to_string())
+    .bind(from.to_rfc3339())
+    .bind(to.to_rfc3339())
+    .bind(project)
+    .bind(language)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter()
+        .map(|r| {
+            let timestamp: String = r.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            Ok((timestamp, r.get("project")))
+        })
+        .collect()
+}
+
+/// Times, projects and activities of events on `projects` in `[from, to)`,
+/// oldest first.
+pub async fn get_billing_timeline(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    projects: &[String],
+) -> Result<Vec<(DateTime<Utc>, String, Option<ActivityType>)>, AppError> {
+    if projects.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; projects.len()].join(", ");
+    let query = format!(
+        "SELECT timestamp, project, activity FROM events WHERE user_id = ? AND timestamp >= ? AND timestamp < ? AND project IN ({placeholders}) ORDER BY timestamp"
+    );
+    let mut q = sqlx::query(&query)
+        .bind(user_id.to_string())
+        .bind(from.to_rfc3339())
+        .bind(to.to_rfc3339());
+    for project in projects {
+        q = q.bind(project);
+    }
+    let rows = q.fetch_all(pool).await.map_err(|e| AppError::Database(e.to_string()))?;
+
+    rows.iter()
+        .map(|r| {
+            let timestamp: String = r.get("timestamp");
+            let timestamp = DateTime::parse_from_rfc3339(&timestamp)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            let activity: Option<String> = r.get("activity");
+            Ok((timestamp, r.get("project"), activity.map(|a| ActivityType::from_str_lossy(&a))))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn dedup_keys_are_backfilled_once() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        init_db(&pool).await.unwrap();
+        let user = create_user(&pool, "dev", None).await.unwrap();
+        // As if stored before deduplication, by a server that never backfilled
+        sqlx::query(
+            "INSERT INTO events (user_id, timestamp, event_type, entity)
+             VALUES (?, '2026-03-01T10:00:00+00:00', 'file', '/a.rs')",
+        )
+        .bind(user.id.to_string())
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("DELETE FROM data_migrations").execute(&pool).await.unwrap();
+        let keyed = "SELECT COUNT(*) FROM events WHERE dedup_key IS NOT NULL";
+
+        init_db(&pool).await.unwrap();
+        let count: i64 = sqlx::query_scalar(keyed).fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 1);
+
+        sqlx::query("UPDATE events SET dedup_key = NULL").execute(&pool).await.unwrap();
+        init_db(&pool).await.unwrap();
+        let count: i64 = sqlx::query_scalar(keyed).fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 0, "backfill ran again");
+    }
+}
@@ -1,149 +1,262 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use chrono::{DateTime, Utc};
-use reqwest::Client;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
 use timeforged_core::api::{
-    BatchEventRequest, BatchEventResponse, CreateEventRequest, SyncStateFile,
+    BatchEventRequest, BatchEventResponse, CreateEventRequest, ExportEventsQuery,
+    ExportEventsResponse, RemoteStatus, SyncDirectionReport, SyncMode, SyncReport, SyncRun,
+    SyncStatusQuery, SyncStatusResponse, SyncTrigger,
 };
-use timeforged_core::config::{CliConfig, config_dir};
+use timeforged_core::config::CliConfig;
+use timeforged_core::error::AppError;
+use timeforged_sync::{Endpoint, Progress, SyncError, remote};
 
+use crate::bus::EventBus;
+use crate::service::event_service;
 use crate::storage::sqlite;
 
-const BATCH_SIZE: usize = 100;
-const PAGE_LIMIT: i64 = 5000;
+/// Sync runs are kept this long.
+const RUN_RETENTION_DAYS: i64 = 30;
 
-fn sync_state_path() -> std::path::PathBuf {
-    config_dir().join("sync-state.toml")
-}
+/// Set while a sync started by `POST /api/v1/sync/run` is going.
+static BACKGROUND_RUN: AtomicBool = AtomicBool::new(false);
 
-fn load_sync_state() -> SyncStateFile {
-    let path = sync_state_path();
-    if let Ok(content) = std::fs::read_to_string(&path) {
-        toml::from_str(&content).unwrap_or(SyncStateFile {
-            last_synced: None,
-            events_synced: 0,
-            last_pulled: None,
-            events_pulled: 0,
-        })
-    } else {
-        SyncStateFile {
-            last_synced: None,
-            events_synced: 0,
-            last_pulled: None,
-            events_pulled: 0,
-        }
+/// The one background sync slot, freed when dropped.
+pub struct BackgroundRun(());
+
+impl BackgroundRun {
+    /// `None` while another background sync holds the slot.
+    pub fn claim() -> Option<Self> {
+        BACKGROUND_RUN
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| Self(()))
     }
 }
 
-fn save_sync_state(state: &SyncStateFile) {
-    let path = sync_state_path();
-    if let Ok(content) = toml::to_string_pretty(state) {
-        let _ = std::fs::create_dir_all(path.parent().unwrap());
-        let _ = std::fs::write(&path, content);
+impl Drop for BackgroundRun {
+    fn drop(&mut self) {
+        BACKGROUND_RUN.store(false, Ordering::Release);
     }
 }
 
-async fn push_batch(
-    http: &Client,
-    remote_url: &str,
-    remote_key: &str,
-    batch: &BatchEventRequest,
-) -> Result<BatchEventResponse, String> {
-    let url = format!("{}/api/v1/events/batch", remote_url.trim_end_matches('/'));
-    let resp = http
-        .post(&url)
-        .header("X-Api-Key", remote_key)
-        .json(batch)
-        .send()
-        .await
-        .map_err(|e| format!("remote request failed: {e}"))?;
+/// This daemon's database as a sync endpoint. Pulled events are ingested
+/// like a batch request, so they show up on the live stream too.
+pub struct LocalStore {
+    pub pool: SqlitePool,
+    pub bus: EventBus,
+    pub user_id: Uuid,
+}
 
-    if resp.status().is_success() {
-        resp.json::<BatchEventResponse>()
+impl Endpoint for LocalStore {
+    async fn export(&self, cursor: &str, limit: usize) -> Result<ExportEventsResponse, SyncError> {
+        let query = ExportEventsQuery {
+            cursor: Some(cursor.to_string()),
+            since: None,
+            limit: limit as i64,
+        };
+        event_service::export_events(&self.pool, self.user_id, query)
             .await
-            .map_err(|e| format!("parse error: {e}"))
-    } else {
-        let status = resp.status();
-        Err(format!("remote returned HTTP {status}"))
+            .map_err(|e| SyncError::Store(e.to_string()))
+    }
+
+    async fn ingest(
+        &self,
+        events: Vec<CreateEventRequest>,
+    ) -> Result<BatchEventResponse, SyncError> {
+        let batch = BatchEventRequest { events };
+        event_service::create_batch(&self.pool, &self.bus, self.user_id, batch)
+            .await
+            .map_err(|e| SyncError::Store(e.to_string()))
     }
 }
 
-async fn sync_once(pool: &SqlitePool, user_id: Uuid, http: &Client, remote_url: &str, remote_key: &str) {
-    let mut state = load_sync_state();
-    let since = state
-        .last_synced
-        .unwrap_or_else(|| DateTime::<Utc>::MIN_UTC);
-
-    let events = match sqlite::list_events(pool, user_id, since, PAGE_LIMIT).await {
-        Ok(ev) => ev,
-        Err(e) => {
-            tracing::warn!("auto-sync: failed to query events: {e}");
-            return;
-        }
-    };
+/// Errors that stop a sync before it starts, as API errors.
+pub fn to_app_error(e: SyncError) -> AppError {
+    match e {
+        SyncError::NotConfigured => AppError::BadRequest(e.to_string()),
+        SyncError::UnknownRemote(_) => AppError::NotFound(e.to_string()),
+        _ => AppError::Internal(e.to_string()),
+    }
+}
 
-    if events.is_empty() {
-        return;
+fn log_progress(p: Progress) {
+    if let Progress::Page { direction, events } = p {
+        tracing::debug!(
+            "sync: {events} events from {} to {}",
+            direction.source(),
+            direction.target()
+        );
     }
+}
 
-    let total = events.len();
-    let mut accepted = 0usize;
-    let mut latest_ts = state.last_synced;
+/// Sync with the remotes in cli.toml (or just `only`), one after another,
+/// and record each attempt in `sync_runs`. A remote another sync holds
+/// right now, whether this daemon's or one started by `tf`, is reported as
+/// such and left alone.
+pub async fn sync_now(
+    pool: &SqlitePool,
+    bus: &EventBus,
+    user_id: Uuid,
+    mode: SyncMode,
+    only: Option<&str>,
+    trigger: SyncTrigger,
+) -> Result<Vec<SyncReport>, SyncError> {
+    let remotes = remote::select(&CliConfig::load(), only)?;
 
-    for chunk in events.chunks(BATCH_SIZE) {
-        let batch_events: Vec<CreateEventRequest> = chunk
-            .iter()
-            .map(|e| {
-                if latest_ts.is_none() || Some(e.timestamp) > latest_ts {
-                    latest_ts = Some(e.timestamp);
-                }
-                CreateEventRequest {
-                    timestamp: e.timestamp,
-                    event_type: e.event_type.clone(),
-                    entity: e.entity.clone(),
-                    project: e.project.clone(),
-                    language: e.language.clone(),
-                    branch: e.branch.clone(),
-                    activity: e.activity.clone(),
-                    machine: e.machine.clone(),
-                    metadata: e.metadata.clone(),
-                }
-            })
-            .collect();
-
-        match push_batch(http, remote_url, remote_key, &BatchEventRequest { events: batch_events }).await {
-            Ok(resp) => accepted += resp.accepted,
-            Err(e) => {
-                tracing::warn!("auto-sync: batch push failed: {e}");
-                break;
-            }
+    let mut reports = Vec::new();
+    for remote in &remotes {
+        let local = LocalStore {
+            pool: pool.clone(),
+            bus: bus.clone(),
+            user_id,
+        };
+        let started_at = Utc::now();
+        let Some(report) = remote::sync_remote(local, remote, mode, &mut log_progress).await
+        else {
+            continue;
+        };
+        log_report(&report);
+        if let Err(e) = record(pool, &report, mode, trigger, started_at).await {
+            tracing::warn!("sync [{}]: could not record run: {e}", report.remote);
         }
+        reports.push(report);
     }
+    Ok(reports)
+}
 
-    if accepted > 0 {
-        state.last_synced = latest_ts;
-        state.events_synced += accepted as u64;
-        save_sync_state(&state);
-        tracing::info!("auto-sync: pushed {accepted}/{total} events to remote");
+fn log_report(report: &SyncReport) {
+    let name = &report.remote;
+    for e in report.errors() {
+        tracing::warn!("sync [{name}]: {e}");
+    }
+    if let Some(ref push) = report.push
+        && push.accepted > 0
+    {
+        tracing::info!(
+            "sync [{name}]: pushed {} new events ({} already there, {} rejected)",
+            push.accepted,
+            push.duplicates,
+            push.rejected
+        );
+    }
+    if let Some(ref pull) = report.pull
+        && pull.accepted > 0
+    {
+        tracing::info!(
+            "sync [{name}]: pulled {} new events ({} already here, {} rejected)",
+            pull.accepted,
+            pull.duplicates,
+            pull.rejected
+        );
     }
 }
 
-pub async fn run(pool: SqlitePool, user_id: Uuid, interval_secs: u64) {
-    let cli_config = CliConfig::load();
-
-    let (remote_url, remote_key) = match (cli_config.remote_url, cli_config.remote_key) {
-        (Some(url), Some(key)) => (url, key),
-        _ => {
-            tracing::debug!("auto-sync: no remote configured, skipping");
-            return;
-        }
+/// Store a report as one run per direction. A remote that was busy with
+/// another sync wasn't attempted, so that isn't recorded.
+async fn record(
+    pool: &SqlitePool,
+    report: &SyncReport,
+    mode: SyncMode,
+    trigger: SyncTrigger,
+    started_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let run = |direction: SyncMode, started_at, d: &SyncDirectionReport| SyncRun {
+        id: 0,
+        remote: report.remote.clone(),
+        direction,
+        trigger,
+        started_at,
+        duration_ms: d.duration_ms,
+        accepted: d.accepted,
+        duplicates: d.duplicates,
+        rejected: d.rejected,
+        skipped: d.skipped,
+        error: d.error.clone().or_else(|| report.error.clone()),
     };
 
-    let http = Client::new();
-    let interval = Duration::from_secs(interval_secs);
+    let mut runs = Vec::new();
+    if let Some(push) = &report.push {
+        runs.push(run(SyncMode::Push, started_at, push));
+    }
+    if let Some(pull) = &report.pull {
+        let pushing = report.push.as_ref().map_or(0, |p| p.duration_ms);
+        let pull_started = started_at + chrono::Duration::milliseconds(pushing as i64);
+        runs.push(run(SyncMode::Pull, pull_started, pull));
+    }
+    if runs.is_empty() && report.error.as_deref() != Some(&SyncError::Locked.to_string()) {
+        runs.push(run(mode, started_at, &SyncDirectionReport::default()));
+    }
+
+    for run in &runs {
+        sqlite::insert_sync_run(pool, run).await?;
+    }
+    Ok(())
+}
+
+/// Sync state of every configured remote. A remote alerts once it has been
+/// failing for longer than `alert_after_secs`.
+pub async fn remote_statuses(pool: &SqlitePool, alert_after_secs: u64) -> Vec<RemoteStatus> {
+    let health = sqlite::sync_health(pool).await.unwrap_or_else(|e| {
+        tracing::warn!("sync: could not read sync runs: {e}");
+        Vec::new()
+    });
+    let alert_after = chrono::Duration::seconds(alert_after_secs as i64);
+    let now = Utc::now();
+
+    CliConfig::load()
+        .all_remotes()
+        .into_iter()
+        .map(|remote| {
+            let state = timeforged_sync::state::read(&remote.name);
+            let health = health.iter().find(|h| h.remote == remote.name);
+            let failing_since = health.and_then(|h| h.failing_since);
+            RemoteStatus {
+                direction: remote.effective_direction(),
+                redacted: !remote.privacy.is_off(),
+                encrypted: remote.encrypted,
+                name: remote.name,
+                events_pushed: state.events_synced,
+                events_pulled: state.events_pulled,
+                last_run_at: state.last_run_at,
+                last_error: state.last_error,
+                retry_at: state.retry_at,
+                last_success_at: health.and_then(|h| h.last_success_at),
+                failing_since,
+                alert: failing_since.is_some_and(|since| now - since > alert_after),
+            }
+        })
+        .collect()
+}
+
+/// Remote states and recent runs, for `GET /api/v1/sync/status`.
+pub async fn status(
+    pool: &SqlitePool,
+    alert_after_secs: u64,
+    query: SyncStatusQuery,
+) -> Result<SyncStatusResponse, AppError> {
+    let limit = query.limit.clamp(1, 200);
+    let runs = sqlite::list_sync_runs(pool, query.remote.as_deref(), limit).await?;
+    let remotes = remote_statuses(pool, alert_after_secs).await;
+    Ok(SyncStatusResponse {
+        alert: remotes.iter().any(|r| r.alert),
+        alert_after_secs,
+        remotes,
+        runs,
+    })
+}
+
+/// Sync each remote `interval_secs` after its last run, whoever ran it.
+/// A failing remote is retried with exponential backoff instead, from 30s
+/// up to an hour; its events wait in the local database until then, behind
+/// the push cursor. cli.toml is read each round, so remotes added or
+/// removed while the daemon runs are picked up.
+pub async fn run(pool: SqlitePool, bus: EventBus, user_id: Uuid, interval_secs: u64) {
+    let max_wait = Duration::from_secs(interval_secs.max(1));
+    let interval = chrono::Duration::seconds(interval_secs as i64);
 
     // Initial delay to let the daemon fully start
     tokio::time::sleep(Duration::from_secs(10)).await;
@@ -151,7 +264,306 @@ pub async fn run(pool: SqlitePool, user_id: Uuid, interval_secs: u64) {
     tracing::info!("auto-sync: started (interval={}s)", interval_secs);
 
     loop {
-        sync_once(&pool, user_id, &http, &remote_url, &remote_key).await;
-        tokio::time::sleep(interval).await;
+        let remotes = CliConfig::load().all_remotes();
+        if remotes.is_empty() {
+            tracing::debug!("auto-sync: no remote configured, skipping");
+        }
+        for remote in &remotes {
+            if due_at(&remote.name, interval) > Utc::now() {
+                continue;
+            }
+            let only = Some(remote.name.as_str());
+            let run = sync_now(&pool, &bus, user_id, SyncMode::Both, only, SyncTrigger::Auto);
+            if let Err(e) = run.await {
+                tracing::warn!("auto-sync: {e}");
+            }
+        }
+        let cutoff = Utc::now() - chrono::Duration::days(RUN_RETENTION_DAYS);
+        if let Err(e) = sqlite::prune_sync_runs(&pool, cutoff).await {
+            tracing::warn!("auto-sync: could not prune sync runs: {e}");
+        }
+
+        let next = remotes.iter().map(|r| due_at(&r.name, interval)).min();
+        let wait = next.map_or(interval, |at| at - Utc::now());
+        let wait = wait.to_std().unwrap_or_default();
+        tokio::time::sleep(wait.clamp(Duration::from_secs(1), max_wait)).await;
+    }
+}
+
+fn due_at(remote: &str, interval: chrono::Duration) -> DateTime<Utc> {
+    timeforged_sync::state::next_due(&timeforged_sync::state::read(remote), interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+    use tokio::sync::mpsc;
+
+    use timeforged_core::api::{CreateApiKeyRequest, EventCursor, SyncDirectionReport};
+    use timeforged_core::config::AppConfig;
+    use timeforged_core::models::{Event, EventType};
+    use timeforged_sync::{
+        HttpEndpoint, SealKey, SealedEndpoint, StateLock, SyncEngine, e2e, supports_cursors,
+    };
+
+    use super::*;
+    use crate::app::{AppState, build_router};
+    use crate::rate_limit::RateLimits;
+    use crate::service::card_service::RenderCache;
+    use crate::service::user_service;
+
+    async fn daemon_db() -> LocalStore {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlite::init_db(&pool).await.unwrap();
+        let user = sqlite::create_user(&pool, "dev", None).await.unwrap();
+        LocalStore {
+            pool,
+            bus: EventBus::new(),
+            user_id: user.id,
+        }
+    }
+
+    /// A full daemon serving the API on a local port.
+    async fn spawn_remote() -> (String, String, LocalStore) {
+        let store = daemon_db().await;
+        let key = user_service::create_api_key(
+            &store.pool,
+            store.user_id,
+            CreateApiKeyRequest { label: "sync".into() },
+        )
+        .await
+        .unwrap()
+        .key;
+
+        let config = AppConfig::default();
+        let cards = RenderCache::from_config(&config.card);
+        let state = AppState {
+            db: store.pool.clone(),
+            rate_limits: RateLimits::from_config(&config.rate_limit),
+            config,
+            watcher_tx: mpsc::channel(1).0,
+            bus: EventBus::new(),
+            cards,
+            stream_tokens: Default::default(),
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = build_router(state).into_make_service_with_connect_info::<std::net::SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, key, store)
+    }
+
+    async fn record(store: &LocalStore, at: DateTime<Utc>, entity: &str, machine: &str) {
+        let event = Event {
+            id: None,
+            user_id: store.user_id,
+            timestamp: at,
+            event_type: EventType::File,
+            entity: entity.into(),
+            project: Some("app".into()),
+            language: Some("Rust".into()),
+            branch: None,
+            activity: None,
+            machine: Some(machine.into()),
+            metadata: None,
+            created_at: None,
+        };
+        sqlite::insert_event(&store.pool, &event).await.unwrap();
+    }
+
+    async fn all_events(store: &LocalStore) -> Vec<Event> {
+        sqlite::list_events_after(&store.pool, store.user_id, 0, 100_000).await.unwrap()
+    }
+
+    fn state_in(name: &str) -> StateLock {
+        let dir = std::env::temp_dir().join(format!("tf-daemon-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        StateLock::acquire_in(&dir, "test").unwrap()
+    }
+
+    fn counts(d: &Option<SyncDirectionReport>) -> (usize, usize) {
+        let d = d.as_ref().unwrap();
+        assert!(d.error.is_none(), "{:?}", d.error);
+        (d.accepted, d.duplicates)
+    }
+
+    #[tokio::test]
+    async fn two_daemons_sync_losslessly_through_remote() {
+        let (url, key, remote) = spawn_remote().await;
+        let a = daemon_db().await;
+        let b = daemon_db().await;
+        let now = Utc::now();
+
+        // More events on one timestamp than a batch holds: a timestamp
+        // watermark would skip the rest of them
+        for i in 0..150 {
+            record(&a, now, &format!("/a/{i}.rs"), "a").await;
+        }
+        for i in 0..10 {
+            record(&b, now, &format!("/b/{i}.rs"), "b").await;
+        }
+
+        let remote_for = || HttpEndpoint::new(&url, Some(key.clone()), "remote");
+        let (mut a_state, mut b_state) = (state_in("a"), state_in("b"));
+        let a_sync = SyncEngine::new(a, remote_for());
+        let b_sync = SyncEngine::new(b, remote_for());
+
+        let a1 = a_sync.run(SyncMode::Push, &mut a_state, &mut |_| {}).await;
+        assert_eq!(counts(&a1.push), (150, 0));
+        let b1 = b_sync.run(SyncMode::Both, &mut b_state, &mut |_| {}).await;
+        assert_eq!(counts(&b1.push), (10, 0));
+        assert_eq!(counts(&b1.pull), (150, 10));
+
+        // Arrives late with an old timestamp, and one more on the boundary
+        record(a_sync.local(), now - chrono::Duration::hours(3), "/a/late.rs", "a").await;
+        record(a_sync.local(), now, "/a/boundary.rs", "a").await;
+        let a2 = a_sync.run(SyncMode::Both, &mut a_state, &mut |_| {}).await;
+        assert_eq!(counts(&a2.push), (2, 0));
+        assert_eq!(counts(&a2.pull).0, 10);
+
+        assert_eq!(all_events(&remote).await.len(), 162);
+
+        // Sync state lost: everything is resent but stored only once
+        let mut fresh = state_in("a-lost");
+        let replay = a_sync.run(SyncMode::Push, &mut fresh, &mut |_| {}).await;
+        assert_eq!(counts(&replay.push), (0, 162));
+        assert_eq!(all_events(&remote).await.len(), 162);
+        let head = all_events(a_sync.local()).await.last().and_then(|e| e.id).unwrap();
+        assert_eq!(fresh.state.push_cursor, Some(EventCursor(head).encode()));
+    }
+
+    /// Every value stored in any table, as text.
+    async fn stored_text(pool: &SqlitePool) -> String {
+        use sqlx::Row;
+
+        let tables: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+                .fetch_all(pool)
+                .await
+                .unwrap();
+        let mut text = String::new();
+        for table in tables {
+            let rows = sqlx::query(&format!("SELECT * FROM \"{table}\""))
+                .fetch_all(pool)
+                .await
+                .unwrap();
+            for row in &rows {
+                for i in 0..row.len() {
+                    if let Ok(Some(value)) = row.try_get::<Option<String>, _>(i) {
+                        text.push_str(&value);
+                        text.push('\n');
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    #[tokio::test]
+    async fn encrypted_sync_keeps_entities_from_the_remote() {
+        let (url, key, remote) = spawn_remote().await;
+        let (a, b) = (daemon_db().await, daemon_db().await);
+        let secret = "/home/dev/acme/secret-merger-plan.rs";
+        record(&a, Utc::now(), secret, "a").await;
+
+        let http = || HttpEndpoint::new(&url, Some(key.clone()), "remote");
+        let a_key = e2e::unlock(&http(), "correct horse").await.unwrap();
+        let b_key = e2e::unlock(&http(), "correct horse").await.unwrap();
+        assert_eq!(a_key, b_key);
+        assert!(matches!(
+            e2e::unlock(&http(), "battery staple").await,
+            Err(SyncError::WrongPassphrase)
+        ));
+
+        let sealed = |k: &str| SealedEndpoint::new(http(), SealKey::from_encoded(k).unwrap());
+        let a_sync = SyncEngine::new(a, sealed(&a_key));
+        let pushed = a_sync.run(SyncMode::Push, &mut state_in("e2e-a"), &mut |_| {}).await;
+        assert_eq!(counts(&pushed.push), (1, 0));
+        let again = a_sync.run(SyncMode::Push, &mut state_in("e2e-a2"), &mut |_| {}).await;
+        assert_eq!(counts(&again.push), (0, 1));
+
+        let stored = stored_text(&remote.pool).await;
+        assert!(all_events(&remote).await.is_empty());
+        for cleartext in [secret, "secret-merger-plan", "acme", "Rust"] {
+            assert!(!stored.contains(cleartext), "{cleartext} reached the remote");
+        }
+
+        let b_sync = SyncEngine::new(b, sealed(&b_key));
+        let pulled = b_sync.run(SyncMode::Pull, &mut state_in("e2e-b"), &mut |_| {}).await;
+        assert_eq!(counts(&pulled.pull), (1, 0));
+        let events = all_events(b_sync.local()).await;
+        assert_eq!(events[0].entity, secret);
+        assert_eq!(events[0].language.as_deref(), Some("Rust"));
+    }
+
+    #[tokio::test]
+    async fn remote_cursor_support_is_detected() {
+        let (url, key, _remote) = spawn_remote().await;
+        let remote = HttpEndpoint::new(&url, Some(key), "remote");
+        assert!(supports_cursors(&remote).await.unwrap());
+        let bad_key = HttpEndpoint::new(&url, Some("bad-key".into()), "remote");
+        assert!(matches!(
+            supports_cursors(&bad_key).await,
+            Err(SyncError::Status { status: 401, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn runs_are_recorded_with_failure_streaks() {
+        let pool = daemon_db().await.pool;
+        let t0 = Utc::now() - chrono::Duration::hours(3);
+        let report = |pull_error: Option<&str>| SyncReport {
+            remote: "work".into(),
+            push: Some(SyncDirectionReport {
+                accepted: 4,
+                duration_ms: 1500,
+                ..Default::default()
+            }),
+            pull: Some(SyncDirectionReport {
+                error: pull_error.map(str::to_string),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let save = |report: SyncReport, at| {
+            let pool = pool.clone();
+            async move {
+                super::record(&pool, &report, SyncMode::Both, SyncTrigger::Auto, at).await.unwrap()
+            }
+        };
+
+        // Pushing keeps working while pulling fails: still a failing remote
+        save(report(Some("HTTP 500")), t0).await;
+        save(report(Some("HTTP 500")), t0 + chrono::Duration::hours(1)).await;
+        let health = sqlite::sync_health(&pool).await.unwrap();
+        let pull_started = t0 + chrono::Duration::milliseconds(1500);
+        let failing_since = health[0].failing_since.unwrap();
+        assert_eq!(failing_since.timestamp_millis(), pull_started.timestamp_millis());
+        assert!(health[0].last_success_at.is_some());
+
+        let runs = sqlite::list_sync_runs(&pool, Some("work"), 10).await.unwrap();
+        assert_eq!(runs.len(), 4);
+        assert_eq!(runs[0].direction, SyncMode::Pull);
+        assert_eq!(runs[0].error.as_deref(), Some("HTTP 500"));
+        assert_eq!((runs[1].direction, runs[1].accepted), (SyncMode::Push, 4));
+
+        // A busy remote wasn't attempted; a recovered one stops failing
+        let busy = SyncReport {
+            remote: "work".into(),
+            error: Some(SyncError::Locked.to_string()),
+            ..Default::default()
+        };
+        save(busy, t0 + chrono::Duration::hours(2)).await;
+        save(report(None), t0 + chrono::Duration::hours(2)).await;
+        let health = sqlite::sync_health(&pool).await.unwrap();
+        assert!(health[0].failing_since.is_none());
+        assert_eq!(sqlite::list_sync_runs(&pool, None, 10).await.unwrap().len(), 6);
+
+        let cutoff = t0 + chrono::Duration::minutes(30);
+        assert_eq!(sqlite::prune_sync_runs(&pool, cutoff).await.unwrap(), 2);
     }
 }
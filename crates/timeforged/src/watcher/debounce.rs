@@ -17,10 +17,10 @@ impl Debouncer {
 
     pub fn should_emit(&mut self, path: &PathBuf) -> bool {
         let now = Instant::now();
-        if let Some(last) = self.last_seen.get(path) {
-            if now.duration_since(*last) < self.interval {
-                return false;
-            }
+        if let Some(last) = self.last_seen.get(path)
+            && now.duration_since(*last) < self.interval
+        {
+            return false;
         }
         self.last_seen.insert(path.clone(), now);
         true
@@ -14,7 +14,8 @@ use timeforged_core::util::{infer_language_from_path, is_ignored_path};
 
 use super::WatcherCommand;
 use super::debounce::Debouncer;
-use crate::storage::sqlite;
+use crate::bus::EventBus;
+use crate::service::event_service;
 
 struct GitBranchCache {
     cache: HashMap<PathBuf, (String, Instant)>,
@@ -30,10 +31,10 @@ impl GitBranchCache {
     }
 
     async fn get_branch(&mut self, dir: &Path) -> Option<String> {
-        if let Some((branch, when)) = self.cache.get(dir) {
-            if when.elapsed() < self.ttl {
-                return Some(branch.clone());
-            }
+        if let Some((branch, when)) = self.cache.get(dir)
+            && when.elapsed() < self.ttl
+        {
+            return Some(branch.clone());
         }
 
         let dir_owned = dir.to_path_buf();
@@ -74,6 +75,7 @@ fn resolve_project_name(watched_root: &Path, file_path: &Path) -> Option<String>
 
 pub async fn run(
     pool: SqlitePool,
+    bus: EventBus,
     user_id: Uuid,
     watcher_config: WatcherConfig,
     initial_dirs: Vec<PathBuf>,
@@ -101,11 +103,11 @@ pub async fn run(
         let initial_dirs = dirs_for_thread;
 
         let mut watcher: RecommendedWatcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
-            if let Ok(event) = res {
-                if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
-                    for path in event.paths {
-                        let _ = event_tx.try_send(path);
-                    }
+            if let Ok(event) = res
+                && matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+            {
+                for path in event.paths {
+                    let _ = event_tx.try_send(path);
                 }
             }
         }).expect("failed to create file watcher");
@@ -223,7 +225,7 @@ pub async fn run(
                     created_at: None,
                 };
 
-                if let Err(e) = sqlite::insert_event(&pool, &event).await {
+                if let Err(e) = event_service::record_event(&pool, &bus, event).await {
                     tracing::warn!("failed to insert watcher event: {e}");
                 }
             }
@@ -10,10 +10,12 @@ use timeforged_core::config::WatcherConfig;
 use timeforged_core::models::{ActivityType, EventType};
 use timeforged_core::util::infer_language_from_path;
 
-use crate::storage::sqlite;
+use crate::bus::EventBus;
+use crate::service::event_service;
 
 pub async fn run(
     pool: SqlitePool,
+    bus: EventBus,
     user_id: Uuid,
     config: WatcherConfig,
     watched_dirs: Arc<Mutex<Vec<PathBuf>>>,
@@ -74,7 +76,7 @@ pub async fn run(
             created_at: None,
         };
 
-        if let Err(e) = sqlite::insert_event(&pool, &event).await {
+        if let Err(e) = event_service::record_event(&pool, &bus, event).await {
             tracing::warn!("window tracker: failed to insert event: {e}");
         }
     }
@@ -90,16 +92,13 @@ async fn get_active_window_title() -> Option<String> {
         .output()
         .await;
 
-    if let Ok(output) = result {
-        if output.status.success() {
-            if let Ok(text) = String::from_utf8(output.stdout) {
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
-                    if let Some(title) = json.get("title").and_then(|v| v.as_str()) {
-                        return Some(title.to_string());
-                    }
-                }
-            }
-        }
+    if let Ok(output) = result
+        && output.status.success()
+        && let Ok(text) = String::from_utf8(output.stdout)
+        && let Ok(json) = serde_json::from_str::<serde_json::Value>(&text)
+        && let Some(title) = json.get("title").and_then(|v| v.as_str())
+    {
+        return Some(title.to_string());
     }
 
     // Fallback to xdotool
@@ -108,12 +107,12 @@ async fn get_active_window_title() -> Option<String> {
         .output()
         .await;
 
-    if let Ok(output) = result {
-        if output.status.success() {
-            return String::from_utf8(output.stdout)
-                .ok()
-                .map(|s| s.trim().to_string());
-        }
+    if let Ok(output) = result
+        && output.status.success()
+    {
+        return String::from_utf8(output.stdout)
+            .ok()
+            .map(|s| s.trim().to_string());
     }
 
     None
@@ -165,7 +164,7 @@ fn extract_file_path(title: &str) -> Option<PathBuf> {
     // "/home/user/projects/foo/file.rs - Editor"
     // "C:\Users\user\projects\foo\file.rs - Editor"
 
-    for part in title.split(|c: char| c == ' ' || c == '\t' || c == '\u{2014}' || c == '\u{2013}') {
+    for part in title.split([' ', '\t', '\u{2014}', '\u{2013}']) {
         let trimmed = part.trim();
 
         // Unix absolute paths
@@ -190,10 +189,10 @@ fn extract_file_path(title: &str) -> Option<PathBuf> {
 
     // Look for ~ paths (Unix-style home shortcut)
     if let Some(home) = dirs::home_dir() {
-        for part in title.split(|c: char| c == ' ' || c == '\t' || c == '(' || c == ')') {
+        for part in title.split([' ', '\t', '(', ')']) {
             let trimmed = part.trim();
-            if trimmed.starts_with("~/") {
-                let expanded: PathBuf = home.join(&trimmed[2..]);
+            if let Some(rest) = trimmed.strip_prefix("~/") {
+                let expanded: PathBuf = home.join(rest);
                 if expanded.extension().is_some() {
                     return Some(expanded);
                 }
@@ -30,6 +30,7 @@ async function request<T>(path: string, options?: RequestInit): Promise<T> {
     throw new Error(body.error || `HTTP ${res.status}`)
   }
 
+  if (res.status === 202 || res.status === 204) return undefined as T
   return res.json()
 }
 
@@ -40,6 +41,42 @@ export interface StatusResponse {
   event_count: number
 }
 
+export interface RemoteStatus {
+  name: string
+  direction: 'push' | 'pull' | 'both'
+  redacted: boolean
+  events_pushed: number
+  events_pulled: number
+  last_run_at: string | null
+  last_error: string | null
+  last_success_at: string | null
+  failing_since: string | null
+  retry_at: string | null
+  encrypted: boolean
+  alert: boolean
+}
+
+export interface SyncRun {
+  id: number
+  remote: string
+  direction: 'push' | 'pull' | 'both'
+  trigger: 'auto' | 'manual'
+  started_at: string
+  duration_ms: number
+  accepted: number
+  duplicates: number
+  rejected: number
+  skipped: number
+  error?: string
+}
+
+export interface SyncStatus {
+  alert: boolean
+  alert_after_secs: number
+  remotes: RemoteStatus[]
+  runs: SyncRun[]
+}
+
 export interface Summary {
   total_seconds: number
   from: string
@@ -74,6 +111,86 @@ export interface HourlyActivity {
   event_count: number
 }
 
+export interface PublicProfileSettings {
+  public: boolean
+  show_totals: boolean
+  show_languages: boolean
+  show_projects: boolean
+  show_streak: boolean
+  show_goals: boolean
+  hidden_projects: string[]
+}
+
+export interface PublicProfileResponse {
+  public_profile: boolean
+  settings: PublicProfileSettings
+  card_url: string
+  profile_url: string
+}
+
+/** A public profile; fields the user hides are missing. */
+export interface PublicProfile {
+  username: string
+  display_name?: string
+  from: string
+  to: string
+  totals?: { total_seconds: number; active_days: number; daily_average_seconds: number }
+  languages?: CategorySummary[]
+  projects?: CategorySummary[]
+  streak?: { current: number; longest: number; longest_from?: string; longest_to?: string }
+}
+
+export type StreamEventType = 'event' | 'totals' | 'session_started' | 'session_ended'
+
+export interface StreamHandle {
+  close: () => void
+}
+
+/**
+ * Subscribe to live updates. EventSource can't send headers and the key
+ * mustn't go in a URL, so each connection opens with a single-use token from
+ * `POST /api/v1/stream/token`. A dropped connection is reopened with a fresh
+ * token; `onError` fires on each failure so callers can fall back to polling.
+ */
+export function stream(
+  onEvent: (type: StreamEventType, data: any) => void,
+  onError?: () => void,
+): StreamHandle {
+  const types: StreamEventType[] = ['event', 'totals', 'session_started', 'session_ended']
+  let source: EventSource | undefined
+  let retry: ReturnType<typeof setTimeout> | undefined
+  let closed = false
+
+  const fail = () => {
+    source?.close()
+    onError?.()
+    if (!closed) retry = setTimeout(open, 5_000)
+  }
+  async function open() {
+    let token: string
+    try {
+      token = (await request<{ token: string }>('/api/v1/stream/token', { method: 'POST' })).token
+    } catch {
+      return fail()
+    }
+    if (closed) return
+    source = new EventSource(`${BASE}/api/v1/stream?token=${encodeURIComponent(token)}`)
+    for (const type of types) {
+      source.addEventListener(type, (e) => onEvent(type, JSON.parse((e as MessageEvent).data)))
+    }
+    source.onerror = fail
+  }
+
+  open()
+  return {
+    close: () => {
+      closed = true
+      clearTimeout(retry)
+      source?.close()
+    },
+  }
+}
+
 export const api = {
   status: () => request<StatusResponse>('/api/v1/status'),
   summary: (from?: string, to?: string) => {
@@ -97,5 +214,17 @@ export const api = {
     const qs = params.toString()
     return request<HourlyActivity[]>(`/api/v1/reports/activity${qs ? '?' + qs : ''}`)
   },
+  syncStatus: () => request<SyncStatus>('/api/v1/sync/status?limit=5'),
+  /** Start a sync in the background; follow it with `syncStatus`. */
+  syncRun: () => request<void>('/api/v1/sync/run', { method: 'POST', body: '{}' }),
   me: () => request<{ id: string; username: string; display_name: string | null }>('/api/v1/me'),
+  profileSettings: () => request<PublicProfileResponse>('/api/v1/me/public-profile'),
+  setProfileSettings: (settings: PublicProfileSettings) =>
+    request<PublicProfileResponse>('/api/v1/me/public-profile', {
+      method: 'PUT',
+      body: JSON.stringify(settings),
+    }),
+  /** Needs no key; 404 when the profile is private. */
+  publicProfile: (username: string) =>
+    request<PublicProfile>(`/api/v1/profile/${encodeURIComponent(username)}`),
 }
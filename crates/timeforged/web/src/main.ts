@@ -3,12 +3,15 @@ import { createRouter, createWebHistory } from 'vue-router'
 import App from './App.vue'
 import Dashboard from './views/Dashboard.vue'
 import Settings from './views/Settings.vue'
+import Profile from './views/Profile.vue'
 
 const router = createRouter({
   history: createWebHistory(),
   routes: [
     { path: '/', component: Dashboard },
     { path: '/settings', component: Settings },
+    // Public: shown without the sidebar, to visitors without a key
+    { path: '/u/:username', component: Profile, meta: { public: true } },
   ],
 })
 